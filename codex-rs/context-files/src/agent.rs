@@ -3,18 +3,25 @@
 //! The `ContextAgent` is the main entry point for processing folders,
 //! querying context, and managing the user's knowledge tree.
 
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
 
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 use walkdir::WalkDir;
 
 use crate::chunker::{Chunk, SemanticChunker};
-use crate::entity::EntityExtractor;
+use crate::entity::{Entity, EntityExtractor};
 use crate::error::{ContextError, Result};
-use crate::llm::{AnalysisContext, LlmAnalyzer, LlmConfig};
-use crate::node::{ContextNode, DomainDetection, NodeType};
-use crate::tree::ContextTree;
+use crate::ignore::IgnoreRules;
+use crate::llm::{AnalysisContext, LlmAnalyzer, LlmConfig, PromptTemplates};
+use crate::node::{ContextNode, DocumentAnalysis, DomainDetection, NodeType, RelatedNode};
+use crate::tree::{
+    ContextTree, DEFAULT_MIN_CROSS_LINK_CONFIDENCE, DEFAULT_MIN_CROSS_LINK_STRENGTH, SearchConfig,
+};
 
 /// Configuration for the context agent.
 #[derive(Debug, Clone)]
@@ -25,12 +32,26 @@ pub struct AgentConfig {
     /// Whether to automatically create cross-links.
     pub auto_cross_link: bool,
 
+    /// Minimum strength for automatically created cross-links.
+    pub min_cross_link_strength: f32,
+
+    /// Minimum confidence of a shared technology entity on both nodes for
+    /// automatically created cross-links.
+    pub min_cross_link_confidence: f32,
+
     /// Minimum confidence for including entities.
     pub min_confidence: f32,
 
     /// Maximum files to process per folder.
     pub max_files_per_folder: usize,
 
+    /// Number of files whose opening lines make up the folder summary used
+    /// for domain detection.
+    pub folder_sample_files: usize,
+
+    /// Maximum number of nodes returned by `query` and `query_faceted`.
+    pub query_result_limit: usize,
+
     /// File extensions to process.
     pub extensions: Vec<String>,
 
@@ -39,6 +60,17 @@ pub struct AgentConfig {
 
     /// Whether to create file reference nodes.
     pub create_file_refs: bool,
+
+    /// Node types to create for folders of particular domains, by domain
+    /// name. Domains without an entry create every node type.
+    pub domain_overrides: HashMap<String, NodeTypePolicy>,
+
+    /// Whether to set project-relative display paths on file nodes.
+    pub relative_display_paths: bool,
+
+    /// Whether to recompute project and module confidence from the
+    /// documents they contain.
+    pub propagate_confidence: bool,
 }
 
 impl Default for AgentConfig {
@@ -46,8 +78,12 @@ impl Default for AgentConfig {
         Self {
             max_depth: None,
             auto_cross_link: true,
+            min_cross_link_strength: DEFAULT_MIN_CROSS_LINK_STRENGTH,
+            min_cross_link_confidence: DEFAULT_MIN_CROSS_LINK_CONFIDENCE,
             min_confidence: 0.3,
             max_files_per_folder: 1000,
+            folder_sample_files: DEFAULT_FOLDER_SAMPLE_FILES,
+            query_result_limit: MAX_QUERY_RESULTS,
             extensions: vec![
                 "md".to_string(),
                 "txt".to_string(),
@@ -64,6 +100,58 @@ impl Default for AgentConfig {
             ],
             recursive: true,
             create_file_refs: true,
+            domain_overrides: HashMap::new(),
+            relative_display_paths: true,
+            propagate_confidence: true,
+        }
+    }
+}
+
+impl AgentConfig {
+    /// Get the default query options, limited to `query_result_limit`.
+    pub fn query_options(&self) -> AgentQueryOptions {
+        AgentQueryOptions {
+            max_results: self.query_result_limit,
+            ..Default::default()
+        }
+    }
+
+    /// Get the node types to create for a folder of `domain`.
+    ///
+    /// File references also require `create_file_refs`.
+    pub fn node_types_for(&self, domain: &str) -> NodeTypePolicy {
+        let policy = self
+            .domain_overrides
+            .get(domain)
+            .copied()
+            .unwrap_or_default();
+        NodeTypePolicy {
+            file_refs: policy.file_refs && self.create_file_refs,
+            ..policy
+        }
+    }
+}
+
+/// Which per-file node types processing creates for a domain.
+///
+/// Domains differ in the leaf granularity they want: a coding project
+/// benefits from a `FileReference` per source file, while a recipe
+/// collection only needs a `Document` per recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeTypePolicy {
+    /// Whether each file gets a `Document` node holding its analysis.
+    pub documents: bool,
+
+    /// Whether each file gets a `FileReference` leaf. Without document
+    /// nodes, file references are placed directly under the project.
+    pub file_refs: bool,
+}
+
+impl Default for NodeTypePolicy {
+    fn default() -> Self {
+        Self {
+            documents: true,
+            file_refs: true,
         }
     }
 }
@@ -86,16 +174,68 @@ pub struct ProcessingResult {
     /// Processing time in milliseconds.
     pub processing_time_ms: u64,
 
-    /// Files processed.
+    /// Files successfully added to the tree.
     pub files_processed: usize,
 
     /// Total entities extracted.
     pub entities_extracted: usize,
 
+    /// Files sampled for the folder summary, at most `folder_sample_files`.
+    pub files_sampled: usize,
+
     /// Errors encountered during processing.
+    ///
+    /// Failures of individual files or of domain detection are recorded here
+    /// and the rest of the folder is still processed.
     pub errors: Vec<String>,
 }
 
+/// Default maximum number of nodes returned by a query.
+const MAX_QUERY_RESULTS: usize = 20;
+
+/// Default number of files sampled for a folder summary.
+const DEFAULT_FOLDER_SAMPLE_FILES: usize = 10;
+
+/// Default minimum relevance score for query results.
+const DEFAULT_MIN_SCORE: f32 = 0.5;
+
+/// Maximum number of entities reported in query facets.
+const MAX_FACET_ENTITIES: usize = 10;
+
+/// Maximum number of a file's entities used to find related nodes.
+const MAX_RELATED_ENTITIES: usize = 5;
+
+/// Score contributed by each entity a node shares with the file.
+const SHARED_ENTITY_WEIGHT: f32 = 0.1;
+
+/// Weight of a keyword in a node's similarity profile.
+const PROFILE_KEYWORD_WEIGHT: f32 = 1.0;
+
+/// Weight of an entity in a node's similarity profile.
+const PROFILE_ENTITY_WEIGHT: f32 = 2.0;
+
+/// Weight of a name or summary word in a node's similarity profile.
+const PROFILE_WORD_WEIGHT: f32 = 0.5;
+
+/// Deepest heading level added to document keywords.
+const MAX_HEADING_KEYWORD_LEVEL: u8 = 3;
+
+/// Maximum number of headings added to a document's keywords.
+const MAX_HEADING_KEYWORDS: usize = 8;
+
+/// A node related to a file, with the reasons it was surfaced.
+#[derive(Debug, Clone)]
+pub struct RelatedResult {
+    /// The related node.
+    pub node: ContextNode,
+
+    /// Combined strength of all relationships to the file.
+    pub score: f32,
+
+    /// Why the node is related (e.g. "Same Technology: Both use rust").
+    pub reasons: Vec<String>,
+}
+
 /// Result of querying the context tree.
 #[derive(Debug, Clone)]
 pub struct AgentQueryResult {
@@ -112,6 +252,58 @@ pub struct AgentQueryResult {
     pub truncated: bool,
 }
 
+/// Options for filtering and limiting query results.
+#[derive(Debug, Clone)]
+pub struct AgentQueryOptions {
+    /// Minimum relevance score (fraction of query terms matched, 0.0-1.0).
+    ///
+    /// When no match reaches this score, the best-scoring matches are still
+    /// returned so weak queries are not always empty.
+    pub min_score: f32,
+
+    /// Maximum number of nodes to return.
+    pub max_results: usize,
+
+    /// Weights for prose and code matches when scoring nodes.
+    pub search: SearchConfig,
+}
+
+impl Default for AgentQueryOptions {
+    fn default() -> Self {
+        Self {
+            min_score: DEFAULT_MIN_SCORE,
+            max_results: MAX_QUERY_RESULTS,
+            search: SearchConfig::default(),
+        }
+    }
+}
+
+/// Facet counts over every node that matched a query.
+#[derive(Debug, Clone, Default)]
+pub struct QueryFacets {
+    /// Number of matches per domain name.
+    pub domains: HashMap<String, usize>,
+
+    /// Number of matches per node type.
+    pub node_types: HashMap<NodeType, usize>,
+
+    /// Most frequent entities (normalized name, matching node count), highest first.
+    pub top_entities: Vec<(String, usize)>,
+}
+
+/// Result of a faceted query: ranked nodes plus facets over all matches.
+#[derive(Debug, Clone)]
+pub struct FacetedQueryResult {
+    /// The ranked (and possibly truncated) query result.
+    pub result: AgentQueryResult,
+
+    /// Facets computed over the full match set.
+    pub facets: QueryFacets,
+
+    /// Total number of matching nodes before truncation.
+    pub total_matches: usize,
+}
+
 /// The main context agent for building and querying the knowledge tree.
 ///
 /// The agent orchestrates:
@@ -194,17 +386,384 @@ impl ContextAgent {
     /// Process a folder and integrate it into the context tree.
     pub async fn process_folder(&mut self, path: &Path) -> Result<ProcessingResult> {
         let start = Instant::now();
-        let mut result = ProcessingResult {
-            root_node_id: String::new(),
-            nodes_created: 0,
-            domain: String::new(),
-            cross_links_created: 0,
-            processing_time_ms: 0,
-            files_processed: 0,
-            entities_extracted: 0,
-            errors: Vec::new(),
+        let processor = FolderProcessor {
+            config: &self.config,
+            analyzer: &self.analyzer,
+        };
+
+        let existing_domains = self
+            .tree
+            .list_domains()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        let analysis = processor.analyze(path, &existing_domains).await?;
+
+        let mut result = processor.apply(&mut self.tree, analysis, start)?;
+
+        let (sources, candidates) = processor.link_candidates(&self.tree, &result.root_node_id);
+        let links = processor.find_links(&sources, &candidates).await;
+        result.cross_links_created += add_links(&mut self.tree, links);
+        result.processing_time_ms = start.elapsed().as_millis() as u64;
+
+        Ok(result)
+    }
+
+    /// Query the context tree.
+    pub fn query(&self, query: &str) -> AgentQueryResult {
+        self.query_with_options(query, &self.config.query_options())
+    }
+
+    /// Query the context tree with custom relevance and size limits.
+    pub fn query_with_options(&self, query: &str, options: &AgentQueryOptions) -> AgentQueryResult {
+        query_tree(&self.tree, query, options)
+    }
+
+    /// Query the context tree and compute facets over all matches.
+    ///
+    /// Facet counts cover every matching node, even when the returned
+    /// node list is truncated.
+    pub fn query_faceted(&self, query: &str) -> FacetedQueryResult {
+        query_tree_faceted(&self.tree, query, &self.config.query_options())
+    }
+
+    /// Get context for a specific domain.
+    pub fn get_domain_context(&self, domain: &str) -> Option<Vec<&ContextNode>> {
+        let domain_node = self.tree.get_domain(domain)?;
+        let descendants = self.tree.get_descendants(&domain_node.id);
+        Some(std::iter::once(domain_node).chain(descendants).collect())
+    }
+
+    /// Summarize everything under a domain and cache it on the domain node.
+    ///
+    /// Uses the LLM when available, otherwise a heuristic overview of the
+    /// domain's projects and topics. An empty summary leaves the cached one
+    /// in place.
+    pub async fn summarize_domain(&mut self, domain: &str) -> Result<String> {
+        let (domain_id, descendants) = domain_descendants(&self.tree, domain)?;
+        let summary = self.analyzer.summarize_children(&descendants).await?;
+        cache_summary(&mut self.tree, &domain_id, &summary);
+        Ok(summary)
+    }
+
+    /// Get the ancestry path for a file.
+    pub fn get_file_context(&self, file_path: &Path) -> Option<Vec<&ContextNode>> {
+        let node = self.tree.get_by_path(file_path)?;
+        Some(self.tree.get_ancestry(&node.id))
+    }
+
+    /// List all domains in the tree.
+    pub fn list_domains(&self) -> Vec<&str> {
+        self.tree.list_domains()
+    }
+
+    /// Find nodes related to the file at `path`, strongest first.
+    ///
+    /// Combines cross-links on the file's node and its ancestors with nodes
+    /// sharing the file's top entities. Returns an empty list if the file is
+    /// not in the tree.
+    pub fn related_to_file(&self, path: &Path, limit: usize) -> Vec<RelatedResult> {
+        let Some(file_node) = self.tree.get_by_path(path) else {
+            return Vec::new();
         };
 
+        // The file, its ancestors, and its descendants are never results
+        let ancestry = self.tree.get_ancestry(&file_node.id);
+        let mut excluded: HashSet<&str> = ancestry.iter().map(|n| n.id.as_str()).collect();
+        excluded.extend(
+            self.tree
+                .get_descendants(&file_node.id)
+                .iter()
+                .map(|n| n.id.as_str()),
+        );
+
+        let mut scores: HashMap<&str, (f32, Vec<String>)> = HashMap::new();
+
+        // Cross-links from the file and the nodes containing it
+        for node in &ancestry {
+            for link in &node.related_nodes {
+                if excluded.contains(link.node_id.as_str()) {
+                    continue;
+                }
+                let reason = match &link.reason {
+                    Some(reason) => format!("{}: {reason}", link.relationship.label()),
+                    None => link.relationship.label().to_string(),
+                };
+                let entry = scores.entry(link.node_id.as_str()).or_default();
+                entry.0 += link.strength;
+                entry.1.push(reason);
+            }
+        }
+
+        // Nodes sharing the file's most confident entities
+        let mut top_entities: Vec<&Entity> = ancestry
+            .iter()
+            .rev()
+            .find(|n| !n.entities.is_empty())
+            .map(|n| n.entities.iter().collect())
+            .unwrap_or_default();
+        top_entities.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.normalized_name.cmp(&b.normalized_name))
+        });
+        top_entities.dedup_by(|a, b| a.is_same_as(b));
+        top_entities.truncate(MAX_RELATED_ENTITIES);
+
+        if !top_entities.is_empty() {
+            for node in self.tree.all_nodes() {
+                if excluded.contains(node.id.as_str()) {
+                    continue;
+                }
+                let mut shared: Vec<&str> = top_entities
+                    .iter()
+                    .filter(|e| node.entities.iter().any(|other| other.is_same_as(e)))
+                    .map(|e| e.name.as_str())
+                    .collect();
+                if shared.is_empty() {
+                    continue;
+                }
+                shared.sort_unstable();
+                let entry = scores.entry(node.id.as_str()).or_default();
+                entry.0 += SHARED_ENTITY_WEIGHT * shared.len() as f32;
+                entry
+                    .1
+                    .push(format!("Shared entities: {}", shared.join(", ")));
+            }
+        }
+
+        let mut results: Vec<RelatedResult> = scores
+            .into_iter()
+            .filter_map(|(id, (score, reasons))| {
+                self.tree.get(id).map(|node| RelatedResult {
+                    node: node.clone(),
+                    score,
+                    reasons,
+                })
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.node.name.cmp(&b.node.name))
+                .then_with(|| a.node.id.cmp(&b.node.id))
+        });
+        results.truncate(limit);
+        results
+    }
+
+    /// Find the nodes most similar to `node_id`, strongest first.
+    ///
+    /// Nodes are compared by the cosine similarity of their profiles: their
+    /// keywords, entities, and the words of their name and summary. Returns
+    /// `(node ID, score)` pairs with scores in `0.0..=1.0`, or an empty list
+    /// if the node is not in the tree.
+    pub fn similar_nodes(&self, node_id: &str, limit: usize) -> Vec<(String, f32)> {
+        let Some(node) = self.tree.get(node_id) else {
+            return Vec::new();
+        };
+        let profile = similarity_profile(node);
+        if profile.is_empty() {
+            return Vec::new();
+        }
+
+        let mut results: Vec<(String, f32)> = self
+            .tree
+            .all_nodes()
+            .filter(|other| other.id != node.id && other.node_type != NodeType::Root)
+            .filter_map(|other| {
+                let score = profile_similarity(&profile, &similarity_profile(other));
+                (score > 0.0).then(|| (other.id.clone(), score))
+            })
+            .collect();
+        results.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        results.truncate(limit);
+        results
+    }
+
+    /// Get nodes modified after `since`, newest first.
+    pub fn changed_since(&self, since: DateTime<Utc>) -> Vec<&ContextNode> {
+        self.tree.changed_since(since)
+    }
+
+    /// Get tree statistics.
+    pub fn stats(&self) -> crate::tree::TreeStats {
+        self.tree.stats()
+    }
+
+    /// Convert this agent into a `SharedContextAgent`.
+    pub fn into_shared(self) -> SharedContextAgent {
+        SharedContextAgent {
+            tree: Arc::new(RwLock::new(self.tree)),
+            analyzer: Arc::new(self.analyzer),
+            config: self.config,
+        }
+    }
+}
+
+/// A context agent that can be shared across async tasks.
+///
+/// The tree lives behind an `Arc<RwLock<_>>`. Folder processing reads and
+/// analyzes files without holding the lock and only takes the write lock
+/// while inserting the results, so queries can proceed concurrently.
+#[derive(Clone)]
+pub struct SharedContextAgent {
+    /// The shared context tree.
+    tree: Arc<RwLock<ContextTree>>,
+
+    /// LLM analyzer for intelligent processing.
+    analyzer: Arc<LlmAnalyzer>,
+
+    /// Agent configuration.
+    config: AgentConfig,
+}
+
+impl SharedContextAgent {
+    /// Create a new shared context agent.
+    pub fn new(config: AgentConfig, llm_config: LlmConfig) -> Self {
+        ContextAgent::new(config, llm_config).into_shared()
+    }
+
+    /// Create a shared agent with heuristic-only mode (no LLM).
+    pub fn heuristic_only() -> Self {
+        ContextAgent::heuristic_only().into_shared()
+    }
+
+    /// Get a handle to the shared context tree.
+    pub fn tree(&self) -> Arc<RwLock<ContextTree>> {
+        Arc::clone(&self.tree)
+    }
+
+    /// Process a folder and integrate it into the context tree.
+    ///
+    /// The write lock is held only while the analyzed folder is applied.
+    pub async fn process_folder(&self, path: &Path) -> Result<ProcessingResult> {
+        let start = Instant::now();
+        let processor = FolderProcessor {
+            config: &self.config,
+            analyzer: &self.analyzer,
+        };
+
+        let existing_domains = self
+            .tree
+            .read()
+            .await
+            .list_domains()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let analysis = processor.analyze(path, &existing_domains).await?;
+
+        let mut result = processor.apply(&mut *self.tree.write().await, analysis, start)?;
+
+        let (sources, candidates) =
+            processor.link_candidates(&*self.tree.read().await, &result.root_node_id);
+        let links = processor.find_links(&sources, &candidates).await;
+        result.cross_links_created += add_links(&mut *self.tree.write().await, links);
+        result.processing_time_ms = start.elapsed().as_millis() as u64;
+
+        Ok(result)
+    }
+
+    /// Query the context tree.
+    pub async fn query(&self, query: &str) -> AgentQueryResult {
+        self.query_with_options(query, &self.config.query_options())
+            .await
+    }
+
+    /// Query the context tree with custom relevance and size limits.
+    pub async fn query_with_options(
+        &self,
+        query: &str,
+        options: &AgentQueryOptions,
+    ) -> AgentQueryResult {
+        query_tree(&*self.tree.read().await, query, options)
+    }
+
+    /// Query the context tree and compute facets over all matches.
+    pub async fn query_faceted(&self, query: &str) -> FacetedQueryResult {
+        query_tree_faceted(
+            &*self.tree.read().await,
+            query,
+            &self.config.query_options(),
+        )
+    }
+
+    /// Summarize everything under a domain and cache it on the domain node.
+    ///
+    /// No lock is held while the summary is generated.
+    pub async fn summarize_domain(&self, domain: &str) -> Result<String> {
+        let (domain_id, descendants) = domain_descendants(&*self.tree.read().await, domain)?;
+        let summary = self.analyzer.summarize_children(&descendants).await?;
+        cache_summary(&mut *self.tree.write().await, &domain_id, &summary);
+        Ok(summary)
+    }
+
+    /// List all domains in the tree.
+    pub async fn list_domains(&self) -> Vec<String> {
+        self.tree
+            .read()
+            .await
+            .list_domains()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Get tree statistics.
+    pub async fn stats(&self) -> crate::tree::TreeStats {
+        self.tree.read().await.stats()
+    }
+}
+
+/// Analysis of a folder, computed without touching the tree.
+struct FolderAnalysis {
+    /// The processed folder.
+    path: PathBuf,
+
+    /// Summary built from sampled files.
+    folder_summary: String,
+
+    /// Number of files sampled for the summary.
+    files_sampled: usize,
+
+    /// Detected domain placement.
+    detection: DomainDetection,
+
+    /// Successfully analyzed files.
+    documents: Vec<AnalyzedFile>,
+
+    /// Errors encountered while collecting and analyzing files.
+    errors: Vec<String>,
+}
+
+/// A file analyzed and ready to be added to the tree.
+struct AnalyzedFile {
+    /// Path to the file.
+    path: PathBuf,
+
+    /// File name used for the node.
+    name: String,
+
+    /// Document analysis result.
+    analysis: DocumentAnalysis,
+}
+
+/// Folder processing shared by `ContextAgent` and `SharedContextAgent`.
+///
+/// Processing is split into an analysis phase that only reads files, an
+/// apply phase that mutates the tree, and a linking phase that finds
+/// analyzer cross-links from a snapshot of the new documents.
+struct FolderProcessor<'a> {
+    config: &'a AgentConfig,
+    analyzer: &'a LlmAnalyzer,
+}
+
+impl FolderProcessor<'_> {
+    /// Collect and analyze the files in a folder.
+    async fn analyze(&self, path: &Path, existing_domains: &[String]) -> Result<FolderAnalysis> {
         // Verify path exists
         if !path.exists() {
             return Err(ContextError::Io(std::io::Error::new(
@@ -216,88 +775,68 @@ impl ContextAgent {
         info!("Processing folder: {}", path.display());
 
         // Collect files to process
-        let files = self.collect_files(path)?;
-        result.files_processed = files.len();
+        let mut errors = Vec::new();
+        let files = self.collect_files(path, &mut errors)?;
 
         // Analyze files to build folder summary
         let (folder_summary, file_extensions) = self.analyze_folder_contents(&files).await;
+        let files_sampled = files.len().min(self.config.folder_sample_files);
+        if files_sampled < files.len() {
+            debug!(
+                "Sampled {} of {} files for the summary of {}",
+                files_sampled,
+                files.len(),
+                path.display()
+            );
+        }
 
-        // Detect domain
-        let existing_domains = self
-            .tree
-            .list_domains()
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
-        let detection = self
+        // Detect domain, falling back to the default domain if detection
+        // fails. Rejections for low confidence are deliberate and kept.
+        let detection = match self
             .analyzer
-            .detect_domain(&folder_summary, &file_extensions, &existing_domains)
-            .await?;
+            .detect_domain(&folder_summary, &file_extensions, existing_domains)
+            .await
+        {
+            Ok(detection) => detection,
+            Err(e @ ContextError::DomainConfidenceTooLow { .. }) => return Err(e),
+            Err(e) => {
+                warn!("Domain detection failed for {}: {}", path.display(), e);
+                errors.push(format!("{}: domain detection failed: {e}", path.display()));
+                self.analyzer.fallback_detection()
+            }
+        };
 
-        result.domain = detection.domain.clone();
         info!(
             "Detected domain: {} (confidence: {})",
             detection.domain, detection.confidence
         );
 
-        // Create project node
-        let folder_name = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
-
-        let mut project_node = ContextNode::project(&folder_name, path.to_path_buf());
-        project_node.summary = folder_summary;
-        project_node.confidence = detection.confidence;
-
-        // Apply domain detection to place in tree
-        let project_id = self.tree.apply_domain_detection(project_node, &detection)?;
-        result.root_node_id = project_id.clone();
-        result.nodes_created += 1;
-
-        // Process each file
+        // Analyze each file
+        let mut documents = Vec::new();
         for file_path in &files {
-            match self.process_file(file_path, &project_id).await {
-                Ok((nodes, entities)) => {
-                    result.nodes_created += nodes;
-                    result.entities_extracted += entities;
-                }
+            match self.analyze_file(file_path, existing_domains).await {
+                Ok(document) => documents.push(document),
                 Err(e) => {
-                    result
-                        .errors
-                        .push(format!("{}: {}", file_path.display(), e));
+                    errors.push(format!("{}: {}", file_path.display(), e));
                     warn!("Error processing file {}: {}", file_path.display(), e);
                 }
             }
         }
 
-        // Build cross-links if enabled
-        if self.config.auto_cross_link {
-            let before = self.count_cross_links();
-            self.tree.build_cross_links();
-            result.cross_links_created = self.count_cross_links() - before;
-        }
-
-        // Update root summary
-        self.update_root_summary().await;
-
-        result.processing_time_ms = start.elapsed().as_millis() as u64;
-
-        info!(
-            "Processed folder {} in {}ms: {} nodes, {} files, {} entities",
-            path.display(),
-            result.processing_time_ms,
-            result.nodes_created,
-            result.files_processed,
-            result.entities_extracted
-        );
-
-        Ok(result)
+        Ok(FolderAnalysis {
+            path: path.to_path_buf(),
+            folder_summary,
+            files_sampled,
+            detection,
+            documents,
+            errors,
+        })
     }
 
     /// Collect files to process from a folder.
-    fn collect_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+    ///
+    /// Entries that can't be read are recorded in `errors` and skipped.
+    fn collect_files(&self, path: &Path, errors: &mut Vec<String>) -> Result<Vec<PathBuf>> {
         let mut files = Vec::new();
 
         let walker = if self.config.recursive {
@@ -306,7 +845,38 @@ impl ContextAgent {
             WalkDir::new(path).max_depth(1)
         };
 
-        for entry in walker.into_iter().filter_map(|e| e.ok()) {
+        // Ignore rules that apply inside each visited directory
+        let mut ignore_rules: HashMap<PathBuf, IgnoreRules> = HashMap::new();
+        ignore_rules.insert(path.to_path_buf(), IgnoreRules::for_root(path));
+
+        let entries = walker.into_iter().filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            let Some(rules) = entry.path().parent().and_then(|p| ignore_rules.get(p)) else {
+                return true;
+            };
+            let is_dir = entry.file_type().is_dir();
+            if rules.is_ignored(entry.path(), is_dir) {
+                return false;
+            }
+            if is_dir {
+                let nested = rules.descend(entry.path());
+                ignore_rules.insert(entry.path().to_path_buf(), nested);
+            }
+            true
+        });
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let entry_path = e.path().unwrap_or(path).display().to_string();
+                    warn!("Error reading {}: {}", entry_path, e);
+                    errors.push(format!("{entry_path}: {e}"));
+                    continue;
+                }
+            };
             if !entry.file_type().is_file() {
                 continue;
             }
@@ -334,12 +904,13 @@ impl ContextAgent {
     }
 
     /// Analyze folder contents to build a summary.
+    ///
+    /// Only the first `folder_sample_files` files are sampled.
     async fn analyze_folder_contents(&self, files: &[PathBuf]) -> (String, Vec<String>) {
         let mut summaries = Vec::new();
         let mut extensions = Vec::new();
 
-        for file in files.iter().take(10) {
-            // Sample first 10 files
+        for file in files.iter().take(self.config.folder_sample_files) {
             if let Some(ext) = file.extension().and_then(|e| e.to_str()) {
                 if !extensions.contains(&ext.to_string()) {
                     extensions.push(ext.to_string());
@@ -359,8 +930,12 @@ impl ContextAgent {
         (folder_summary, extensions)
     }
 
-    /// Process a single file and add nodes to the tree.
-    async fn process_file(&mut self, file_path: &Path, parent_id: &str) -> Result<(usize, usize)> {
+    /// Read and analyze a single file.
+    async fn analyze_file(
+        &self,
+        file_path: &Path,
+        existing_domains: &[String],
+    ) -> Result<AnalyzedFile> {
         let content = std::fs::read_to_string(file_path).map_err(ContextError::Io)?;
 
         let file_name = file_path
@@ -381,123 +956,510 @@ impl ContextAgent {
                 .and_then(|p| p.file_name())
                 .and_then(|n| n.to_str())
                 .map(|s| s.to_string()),
+            existing_domains: existing_domains.to_vec(),
             ..Default::default()
         };
 
         let analysis = self.analyzer.analyze_document(&content, &context).await?;
 
+        Ok(AnalyzedFile {
+            path: file_path.to_path_buf(),
+            name: file_name,
+            analysis,
+        })
+    }
+
+    /// Apply an analyzed folder to the tree.
+    fn apply(
+        &self,
+        tree: &mut ContextTree,
+        analysis: FolderAnalysis,
+        start: Instant,
+    ) -> Result<ProcessingResult> {
+        let FolderAnalysis {
+            path,
+            folder_summary,
+            files_sampled,
+            detection,
+            documents,
+            errors,
+        } = analysis;
+
+        let mut result = ProcessingResult {
+            root_node_id: String::new(),
+            nodes_created: 0,
+            domain: detection.domain.clone(),
+            cross_links_created: 0,
+            processing_time_ms: 0,
+            files_processed: 0,
+            entities_extracted: 0,
+            files_sampled,
+            errors,
+        };
+
+        // Create project node
+        let folder_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        let mut project_node =
+            ContextNode::project(&folder_name, path.clone()).with_id(tree.next_id());
+        project_node.summary = folder_summary;
+        project_node.confidence = detection.confidence;
+
+        // Apply domain detection to place in tree, directly under the domain
+        // if the detected category can't be created
+        let project_id = match tree.apply_domain_detection(project_node.clone(), &detection) {
+            Ok(project_id) => project_id,
+            Err(e) => {
+                warn!("Failed to place {} in the tree: {}", path.display(), e);
+                result.errors.push(format!("{}: {}", path.display(), e));
+                let domain_id = tree.ensure_domain(&detection.domain);
+                tree.add_child(&domain_id, project_node)?
+            }
+        };
+        result.root_node_id = project_id.clone();
+        result.nodes_created += 1;
+
+        // Add each analyzed file
+        let policy = self.config.node_types_for(&detection.domain);
+        for document in documents {
+            let file_path = document.path.clone();
+            match self.add_document(tree, document, &project_id, policy) {
+                Ok((nodes, entities)) => {
+                    result.nodes_created += nodes;
+                    result.entities_extracted += entities;
+                    result.files_processed += 1;
+                }
+                Err(e) => {
+                    result
+                        .errors
+                        .push(format!("{}: {}", file_path.display(), e));
+                    warn!("Error processing file {}: {}", file_path.display(), e);
+                }
+            }
+        }
+
+        if self.config.propagate_confidence {
+            propagate_confidence(tree, &project_id);
+        }
+
+        // Build cross-links if enabled
+        if self.config.auto_cross_link {
+            let before = count_cross_links(tree);
+            tree.build_cross_links_with_options(
+                self.config.min_cross_link_strength,
+                self.config.min_cross_link_confidence,
+            );
+            result.cross_links_created = count_cross_links(tree) - before;
+        }
+
+        // Update root summary
+        update_root_summary(tree);
+
+        result.processing_time_ms = start.elapsed().as_millis() as u64;
+
+        info!(
+            "Processed folder {} in {}ms: {} nodes, {} files, {} entities",
+            path.display(),
+            result.processing_time_ms,
+            result.nodes_created,
+            result.files_processed,
+            result.entities_extracted
+        );
+
+        Ok(result)
+    }
+
+    /// Snapshot the documents of a newly applied project and the documents
+    /// of other projects they may link to.
+    ///
+    /// Returns empty lists when auto cross-linking is disabled.
+    fn link_candidates(
+        &self,
+        tree: &ContextTree,
+        project_id: &str,
+    ) -> (Vec<ContextNode>, Vec<ContextNode>) {
+        if !self.config.auto_cross_link {
+            return (Vec::new(), Vec::new());
+        }
+
+        let is_document = |n: &&ContextNode| n.node_type == NodeType::Document;
+        let sources: Vec<ContextNode> = tree
+            .get_descendants(project_id)
+            .into_iter()
+            .filter(is_document)
+            .cloned()
+            .collect();
+        let source_ids: HashSet<&str> = sources.iter().map(|n| n.id.as_str()).collect();
+        let candidates: Vec<ContextNode> = tree
+            .all_nodes()
+            .filter(is_document)
+            .filter(|n| !source_ids.contains(n.id.as_str()))
+            .cloned()
+            .collect();
+
+        (sources, candidates)
+    }
+
+    /// Ask the analyzer for cross-links from each source to the candidates.
+    ///
+    /// Links weaker than `min_cross_link_strength` are dropped.
+    async fn find_links(
+        &self,
+        sources: &[ContextNode],
+        candidates: &[ContextNode],
+    ) -> Vec<(String, RelatedNode)> {
+        let mut links = Vec::new();
+        if candidates.is_empty() {
+            return links;
+        }
+
+        for source in sources {
+            match self.analyzer.find_relationships(source, candidates).await {
+                Ok(related) => links.extend(
+                    related
+                        .into_iter()
+                        .filter(|link| link.strength >= self.config.min_cross_link_strength)
+                        .map(|link| (source.id.clone(), link)),
+                ),
+                Err(e) => warn!("Failed to find relationships for {}: {}", source.name, e),
+            }
+        }
+
+        links
+    }
+
+    /// Add an analyzed file to the tree under the given parent, creating
+    /// the node types `policy` allows.
+    fn add_document(
+        &self,
+        tree: &mut ContextTree,
+        document: AnalyzedFile,
+        parent_id: &str,
+        policy: NodeTypePolicy,
+    ) -> Result<(usize, usize)> {
+        let AnalyzedFile {
+            path: file_path,
+            name: file_name,
+            analysis,
+        } = document;
+
         let mut nodes_created = 0;
         let entities_count = analysis.entities.len();
+        let topics_count = analysis.topics.len();
+
+        // Paths are shown relative to the parent project
+        let display_path = if self.config.relative_display_paths {
+            tree.get(parent_id)
+                .and_then(|parent| parent.path.as_deref())
+                .and_then(|base| relative_display_path(&file_path, base))
+        } else {
+            None
+        };
 
         // Create document node
-        let mut doc_node = ContextNode::document(&file_name, file_path.to_path_buf());
-        doc_node.summary = analysis.summary;
-        doc_node.entities = analysis.entities;
-        doc_node.confidence = analysis.confidence;
+        let mut file_ref_parent = parent_id.to_string();
+        if policy.documents {
+            let mut doc_node =
+                ContextNode::document(&file_name, file_path.clone()).with_id(tree.next_id());
+            doc_node.display_path = display_path.clone();
+
+            for topic in &analysis.topics {
+                doc_node.add_keyword(topic);
+            }
 
-        for topic in &analysis.topics {
-            doc_node.add_keyword(topic);
-        }
+            // Top-level sections come first so they outrank deeper subsections.
+            let headings =
+                analysis.prominent_headings(MAX_HEADING_KEYWORD_LEVEL, MAX_HEADING_KEYWORDS);
+            for heading in headings {
+                doc_node.add_keyword(heading);
+            }
 
-        let doc_id = self.tree.add_child(parent_id, doc_node)?;
-        nodes_created += 1;
+            doc_node.summary = analysis.summary;
+            doc_node.entities = analysis.entities;
+            doc_node.confidence = analysis.confidence;
+
+            file_ref_parent = tree.add_child(parent_id, doc_node)?;
+            nodes_created += 1;
+        }
 
         // Create file reference node if enabled
-        if self.config.create_file_refs {
-            let file_ref = ContextNode::file_reference(&file_name, file_path.to_path_buf());
-            self.tree.add_child(&doc_id, file_ref)?;
+        if policy.file_refs {
+            let mut file_ref = ContextNode::file_reference(&file_name, file_path);
+            file_ref.display_path = display_path;
+            tree.add_child(&file_ref_parent, file_ref)?;
             nodes_created += 1;
         }
 
         debug!(
             "Processed file {}: {} entities, {} topics",
-            file_name,
-            entities_count,
-            analysis.topics.len()
+            file_name, entities_count, topics_count
         );
 
         Ok((nodes_created, entities_count))
     }
+}
 
-    /// Count total cross-links in the tree.
-    fn count_cross_links(&self) -> usize {
-        self.tree.all_nodes().map(|n| n.related_nodes.len()).sum()
+/// Build a weighted term profile of a node for similarity comparisons.
+fn similarity_profile(node: &ContextNode) -> HashMap<String, f32> {
+    let mut profile: HashMap<String, f32> = HashMap::new();
+    for keyword in &node.keywords {
+        *profile.entry(keyword.to_lowercase()).or_default() += PROFILE_KEYWORD_WEIGHT;
+    }
+    for entity in &node.entities {
+        let term = format!("{}:{}", entity.entity_type.as_str(), entity.normalized_name);
+        *profile.entry(term).or_default() += PROFILE_ENTITY_WEIGHT;
+    }
+    let text = format!("{} {}", node.name, node.summary).to_lowercase();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 3)
+    {
+        *profile.entry(word.to_string()).or_default() += PROFILE_WORD_WEIGHT;
     }
+    profile
+}
 
-    /// Update the root node summary based on domains.
-    async fn update_root_summary(&mut self) {
-        let domains = self.tree.list_domains();
-        let domain_count = domains.len();
+/// Cosine similarity of two term profiles.
+fn profile_similarity(a: &HashMap<String, f32>, b: &HashMap<String, f32>) -> f32 {
+    let dot: f32 = a
+        .iter()
+        .filter_map(|(term, weight)| b.get(term).map(|other| weight * other))
+        .sum();
+    if dot == 0.0 {
+        return 0.0;
+    }
+    let norm = |profile: &HashMap<String, f32>| profile.values().map(|w| w * w).sum::<f32>().sqrt();
+    (dot / (norm(a) * norm(b))).min(1.0)
+}
 
-        if domain_count == 0 {
-            return;
-        }
+/// Render `path` relative to `base` with `/` separators.
+fn relative_display_path(path: &Path, base: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
 
-        // Collect domain summaries
-        let mut domain_info = Vec::new();
-        for domain in domains {
-            if let Some(node) = self.tree.get_domain(domain) {
-                let project_count = node.children.len();
-                domain_info.push(format!("{} ({} items)", domain, project_count));
-            }
+/// Clone the nodes under a domain, along with the domain node's ID.
+fn domain_descendants(tree: &ContextTree, domain: &str) -> Result<(String, Vec<ContextNode>)> {
+    let domain_node = tree
+        .get_domain(domain)
+        .ok_or_else(|| ContextError::DomainNotFound(domain.to_string()))?;
+    let descendants = tree
+        .get_descendants(&domain_node.id)
+        .into_iter()
+        .cloned()
+        .collect();
+    Ok((domain_node.id.clone(), descendants))
+}
+
+/// Store a generated summary on a node unless it is empty.
+fn cache_summary(tree: &mut ContextTree, node_id: &str, summary: &str) {
+    if summary.is_empty() {
+        return;
+    }
+    if let Some(node) = tree.get_mut(node_id) {
+        node.summary = summary.to_string();
+        node.touch();
+    }
+}
+
+/// Add analyzer cross-links in both directions, skipping existing links.
+///
+/// Returns the number of links created.
+fn add_links(tree: &mut ContextTree, links: Vec<(String, RelatedNode)>) -> usize {
+    let mut created = 0;
+    for (from_id, link) in links {
+        let mut reverse = RelatedNode::new(from_id.clone(), link.relationship, link.strength);
+        reverse.reason = link.reason.clone();
+        let to_id = link.node_id.clone();
+
+        if tree.add_cross_link(&from_id, link) {
+            created += 1;
+        }
+        if tree.add_cross_link(&to_id, reverse) {
+            created += 1;
         }
+    }
 
-        let summary = format!(
-            "User knowledge across {} domain{}: {}",
-            domain_count,
-            if domain_count > 1 { "s" } else { "" },
-            domain_info.join(", ")
-        );
+    if created > 0 {
+        debug!("Added {created} analyzer cross-links");
+    }
+    created
+}
 
-        if let Some(root) = self.tree.get_mut(&self.tree.root().id.clone()) {
-            root.summary = summary;
+/// Count total cross-links in the tree.
+fn count_cross_links(tree: &ContextTree) -> usize {
+    tree.all_nodes().map(|n| n.related_nodes.len()).sum()
+}
+
+/// Recompute container confidence below `node_id` from its documents.
+///
+/// Each container's confidence becomes the mean of its children's
+/// confidences, weighted by the number of documents each child covers.
+/// Containers without documents keep their confidence. Returns the node's
+/// confidence and document count, or `None` if it covers no documents.
+fn propagate_confidence(tree: &mut ContextTree, node_id: &str) -> Option<(f32, usize)> {
+    let node = tree.get(node_id)?;
+    match node.node_type {
+        NodeType::Document => return Some((node.confidence, 1)),
+        node_type if !node_type.is_container() => return None,
+        _ => {}
+    }
+
+    let children = node.children.clone();
+    let (weighted_sum, documents) = children
+        .iter()
+        .filter_map(|child| propagate_confidence(tree, child))
+        .fold((0.0, 0), |(sum, count), (confidence, documents)| {
+            (sum + confidence * documents as f32, count + documents)
+        });
+    if documents == 0 {
+        return None;
+    }
+
+    let confidence = weighted_sum / documents as f32;
+    if let Some(node) = tree.get_mut(node_id) {
+        node.confidence = confidence;
+    }
+    Some((confidence, documents))
+}
+
+/// Update the root node summary based on domains.
+fn update_root_summary(tree: &mut ContextTree) {
+    let domains = tree.list_domains();
+    let domain_count = domains.len();
+
+    if domain_count == 0 {
+        return;
+    }
+
+    // Collect domain summaries
+    let mut domain_info = Vec::new();
+    for domain in domains {
+        if let Some(node) = tree.get_domain(domain) {
+            let project_count = node.children.len();
+            domain_info.push(format!("{} ({} items)", domain, project_count));
         }
     }
 
-    /// Query the context tree.
-    pub fn query(&self, query: &str) -> AgentQueryResult {
-        let start = Instant::now();
+    let summary = format!(
+        "User knowledge across {} domain{}: {}",
+        domain_count,
+        if domain_count > 1 { "s" } else { "" },
+        domain_info.join(", ")
+    );
+
+    tree.root_mut().summary = summary;
+}
+
+/// Search the tree and build a filtered, truncated query result.
+fn query_tree(tree: &ContextTree, query: &str, options: &AgentQueryOptions) -> AgentQueryResult {
+    let start = Instant::now();
+
+    let matches = filter_by_score(
+        tree.search_scored_with(query, &options.search),
+        options.min_score,
+    );
+
+    build_query_result(query, matches, options.max_results, start)
+}
+
+/// Search the tree and compute facets over all matches.
+fn query_tree_faceted(
+    tree: &ContextTree,
+    query: &str,
+    options: &AgentQueryOptions,
+) -> FacetedQueryResult {
+    let start = Instant::now();
+
+    let matches = filter_by_score(
+        tree.search_scored_with(query, &options.search),
+        options.min_score,
+    );
+    let total_matches = matches.len();
+    let facets = compute_facets(tree, &matches);
+
+    FacetedQueryResult {
+        result: build_query_result(query, matches, options.max_results, start),
+        facets,
+        total_matches,
+    }
+}
 
-        let nodes = self.tree.search(query);
-        let result_nodes: Vec<ContextNode> = nodes.into_iter().cloned().collect();
+/// Drop matches scoring below `min_score`.
+///
+/// The cutoff is lowered to the best available score, so the strongest
+/// matches are always kept.
+fn filter_by_score(scored: Vec<(&ContextNode, f32)>, min_score: f32) -> Vec<&ContextNode> {
+    let best = scored
+        .iter()
+        .map(|(_, score)| *score)
+        .fold(0.0_f32, f32::max);
+    let cutoff = min_score.min(best);
+
+    scored
+        .into_iter()
+        .filter(|(_, score)| *score >= cutoff)
+        .map(|(node, _)| node)
+        .collect()
+}
 
-        let truncated = result_nodes.len() > 20;
-        let nodes = if truncated {
-            result_nodes.into_iter().take(20).collect()
-        } else {
-            result_nodes
-        };
+/// Truncate ranked matches into an `AgentQueryResult`.
+fn build_query_result(
+    query: &str,
+    matches: Vec<&ContextNode>,
+    max_results: usize,
+    start: Instant,
+) -> AgentQueryResult {
+    let truncated = matches.len() > max_results;
+    let nodes = matches.into_iter().take(max_results).cloned().collect();
+
+    AgentQueryResult {
+        nodes,
+        processing_time_ms: start.elapsed().as_millis() as u64,
+        query: query.to_string(),
+        truncated,
+    }
+}
 
-        AgentQueryResult {
-            nodes,
-            processing_time_ms: start.elapsed().as_millis() as u64,
-            query: query.to_string(),
-            truncated,
+/// Count matches by domain, node type, and entity.
+fn compute_facets(tree: &ContextTree, matches: &[&ContextNode]) -> QueryFacets {
+    let mut facets = QueryFacets::default();
+    let mut entity_counts: HashMap<String, usize> = HashMap::new();
+
+    for node in matches {
+        if let Some(domain) = tree.get_domain_for_node(&node.id) {
+            *facets
+                .domains
+                .entry(domain.name.to_lowercase())
+                .or_default() += 1;
         }
-    }
 
-    /// Get context for a specific domain.
-    pub fn get_domain_context(&self, domain: &str) -> Option<Vec<&ContextNode>> {
-        let domain_node = self.tree.get_domain(domain)?;
-        let descendants = self.tree.get_descendants(&domain_node.id);
-        Some(std::iter::once(domain_node).chain(descendants).collect())
-    }
+        *facets.node_types.entry(node.node_type).or_default() += 1;
 
-    /// Get the ancestry path for a file.
-    pub fn get_file_context(&self, file_path: &Path) -> Option<Vec<&ContextNode>> {
-        let node = self.tree.get_by_path(file_path)?;
-        Some(self.tree.get_ancestry(&node.id))
+        let mut seen = HashSet::new();
+        for entity in &node.entities {
+            if seen.insert(entity.normalized_name.as_str()) {
+                *entity_counts
+                    .entry(entity.normalized_name.clone())
+                    .or_default() += 1;
+            }
+        }
     }
 
-    /// List all domains in the tree.
-    pub fn list_domains(&self) -> Vec<&str> {
-        self.tree.list_domains()
-    }
+    let mut top_entities: Vec<(String, usize)> = entity_counts.into_iter().collect();
+    top_entities.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top_entities.truncate(MAX_FACET_ENTITIES);
+    facets.top_entities = top_entities;
 
-    /// Get tree statistics.
-    pub fn stats(&self) -> crate::tree::TreeStats {
-        self.tree.stats()
-    }
+    facets
 }
 
 /// Builder for creating a context agent with custom configuration.
@@ -535,6 +1497,12 @@ impl AgentBuilder {
         self
     }
 
+    /// Set minimum strength for automatically created cross-links.
+    pub fn min_cross_link_strength(mut self, strength: f32) -> Self {
+        self.config.min_cross_link_strength = strength;
+        self
+    }
+
     /// Set minimum confidence threshold.
     pub fn min_confidence(mut self, confidence: f32) -> Self {
         self.config.min_confidence = confidence;
@@ -547,24 +1515,60 @@ impl AgentBuilder {
         self
     }
 
+    /// Set the number of files sampled for folder summaries.
+    pub fn folder_sample_files(mut self, count: usize) -> Self {
+        self.config.folder_sample_files = count;
+        self
+    }
+
+    /// Set the maximum number of nodes returned by queries.
+    pub fn query_result_limit(mut self, limit: usize) -> Self {
+        self.config.query_result_limit = limit;
+        self
+    }
+
     /// Set recursive processing.
     pub fn recursive(mut self, recursive: bool) -> Self {
         self.config.recursive = recursive;
         self
     }
 
+    /// Enable or disable project-relative display paths on file nodes.
+    pub fn relative_display_paths(mut self, enabled: bool) -> Self {
+        self.config.relative_display_paths = enabled;
+        self
+    }
+
+    /// Enable or disable propagating document confidence to containers.
+    pub fn propagate_confidence(mut self, enabled: bool) -> Self {
+        self.config.propagate_confidence = enabled;
+        self
+    }
+
     /// Set heuristic-only mode.
     pub fn heuristic_only(mut self) -> Self {
         self.llm_config.fallback_to_heuristic = true;
         self
     }
 
+    /// Set the node types created for folders of `domain`.
+    pub fn domain_override(mut self, domain: impl Into<String>, policy: NodeTypePolicy) -> Self {
+        self.config.domain_overrides.insert(domain.into(), policy);
+        self
+    }
+
     /// Set known domains.
     pub fn known_domains(mut self, domains: Vec<String>) -> Self {
         self.llm_config.known_domains = domains;
         self
     }
 
+    /// Set the prompt templates sent to the LLM client.
+    pub fn prompt_templates(mut self, prompts: PromptTemplates) -> Self {
+        self.llm_config.prompts = prompts;
+        self
+    }
+
     /// Use an existing tree.
     pub fn with_tree(mut self, tree: ContextTree) -> Self {
         self.tree = Some(tree);
@@ -584,6 +1588,7 @@ impl AgentBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::node::CrossLinkType;
     use std::fs;
     use tempfile::TempDir;
 
@@ -636,6 +1641,31 @@ mod tests {
         assert!(result.files_processed >= 2);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_process_folder_with_unreadable_file() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_project(temp_dir.path());
+        // Invalid UTF-8 fails to read whatever the caller's privileges
+        let locked = temp_dir.path().join("locked.rs");
+        fs::write(&locked, b"fn secret() {}\n\xff\xfe\n").unwrap();
+
+        let mut agent = ContextAgent::heuristic_only();
+        let result = agent.process_folder(temp_dir.path()).await.unwrap();
+
+        assert_eq!(result.domain, "coding");
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("locked.rs"));
+        assert!(result.files_processed >= 2);
+        assert!(agent.tree().get_by_path(&locked).is_none());
+        assert!(
+            agent
+                .tree()
+                .get_by_path(&temp_dir.path().join("src/main.rs"))
+                .is_some()
+        );
+    }
+
     #[tokio::test]
     async fn test_process_cooking_folder() {
         let temp_dir = TempDir::new().unwrap();
@@ -682,6 +1712,115 @@ mod tests {
         assert_eq!(stats.domains, 2);
     }
 
+    #[tokio::test]
+    async fn test_domain_node_type_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        let coding_dir = temp_dir.path().join("my-rust-app");
+        fs::create_dir_all(&coding_dir).unwrap();
+        create_test_project(&coding_dir);
+        let cooking_dir = temp_dir.path().join("recipes");
+        fs::create_dir_all(&cooking_dir).unwrap();
+        create_cooking_project(&cooking_dir);
+
+        let mut agent = AgentBuilder::new()
+            .heuristic_only()
+            .domain_override(
+                "cooking",
+                NodeTypePolicy {
+                    documents: true,
+                    file_refs: false,
+                },
+            )
+            .build();
+
+        // Coding keeps the default: a document and a file reference per file
+        let coding = agent.process_folder(&coding_dir).await.unwrap();
+        assert_eq!(coding.domain, "coding");
+        let after_coding = agent.stats();
+        assert_eq!(after_coding.documents, coding.files_processed);
+        assert_eq!(after_coding.files, coding.files_processed);
+
+        // Cooking creates only document nodes
+        let cooking = agent.process_folder(&cooking_dir).await.unwrap();
+        assert_eq!(cooking.domain, "cooking");
+        assert_eq!(cooking.nodes_created, cooking.files_processed + 1);
+        let after_cooking = agent.stats();
+        assert_eq!(
+            after_cooking.documents,
+            after_coding.documents + cooking.files_processed
+        );
+        assert_eq!(after_cooking.files, after_coding.files);
+
+        // The agent-wide switch still applies on top of the policy
+        let config = AgentConfig {
+            create_file_refs: false,
+            ..Default::default()
+        };
+        assert!(!config.node_types_for("coding").file_refs);
+        assert!(config.node_types_for("coding").documents);
+    }
+
+    #[tokio::test]
+    async fn test_codexignore() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_project(temp_dir.path());
+        fs::write(temp_dir.path().join("src/secrets.md"), "# Secrets\n").unwrap();
+        fs::write(temp_dir.path().join(".codexignore"), "secrets.md\n").unwrap();
+
+        let mut agent = ContextAgent::heuristic_only();
+        let result = agent.process_folder(temp_dir.path()).await.unwrap();
+
+        assert!(result.files_processed >= 2);
+        assert!(agent.tree().all_nodes().all(|node| {
+            node.path
+                .as_deref()
+                .is_none_or(|path| !path.ends_with("secrets.md"))
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_changed_since() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let coding_dir = temp_dir.path().join("my-rust-app");
+        fs::create_dir_all(&coding_dir).unwrap();
+        create_test_project(&coding_dir);
+
+        let cooking_dir = temp_dir.path().join("recipes");
+        fs::create_dir_all(&cooking_dir).unwrap();
+        create_cooking_project(&cooking_dir);
+
+        let mut agent = AgentBuilder::new()
+            .auto_cross_link(false)
+            .heuristic_only()
+            .build();
+
+        agent.process_folder(&coding_dir).await.unwrap();
+        let checkpoint = Utc::now();
+        agent.process_folder(&cooking_dir).await.unwrap();
+
+        let changed = agent.changed_since(checkpoint);
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|node| node.last_updated > checkpoint));
+        assert!(
+            changed
+                .windows(2)
+                .all(|pair| pair[0].last_updated >= pair[1].last_updated)
+        );
+
+        // Nothing from the first folder was modified by the second run.
+        assert!(changed.iter().all(|node| {
+            node.path
+                .as_ref()
+                .is_none_or(|path| !path.starts_with(&coding_dir))
+        }));
+        assert!(
+            changed
+                .iter()
+                .any(|node| node.path.as_deref() == Some(cooking_dir.join("pasta.md").as_path()))
+        );
+    }
+
     #[tokio::test]
     async fn test_query() {
         let temp_dir = TempDir::new().unwrap();
@@ -708,6 +1847,227 @@ mod tests {
         assert!(!nodes.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_heading_keywords() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("guide.md"),
+            "# Guide\n\nHow to set things up.\n\n## Installation\n\nRun the installer.\n\n\
+             ## Configuration\n\nEdit the settings file.\n\n#### Footnotes\n\nMinor details.\n",
+        )
+        .unwrap();
+
+        let mut agent = ContextAgent::heuristic_only();
+        agent.process_folder(temp_dir.path()).await.unwrap();
+
+        let doc = agent
+            .tree()
+            .all_nodes()
+            .find(|n| n.node_type == NodeType::Document)
+            .unwrap();
+        assert!(doc.keywords.contains(&"installation".to_string()));
+        assert!(doc.keywords.contains(&"configuration".to_string()));
+        assert!(!doc.keywords.contains(&"footnotes".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_analyzer_cross_links() {
+        let temp_dir = TempDir::new().unwrap();
+        for project in ["alpha", "beta"] {
+            let dir = temp_dir.path().join(project);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("guide.md"),
+                format!(
+                    "# {project}\n\nProject guide.\n\n## Installation\n\nRun the installer.\n\n\
+                     ## Configuration\n\nEdit the settings.\n\n## Deployment\n\nShip it.\n"
+                ),
+            )
+            .unwrap();
+        }
+
+        let mut agent = ContextAgent::heuristic_only();
+        agent
+            .process_folder(&temp_dir.path().join("alpha"))
+            .await
+            .unwrap();
+        let result = agent
+            .process_folder(&temp_dir.path().join("beta"))
+            .await
+            .unwrap();
+        assert!(result.cross_links_created >= 2);
+
+        let link = agent
+            .tree()
+            .all_nodes()
+            .flat_map(|n| &n.related_nodes)
+            .find(|r| r.relationship == CrossLinkType::SimilarTopic)
+            .unwrap();
+        assert!(link.reason.as_deref().unwrap().contains("installation"));
+
+        let mut agent = AgentBuilder::new()
+            .heuristic_only()
+            .min_cross_link_strength(0.9)
+            .build();
+        agent
+            .process_folder(&temp_dir.path().join("alpha"))
+            .await
+            .unwrap();
+        let result = agent
+            .process_folder(&temp_dir.path().join("beta"))
+            .await
+            .unwrap();
+        assert_eq!(result.cross_links_created, 0);
+    }
+
+    #[tokio::test]
+    async fn test_confidence_propagation() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("notes").join("docs");
+        fs::create_dir_all(&docs).unwrap();
+        fs::write(docs.join("a.rs"), "// misc\n").unwrap();
+        fs::write(docs.join("b.rs"), "// more misc\n").unwrap();
+
+        let project_confidence = |agent: &ContextAgent| {
+            agent
+                .tree()
+                .all_nodes()
+                .find(|n| n.node_type == NodeType::Project)
+                .unwrap()
+                .confidence
+        };
+
+        let mut agent = AgentBuilder::new()
+            .heuristic_only()
+            .propagate_confidence(false)
+            .build();
+        agent.process_folder(temp_dir.path()).await.unwrap();
+        let detected = project_confidence(&agent);
+
+        let mut agent = ContextAgent::heuristic_only();
+        agent.process_folder(temp_dir.path()).await.unwrap();
+        let doc_confidence = agent
+            .tree()
+            .all_nodes()
+            .find(|n| n.node_type == NodeType::Document)
+            .unwrap()
+            .confidence;
+
+        assert!(doc_confidence < detected);
+        assert_eq!(project_confidence(&agent), doc_confidence);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_domain() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut agent = ContextAgent::heuristic_only();
+        for project in ["alpha", "beta"] {
+            let dir = temp_dir.path().join(project);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+            agent.process_folder(&dir).await.unwrap();
+        }
+
+        let summary = agent.summarize_domain("coding").await.unwrap();
+        assert!(summary.contains("2 projects"), "{summary}");
+        assert!(
+            summary.contains("alpha") && summary.contains("beta"),
+            "{summary}"
+        );
+        assert_eq!(agent.tree().get_domain("coding").unwrap().summary, summary);
+
+        assert!(matches!(
+            agent.summarize_domain("cooking").await,
+            Err(ContextError::DomainNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_domain() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("thoughts.txt"), "Random musings.\n").unwrap();
+
+        let mut agent = ContextAgent::new(
+            AgentConfig::default(),
+            LlmConfig {
+                fallback_domain: Some("inbox".to_string()),
+                ..Default::default()
+            },
+        );
+        agent.process_folder(temp_dir.path()).await.unwrap();
+
+        assert!(agent.tree().get_domain("inbox").is_some());
+        assert!(agent.tree().get_domain("other").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_min_domain_confidence_rejects_folder() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes");
+        let code = temp_dir.path().join("code");
+        fs::create_dir_all(&notes).unwrap();
+        fs::create_dir_all(&code).unwrap();
+        fs::write(notes.join("thoughts.txt"), "Random musings.\n").unwrap();
+        fs::write(code.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let mut agent = ContextAgent::new(
+            AgentConfig::default(),
+            LlmConfig {
+                min_domain_confidence: 0.5,
+                ..Default::default()
+            },
+        );
+        let nodes_before = agent.stats().total_nodes;
+
+        let err = agent.process_folder(&notes).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ContextError::DomainConfidenceTooLow { ref domain, .. } if domain == "other"
+        ));
+        assert_eq!(agent.stats().total_nodes, nodes_before);
+
+        agent.process_folder(&code).await.unwrap();
+        assert!(agent.tree().get_domain("coding").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_relative_display_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        for module in ["parser", "storage"] {
+            let dir = temp_dir.path().join("src").join(module);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("mod.rs"), format!("//! The {module} module.\n")).unwrap();
+        }
+
+        let mut agent = ContextAgent::heuristic_only();
+        agent.process_folder(temp_dir.path()).await.unwrap();
+
+        let mut display_paths: Vec<&str> = agent
+            .tree()
+            .all_nodes()
+            .filter(|n| n.node_type == NodeType::Document)
+            .map(ContextNode::display_name)
+            .collect();
+        display_paths.sort_unstable();
+        assert_eq!(
+            display_paths,
+            vec!["src/parser/mod.rs", "src/storage/mod.rs"]
+        );
+
+        let mut agent = AgentBuilder::new()
+            .heuristic_only()
+            .relative_display_paths(false)
+            .build();
+        agent.process_folder(temp_dir.path()).await.unwrap();
+        assert!(
+            agent
+                .tree()
+                .all_nodes()
+                .filter(|n| n.node_type == NodeType::Document)
+                .all(|n| n.display_path.is_none() && n.display_name() == "mod.rs")
+        );
+    }
+
     #[test]
     fn test_agent_builder() {
         let agent = AgentBuilder::new()
@@ -735,6 +2095,322 @@ mod tests {
         assert!(!profile.summary.is_empty());
     }
 
+    #[test]
+    fn test_query_faceted_counts_all_matches() {
+        use crate::entity::{Entity, EntityType};
+
+        let mut tree = ContextTree::new();
+        let domains = [("coding", 18), ("work", 5), ("personal", 2)];
+        for (domain, count) in domains {
+            let domain_id = tree.ensure_domain(domain);
+            for i in 0..count {
+                let mut node = ContextNode::document(
+                    format!("{domain} notes {i}"),
+                    PathBuf::from(format!("/{domain}/notes-{i}.md")),
+                );
+                if domain == "coding" {
+                    node.add_entity(Entity::new("Rust", EntityType::Technology, 0.9));
+                }
+                tree.add_child(&domain_id, node).unwrap();
+            }
+        }
+
+        let agent = AgentBuilder::new().heuristic_only().with_tree(tree).build();
+        let faceted = agent.query_faceted("notes");
+
+        assert_eq!(faceted.total_matches, 25);
+        assert_eq!(faceted.result.nodes.len(), MAX_QUERY_RESULTS);
+        assert!(faceted.result.truncated);
+
+        assert_eq!(faceted.facets.domains.get("coding"), Some(&18));
+        assert_eq!(faceted.facets.domains.get("work"), Some(&5));
+        assert_eq!(faceted.facets.domains.get("personal"), Some(&2));
+        assert_eq!(
+            faceted.facets.node_types.get(&NodeType::Document),
+            Some(&25)
+        );
+        assert_eq!(
+            faceted.facets.top_entities.first(),
+            Some(&("rust".to_string(), 18))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_folder_sample_and_query_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let files: Vec<PathBuf> = (0..5)
+            .map(|i| {
+                let path = temp_dir.path().join(format!("note-{i}.md"));
+                fs::write(&path, format!("Sample note {i}\n")).unwrap();
+                path
+            })
+            .collect();
+
+        // The summary only includes the configured number of files
+        let analyzer = LlmAnalyzer::heuristic_only();
+        let mut summaries = Vec::new();
+        for count in [2, 4] {
+            let config = AgentConfig {
+                folder_sample_files: count,
+                ..Default::default()
+            };
+            let processor = FolderProcessor {
+                config: &config,
+                analyzer: &analyzer,
+            };
+            let (summary, _) = processor.analyze_folder_contents(&files).await;
+            assert_eq!(summary.matches("Sample note").count(), count);
+            summaries.push(summary);
+        }
+        assert_ne!(summaries[0], summaries[1]);
+
+        let mut agent = AgentBuilder::new()
+            .heuristic_only()
+            .folder_sample_files(3)
+            .query_result_limit(2)
+            .build();
+        let result = agent.process_folder(temp_dir.path()).await.unwrap();
+        assert_eq!(result.files_sampled, 3);
+        assert_eq!(result.files_processed, 5);
+
+        let query = agent.query("sample note");
+        assert_eq!(query.nodes.len(), 2);
+        assert!(query.truncated);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_shared_agent_concurrent_queries() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_project(temp_dir.path());
+        for i in 0..20 {
+            fs::write(
+                temp_dir.path().join(format!("notes-{i}.md")),
+                format!("# Notes {i}\n\nRust async notes using tokio.\n"),
+            )
+            .unwrap();
+        }
+
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        tree.add_child(
+            &domain_id,
+            ContextNode::project("existing-project", PathBuf::from("/existing"))
+                .with_summary("An existing project"),
+        )
+        .unwrap();
+        let agent = AgentBuilder::new()
+            .heuristic_only()
+            .with_tree(tree)
+            .build()
+            .into_shared();
+
+        let processor = {
+            let agent = agent.clone();
+            let path = temp_dir.path().to_path_buf();
+            tokio::spawn(async move { agent.process_folder(&path).await })
+        };
+
+        let queries: Vec<_> = (0..8)
+            .map(|_| {
+                let agent = agent.clone();
+                tokio::spawn(async move {
+                    for _ in 0..20 {
+                        let result = agent.query("existing").await;
+                        assert!(result.nodes.iter().any(|n| n.name == "existing-project"));
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+
+        let all = async {
+            for query in queries {
+                query.await.unwrap();
+            }
+            processor.await.unwrap()
+        };
+        let processed = tokio::time::timeout(std::time::Duration::from_secs(30), all)
+            .await
+            .expect("shared agent deadlocked")
+            .unwrap();
+
+        assert!(processed.files_processed >= 20);
+        let result = agent.query("notes").await;
+        assert!(!result.nodes.is_empty());
+        assert!(agent.stats().await.documents >= 20);
+    }
+
+    #[test]
+    fn test_query_min_score_drops_weak_matches() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        tree.add_child(
+            &domain_id,
+            ContextNode::project("rust-parser", PathBuf::from("/code/parser"))
+                .with_summary("Rust parser with error recovery"),
+        )
+        .unwrap();
+        tree.add_child(
+            &domain_id,
+            ContextNode::project("logger", PathBuf::from("/code/logger"))
+                .with_summary("Writes error logs to disk"),
+        )
+        .unwrap();
+
+        let agent = AgentBuilder::new().heuristic_only().with_tree(tree).build();
+
+        let result = agent.query("the rust parser error recovery");
+        let names: Vec<_> = result.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["rust-parser"]);
+
+        let lenient = AgentQueryOptions {
+            min_score: 0.0,
+            ..Default::default()
+        };
+        let result = agent.query_with_options("the rust parser error recovery", &lenient);
+        assert_eq!(result.nodes.len(), 2);
+
+        // A weak best match is still returned rather than nothing.
+        let result = agent.query("error handling guide");
+        assert_eq!(result.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_similar_nodes() {
+        let project = |name: &str, keywords: &[&str]| {
+            let mut node = ContextNode::project(name, PathBuf::from(format!("/code/{name}")));
+            for keyword in keywords {
+                node.add_keyword(*keyword);
+            }
+            node
+        };
+
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let server = tree
+            .add_child(
+                &domain_id,
+                project("server", &["async", "http", "tokio", "database", "rest"]),
+            )
+            .unwrap();
+        let gateway = tree
+            .add_child(
+                &domain_id,
+                project("gateway", &["async", "http", "tokio", "rest", "proxy"]),
+            )
+            .unwrap();
+        let renderer = tree
+            .add_child(
+                &domain_id,
+                project("renderer", &["graphics", "shaders", "http"]),
+            )
+            .unwrap();
+
+        let agent = AgentBuilder::new().heuristic_only().with_tree(tree).build();
+        let similar = agent.similar_nodes(&server, 10);
+
+        let ids: Vec<&str> = similar.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec![gateway.as_str(), renderer.as_str()]);
+        assert!(similar[0].1 > similar[1].1);
+        assert!(similar.iter().all(|(_, score)| (0.0..=1.0).contains(score)));
+
+        assert_eq!(agent.similar_nodes(&server, 1).len(), 1);
+        assert!(agent.similar_nodes("missing", 10).is_empty());
+    }
+
+    #[test]
+    fn test_related_to_file() {
+        use crate::entity::EntityType;
+
+        let doc = |name: &str, path: &str, entities: &[(&str, EntityType)]| {
+            let mut node = ContextNode::document(name, PathBuf::from(path));
+            for (entity, entity_type) in entities {
+                node.add_entity(Entity::new(*entity, *entity_type, 0.9));
+            }
+            node
+        };
+
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let mut add_project = |name: &str, dir: &str, file: ContextNode| {
+            let project_id = tree
+                .add_child(&domain_id, ContextNode::project(name, PathBuf::from(dir)))
+                .unwrap();
+            tree.add_child(&project_id, file).unwrap();
+        };
+        add_project(
+            "server",
+            "/code/server",
+            doc(
+                "main.rs",
+                "/code/server/main.rs",
+                &[
+                    ("tokio", EntityType::Technology),
+                    ("Alice Smith", EntityType::Person),
+                ],
+            ),
+        );
+        add_project(
+            "client",
+            "/code/client",
+            doc(
+                "lib.rs",
+                "/code/client/lib.rs",
+                &[("tokio", EntityType::Technology)],
+            ),
+        );
+        add_project(
+            "notes",
+            "/notes",
+            doc(
+                "todo.md",
+                "/notes/todo.md",
+                &[("Alice Smith", EntityType::Person)],
+            ),
+        );
+        add_project(
+            "recipes",
+            "/recipes",
+            doc("cake.md", "/recipes/cake.md", &[]),
+        );
+        tree.build_cross_links();
+
+        let agent = AgentBuilder::new().heuristic_only().with_tree(tree).build();
+        let related = agent.related_to_file(Path::new("/code/server/main.rs"), 10);
+
+        let paths: Vec<_> = related
+            .iter()
+            .map(|r| r.node.path.clone().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/code/client/lib.rs"),
+                PathBuf::from("/notes/todo.md")
+            ]
+        );
+        assert!(
+            related[0]
+                .reasons
+                .iter()
+                .any(|r| r.starts_with("Same Technology"))
+        );
+        assert!(related[0].score > related[1].score);
+        assert_eq!(related[1].reasons, vec!["Shared entities: Alice Smith"]);
+
+        assert_eq!(
+            agent
+                .related_to_file(Path::new("/code/server/main.rs"), 1)
+                .len(),
+            1
+        );
+        assert!(
+            agent
+                .related_to_file(Path::new("/missing.rs"), 10)
+                .is_empty()
+        );
+    }
+
     #[tokio::test]
     async fn test_nonexistent_path() {
         let mut agent = ContextAgent::heuristic_only();
@@ -5,7 +5,26 @@
 //! (paragraphs, sections) rather than fixed character counts.
 
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::BufRead;
+use std::ops::Range;
 use std::path::Path;
+use std::sync::Arc;
+
+use crate::id::{IdGenerator, default_id_generator};
+
+/// Multiple of the maximum chunk size a streamed block may grow to before it
+/// is cut at a line boundary, even inside a paragraph or code block.
+const STREAM_HARD_LIMIT_FACTOR: usize = 4;
+
+/// Common words ignored when scoring how representative a chunk is.
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "are", "but", "not", "you", "all", "any", "can", "has", "had", "was",
+    "were", "this", "that", "with", "from", "have", "into", "its", "they", "them", "then", "than",
+    "there", "their", "these", "those", "what", "when", "which", "while", "will", "would",
+    "should", "could", "also", "been", "being", "each", "only", "other", "some", "such", "very",
+    "more", "most", "our", "your", "about", "over", "out",
+];
 
 /// A chunk of text extracted from a document.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,7 +101,7 @@ impl Chunk {
 }
 
 /// Type of chunk content.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ChunkType {
     /// Full document.
@@ -142,6 +161,13 @@ pub struct ChunkerConfig {
 
     /// Whether to include section headers in chunks.
     pub include_headers: bool,
+
+    /// Whether to break paragraphs longer than `max_tokens` into
+    /// paragraph-sized pieces before splitting.
+    ///
+    /// Text without blank lines, such as minified code or single-line JSON,
+    /// otherwise forms one huge paragraph.
+    pub break_long_paragraphs: bool,
 }
 
 impl Default for ChunkerConfig {
@@ -153,6 +179,32 @@ impl Default for ChunkerConfig {
             min_tokens: 50,
             preserve_code_blocks: true,
             include_headers: true,
+            break_long_paragraphs: true,
+        }
+    }
+}
+
+impl ChunkerConfig {
+    /// Settings for source code: larger chunks so functions stay intact,
+    /// with little overlap.
+    pub fn code() -> Self {
+        Self {
+            target_tokens: 1024,
+            max_tokens: 2048,
+            overlap_fraction: 0.1,
+            min_tokens: 20,
+            ..Self::default()
+        }
+    }
+
+    /// Settings for prose: smaller chunks that follow paragraph boundaries.
+    pub fn prose() -> Self {
+        Self {
+            target_tokens: 256,
+            max_tokens: 512,
+            overlap_fraction: 0.2,
+            min_tokens: 25,
+            ..Self::default()
         }
     }
 }
@@ -166,19 +218,27 @@ impl Default for ChunkerConfig {
 /// 4. List boundaries
 pub struct SemanticChunker {
     config: ChunkerConfig,
+    id_generator: Arc<dyn IdGenerator>,
 }
 
 impl SemanticChunker {
     /// Create a new chunker with default configuration.
     pub fn new() -> Self {
-        Self {
-            config: ChunkerConfig::default(),
-        }
+        Self::with_config(ChunkerConfig::default())
     }
 
     /// Create a chunker with custom configuration.
     pub fn with_config(config: ChunkerConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            id_generator: default_id_generator(),
+        }
+    }
+
+    /// Set the generator used for chunk IDs.
+    pub fn with_id_generator(mut self, id_generator: Arc<dyn IdGenerator>) -> Self {
+        self.id_generator = id_generator;
+        self
     }
 
     /// Chunk a document from a file path.
@@ -189,10 +249,21 @@ impl SemanticChunker {
     }
 
     /// Chunk text content with a source identifier.
+    ///
+    /// Chunks of source files in a recognized programming language are
+    /// marked as code and tagged with that language. This covers every
+    /// chunk of such a file, doc comments and other prose included, so
+    /// relationship `chunk_type_weights` treat all of its co-occurrences as
+    /// code.
     pub fn chunk_with_source(&self, content: &str, source: &str) -> Vec<Chunk> {
+        let language = source_language(source);
         let mut chunks = self.chunk(content);
         for chunk in &mut chunks {
             chunk.source = Some(source.to_string());
+            if let Some(language) = language {
+                chunk.chunk_type = ChunkType::Code;
+                chunk.metadata.language = Some(language.to_string());
+            }
         }
         chunks
     }
@@ -218,6 +289,67 @@ impl SemanticChunker {
         chunks
     }
 
+    /// Chunk text streamed from `reader`, without reading it all into memory.
+    ///
+    /// Lines are buffered into blocks that end at a blank line outside a code
+    /// block once they reach the maximum chunk size, and each block is
+    /// chunked like `chunk` would. Blocks that grow to several times that
+    /// size are cut at the next line instead, so very long paragraphs or
+    /// code blocks may be split where `chunk` would not. Invalid UTF-8 is
+    /// replaced with U+FFFD, and offsets are byte positions in that decoded
+    /// text, so they always match the chunk contents; for valid UTF-8 they
+    /// are byte positions in the stream. Reading stops at the first I/O
+    /// error, which is available from `ChunkReader::take_error`.
+    pub fn chunk_reader<R: BufRead>(&self, reader: R) -> ChunkReader<'_, R> {
+        ChunkReader {
+            chunker: self,
+            reader,
+            buffer: String::new(),
+            offset: 0,
+            in_code_block: false,
+            pending: VecDeque::new(),
+            previous: None,
+            error: None,
+            done: false,
+        }
+    }
+
+    /// Chunk text content and return the `n` chunks most representative of it.
+    ///
+    /// Each chunk is scored by how densely it uses the document's recurring
+    /// terms: the average document-wide frequency of its words, ignoring
+    /// stop words. Ties keep document order.
+    pub fn top_chunks(&self, content: &str, n: usize) -> Vec<Chunk> {
+        let chunks = self.chunk(content);
+
+        let mut frequencies: HashMap<String, usize> = HashMap::new();
+        for chunk in &chunks {
+            for term in key_terms(&chunk.content) {
+                *frequencies.entry(term).or_default() += 1;
+            }
+        }
+
+        let mut scored: Vec<(f32, Chunk)> = chunks
+            .into_iter()
+            .filter_map(|chunk| {
+                let terms = key_terms(&chunk.content);
+                if terms.is_empty() {
+                    return None;
+                }
+                let total: usize = terms
+                    .iter()
+                    .map(|t| frequencies.get(t).copied().unwrap_or_default())
+                    .sum();
+                Some((total as f32 / terms.len() as f32, chunk))
+            })
+            .collect();
+
+        // Stable sort, so equally scored chunks stay in document order
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(n);
+        scored.into_iter().map(|(_, chunk)| chunk).collect()
+    }
+
     /// Parse document structure into elements.
     fn parse_structure(&self, content: &str) -> Vec<StructuralElement> {
         let mut elements = Vec::new();
@@ -308,17 +440,18 @@ impl SemanticChunker {
             // Regular paragraph - collect until empty line or structural element
             let (paragraph, lines_consumed) = self.extract_paragraph(&lines[i..]);
             if !paragraph.trim().is_empty() {
-                let end_offset = current_offset + paragraph.len();
-                elements.push(StructuralElement {
-                    content: paragraph,
-                    element_type: ChunkType::Paragraph,
-                    start_offset: current_offset,
-                    end_offset,
-                    metadata: ChunkMetadata {
-                        line_number: Some(i + 1),
-                        ..Default::default()
-                    },
-                });
+                for piece in self.break_paragraph(&paragraph) {
+                    elements.push(StructuralElement {
+                        content: paragraph[piece.clone()].to_string(),
+                        element_type: ChunkType::Paragraph,
+                        start_offset: current_offset + piece.start,
+                        end_offset: current_offset + piece.end,
+                        metadata: ChunkMetadata {
+                            line_number: Some(i + 1),
+                            ..Default::default()
+                        },
+                    });
+                }
             }
 
             for _ in 0..lines_consumed {
@@ -430,6 +563,46 @@ impl SemanticChunker {
         (content.trim_end().to_string(), count.max(1))
     }
 
+    /// Byte ranges of `paragraph` to emit as separate paragraphs.
+    ///
+    /// Paragraphs within `max_tokens` (or all paragraphs, when
+    /// `break_long_paragraphs` is off) are kept whole. Longer ones are broken
+    /// into pieces of at most `max_tokens`, after the last whitespace in
+    /// each piece where there is one.
+    fn break_paragraph(&self, paragraph: &str) -> Vec<Range<usize>> {
+        let max_bytes = self.config.max_tokens.saturating_mul(4);
+        if !self.config.break_long_paragraphs || paragraph.len() <= max_bytes {
+            return vec![Range {
+                start: 0,
+                end: paragraph.len(),
+            }];
+        }
+
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < paragraph.len() {
+            let mut end = (start + max_bytes).min(paragraph.len());
+            while !paragraph.is_char_boundary(end) {
+                end -= 1;
+            }
+            if end < paragraph.len()
+                && let Some((pos, ws)) = paragraph[start..end]
+                    .char_indices()
+                    .rfind(|(_, c)| c.is_whitespace())
+                && pos > 0
+            {
+                end = start + pos + ws.len_utf8();
+            }
+            if end == start {
+                // A single character wider than the limit; emit it whole
+                end = start + paragraph[start..].chars().next().map_or(1, char::len_utf8);
+            }
+            pieces.push(start..end);
+            start = end;
+        }
+        pieces
+    }
+
     /// Chunk a structural element into appropriately sized chunks.
     fn chunk_element(&self, element: &StructuralElement) -> Vec<Chunk> {
         let estimated_tokens = element.content.len() / 4;
@@ -437,7 +610,7 @@ impl SemanticChunker {
         // If element fits in target size, return as single chunk
         if estimated_tokens <= self.config.max_tokens {
             return vec![Chunk {
-                id: uuid::Uuid::new_v4().to_string(),
+                id: self.id_generator.next_id(),
                 content: element.content.clone(),
                 source: None,
                 chunk_type: element.element_type,
@@ -484,10 +657,10 @@ impl SemanticChunker {
         let estimated_tokens = text.len() / 4;
 
         // If small enough, add as chunk
-        if estimated_tokens <= self.config.max_tokens || sep_index >= separators.len() {
+        if estimated_tokens <= self.config.max_tokens {
             if estimated_tokens >= self.config.min_tokens {
                 chunks.push(Chunk {
-                    id: uuid::Uuid::new_v4().to_string(),
+                    id: self.id_generator.next_id(),
                     content: text.to_string(),
                     source: None,
                     chunk_type,
@@ -500,6 +673,12 @@ impl SemanticChunker {
             return;
         }
 
+        // Out of separators and still too large: split at character boundaries
+        if sep_index >= separators.len() {
+            self.split_hard(text, base_offset, chunk_type, metadata, chunks);
+            return;
+        }
+
         // Split by current separator
         let separator = separators[sep_index];
         let parts: Vec<&str> = text.split(separator).collect();
@@ -568,32 +747,171 @@ impl SemanticChunker {
         }
     }
 
+    /// Split text with no usable separators into `max_tokens`-sized pieces.
+    fn split_hard(
+        &self,
+        text: &str,
+        base_offset: usize,
+        chunk_type: ChunkType,
+        metadata: &ChunkMetadata,
+        chunks: &mut Vec<Chunk>,
+    ) {
+        let max_bytes = self.config.max_tokens.saturating_mul(4);
+        let mut start = 0;
+
+        while start < text.len() {
+            let mut end = (start + max_bytes).min(text.len());
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            if end == start {
+                // A single character wider than the limit; emit it whole
+                end = start + text[start..].chars().next().map_or(1, char::len_utf8);
+            }
+
+            chunks.push(Chunk {
+                id: self.id_generator.next_id(),
+                content: text[start..end].to_string(),
+                source: None,
+                chunk_type,
+                start_offset: base_offset + start,
+                end_offset: base_offset + end,
+                parent_id: None,
+                metadata: metadata.clone(),
+            });
+            start = end;
+        }
+    }
+
     /// Apply overlap between chunks.
     fn apply_overlap(&self, chunks: Vec<Chunk>) -> Vec<Chunk> {
         if chunks.len() <= 1 {
             return chunks;
         }
 
+        let mut result: Vec<Chunk> = Vec::with_capacity(chunks.len());
+
+        for mut chunk in chunks {
+            // Get overlap from previous chunk
+            if let Some(prev) = result.last() {
+                self.overlap_from(&prev.content, &mut chunk);
+            }
+            result.push(chunk);
+        }
+
+        result
+    }
+
+    /// Prefix `chunk` with the tail of the chunk before it.
+    fn overlap_from(&self, prev: &str, chunk: &mut Chunk) {
         let overlap_chars =
             (self.config.target_tokens as f32 * 4.0 * self.config.overlap_fraction) as usize;
+        if overlap_chars == 0 || prev.len() <= overlap_chars {
+            return;
+        }
 
-        let mut result: Vec<Chunk> = Vec::with_capacity(chunks.len());
+        let mut start = prev.len() - overlap_chars;
+        while !prev.is_char_boundary(start) {
+            start += 1;
+        }
+        chunk.content = format!("{}{}", &prev[start..], chunk.content);
+        chunk.metadata.is_continuation = true;
+    }
+}
 
-        for (i, mut chunk) in chunks.into_iter().enumerate() {
-            if i > 0 && overlap_chars > 0 {
-                // Get overlap from previous chunk
-                if let Some(prev) = result.last() {
-                    if prev.content.len() > overlap_chars {
-                        let overlap = &prev.content[prev.content.len() - overlap_chars..];
-                        chunk.content = format!("{}{}", overlap, chunk.content);
-                        chunk.metadata.is_continuation = true;
+/// Iterator over chunks of streamed text, created by
+/// [`SemanticChunker::chunk_reader`].
+pub struct ChunkReader<'a, R> {
+    chunker: &'a SemanticChunker,
+    reader: R,
+    /// Lines read but not yet chunked.
+    buffer: String,
+    /// Offset in the decoded text just past the last line read.
+    offset: usize,
+    /// Whether the last line read is inside a fenced code block.
+    in_code_block: bool,
+    /// Chunks of the last block, not yet yielded.
+    pending: VecDeque<Chunk>,
+    /// Content of the last yielded chunk, for overlap.
+    previous: Option<String>,
+    error: Option<std::io::Error>,
+    done: bool,
+}
+
+impl<R: BufRead> ChunkReader<'_, R> {
+    /// Take the I/O error that ended the stream early, if any.
+    pub fn take_error(&mut self) -> Option<std::io::Error> {
+        self.error.take()
+    }
+
+    /// Read the next block of lines and chunk it into `pending`.
+    fn read_block(&mut self) {
+        let block_start = self.offset;
+        let soft_limit = self.chunker.config.max_tokens.saturating_mul(4);
+        let hard_limit = soft_limit.saturating_mul(STREAM_HARD_LIMIT_FACTOR);
+
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            match self.reader.read_until(b'\n', &mut line) {
+                Ok(0) => {
+                    self.done = true;
+                    break;
+                }
+                Ok(_) => {
+                    let text = String::from_utf8_lossy(&line);
+                    self.offset += text.len();
+                    if text.trim().starts_with("```") {
+                        self.in_code_block = !self.in_code_block;
                     }
+                    self.buffer.push_str(&text);
+
+                    let at_boundary = text.trim().is_empty() && !self.in_code_block;
+                    if (at_boundary && self.buffer.len() >= soft_limit)
+                        || self.buffer.len() >= hard_limit
+                    {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.error = Some(e);
+                    self.done = true;
+                    break;
                 }
             }
-            result.push(chunk);
         }
 
-        result
+        let block = std::mem::take(&mut self.buffer);
+        for element in self.chunker.parse_structure(&block) {
+            for mut chunk in self.chunker.chunk_element(&element) {
+                chunk.start_offset += block_start;
+                chunk.end_offset += block_start;
+                self.pending.push_back(chunk);
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ChunkReader<'_, R> {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        while self.pending.is_empty() {
+            if self.done {
+                return None;
+            }
+            self.read_block();
+        }
+
+        let mut chunk = self.pending.pop_front()?;
+        if self.chunker.config.overlap_fraction > 0.0 {
+            if let Some(prev) = &self.previous {
+                self.chunker.overlap_from(prev, &mut chunk);
+            }
+            self.previous = Some(chunk.content.clone());
+        }
+        Some(chunk)
     }
 }
 
@@ -612,6 +930,30 @@ struct StructuralElement {
     metadata: ChunkMetadata,
 }
 
+/// Get the programming language of a source file from its extension.
+fn source_language(source: &str) -> Option<&'static str> {
+    let extension = Path::new(source).extension()?.to_str()?.to_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "go" => "go",
+        "java" => "java",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Split text into lowercase words worth scoring, skipping stop words.
+fn key_terms(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.len() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -651,6 +993,22 @@ mod tests {
         assert_eq!(code_chunks[0].metadata.language, Some("rust".to_string()));
     }
 
+    #[test]
+    fn test_source_files_are_code() {
+        let chunker = SemanticChunker::new();
+
+        let chunks = chunker.chunk_with_source("# Setup\n\nimport os\n", "tools/setup.py");
+        assert!(chunks.iter().all(|c| c.chunk_type == ChunkType::Code));
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.metadata.language.as_deref() == Some("python"))
+        );
+
+        let chunks = chunker.chunk_with_source("# Setup\n\nRun it.\n", "docs/setup.md");
+        assert!(chunks.iter().all(|c| c.chunk_type != ChunkType::Code));
+    }
+
     #[test]
     fn test_chunk_list() {
         let chunker = SemanticChunker::new();
@@ -664,6 +1022,56 @@ mod tests {
         assert_eq!(list_chunks.len(), 1);
     }
 
+    #[test]
+    fn test_split_without_separators() {
+        // Overlap is added after splitting, so disable it to check the split itself
+        let chunker = SemanticChunker::with_config(ChunkerConfig {
+            overlap_fraction: 0.0,
+            ..ChunkerConfig::default()
+        });
+        let text = "aé".repeat(25_000);
+
+        let chunks = chunker.chunk(&text);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.within_limit(1024)));
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|c| c.content.as_str())
+                .collect::<String>(),
+            text
+        );
+    }
+
+    #[test]
+    fn test_long_line_broken_into_paragraphs() {
+        let chunker = SemanticChunker::with_config(ChunkerConfig {
+            overlap_fraction: 0.0,
+            ..ChunkerConfig::default()
+        });
+        // Minified code: one 100k-char line with no blank lines
+        let text = "x=1;".repeat(25_000);
+
+        let chunks = chunker.chunk(&text);
+        assert!(chunks.len() >= 100_000 / (1024 * 4));
+        for chunk in &chunks {
+            assert_eq!(chunk.chunk_type, ChunkType::Paragraph);
+            assert!(chunk.within_limit(1024));
+            assert_eq!(&text[chunk.start_offset..chunk.end_offset], chunk.content);
+        }
+
+        // Breaks fall after whitespace when there is some
+        let prose = "word ".repeat(20_000);
+        let chunks = chunker.chunk(&prose);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.within_limit(1024)));
+        assert!(
+            chunks[..chunks.len() - 1]
+                .iter()
+                .all(|c| c.content.ends_with(' '))
+        );
+    }
+
     #[test]
     fn test_estimated_tokens() {
         let chunk = Chunk::new("This is a test with about 40 characters.", ChunkType::Text);
@@ -679,4 +1087,107 @@ mod tests {
         assert_eq!(chunker.detect_header_level("Not a header"), None);
         assert_eq!(chunker.detect_header_level("#hashtag"), None);
     }
+
+    #[test]
+    fn test_chunk_reader_streams() {
+        use std::cell::Cell;
+        use std::io::{BufReader, Read};
+        use std::rc::Rc;
+
+        /// Log of ten-line paragraphs, generated as it is read.
+        struct GeneratedLog {
+            paragraphs: usize,
+            next: usize,
+            pending: VecDeque<u8>,
+            bytes_read: Rc<Cell<usize>>,
+        }
+
+        impl Read for GeneratedLog {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.pending.is_empty() && self.next < self.paragraphs {
+                    for line in 0..10 {
+                        let text = format!("entry {:05} line {line} ", self.next);
+                        self.pending.extend(format!("{text:x<99}\n").bytes());
+                    }
+                    self.pending.push_back(b'\n');
+                    self.next += 1;
+                }
+                let n = buf.len().min(self.pending.len());
+                for (slot, byte) in buf.iter_mut().zip(self.pending.drain(..n)) {
+                    *slot = byte;
+                }
+                self.bytes_read.set(self.bytes_read.get() + n);
+                Ok(n)
+            }
+        }
+
+        const PARAGRAPHS: usize = 2000;
+        const PARAGRAPH_STRIDE: usize = 10 * 100 + 1;
+        let bytes_read = Rc::new(Cell::new(0));
+        let log = GeneratedLog {
+            paragraphs: PARAGRAPHS,
+            next: 0,
+            pending: VecDeque::new(),
+            bytes_read: Rc::clone(&bytes_read),
+        };
+        let chunker = SemanticChunker::new();
+        // One hard-limit block plus the reader's buffer
+        let max_read_ahead =
+            ChunkerConfig::default().max_tokens * 4 * STREAM_HARD_LIMIT_FACTOR + 8 * 1024;
+
+        let mut count = 0;
+        for (i, chunk) in chunker.chunk_reader(BufReader::new(log)).enumerate() {
+            assert_eq!(chunk.start_offset, i * PARAGRAPH_STRIDE);
+            assert_eq!(chunk.end_offset, chunk.start_offset + PARAGRAPH_STRIDE - 2);
+            assert!(bytes_read.get() - chunk.start_offset <= max_read_ahead);
+            count += 1;
+        }
+        assert_eq!(count, PARAGRAPHS);
+        assert_eq!(bytes_read.get(), PARAGRAPHS * PARAGRAPH_STRIDE);
+    }
+
+    #[test]
+    fn test_chunk_reader_offsets_with_invalid_utf8() {
+        let chunker = SemanticChunker::with_config(ChunkerConfig {
+            max_tokens: 16,
+            overlap_fraction: 0.0,
+            ..Default::default()
+        });
+        let mut bytes = Vec::new();
+        for paragraph in 0..4 {
+            bytes.extend_from_slice(format!("Paragraph {paragraph} caf").as_bytes());
+            bytes.extend_from_slice(b"\xe9 \xff\xfe menu with enough text to fill a block.\n\n");
+        }
+        let decoded = String::from_utf8_lossy(&bytes);
+
+        let chunks: Vec<Chunk> = chunker.chunk_reader(bytes.as_slice()).collect();
+        assert!(chunks.len() >= 4);
+        for chunk in &chunks {
+            assert_eq!(
+                &decoded[chunk.start_offset..chunk.end_offset],
+                chunk.content
+            );
+        }
+    }
+
+    #[test]
+    fn test_top_chunks() {
+        let chunker = SemanticChunker::with_config(ChunkerConfig {
+            overlap_fraction: 0.0,
+            ..Default::default()
+        });
+        let text = "Lunch was pleasant yesterday, everyone enjoyed sunny weather outside.\n\n\
+                    The cache evicts stale cache entries; cache eviction keeps the cache small \
+                    and cache lookups fast.\n\n\
+                    Configure the cache size in settings.\n\n\
+                    Parking opens early on weekdays.";
+
+        let top = chunker.top_chunks(text, 2);
+        assert_eq!(top.len(), 2);
+        assert!(top[0].content.starts_with("The cache evicts"));
+        assert!(top[1].content.starts_with("Configure the cache"));
+
+        assert_eq!(chunker.top_chunks(text, 10).len(), 4);
+        assert!(chunker.top_chunks(text, 0).is_empty());
+    }
 }
@@ -0,0 +1,453 @@
+//! Structured extraction from configuration and manifest files.
+//!
+//! Manifests such as `Cargo.toml` and `package.json` state a project's
+//! name, version, authors, and dependencies exactly. Rather than running
+//! prose-oriented pattern matching over them, `ConfigFileParser` parses the
+//! file and maps its fields to entities and relationships directly.
+
+use std::path::Path;
+
+use serde_json::Value;
+use tracing::debug;
+
+use crate::chunker::Chunk;
+use crate::entity::{Entity, EntityMention, EntityType};
+use crate::relationship::{EvidenceType, Relationship, RelationshipEvidence, RelationshipType};
+
+/// Confidence of entities and relationships read from a manifest.
+const MANIFEST_CONFIDENCE: f32 = 0.95;
+
+/// Supported configuration file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// Rust `Cargo.toml` manifest.
+    Cargo,
+    /// Node.js `package.json` manifest.
+    PackageJson,
+    /// Python `pyproject.toml` (PEP 621 or Poetry).
+    PyProject,
+    /// YAML file with top-level `name`, `version`, and `dependencies` keys
+    /// (e.g. `pubspec.yaml`, conda `environment.yml`).
+    Yaml,
+}
+
+/// Entities and relationships parsed from a configuration file.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedConfig {
+    /// Project, version, author, URL, and dependency entities.
+    pub entities: Vec<Entity>,
+
+    /// Relationships from the project to its version, authors, and dependencies.
+    pub relationships: Vec<Relationship>,
+}
+
+/// Metadata common to the supported manifest formats.
+#[derive(Debug, Default)]
+struct Manifest {
+    name: Option<String>,
+    version: Option<String>,
+    authors: Vec<String>,
+    urls: Vec<String>,
+    dependencies: Vec<String>,
+}
+
+/// Parser for structured metadata in configuration files.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigFileParser {
+    format: ConfigFormat,
+}
+
+impl ConfigFileParser {
+    /// Create a parser for the given format.
+    pub fn new(format: ConfigFormat) -> Self {
+        Self { format }
+    }
+
+    /// Select a parser from a file's name or extension.
+    pub fn for_source(source: &str) -> Option<Self> {
+        let path = Path::new(source);
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        let format = match file_name.as_str() {
+            "cargo.toml" => ConfigFormat::Cargo,
+            "package.json" => ConfigFormat::PackageJson,
+            "pyproject.toml" => ConfigFormat::PyProject,
+            _ => match path.extension()?.to_str()?.to_lowercase().as_str() {
+                "yaml" | "yml" => ConfigFormat::Yaml,
+                _ => return None,
+            },
+        };
+        Some(Self::new(format))
+    }
+
+    /// Get the format this parser reads.
+    pub fn format(&self) -> ConfigFormat {
+        self.format
+    }
+
+    /// Parse `content`, attributing mentions to the chunk containing each value.
+    ///
+    /// Returns `None` if the file cannot be parsed or names no project, so
+    /// callers can fall back to prose extraction.
+    pub fn parse(&self, content: &str, chunks: &[Chunk]) -> Option<ParsedConfig> {
+        let value = match self.format {
+            ConfigFormat::Cargo | ConfigFormat::PyProject => toml::from_str::<Value>(content).ok(),
+            ConfigFormat::PackageJson => serde_json::from_str(content).ok(),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).ok(),
+        };
+        let Some(value) = value else {
+            debug!("Failed to parse {:?} file", self.format);
+            return None;
+        };
+
+        let manifest = match self.format {
+            ConfigFormat::Cargo => cargo_manifest(&value),
+            ConfigFormat::PackageJson => package_json_manifest(&value),
+            ConfigFormat::PyProject => pyproject_manifest(&value),
+            ConfigFormat::Yaml => yaml_manifest(&value),
+        };
+        let name = manifest.name.as_deref()?;
+
+        let mut parsed = ParsedConfig::default();
+        let project = entity(name, EntityType::Project, chunks);
+
+        let mut related = Vec::new();
+        if let Some(version) = &manifest.version {
+            related.push((
+                entity(version, EntityType::Version, chunks),
+                RelationshipType::VersionOf,
+                "version",
+            ));
+        }
+        for author in &manifest.authors {
+            related.push((
+                entity(author, EntityType::Person, chunks),
+                RelationshipType::CreatedBy,
+                "authors",
+            ));
+        }
+        for url in &manifest.urls {
+            related.push((
+                entity(url, EntityType::Url, chunks),
+                RelationshipType::References,
+                "repository",
+            ));
+        }
+        for dependency in &manifest.dependencies {
+            related.push((
+                entity(dependency, EntityType::Technology, chunks),
+                RelationshipType::DependsOn,
+                "dependencies",
+            ));
+        }
+
+        for (target, relationship_type, field) in related {
+            // Versions point at what they version; everything else hangs
+            // off the project.
+            let mut rel = if relationship_type == RelationshipType::VersionOf {
+                Relationship::new(&target, &project, relationship_type, MANIFEST_CONFIDENCE)
+            } else {
+                Relationship::new(&project, &target, relationship_type, MANIFEST_CONFIDENCE)
+            };
+            rel.add_evidence(RelationshipEvidence {
+                evidence_type: EvidenceType::Structural,
+                text: format!("{field}: {}", target.name),
+                chunk_id: target.mentions.first().map(|m| m.chunk_id.clone()),
+                confidence_contribution: MANIFEST_CONFIDENCE,
+            });
+            parsed.relationships.push(rel);
+            parsed.entities.push(target);
+        }
+        parsed.entities.insert(0, project);
+
+        Some(parsed)
+    }
+}
+
+/// Create an entity mentioned in the first chunk containing `name`.
+fn entity(name: &str, entity_type: EntityType, chunks: &[Chunk]) -> Entity {
+    let mut entity = Entity::new(name, entity_type, MANIFEST_CONFIDENCE);
+    let found = chunks
+        .iter()
+        .find_map(|chunk| chunk.content.find(name).map(|position| (chunk, position)));
+    if let Some((chunk, position)) = found.or_else(|| chunks.first().map(|chunk| (chunk, 0))) {
+        entity.add_mention(EntityMention {
+            chunk_id: chunk.id.clone(),
+            position,
+            matched_text: name.to_string(),
+            context: None,
+            source: chunk.source.clone(),
+        });
+    }
+    entity
+}
+
+fn cargo_manifest(value: &Value) -> Manifest {
+    let package = &value["package"];
+    let mut dependencies = table_keys(&value["dependencies"]);
+    dependencies.extend(table_keys(&value["build-dependencies"]));
+    dependencies.extend(table_keys(&value["workspace"]["dependencies"]));
+    Manifest {
+        name: string(&package["name"]),
+        version: string(&package["version"]),
+        authors: strings(&package["authors"])
+            .iter()
+            .map(|a| person(a))
+            .collect(),
+        urls: [&package["repository"], &package["homepage"]]
+            .into_iter()
+            .filter_map(string)
+            .collect(),
+        dependencies: dedup(dependencies),
+    }
+}
+
+fn package_json_manifest(value: &Value) -> Manifest {
+    let mut dependencies = table_keys(&value["dependencies"]);
+    dependencies.extend(table_keys(&value["peerDependencies"]));
+    let authors = std::iter::once(&value["author"])
+        .chain(value["contributors"].as_array().into_iter().flatten())
+        .filter_map(|author| string(author).or_else(|| string(&author["name"])))
+        .map(|author| person(&author))
+        .collect();
+    let repository = string(&value["repository"]).or_else(|| string(&value["repository"]["url"]));
+    Manifest {
+        name: string(&value["name"]),
+        version: string(&value["version"]),
+        authors,
+        urls: repository
+            .into_iter()
+            .chain(string(&value["homepage"]))
+            .collect(),
+        dependencies: dedup(dependencies),
+    }
+}
+
+fn pyproject_manifest(value: &Value) -> Manifest {
+    let project = &value["project"];
+    let poetry = &value["tool"]["poetry"];
+    let mut dependencies: Vec<String> = strings(&project["dependencies"])
+        .iter()
+        .map(|requirement| requirement_name(requirement))
+        .collect();
+    dependencies.extend(
+        table_keys(&poetry["dependencies"])
+            .into_iter()
+            .filter(|name| name != "python"),
+    );
+    let authors = project["authors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|author| string(&author["name"]))
+        .chain(strings(&poetry["authors"]).iter().map(|a| person(a)))
+        .collect();
+    Manifest {
+        name: string(&project["name"]).or_else(|| string(&poetry["name"])),
+        version: string(&project["version"]).or_else(|| string(&poetry["version"])),
+        authors,
+        urls: string(&poetry["repository"]).into_iter().collect(),
+        dependencies: dedup(dependencies),
+    }
+}
+
+fn yaml_manifest(value: &Value) -> Manifest {
+    // Dependencies are a map (pubspec) or a list of requirement strings (conda).
+    let dependencies = match &value["dependencies"] {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(string)
+            .map(|requirement| requirement_name(&requirement))
+            .collect(),
+        other => table_keys(other),
+    };
+    // Plenty of YAML files (CI workflows, compose files) have a `name` but
+    // are not manifests; only treat files that declare dependencies as one.
+    let name = if value["dependencies"].is_null() {
+        None
+    } else {
+        string(&value["name"])
+    };
+    Manifest {
+        name,
+        version: string(&value["version"]),
+        authors: Vec::new(),
+        urls: string(&value["repository"]).into_iter().collect(),
+        dependencies: dedup(dependencies),
+    }
+}
+
+/// Get a non-empty string value.
+fn string(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Get the non-empty strings in an array value.
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(string)
+        .collect()
+}
+
+/// Get the keys of a table value.
+fn table_keys(value: &Value) -> Vec<String> {
+    value
+        .as_object()
+        .map(|table| table.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Strip an email address from an author such as `Name <name@example.com>`.
+fn person(author: &str) -> String {
+    author
+        .split('<')
+        .next()
+        .unwrap_or(author)
+        .trim()
+        .to_string()
+}
+
+/// Get the package name from a requirement such as `requests>=2.0`.
+fn requirement_name(requirement: &str) -> String {
+    requirement
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .next()
+        .unwrap_or(requirement)
+        .to_string()
+}
+
+/// Remove duplicate names, keeping the first occurrence.
+fn dedup(names: Vec<String>) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    names
+        .into_iter()
+        .filter(|name| !name.is_empty() && seen.insert(name.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::SemanticChunker;
+    use pretty_assertions::assert_eq;
+
+    const CARGO_TOML: &str = r#"[package]
+name = "dataflow"
+version = "0.1.0"
+authors = ["Alice Johnson <alice@techcorp.com>"]
+repository = "https://github.com/techcorp/dataflow"
+
+[dependencies]
+tokio = { version = "1.35", features = ["full"] }
+serde = "1.0"
+
+[dev-dependencies]
+pretty_assertions = "1.4"
+"#;
+
+    fn parse(source: &str, content: &str) -> ParsedConfig {
+        let chunks = SemanticChunker::new().chunk_with_source(content, source);
+        ConfigFileParser::for_source(source)
+            .unwrap()
+            .parse(content, &chunks)
+            .unwrap()
+    }
+
+    fn names(parsed: &ParsedConfig, entity_type: EntityType) -> Vec<&str> {
+        let mut names: Vec<&str> = parsed
+            .entities
+            .iter()
+            .filter(|e| e.entity_type == entity_type)
+            .map(|e| e.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn test_cargo_toml() {
+        let parsed = parse("/repo/Cargo.toml", CARGO_TOML);
+
+        assert_eq!(names(&parsed, EntityType::Project), vec!["dataflow"]);
+        assert_eq!(names(&parsed, EntityType::Version), vec!["0.1.0"]);
+        assert_eq!(names(&parsed, EntityType::Person), vec!["Alice Johnson"]);
+        assert_eq!(
+            names(&parsed, EntityType::Technology),
+            vec!["serde", "tokio"]
+        );
+
+        let mut depends_on: Vec<(&str, &str)> = parsed
+            .relationships
+            .iter()
+            .filter(|r| r.relationship_type == RelationshipType::DependsOn)
+            .map(|r| (r.source_name.as_str(), r.target_name.as_str()))
+            .collect();
+        depends_on.sort_unstable();
+        assert_eq!(
+            depends_on,
+            vec![("dataflow", "serde"), ("dataflow", "tokio")]
+        );
+
+        let project = &parsed.entities[0];
+        let entity_ids: Vec<_> = parsed.entities.iter().map(|e| &e.id).collect();
+        for rel in &parsed.relationships {
+            let ids = [&rel.source_id, &rel.target_id];
+            assert!(ids.iter().all(|id| entity_ids.contains(id)));
+            assert!(ids.contains(&&project.id));
+        }
+
+        let tokio = parsed.entities.iter().find(|e| e.name == "tokio").unwrap();
+        assert_eq!(tokio.mentions[0].matched_text, "tokio");
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let parsed = parse(
+            "web/package.json",
+            r#"{"name": "web", "version": "2.0.0", "author": "Bob Smith <bob@example.com>",
+                "dependencies": {"react": "^18.0.0"}, "devDependencies": {"jest": "^29"}}"#,
+        );
+        assert_eq!(names(&parsed, EntityType::Person), vec!["Bob Smith"]);
+        assert_eq!(names(&parsed, EntityType::Technology), vec!["react"]);
+
+        let parsed = parse(
+            "pyproject.toml",
+            "[project]\nname = \"tool\"\ndependencies = [\"requests>=2.0\", \"click\"]\n",
+        );
+        assert_eq!(names(&parsed, EntityType::Project), vec!["tool"]);
+        assert_eq!(
+            names(&parsed, EntityType::Technology),
+            vec!["click", "requests"]
+        );
+
+        let parsed = parse(
+            "environment.yml",
+            "name: analysis\ndependencies:\n  - numpy=1.26\n  - pandas\n",
+        );
+        assert_eq!(
+            names(&parsed, EntityType::Technology),
+            vec!["numpy", "pandas"]
+        );
+
+        assert!(ConfigFileParser::for_source("notes.md").is_none());
+        let chunks = SemanticChunker::new().chunk("key: value\n");
+        assert!(
+            ConfigFileParser::new(ConfigFormat::Yaml)
+                .parse("key: value\n", &chunks)
+                .is_none()
+        );
+        let workflow = "name: CI\non: push\n";
+        let chunks = SemanticChunker::new().chunk(workflow);
+        assert!(
+            ConfigFileParser::new(ConfigFormat::Yaml)
+                .parse(workflow, &chunks)
+                .is_none()
+        );
+    }
+}
@@ -4,6 +4,8 @@
 //! Each context file contains structured metadata, content references, and
 //! semantic embeddings for retrieval.
 
+use std::fmt::Write;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -31,6 +33,10 @@ pub struct ContextFile {
 
     /// The actual content of the context file.
     pub content: ContextContent,
+
+    /// ID of the context tree node this file was generated from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
 }
 
 impl ContextFile {
@@ -49,6 +55,7 @@ impl ContextFile {
             },
             summary: summary.into(),
             content: ContextContent::default(),
+            node_id: None,
         }
     }
 
@@ -89,6 +96,123 @@ impl ContextFile {
         self.content.embedding = Some(embedding);
         self.touch();
     }
+
+    /// Render this context file as a standalone markdown document.
+    ///
+    /// The concept becomes the title and the summary the body, followed by
+    /// sections for related concepts (linked to `<concept>.md`), structured
+    /// details, and sources. Empty sections are omitted and text is escaped
+    /// so it renders literally.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}", markdown_escape(&inline(&self.concept)));
+
+        if !self.summary.trim().is_empty() {
+            let _ = writeln!(out, "\n{}", markdown_escape(self.summary.trim()));
+        }
+
+        if !self.metadata.related_concepts.is_empty() {
+            out.push_str("\n## Related concepts\n\n");
+            for concept in &self.metadata.related_concepts {
+                let _ = writeln!(
+                    out,
+                    "- [{}]({}.md)",
+                    markdown_escape(&inline(concept)),
+                    link_target(concept)
+                );
+            }
+        }
+
+        if !self.content.structured.is_empty() {
+            out.push_str("\n## Details\n\n");
+            let mut entries: Vec<_> = self.content.structured.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (key, value) in entries {
+                let value = match value {
+                    serde_json::Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                let _ = writeln!(
+                    out,
+                    "- **{}**: {}",
+                    markdown_escape(&inline(key)),
+                    markdown_escape(&inline(&value))
+                );
+            }
+        }
+
+        if !self.content.references.is_empty() {
+            out.push_str("\n## Sources\n\n");
+            for reference in &self.content.references {
+                let _ = writeln!(
+                    out,
+                    "- {}: {} (relevance {:.2})",
+                    reference.reference_type.label(),
+                    markdown_escape(&inline(&reference.path)),
+                    reference.relevance
+                );
+                if let Some(excerpt) = &reference.excerpt {
+                    let _ = writeln!(out, "  > {}", markdown_escape(&inline(excerpt)));
+                }
+            }
+        }
+
+        out
+    }
+}
+
+/// Collapse line breaks so text stays on one markdown line.
+fn inline(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Escape markdown syntax so text renders literally.
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        // A leading `-` or `+` would start a list item.
+        let body = line.trim_start();
+        if body.starts_with(['-', '+']) {
+            escaped.push_str(&line[..line.len() - body.len()]);
+            escaped.push('\\');
+            escaped.push_str(&escape_inline(body));
+        } else {
+            escaped.push_str(&escape_inline(line));
+        }
+    }
+    escaped
+}
+
+/// Backslash-escape inline markdown punctuation.
+fn escape_inline(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '!'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Percent-encode characters that would break a markdown link target.
+fn link_target(concept: &str) -> String {
+    let mut target = String::with_capacity(concept.len());
+    for c in concept.chars() {
+        match c {
+            ' ' => target.push_str("%20"),
+            '(' => target.push_str("%28"),
+            ')' => target.push_str("%29"),
+            '<' => target.push_str("%3C"),
+            '>' => target.push_str("%3E"),
+            c if c.is_control() => {}
+            c => target.push(c),
+        }
+    }
+    target
 }
 
 /// Metadata about a context file.
@@ -140,6 +264,14 @@ pub struct ContentReference {
     /// How relevant this reference is to the concept (0.0 to 1.0).
     pub relevance: f32,
 
+    /// ID of the chunk within the source that the reference points at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_id: Option<String>,
+
+    /// Character offset within the chunk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+
     /// When this reference was added.
     pub added: DateTime<Utc>,
 }
@@ -152,10 +284,19 @@ impl ContentReference {
             path: path.into(),
             excerpt: None,
             relevance,
+            chunk_id: None,
+            offset: None,
             added: Utc::now(),
         }
     }
 
+    /// Point this reference at an offset within a chunk of the source.
+    pub fn with_location(mut self, chunk_id: impl Into<String>, offset: usize) -> Self {
+        self.chunk_id = Some(chunk_id.into());
+        self.offset = Some(offset);
+        self
+    }
+
     /// Add an excerpt to this reference.
     pub fn with_excerpt(mut self, excerpt: impl Into<String>) -> Self {
         self.excerpt = Some(excerpt.into());
@@ -177,6 +318,18 @@ pub enum ReferenceType {
     External,
 }
 
+impl ReferenceType {
+    /// Get a human-readable label for this reference type.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::File => "File",
+            Self::Conversation => "Conversation",
+            Self::Note => "Note",
+            Self::External => "External",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,4 +360,30 @@ mod tests {
             Some(&serde_json::json!("March 15"))
         );
     }
+
+    #[test]
+    fn test_to_markdown() {
+        let mut cf = ContextFile::new("rust_tools", "Tools for *Rust* projects.\n- cargo first");
+        cf.add_related_concept("build systems");
+        cf.add_related_concept("testing");
+        cf.set_structured("editor", serde_json::json!("VS Code"));
+        cf.set_structured("crates", serde_json::json!(["serde", "tokio"]));
+        cf.add_reference(
+            ContentReference::new(ReferenceType::File, "/src/main.rs", 0.9)
+                .with_excerpt("fn main() {}"),
+        );
+
+        let markdown = cf.to_markdown();
+
+        assert!(markdown.starts_with("# rust\\_tools\n"));
+        assert!(markdown.contains("Tools for \\*Rust\\* projects.\n\\- cargo first"));
+        assert!(markdown.contains("- [build systems](build%20systems.md)"));
+        assert!(markdown.contains("- [testing](testing.md)"));
+        assert!(markdown.contains("- **editor**: VS Code"));
+        assert!(markdown.contains("- **crates**: \\[\"serde\",\"tokio\"\\]"));
+        assert!(markdown.contains("- File: /src/main.rs (relevance 0.90)\n  > fn main() {}"));
+
+        let empty = ContextFile::new("empty", "").to_markdown();
+        assert_eq!(empty, "# empty\n");
+    }
 }
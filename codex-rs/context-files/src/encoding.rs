@@ -0,0 +1,161 @@
+//! Character encoding detection for file contents.
+//!
+//! Files are decoded as UTF-8 when they are valid UTF-8. Otherwise a byte
+//! order mark or the NUL-byte pattern of UTF-16 text selects the encoding,
+//! and a configurable single-byte fallback (Windows-1252 by default) covers
+//! legacy Latin-1 documents. Lossy UTF-8 decoding is the last resort.
+
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE};
+
+/// Label of the default fallback encoding for non-UTF-8 text.
+pub const DEFAULT_FALLBACK_ENCODING: &str = "windows-1252";
+
+/// Minimum fraction of NUL bytes in one byte position for UTF-16 detection.
+const UTF16_NUL_FRACTION: f32 = 0.3;
+
+/// Text decoded from raw file contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    /// The decoded text.
+    pub text: String,
+
+    /// Name of the encoding the text was decoded from.
+    pub encoding: &'static str,
+
+    /// Whether invalid sequences were replaced during decoding.
+    pub lossy: bool,
+}
+
+/// Decode file contents, detecting their encoding.
+///
+/// `fallback` is tried for text that is neither UTF-8 nor UTF-16. Returns
+/// `None` for binary content (NUL bytes outside of UTF-16 text).
+pub fn decode(bytes: &[u8], fallback: Option<&'static Encoding>) -> Option<DecodedText> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, lossy) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return Some(decoded(text.into_owned(), encoding, lossy));
+    }
+
+    // NUL bytes are valid UTF-8 but usually mean UTF-16 or binary content.
+    if let Ok(text) = std::str::from_utf8(bytes)
+        && !text.contains('\0')
+    {
+        return Some(decoded(text.to_string(), UTF_8, false));
+    }
+
+    if let Some(encoding) = detect_utf16(bytes)
+        && let Some(text) = encoding.decode_without_bom_handling_and_without_replacement(bytes)
+    {
+        return Some(decoded(text.into_owned(), encoding, false));
+    }
+
+    if bytes.contains(&0) {
+        return None;
+    }
+
+    if let Some(encoding) = fallback
+        && let Some(text) = encoding.decode_without_bom_handling_and_without_replacement(bytes)
+    {
+        return Some(decoded(text.into_owned(), encoding, false));
+    }
+
+    Some(decoded(
+        String::from_utf8_lossy(bytes).into_owned(),
+        UTF_8,
+        true,
+    ))
+}
+
+/// Resolve an encoding label such as `"latin1"` or `"windows-1252"`.
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.trim().as_bytes())
+}
+
+fn decoded(text: String, encoding: &'static Encoding, lossy: bool) -> DecodedText {
+    DecodedText {
+        text,
+        encoding: encoding.name(),
+        lossy,
+    }
+}
+
+/// Detect BOM-less UTF-16 from NUL bytes concentrated in one byte position.
+fn detect_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 2 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let pairs = bytes.len() / 2;
+    let even_nuls = bytes.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = bytes.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let threshold = (pairs as f32 * UTF16_NUL_FRACTION).max(1.0);
+
+    // ASCII text in UTF-16LE has a NUL in the high (odd) byte of each unit.
+    if odd_nuls as f32 >= threshold && even_nuls == 0 {
+        Some(UTF_16LE)
+    } else if even_nuls as f32 >= threshold && odd_nuls == 0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::WINDOWS_1252;
+    use pretty_assertions::assert_eq;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn test_decode_detects_encodings() {
+        let text = "Café by José";
+
+        let utf8 = decode(text.as_bytes(), None).unwrap();
+        assert_eq!((utf8.text.as_str(), utf8.encoding), (text, "UTF-8"));
+
+        let mut with_bom = vec![0xFF, 0xFE];
+        with_bom.extend(utf16le(text));
+        let decoded = decode(&with_bom, None).unwrap();
+        assert_eq!(
+            (decoded.text.as_str(), decoded.encoding),
+            (text, "UTF-16LE")
+        );
+
+        let decoded = decode(&utf16le(text), None).unwrap();
+        assert_eq!(
+            (decoded.text.as_str(), decoded.encoding),
+            (text, "UTF-16LE")
+        );
+
+        let decoded = decode(&utf16le("plain ascii"), None).unwrap();
+        assert_eq!(
+            (decoded.text.as_str(), decoded.encoding),
+            ("plain ascii", "UTF-16LE")
+        );
+
+        let (latin1, _, _) = WINDOWS_1252.encode(text);
+        let decoded = decode(&latin1, Some(WINDOWS_1252)).unwrap();
+        assert_eq!(
+            (decoded.text.as_str(), decoded.encoding, decoded.lossy),
+            (text, "windows-1252", false)
+        );
+
+        let decoded = decode(&latin1, None).unwrap();
+        assert!(decoded.lossy);
+        assert_eq!(decoded.text, "Caf\u{FFFD} by Jos\u{FFFD}");
+    }
+
+    #[test]
+    fn test_decode_rejects_binary() {
+        assert_eq!(
+            decode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", Some(WINDOWS_1252)),
+            None
+        );
+        assert_eq!(decode(b"text\0with nul", Some(WINDOWS_1252)), None);
+        assert_eq!(encoding_for_label("latin1"), Some(WINDOWS_1252));
+    }
+}
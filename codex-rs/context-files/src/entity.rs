@@ -3,14 +3,43 @@
 //! Extracts named entities (people, projects, technologies, dates, etc.)
 //! from document chunks using regex patterns and heuristics.
 
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
 
-use serde::{Deserialize, Serialize};
+use codex_embeddings::{EmbeddingProvider, EmbeddingRequest, cosine_similarity};
+use serde::{Deserialize, Deserializer, Serialize};
+use tracing::{debug, warn};
 
-use crate::chunker::Chunk;
+use crate::chunker::{Chunk, ChunkType};
+use crate::id::{IdGenerator, default_id_generator};
+
+/// Words ending in "s" that are not plurals of a shorter word.
+const INVARIANT_PLURALS: &[&str] = &["news", "series", "species", "means", "lens", "always"];
+
+/// Confidence of a project entity named after a file.
+///
+/// Path-derived entities rank below those found in content.
+const PATH_FILE_CONFIDENCE: f32 = 0.6;
+
+/// Confidence of a concept entity named after a containing directory.
+const PATH_DIRECTORY_CONFIDENCE: f32 = 0.5;
+
+/// File and directory names too generic to name an entity.
+const GENERIC_PATH_NAMES: &[&str] = &[
+    "src", "lib", "bin", "test", "tests", "doc", "docs", "main", "mod", "index", "readme", "init",
+    "__init__", "utils", "util", "common", "misc",
+];
+
+/// Stem endings whose plural adds "es" ("classes", "branches", "boxes").
+///
+/// A bare "ch" is not listed so "caches" keeps its "e".
+const ES_PLURAL_STEMS: &[&str] = &["ss", "sh", "x", "tch", "nch", "rch", "oach", "each"];
 
 /// An extracted entity.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Entity {
     /// Unique identifier.
     pub id: String,
@@ -18,9 +47,15 @@ pub struct Entity {
     /// The entity name/value.
     pub name: String,
 
-    /// Normalized form of the name (lowercase, trimmed).
+    /// Normalized form of the name (lowercase, trimmed, and for concepts
+    /// singularized).
     pub normalized_name: String,
 
+    /// Name for display: the most common original casing across mentions.
+    ///
+    /// Entities saved without one load with their `name` instead.
+    pub display_name: String,
+
     /// Type of entity.
     pub entity_type: EntityType,
 
@@ -34,13 +69,46 @@ pub struct Entity {
     pub attributes: HashMap<String, String>,
 }
 
+impl<'de> Deserialize<'de> for Entity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawEntity {
+            id: String,
+            name: String,
+            normalized_name: String,
+            #[serde(default)]
+            display_name: Option<String>,
+            entity_type: EntityType,
+            confidence: f32,
+            mentions: Vec<EntityMention>,
+            attributes: HashMap<String, String>,
+        }
+
+        let raw = RawEntity::deserialize(deserializer)?;
+        Ok(Self {
+            display_name: raw.display_name.unwrap_or_else(|| raw.name.clone()),
+            id: raw.id,
+            name: raw.name,
+            normalized_name: raw.normalized_name,
+            entity_type: raw.entity_type,
+            confidence: raw.confidence,
+            mentions: raw.mentions,
+            attributes: raw.attributes,
+        })
+    }
+}
+
 impl Entity {
     /// Create a new entity.
     pub fn new(name: impl Into<String>, entity_type: EntityType, confidence: f32) -> Self {
         let name = name.into();
-        let normalized_name = Self::normalize(&name);
+        let normalized_name = Self::normalize_with(&name, entity_type, Singularization::default());
         Self {
             id: uuid::Uuid::new_v4().to_string(),
+            display_name: name.clone(),
             name,
             normalized_name,
             entity_type,
@@ -53,6 +121,7 @@ impl Entity {
     /// Add a mention of this entity.
     pub fn add_mention(&mut self, mention: EntityMention) {
         self.mentions.push(mention);
+        self.resolve_display_name();
     }
 
     /// Set an attribute.
@@ -60,16 +129,85 @@ impl Entity {
         self.attributes.insert(key.into(), value.into());
     }
 
+    /// Re-normalize the name under a different singularization mode.
+    pub fn with_singularization(mut self, singularization: Singularization) -> Self {
+        self.normalized_name = Self::normalize_with(&self.name, self.entity_type, singularization);
+        self
+    }
+
+    /// Re-normalize the name, keeping its case for matching or folding it.
+    ///
+    /// By default only types that are identifiers (see
+    /// `EntityType::preserves_case`) keep their case. Plurals are folded
+    /// with the default singularization.
+    pub fn with_case_matching(mut self, preserve_case: bool) -> Self {
+        let normalized = Self::normalize_case(&self.name, preserve_case);
+        self.normalized_name = if self.entity_type.has_plural_forms() {
+            singularize_last_word(&normalized)
+        } else {
+            normalized
+        };
+        self
+    }
+
     /// Normalize an entity name for comparison.
     fn normalize(name: &str) -> String {
-        name.to_lowercase()
-            .trim()
+        Self::normalize_case(name, false)
+    }
+
+    /// Normalize separators and whitespace, lowercasing unless `preserve_case`.
+    fn normalize_case(name: &str, preserve_case: bool) -> String {
+        let name = if preserve_case {
+            Cow::Borrowed(name)
+        } else {
+            Cow::Owned(name.to_lowercase())
+        };
+        name.trim()
             .replace(['_', '-'], " ")
             .split_whitespace()
             .collect::<Vec<_>>()
             .join(" ")
     }
 
+    /// Normalize a name of the given type, folding plurals per `singularization`.
+    ///
+    /// Only types with plural forms (see `EntityType::has_plural_forms`) are
+    /// singularized, and only their last word: "build servers" becomes
+    /// "build server". Types that preserve case keep it.
+    pub fn normalize_with(
+        name: &str,
+        entity_type: EntityType,
+        singularization: Singularization,
+    ) -> String {
+        let normalized = Self::normalize_case(name, entity_type.preserves_case());
+        if singularization == Singularization::None || !entity_type.has_plural_forms() {
+            return normalized;
+        }
+        singularize_last_word(&normalized)
+    }
+
+    /// Key identifying entities that should be merged.
+    ///
+    /// Combines the stable type identifier with the normalized name. Type
+    /// identifiers never contain `:`, so keys cannot collide across types.
+    pub fn merge_key(&self) -> String {
+        format!("{}:{}", self.entity_type.as_str(), self.normalized_name)
+    }
+
+    /// Get the source this entity is mentioned in most often.
+    ///
+    /// Ties go to the lexicographically smallest source.
+    pub fn primary_source(&self) -> Option<&str> {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for source in self.mentions.iter().filter_map(|m| m.source.as_deref()) {
+            *counts.entry(source).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .max_by_key(|&(source, count)| (count, Reverse(source)))
+            .map(|(source, _)| source)
+    }
+
     /// Check if two entities are likely the same.
     pub fn is_same_as(&self, other: &Entity) -> bool {
         self.entity_type == other.entity_type && self.normalized_name == other.normalized_name
@@ -83,6 +221,36 @@ impl Entity {
         }
         // Keep the higher confidence
         self.confidence = self.confidence.max(other.confidence);
+        self.resolve_display_name();
+    }
+
+    /// Pick the most common original casing among mentions as the display name.
+    ///
+    /// Only mentions that normalize to this entity's name are considered, so
+    /// fuzzy or partial matches don't leak into display. Ties go to the form
+    /// mentioned first.
+    pub fn resolve_display_name(&mut self) {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for mention in &self.mentions {
+            let text = mention.matched_text.trim();
+            let normalized = Self::normalize(text);
+            if normalized != self.normalized_name
+                && singularize_last_word(&normalized) != self.normalized_name
+                && Self::normalize_case(text, true) != self.normalized_name
+            {
+                continue;
+            }
+            match counts.iter_mut().find(|(form, _)| *form == text) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((text, 1)),
+            }
+        }
+
+        // `max_by_key` keeps the last maximum, so scan in reverse to favor
+        // the earliest form on ties.
+        if let Some((form, _)) = counts.iter().rev().max_by_key(|(_, count)| *count) {
+            self.display_name = form.to_string();
+        }
     }
 }
 
@@ -114,9 +282,48 @@ pub enum EntityType {
     File,
     /// A function, class, or code element.
     CodeElement,
+    /// An outstanding task marker (TODO, FIXME, etc.).
+    Task,
 }
 
 impl EntityType {
+    /// Get a string representation.
+    ///
+    /// These values are stable: they are used in merge keys and exports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Person => "person",
+            Self::Project => "project",
+            Self::Technology => "technology",
+            Self::Date => "date",
+            Self::Location => "location",
+            Self::Organization => "organization",
+            Self::Version => "version",
+            Self::Url => "url",
+            Self::Email => "email",
+            Self::Concept => "concept",
+            Self::File => "file",
+            Self::CodeElement => "code_element",
+            Self::Task => "task",
+        }
+    }
+
+    /// Whether names of this type are common nouns with plural forms.
+    ///
+    /// Other types are proper names or identifiers ("Kubernetes", "Redis",
+    /// `parse_args`) whose trailing "s" is part of the name.
+    pub fn has_plural_forms(&self) -> bool {
+        matches!(self, Self::Concept)
+    }
+
+    /// Whether names of this type are case-sensitive by default.
+    ///
+    /// Code identifiers and file paths are (`Foo` and `foo` are different
+    /// symbols), while prose names like "Rust" and "rust" are the same.
+    pub fn preserves_case(&self) -> bool {
+        matches!(self, Self::CodeElement | Self::File)
+    }
+
     /// Get a display name for this entity type.
     pub fn display_name(&self) -> &'static str {
         match self {
@@ -132,10 +339,67 @@ impl EntityType {
             Self::Concept => "Concept",
             Self::File => "File",
             Self::CodeElement => "Code Element",
+            Self::Task => "Task",
         }
     }
 }
 
+/// How plural forms are folded when normalizing entity names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Singularization {
+    /// Keep names as written.
+    None,
+
+    /// Strip regular English plural endings ("servers", "technologies").
+    #[default]
+    English,
+}
+
+/// Singularize the last word of a normalized name using English rules.
+///
+/// Deliberately light: only regular endings are handled, and words ending in
+/// "ss", "us", "is", or "ics" ("business", "status", "analysis", "physics")
+/// are left intact rather than over-stemmed.
+fn singularize_last_word(name: &str) -> String {
+    let (prefix, word) = match name.rsplit_once(' ') {
+        Some((prefix, word)) => (Some(prefix), word),
+        None => (None, name),
+    };
+    let singular = singularize_english(word);
+    match prefix {
+        Some(prefix) => format!("{prefix} {singular}"),
+        None => singular.to_string(),
+    }
+}
+
+/// Singularize one lowercase English word.
+fn singularize_english(word: &str) -> Cow<'_, str> {
+    if word.len() <= 3
+        || !word.chars().all(|c| c.is_ascii_alphabetic())
+        || INVARIANT_PLURALS.contains(&word)
+        || ["ss", "us", "is", "ics"]
+            .iter()
+            .any(|end| word.ends_with(end))
+    {
+        return Cow::Borrowed(word);
+    }
+
+    if let Some(stem) = word.strip_suffix("ies")
+        && stem.len() > 1
+    {
+        return Cow::Owned(format!("{stem}y"));
+    }
+    if let Some(stem) = word.strip_suffix("es")
+        && ES_PLURAL_STEMS.iter().any(|end| stem.ends_with(end))
+    {
+        return Cow::Borrowed(stem);
+    }
+    match word.strip_suffix('s') {
+        Some(stem) => Cow::Borrowed(stem),
+        None => Cow::Borrowed(word),
+    }
+}
+
 /// A mention of an entity in a chunk.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntityMention {
@@ -150,6 +414,10 @@ pub struct EntityMention {
 
     /// Context around the mention.
     pub context: Option<String>,
+
+    /// Source of the chunk, such as a file path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 /// Configuration for entity extraction.
@@ -182,8 +450,47 @@ pub struct EntityExtractorConfig {
     /// Whether to extract code elements.
     pub extract_code_elements: bool,
 
-    /// Context window size (chars before/after mention).
+    /// Whether to extract TODO/FIXME/HACK/XXX markers as tasks (off by
+    /// default).
+    pub extract_tasks: bool,
+
+    /// Whether to store surrounding text on each mention.
+    pub store_context: bool,
+
+    /// Context window size (chars before/after mention, 0 = no context).
     pub context_window: usize,
+
+    /// Maximum edit distance for fuzzy technology matching (0 = exact only).
+    ///
+    /// When non-zero, words close to a known technology (e.g. "ReactJS",
+    /// "Postgre") are mapped to the canonical technology name.
+    pub fuzzy_tech_distance: usize,
+
+    /// Whether `EntityExtractor::semantic_dedup` merges entities whose
+    /// names embed close together (e.g. "NYC" and "New York City").
+    ///
+    /// Requires an embedding provider, so it is off by default.
+    pub semantic_dedup: bool,
+
+    /// Minimum cosine similarity between entity names for semantic merging.
+    pub semantic_dedup_threshold: f32,
+
+    /// How to handle a name extracted under several entity types.
+    pub cross_type_resolution: CrossTypeResolution,
+
+    /// Minimum confidence lead the best type needs over the runner-up before
+    /// `CrossTypeResolution::PreferHigherConfidence` collapses the others.
+    pub cross_type_confidence_gap: f32,
+
+    /// Entity types matched case-sensitively.
+    ///
+    /// Names of these types keep their case when normalized, so `Foo` and
+    /// `foo` stay distinct. Defaults to the types that preserve case (see
+    /// `EntityType::preserves_case`).
+    pub case_sensitive_types: Vec<EntityType>,
+
+    /// Confidence assigned to each kind of extracted entity.
+    pub confidence_weights: ConfidenceWeights,
 }
 
 impl Default for EntityExtractorConfig {
@@ -198,24 +505,104 @@ impl Default for EntityExtractorConfig {
             extract_emails: true,
             extract_files: true,
             extract_code_elements: true,
+            extract_tasks: false,
+            store_context: true,
             context_window: 50,
+            fuzzy_tech_distance: 0,
+            semantic_dedup: false,
+            semantic_dedup_threshold: 0.9,
+            cross_type_resolution: CrossTypeResolution::KeepAll,
+            cross_type_confidence_gap: 0.3,
+            case_sensitive_types: vec![EntityType::File, EntityType::CodeElement],
+            confidence_weights: ConfidenceWeights::default(),
+        }
+    }
+}
+
+/// Confidence assigned to entities by how they were extracted.
+///
+/// Dictionary matches are more trustworthy than names guessed from
+/// surrounding patterns, so the defaults rank them accordingly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfidenceWeights {
+    /// People found by name patterns ("by Alice Smith").
+    pub people: f32,
+
+    /// Projects found by name patterns ("project Apollo").
+    pub projects: f32,
+
+    /// Technologies found in the known-technology dictionary.
+    ///
+    /// Fuzzy matches start here and lose 0.1 per edit.
+    pub technology_dictionary: f32,
+
+    /// Technologies guessed from usage patterns ("using Foo").
+    pub technology_pattern: f32,
+
+    /// Dates.
+    pub dates: f32,
+
+    /// File references.
+    pub files: f32,
+
+    /// Code elements (functions, types).
+    pub code_elements: f32,
+}
+
+impl Default for ConfidenceWeights {
+    fn default() -> Self {
+        Self {
+            people: 0.8,
+            projects: 0.9,
+            technology_dictionary: 0.9,
+            technology_pattern: 0.7,
+            dates: 0.95,
+            files: 0.85,
+            code_elements: 0.9,
         }
     }
 }
 
+/// How entities sharing a normalized name across types are resolved.
+///
+/// "Python" may be a technology in one document and a concept in a biology
+/// note; merge keys are type-qualified, so both are kept unless configured
+/// otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrossTypeResolution {
+    /// Keep one entity per type.
+    #[default]
+    KeepAll,
+
+    /// Collapse into the highest-confidence type when it leads the
+    /// runner-up by at least `cross_type_confidence_gap`.
+    PreferHigherConfidence,
+}
+
+/// A normalized name extracted under more than one entity type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossTypeConflict {
+    /// The shared normalized name.
+    pub normalized_name: String,
+
+    /// Each type with its confidence, highest confidence first.
+    pub candidates: Vec<(EntityType, f32)>,
+
+    /// The type the conflict resolves to, if the others are collapsed.
+    pub resolved: Option<EntityType>,
+}
+
 /// Entity extractor using pattern matching.
 pub struct EntityExtractor {
     config: EntityExtractorConfig,
     known_technologies: HashSet<String>,
+    id_generator: Arc<dyn IdGenerator>,
 }
 
 impl EntityExtractor {
     /// Create a new entity extractor with default configuration.
     pub fn new() -> Self {
-        Self {
-            config: EntityExtractorConfig::default(),
-            known_technologies: Self::default_technologies(),
-        }
+        Self::with_config(EntityExtractorConfig::default())
     }
 
     /// Create an extractor with custom configuration.
@@ -223,9 +610,19 @@ impl EntityExtractor {
         Self {
             config,
             known_technologies: Self::default_technologies(),
+            id_generator: default_id_generator(),
         }
     }
 
+    /// Set the generator used for entity IDs.
+    ///
+    /// IDs are assigned once extraction has settled on its final, sorted
+    /// entities, so a seeded generator yields the same IDs for the same input.
+    pub fn with_id_generator(mut self, id_generator: Arc<dyn IdGenerator>) -> Self {
+        self.id_generator = id_generator;
+        self
+    }
+
     /// Get default known technologies.
     fn default_technologies() -> HashSet<String> {
         [
@@ -341,23 +738,231 @@ impl EntityExtractor {
         for chunk in chunks {
             let chunk_entities = self.extract_from_chunk(chunk);
 
-            for entity in chunk_entities {
-                let key = format!("{:?}:{}", entity.entity_type, entity.normalized_name);
-
+            for mut entity in chunk_entities {
+                let preserve_case = self
+                    .config
+                    .case_sensitive_types
+                    .contains(&entity.entity_type);
+                if preserve_case != entity.entity_type.preserves_case() {
+                    entity = entity.with_case_matching(preserve_case);
+                }
                 entities
-                    .entry(key)
+                    .entry(entity.merge_key())
                     .and_modify(|e| e.merge(entity.clone()))
                     .or_insert(entity);
             }
         }
 
-        // Filter by confidence and return
-        entities
+        // Filter by confidence and return in a stable order
+        let entities: Vec<Entity> = entities
             .into_values()
             .filter(|e| e.confidence >= self.config.min_confidence)
+            .collect();
+        let mut entities = self.resolve_cross_types(entities);
+        sort_entities(&mut entities);
+        self.assign_ids(&mut entities);
+        entities
+    }
+
+    /// Give each entity a fresh ID from the configured generator.
+    fn assign_ids(&self, entities: &mut [Entity]) {
+        for entity in entities {
+            entity.id = self.id_generator.next_id();
+        }
+    }
+
+    /// Report names that appear under several entity types.
+    ///
+    /// Each conflict records how `resolve_cross_types` would resolve it under
+    /// the current configuration. Conflicts are ordered by name.
+    pub fn cross_type_conflicts(&self, entities: &[Entity]) -> Vec<CrossTypeConflict> {
+        let mut by_name: BTreeMap<&str, Vec<&Entity>> = BTreeMap::new();
+        for entity in entities {
+            by_name
+                .entry(&entity.normalized_name)
+                .or_default()
+                .push(entity);
+        }
+
+        by_name
+            .into_iter()
+            .filter_map(|(name, group)| {
+                let mut candidates: Vec<(EntityType, f32)> = Vec::new();
+                for entity in group {
+                    match candidates
+                        .iter_mut()
+                        .find(|(t, _)| *t == entity.entity_type)
+                    {
+                        Some((_, confidence)) => *confidence = confidence.max(entity.confidence),
+                        None => candidates.push((entity.entity_type, entity.confidence)),
+                    }
+                }
+                if candidates.len() < 2 {
+                    return None;
+                }
+                candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.as_str().cmp(b.0.as_str())));
+
+                let resolved = match self.config.cross_type_resolution {
+                    CrossTypeResolution::KeepAll => None,
+                    CrossTypeResolution::PreferHigherConfidence => {
+                        (candidates[0].1 - candidates[1].1 >= self.config.cross_type_confidence_gap)
+                            .then_some(candidates[0].0)
+                    }
+                };
+                Some(CrossTypeConflict {
+                    normalized_name: name.to_string(),
+                    candidates,
+                    resolved,
+                })
+            })
             .collect()
     }
 
+    /// Collapse cross-type conflicts according to `cross_type_resolution`.
+    ///
+    /// Entities of the losing types are merged into the winning entity so
+    /// their mentions are kept.
+    pub fn resolve_cross_types(&self, entities: Vec<Entity>) -> Vec<Entity> {
+        let resolved: HashMap<String, EntityType> = self
+            .cross_type_conflicts(&entities)
+            .into_iter()
+            .filter_map(|c| Some((c.normalized_name, c.resolved?)))
+            .collect();
+        if resolved.is_empty() {
+            return entities;
+        }
+
+        let (mut kept, collapsed): (Vec<Entity>, Vec<Entity>) =
+            entities.into_iter().partition(|e| {
+                resolved
+                    .get(&e.normalized_name)
+                    .is_none_or(|t| *t == e.entity_type)
+            });
+        for entity in collapsed {
+            if let Some(winner) = kept
+                .iter_mut()
+                .find(|k| k.normalized_name == entity.normalized_name)
+            {
+                debug!(
+                    "Collapsing {} '{}' into {}",
+                    entity.entity_type.as_str(),
+                    entity.name,
+                    winner.entity_type.as_str()
+                );
+                winner.merge(entity);
+            }
+        }
+        kept
+    }
+
+    /// Merge entities of the same type whose names are semantically close.
+    ///
+    /// Entity names are embedded with `provider` and pairs above
+    /// `semantic_dedup_threshold` are merged, with the most-mentioned entity
+    /// absorbing the others. Returns the entities unchanged when
+    /// `semantic_dedup` is disabled or the provider fails.
+    pub async fn semantic_dedup(
+        &self,
+        entities: Vec<Entity>,
+        provider: &dyn EmbeddingProvider,
+    ) -> Vec<Entity> {
+        if !self.config.semantic_dedup || entities.len() < 2 {
+            return entities;
+        }
+
+        let requests = entities
+            .iter()
+            .map(|e| EmbeddingRequest::new(e.name.clone()))
+            .collect();
+        let embeddings: Vec<Vec<f32>> = match provider.embed_batch(requests).await {
+            Ok(responses) if responses.len() == entities.len() => {
+                responses.into_iter().map(|r| r.embedding).collect()
+            }
+            Ok(responses) => {
+                warn!(
+                    "Skipping semantic deduplication: got {} embeddings for {} entities",
+                    responses.len(),
+                    entities.len()
+                );
+                return entities;
+            }
+            Err(e) => {
+                warn!("Skipping semantic deduplication: {e}");
+                return entities;
+            }
+        };
+
+        let threshold = self.config.semantic_dedup_threshold;
+        let types: Vec<EntityType> = entities.iter().map(|e| e.entity_type).collect();
+        let mut order: Vec<usize> = (0..entities.len()).collect();
+        order.sort_by_key(|&i| Reverse(entities[i].mentions.len()));
+
+        let mut slots: Vec<Option<Entity>> = entities.into_iter().map(Some).collect();
+        let mut kept: Vec<usize> = Vec::new();
+        for i in order {
+            let target = kept.iter().copied().find(|&k| {
+                types[k] == types[i]
+                    && cosine_similarity(&embeddings[i], &embeddings[k])
+                        .is_ok_and(|similarity| similarity >= threshold)
+            });
+            match target {
+                Some(k) => {
+                    if let Some(duplicate) = slots[i].take()
+                        && let Some(entity) = slots[k].as_mut()
+                    {
+                        entity.merge(duplicate);
+                    }
+                }
+                None => kept.push(i),
+            }
+        }
+
+        let mut entities: Vec<Entity> = slots.into_iter().flatten().collect();
+        sort_entities(&mut entities);
+        entities
+    }
+
+    /// Derive entities from a file's path, relative to the processed root.
+    ///
+    /// The file stem names a project ("payment-service.rs") and each
+    /// directory names a concept ("billing/"). Generic names like `src` or
+    /// `main` are skipped. These entities have lower confidence than ones
+    /// found in content, and their mentions point at `chunk_id`.
+    pub fn extract_from_path(&self, path: &Path, chunk_id: &str) -> Vec<Entity> {
+        let directories = path
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .filter_map(|c| c.as_os_str().to_str())
+            .map(|name| (name, EntityType::Concept, PATH_DIRECTORY_CONFIDENCE));
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|name| (name, EntityType::Project, PATH_FILE_CONFIDENCE));
+
+        let mut entities = directories
+            .chain(stem)
+            .filter(|(name, ..)| {
+                name.chars().any(char::is_alphabetic)
+                    && !name.starts_with('.')
+                    && !GENERIC_PATH_NAMES.contains(&name.to_lowercase().as_str())
+            })
+            .map(|(name, entity_type, confidence)| {
+                let mut entity = Entity::new(name, entity_type, confidence);
+                entity.add_mention(EntityMention {
+                    chunk_id: chunk_id.to_string(),
+                    position: 0,
+                    matched_text: name.to_string(),
+                    context: Some(path.to_string_lossy().to_string()),
+                    source: None,
+                });
+                entity
+            })
+            .collect::<Vec<_>>();
+        self.assign_ids(&mut entities);
+        entities
+    }
+
     /// Extract entities from a single chunk.
     fn extract_from_chunk(&self, chunk: &Chunk) -> Vec<Entity> {
         let mut entities = Vec::new();
@@ -392,8 +997,21 @@ impl EntityExtractor {
             entities.extend(self.extract_files(text, &chunk.id));
         }
 
-        if self.config.extract_code_elements {
-            entities.extend(self.extract_code_elements(text, &chunk.id));
+        if self.config.extract_code_elements
+            && chunk.chunk_type == ChunkType::Code
+            && let Some(patterns) = chunk.metadata.language.as_deref().and_then(code_patterns)
+        {
+            entities.extend(self.extract_code_elements(text, &chunk.id, patterns));
+        }
+
+        if self.config.extract_tasks {
+            entities.extend(self.extract_tasks(text, &chunk.id));
+        }
+
+        if let Some(source) = &chunk.source {
+            for mention in entities.iter_mut().flat_map(|e| e.mentions.iter_mut()) {
+                mention.source = Some(source.clone());
+            }
         }
 
         entities
@@ -416,12 +1034,17 @@ impl EntityExtractor {
                     if let Some(name) = cap.get(1) {
                         let name_str = name.as_str().to_string();
                         if name_str.len() >= 2 && name_str.len() <= 50 {
-                            let mut entity = Entity::new(&name_str, EntityType::Person, 0.8);
+                            let mut entity = Entity::new(
+                                &name_str,
+                                EntityType::Person,
+                                self.config.confidence_weights.people,
+                            );
                             entity.add_mention(EntityMention {
                                 chunk_id: chunk_id.to_string(),
                                 position: name.start(),
                                 matched_text: name_str.clone(),
                                 context: self.get_context(text, name.start(), name.end()),
+                                source: None,
                             });
                             entities.push(entity);
                         }
@@ -449,12 +1072,17 @@ impl EntityExtractor {
                 for cap in re.captures_iter(text) {
                     if let Some(name) = cap.get(1) {
                         let name_str = name.as_str().to_string();
-                        let mut entity = Entity::new(&name_str, EntityType::Project, 0.9);
+                        let mut entity = Entity::new(
+                            &name_str,
+                            EntityType::Project,
+                            self.config.confidence_weights.projects,
+                        );
                         entity.add_mention(EntityMention {
                             chunk_id: chunk_id.to_string(),
                             position: name.start(),
                             matched_text: name_str,
                             context: self.get_context(text, name.start(), name.end()),
+                            source: None,
                         });
                         entities.push(entity);
                     }
@@ -468,28 +1096,37 @@ impl EntityExtractor {
     /// Extract technology entities.
     fn extract_technologies(&self, text: &str, chunk_id: &str) -> Vec<Entity> {
         let mut entities = Vec::new();
-        let text_lower = text.to_lowercase();
 
-        // Check for known technologies
+        // Check for known technologies. Matching case-insensitively in the
+        // original text keeps offsets valid when lowercasing would change
+        // the byte length of non-ASCII characters.
         for tech in &self.known_technologies {
             // Word boundary matching
-            let pattern = format!(r"\b{}\b", regex_lite::escape(tech));
+            let pattern = format!(r"(?i)\b{}\b", regex_lite::escape(tech));
             if let Ok(re) = regex_lite::Regex::new(&pattern) {
-                for mat in re.find_iter(&text_lower) {
-                    // Get the original case from the text
-                    let original = &text[mat.start()..mat.end()];
-                    let mut entity = Entity::new(original, EntityType::Technology, 0.9);
+                for mat in re.find_iter(text) {
+                    let original = mat.as_str();
+                    let mut entity = Entity::new(
+                        original,
+                        EntityType::Technology,
+                        self.config.confidence_weights.technology_dictionary,
+                    );
                     entity.add_mention(EntityMention {
                         chunk_id: chunk_id.to_string(),
                         position: mat.start(),
                         matched_text: original.to_string(),
                         context: self.get_context(text, mat.start(), mat.end()),
+                        source: None,
                     });
                     entities.push(entity);
                 }
             }
         }
 
+        if self.config.fuzzy_tech_distance > 0 {
+            entities.extend(self.extract_fuzzy_technologies(text, chunk_id));
+        }
+
         // Pattern: "using [Tech]", "built with [Tech]"
         let tech_patterns = [
             r"(?i)(?:using|built with|powered by|requires|depends on)\s+([A-Z][a-zA-Z0-9]+(?:\s+[\d.]+)?)",
@@ -500,13 +1137,21 @@ impl EntityExtractor {
                 for cap in re.captures_iter(text) {
                     if let Some(tech) = cap.get(1) {
                         let tech_str = tech.as_str().to_string();
-                        if !self.known_technologies.contains(&tech_str.to_lowercase()) {
-                            let mut entity = Entity::new(&tech_str, EntityType::Technology, 0.7);
+                        let tech_lower = tech_str.to_lowercase();
+                        if !self.known_technologies.contains(&tech_lower)
+                            && self.fuzzy_match_technology(&tech_lower).is_none()
+                        {
+                            let mut entity = Entity::new(
+                                &tech_str,
+                                EntityType::Technology,
+                                self.config.confidence_weights.technology_pattern,
+                            );
                             entity.add_mention(EntityMention {
                                 chunk_id: chunk_id.to_string(),
                                 position: tech.start(),
                                 matched_text: tech_str,
                                 context: self.get_context(text, tech.start(), tech.end()),
+                                source: None,
                             });
                             entities.push(entity);
                         }
@@ -518,6 +1163,66 @@ impl EntityExtractor {
         entities
     }
 
+    /// Extract technologies that are close variants of known technologies.
+    ///
+    /// The entity is named after the canonical technology while the mention
+    /// keeps the text as written.
+    fn extract_fuzzy_technologies(&self, text: &str, chunk_id: &str) -> Vec<Entity> {
+        let mut entities = Vec::new();
+
+        let Ok(word_re) = regex_lite::Regex::new(r"\b[A-Za-z][A-Za-z0-9]*\b") else {
+            return entities;
+        };
+
+        for word in word_re.find_iter(text) {
+            let Some((canonical, distance)) =
+                self.fuzzy_match_technology(&word.as_str().to_lowercase())
+            else {
+                continue;
+            };
+
+            let confidence = (self.config.confidence_weights.technology_dictionary
+                - 0.1 * distance as f32)
+                .max(0.5);
+            let mut entity = Entity::new(canonical, EntityType::Technology, confidence);
+            entity.add_mention(EntityMention {
+                chunk_id: chunk_id.to_string(),
+                position: word.start(),
+                matched_text: word.as_str().to_string(),
+                context: self.get_context(text, word.start(), word.end()),
+                source: None,
+            });
+            entities.push(entity);
+        }
+
+        entities
+    }
+
+    /// Find the closest known technology within the fuzzy edit distance.
+    ///
+    /// Exact matches return `None` since they are handled by word-boundary
+    /// matching. Short words are ignored to avoid noisy matches.
+    fn fuzzy_match_technology(&self, word: &str) -> Option<(&str, usize)> {
+        const MIN_FUZZY_LEN: usize = 5;
+
+        let max_distance = self.config.fuzzy_tech_distance;
+        if max_distance == 0 || word.len() < MIN_FUZZY_LEN || self.known_technologies.contains(word)
+        {
+            return None;
+        }
+
+        self.known_technologies
+            .iter()
+            .filter(|tech| {
+                tech.len() >= MIN_FUZZY_LEN && tech.chars().next() == word.chars().next()
+            })
+            .filter_map(|tech| {
+                let distance = edit_distance(word, tech);
+                (distance <= max_distance).then_some((tech.as_str(), distance))
+            })
+            .min_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(b.0)))
+    }
+
     /// Extract date entities.
     fn extract_dates(&self, text: &str, chunk_id: &str) -> Vec<Entity> {
         let mut entities = Vec::new();
@@ -534,12 +1239,17 @@ impl EntityExtractor {
                 for cap in re.captures_iter(text) {
                     if let Some(date) = cap.get(1) {
                         let date_str = date.as_str().to_string();
-                        let mut entity = Entity::new(&date_str, EntityType::Date, 0.95);
+                        let mut entity = Entity::new(
+                            &date_str,
+                            EntityType::Date,
+                            self.config.confidence_weights.dates,
+                        );
                         entity.add_mention(EntityMention {
                             chunk_id: chunk_id.to_string(),
                             position: date.start(),
                             matched_text: date_str,
                             context: self.get_context(text, date.start(), date.end()),
+                            source: None,
                         });
                         entities.push(entity);
                     }
@@ -564,6 +1274,7 @@ impl EntityExtractor {
                     position: mat.start(),
                     matched_text: url.to_string(),
                     context: self.get_context(text, mat.start(), mat.end()),
+                    source: None,
                 });
                 entities.push(entity);
             }
@@ -586,6 +1297,7 @@ impl EntityExtractor {
                     position: mat.start(),
                     matched_text: email.to_string(),
                     context: self.get_context(text, mat.start(), mat.end()),
+                    source: None,
                 });
                 entities.push(entity);
             }
@@ -606,17 +1318,22 @@ impl EntityExtractor {
         for pattern in file_patterns {
             if let Ok(re) = regex_lite::Regex::new(pattern) {
                 for cap in re.captures_iter(text) {
-                    let file = cap
-                        .get(1)
-                        .map(|m| m.as_str())
-                        .unwrap_or(cap.get(0).unwrap().as_str());
+                    let Some(mat) = cap.get(1).or_else(|| cap.get(0)) else {
+                        continue;
+                    };
+                    let file = mat.as_str();
                     if file.len() >= 3 && file.len() <= 100 {
-                        let mut entity = Entity::new(file, EntityType::File, 0.85);
+                        let mut entity = Entity::new(
+                            file,
+                            EntityType::File,
+                            self.config.confidence_weights.files,
+                        );
                         entity.add_mention(EntityMention {
                             chunk_id: chunk_id.to_string(),
-                            position: cap.get(0).unwrap().start(),
+                            position: mat.start(),
                             matched_text: file.to_string(),
                             context: None,
+                            source: None,
                         });
                         entities.push(entity);
                     }
@@ -627,28 +1344,28 @@ impl EntityExtractor {
         entities
     }
 
-    /// Extract code element entities (functions, classes, etc.).
-    fn extract_code_elements(&self, text: &str, chunk_id: &str) -> Vec<Entity> {
+    /// Extract code element entities (functions, classes, etc.) using the
+    /// definition patterns of the chunk's language.
+    fn extract_code_elements(&self, text: &str, chunk_id: &str, patterns: &[&str]) -> Vec<Entity> {
         let mut entities = Vec::new();
 
-        let code_patterns = [
-            r"(?:fn|func|function|def)\s+([a-zA-Z_][a-zA-Z0-9_]*)", // Function definitions
-            r"(?:struct|class|type|interface)\s+([A-Z][a-zA-Z0-9_]*)", // Type definitions
-            r"(?:const|let|var)\s+([A-Z_][A-Z0-9_]*)\s*=",          // Constants
-        ];
-
-        for pattern in code_patterns {
+        for pattern in patterns {
             if let Ok(re) = regex_lite::Regex::new(pattern) {
                 for cap in re.captures_iter(text) {
                     if let Some(name) = cap.get(1) {
                         let name_str = name.as_str();
                         if name_str.len() >= 2 {
-                            let mut entity = Entity::new(name_str, EntityType::CodeElement, 0.9);
+                            let mut entity = Entity::new(
+                                name_str,
+                                EntityType::CodeElement,
+                                self.config.confidence_weights.code_elements,
+                            );
                             entity.add_mention(EntityMention {
                                 chunk_id: chunk_id.to_string(),
                                 position: name.start(),
                                 matched_text: name_str.to_string(),
                                 context: self.get_context(text, name.start(), name.end()),
+                                source: None,
                             });
                             entities.push(entity);
                         }
@@ -660,9 +1377,57 @@ impl EntityExtractor {
         entities
     }
 
+    /// Extract task entities from TODO/FIXME/HACK/XXX markers.
+    ///
+    /// The marker and the text following it are stored in the `marker` and
+    /// `description` attributes.
+    fn extract_tasks(&self, text: &str, chunk_id: &str) -> Vec<Entity> {
+        let mut entities = Vec::new();
+
+        let Ok(re) =
+            regex_lite::Regex::new(r"\b(TODO|FIXME|HACK|XXX)\b(?:\([^)]*\))?:?[ \t]*([^\r\n]*)")
+        else {
+            return entities;
+        };
+
+        for cap in re.captures_iter(text) {
+            let (Some(marker), Some(description)) = (cap.get(1), cap.get(2)) else {
+                continue;
+            };
+
+            let description = description
+                .as_str()
+                .trim()
+                .trim_end_matches("*/")
+                .trim_end_matches("-->")
+                .trim();
+            if description.is_empty() {
+                continue;
+            }
+
+            let name = format!("{}: {description}", marker.as_str());
+            let mut entity = Entity::new(&name, EntityType::Task, 0.9);
+            entity.set_attribute("marker", marker.as_str());
+            entity.set_attribute("description", description);
+            entity.add_mention(EntityMention {
+                chunk_id: chunk_id.to_string(),
+                position: marker.start(),
+                matched_text: cap[0].trim_end().to_string(),
+                context: self.get_context(text, marker.start(), marker.end()),
+                source: None,
+            });
+            entities.push(entity);
+        }
+
+        entities
+    }
+
     /// Get context around a mention.
     fn get_context(&self, text: &str, start: usize, end: usize) -> Option<String> {
         let window = self.config.context_window;
+        if !self.config.store_context || window == 0 {
+            return None;
+        }
         let ctx_start = start.saturating_sub(window);
         let ctx_end = (end + window).min(text.len());
 
@@ -681,11 +1446,127 @@ impl Default for EntityExtractor {
     }
 }
 
+/// Get the code element definition patterns for a language.
+///
+/// Each pattern captures the defined name. Returns `None` for languages
+/// without patterns, whose chunks yield no code elements.
+fn code_patterns(language: &str) -> Option<&'static [&'static str]> {
+    let patterns: &'static [&'static str] = match language.to_lowercase().as_str() {
+        "rust" | "rs" => &[
+            r"\bfn\s+([a-zA-Z_][a-zA-Z0-9_]*)",
+            r"\b(?:struct|enum|trait|type|union)\s+([A-Z][a-zA-Z0-9_]*)",
+            r"\b(?:const|static)\s+([A-Z_][A-Z0-9_]*)\s*:",
+        ],
+        "python" | "py" => &[
+            r"\bdef\s+([a-zA-Z_][a-zA-Z0-9_]*)\s*\(",
+            r"\bclass\s+([A-Z][a-zA-Z0-9_]*)",
+            r"(?m)^([A-Z_][A-Z0-9_]*)\s*=",
+        ],
+        "javascript" | "js" | "jsx" => &[
+            r"\bfunction\*?\s+([a-zA-Z_$][a-zA-Z0-9_$]*)",
+            r"\bclass\s+([A-Z][a-zA-Z0-9_]*)",
+            r"\b(?:const|let|var)\s+([A-Z_][A-Z0-9_]*)\s*=",
+        ],
+        "typescript" | "ts" | "tsx" => &[
+            r"\bfunction\*?\s+([a-zA-Z_$][a-zA-Z0-9_$]*)",
+            r"\b(?:class|interface|type|enum)\s+([A-Z][a-zA-Z0-9_]*)",
+            r"\b(?:const|let|var)\s+([A-Z_][A-Z0-9_]*)\s*[:=]",
+        ],
+        "go" | "golang" => &[
+            r"\bfunc\s+(?:\([^)]*\)\s*)?([a-zA-Z_][a-zA-Z0-9_]*)",
+            r"\btype\s+([a-zA-Z_][a-zA-Z0-9_]*)\s+(?:struct|interface)",
+            r"\bconst\s+([A-Z_][A-Z0-9_]*)\s*=",
+        ],
+        "java" => &[
+            r"\b(?:class|interface|enum|record)\s+([A-Z][a-zA-Z0-9_]*)",
+            r"\b(?:public|private|protected|static)\s+[a-zA-Z0-9_<>\[\]]+\s+([a-z][a-zA-Z0-9_]*)\s*\(",
+            r"\bstatic\s+final\s+[a-zA-Z0-9_<>]+\s+([A-Z_][A-Z0-9_]*)\s*=",
+        ],
+        _ => return None,
+    };
+    Some(patterns)
+}
+
+/// Sort entities into a stable order by type, then name.
+///
+/// Names compare case-insensitively first, so case-sensitive types like
+/// code elements sort the same way as case-folded ones.
+pub(crate) fn sort_entities(entities: &mut [Entity]) {
+    entities.sort_by_cached_key(|e| {
+        (
+            e.entity_type.as_str(),
+            e.normalized_name.to_lowercase(),
+            e.normalized_name.clone(),
+            e.name.clone(),
+        )
+    });
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut curr = vec![0; b_chars.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b_chars.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use codex_embeddings::EmbeddingResponse;
     use pretty_assertions::assert_eq;
 
+    /// Provider returning fixed embeddings for known names.
+    struct StubProvider;
+
+    #[async_trait]
+    impl EmbeddingProvider for StubProvider {
+        fn name(&self) -> &str {
+            "stub"
+        }
+
+        fn default_model(&self) -> &str {
+            "stub"
+        }
+
+        fn default_dimension(&self) -> usize {
+            3
+        }
+
+        async fn embed(
+            &self,
+            request: EmbeddingRequest,
+        ) -> codex_embeddings::Result<EmbeddingResponse> {
+            let embedding = match request.text.as_str() {
+                "JavaScript" => vec![1.0, 0.0, 0.0],
+                "JS" => vec![0.98, 0.1, 0.0],
+                "Docker" => vec![0.0, 0.0, 1.0],
+                _ => vec![0.0, 1.0, 0.0],
+            };
+            Ok(EmbeddingResponse {
+                embedding,
+                model: "stub".to_string(),
+                dimension: 3,
+                tokens_used: None,
+            })
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+    }
+
     fn make_chunk(content: &str) -> Chunk {
         Chunk::new(content.to_string(), crate::chunker::ChunkType::Text)
     }
@@ -724,6 +1605,101 @@ mod tests {
         assert!(tech_names.contains(&"typescript"));
     }
 
+    #[test]
+    fn test_mention_offsets_with_non_ascii_text() {
+        // "İ" grows by a byte when lowercased, shifting lowercase offsets.
+        let text = "İstanbul's team deploys Rust from `src/main.rs`.";
+        let entities = EntityExtractor::new().extract(&[make_chunk(text)]);
+
+        let rust = entities
+            .iter()
+            .find(|e| e.entity_type == EntityType::Technology && e.normalized_name == "rust")
+            .unwrap();
+        let mention = &rust.mentions[0];
+        assert_eq!(mention.matched_text, "Rust");
+        assert_eq!(mention.position, text.find("Rust").unwrap());
+
+        let file = entities
+            .iter()
+            .find(|e| e.entity_type == EntityType::File)
+            .unwrap();
+        let mention = &file.mentions[0];
+        assert_eq!(mention.matched_text, "src/main.rs");
+        assert_eq!(mention.position, text.find("src/").unwrap());
+    }
+
+    #[test]
+    fn test_fuzzy_technology_matching() {
+        let chunks = vec![make_chunk("The dashboard frontend is written in ReactJS.")];
+
+        let exact = EntityExtractor::new().extract(&chunks);
+        assert!(!exact.iter().any(|e| e.normalized_name == "react"));
+
+        let extractor = EntityExtractor::with_config(EntityExtractorConfig {
+            fuzzy_tech_distance: 2,
+            ..Default::default()
+        });
+        let entities = extractor.extract(&chunks);
+        let react = entities
+            .iter()
+            .find(|e| e.entity_type == EntityType::Technology && e.name == "react")
+            .expect("ReactJS should map to react");
+
+        assert_eq!(react.mentions[0].matched_text, "ReactJS");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("react", "react"), 0);
+        assert_eq!(edit_distance("postgre", "postgres"), 1);
+        assert_eq!(edit_distance("reactjs", "react"), 2);
+        assert_eq!(edit_distance("", "rust"), 4);
+    }
+
+    #[test]
+    fn test_extract_task() {
+        let extractor = EntityExtractor::with_config(EntityExtractorConfig {
+            extract_tasks: true,
+            ..Default::default()
+        });
+        let chunks = vec![make_chunk(
+            "fn parse() {\n    // TODO: refactor parser\n    // FIXME(alice) handle errors */\n}",
+        )];
+
+        let entities = extractor.extract(&chunks);
+        let tasks: Vec<_> = entities
+            .iter()
+            .filter(|e| e.entity_type == EntityType::Task)
+            .collect();
+
+        assert_eq!(tasks.len(), 2);
+        let todo = tasks
+            .iter()
+            .find(|e| e.attributes.get("marker").map(String::as_str) == Some("TODO"))
+            .expect("TODO task");
+        assert_eq!(todo.name, "TODO: refactor parser");
+        assert_eq!(
+            todo.attributes.get("description").map(String::as_str),
+            Some("refactor parser")
+        );
+
+        let fixme = tasks
+            .iter()
+            .find(|e| e.attributes.get("marker").map(String::as_str) == Some("FIXME"))
+            .expect("FIXME task");
+        assert_eq!(
+            fixme.attributes.get("description").map(String::as_str),
+            Some("handle errors")
+        );
+
+        assert!(
+            !EntityExtractor::new()
+                .extract(&chunks)
+                .iter()
+                .any(|e| e.entity_type == EntityType::Task)
+        );
+    }
+
     #[test]
     fn test_extract_date() {
         let extractor = EntityExtractor::new();
@@ -788,4 +1764,359 @@ mod tests {
         assert_eq!(rust_entities.len(), 1);
         assert!(rust_entities[0].mentions.len() >= 2);
     }
+
+    #[test]
+    fn test_store_context_disabled() {
+        let chunks = vec![make_chunk(
+            "This project was created by John Smith. Built with Rust and Docker. \
+             Contact: john@example.com",
+        )];
+
+        let with_context = EntityExtractor::new().extract(&chunks);
+        assert!(
+            with_context
+                .iter()
+                .flat_map(|e| &e.mentions)
+                .any(|m| m.context.is_some())
+        );
+
+        for config in [
+            EntityExtractorConfig {
+                store_context: false,
+                ..Default::default()
+            },
+            EntityExtractorConfig {
+                context_window: 0,
+                ..Default::default()
+            },
+        ] {
+            let without_context = EntityExtractor::with_config(config).extract(&chunks);
+
+            assert!(
+                without_context
+                    .iter()
+                    .flat_map(|e| &e.mentions)
+                    .all(|m| m.context.is_none())
+            );
+
+            let summary = |entities: &[Entity]| {
+                entities
+                    .iter()
+                    .map(|e| (e.entity_type, e.normalized_name.clone(), e.mentions.len()))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(summary(&without_context), summary(&with_context));
+        }
+    }
+
+    #[test]
+    fn test_display_name_prefers_most_common_casing() {
+        let mention = |text: &str| EntityMention {
+            chunk_id: "c1".to_string(),
+            position: 0,
+            matched_text: text.to_string(),
+            context: None,
+            source: None,
+        };
+
+        let mut entity = Entity::new("postgresql", EntityType::Technology, 0.9);
+        entity.add_mention(mention("postgresql"));
+        assert_eq!(entity.display_name, "postgresql");
+
+        for text in ["PostgreSQL", "Postgresql", "PostgreSQL"] {
+            let mut other = Entity::new(text, EntityType::Technology, 0.9);
+            other.add_mention(mention(text));
+            entity.merge(other);
+        }
+
+        assert_eq!(entity.display_name, "PostgreSQL");
+        assert_eq!(entity.name, "postgresql");
+        assert_eq!(entity.normalized_name, "postgresql");
+    }
+
+    #[test]
+    fn test_display_name_defaults_to_name_on_load() {
+        let mut entity = Entity::new("PostgreSQL", EntityType::Technology, 0.9);
+        entity.display_name = "Postgres".to_string();
+        let json = serde_json::to_value(&entity).unwrap();
+        let loaded: Entity = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(loaded.display_name, "Postgres");
+
+        // Entities saved before display names existed
+        let mut old = json;
+        old.as_object_mut().unwrap().remove("display_name");
+        let loaded: Entity = serde_json::from_value(old).unwrap();
+        assert_eq!(loaded.display_name, "PostgreSQL");
+        assert_eq!(loaded.name, "PostgreSQL");
+    }
+
+    #[test]
+    fn test_merge_key_is_stable() {
+        let element = Entity::new("parse_config", EntityType::CodeElement, 0.8);
+        assert_eq!(element.merge_key(), "code_element:parse config");
+        assert_ne!(
+            element.merge_key(),
+            format!("{:?}:{}", element.entity_type, element.normalized_name)
+        );
+
+        // Same normalized name, different type: distinct keys.
+        let project = Entity::new("Parse-Config", EntityType::Project, 0.8);
+        assert_eq!(project.normalized_name, element.normalized_name);
+        assert_ne!(project.merge_key(), element.merge_key());
+
+        // Repeated mentions across chunks merge into one entity.
+        let extractor = EntityExtractor::new();
+        let chunks = vec![
+            make_chunk("Built with Rust."),
+            make_chunk("The server also uses rust for tooling."),
+        ];
+        let entities = extractor.extract(&chunks);
+        let rust: Vec<_> = entities
+            .iter()
+            .filter(|e| e.merge_key() == "technology:rust")
+            .collect();
+        assert_eq!(rust.len(), 1);
+        assert_eq!(rust[0].mentions.len(), 2);
+    }
+
+    #[test]
+    fn test_singularize_concepts() {
+        let mention = |text: &str| EntityMention {
+            chunk_id: "chunk-1".to_string(),
+            position: 0,
+            matched_text: text.to_string(),
+            context: None,
+            source: None,
+        };
+
+        let mut server = Entity::new("server", EntityType::Concept, 0.8);
+        server.add_mention(mention("server"));
+        let mut servers = Entity::new("Servers", EntityType::Concept, 0.8);
+        servers.add_mention(mention("Servers"));
+        servers.add_mention(mention("Servers"));
+
+        assert_eq!(servers.merge_key(), server.merge_key());
+        assert!(servers.is_same_as(&server));
+        server.merge(servers);
+        assert_eq!(server.normalized_name, "server");
+        assert_eq!(server.display_name, "Servers");
+
+        let normalized = |name: &str| Entity::new(name, EntityType::Concept, 0.8).normalized_name;
+        assert_eq!(normalized("business"), "business");
+        assert_eq!(normalized("technologies"), "technology");
+        assert_eq!(normalized("Build_Caches"), "build cache");
+        assert_eq!(normalized("branches"), "branch");
+        assert_eq!(normalized("status"), "status");
+
+        // Proper names and unsingularized configurations keep the plural
+        let kubernetes = Entity::new("Kubernetes", EntityType::Technology, 0.9);
+        assert_eq!(kubernetes.normalized_name, "kubernetes");
+        let literal = Entity::new("servers", EntityType::Concept, 0.8)
+            .with_singularization(Singularization::None);
+        assert_eq!(literal.normalized_name, "servers");
+    }
+
+    #[test]
+    fn test_primary_source() {
+        let sourced = |content: &str, source: &str| {
+            let mut chunk = make_chunk(content);
+            chunk.source = Some(source.to_string());
+            chunk
+        };
+        let extractor = EntityExtractor::new();
+        let chunks = vec![
+            sourced("Built with Rust.", "b.md"),
+            sourced("Rust is fast.", "a.md"),
+            sourced("More Rust here.", "b.md"),
+            make_chunk("Uses Python."),
+        ];
+        let entities = extractor.extract(&chunks);
+
+        let rust = entities
+            .iter()
+            .find(|e| e.merge_key() == "technology:rust")
+            .unwrap();
+        assert_eq!(rust.primary_source(), Some("b.md"));
+        let python = entities
+            .iter()
+            .find(|e| e.merge_key() == "technology:python")
+            .unwrap();
+        assert_eq!(python.primary_source(), None);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_dedup() {
+        let mut javascript = Entity::new("JavaScript", EntityType::Technology, 0.8);
+        javascript.add_mention(EntityMention {
+            chunk_id: "chunk-1".to_string(),
+            position: 0,
+            matched_text: "JavaScript".to_string(),
+            context: None,
+            source: None,
+        });
+        let entities = vec![
+            Entity::new("JS", EntityType::Technology, 0.9),
+            javascript,
+            Entity::new("Docker", EntityType::Technology, 0.9),
+            Entity::new("JS", EntityType::Concept, 0.9),
+        ];
+
+        let extractor = EntityExtractor::new();
+        let unchanged = extractor
+            .semantic_dedup(entities.clone(), &StubProvider)
+            .await;
+        assert_eq!(unchanged.len(), 4);
+
+        let extractor = EntityExtractor::with_config(EntityExtractorConfig {
+            semantic_dedup: true,
+            ..Default::default()
+        });
+        let deduped = extractor.semantic_dedup(entities, &StubProvider).await;
+
+        let keys: Vec<String> = deduped.iter().map(Entity::merge_key).collect();
+        assert_eq!(
+            keys,
+            vec!["concept:js", "technology:docker", "technology:javascript"]
+        );
+        let javascript = &deduped[2];
+        assert_eq!(javascript.confidence, 0.9);
+    }
+
+    #[test]
+    fn test_cross_type_resolution() {
+        let mut concept = Entity::new("python", EntityType::Concept, 0.5);
+        concept.add_mention(EntityMention {
+            chunk_id: "chunk-2".to_string(),
+            position: 0,
+            matched_text: "python".to_string(),
+            context: None,
+            source: Some("snakes.md".to_string()),
+        });
+        let entities = vec![
+            Entity::new("Python", EntityType::Technology, 0.9),
+            concept,
+            Entity::new("Rust", EntityType::Technology, 0.9),
+        ];
+
+        // Both types are kept by default, but the conflict is reported
+        let extractor = EntityExtractor::new();
+        assert_eq!(
+            extractor.cross_type_conflicts(&entities),
+            vec![CrossTypeConflict {
+                normalized_name: "python".to_string(),
+                candidates: vec![(EntityType::Technology, 0.9), (EntityType::Concept, 0.5)],
+                resolved: None,
+            }]
+        );
+        assert_eq!(extractor.resolve_cross_types(entities.clone()).len(), 3);
+
+        // Preferring the confident type collapses the concept into it
+        let extractor = EntityExtractor::with_config(EntityExtractorConfig {
+            cross_type_resolution: CrossTypeResolution::PreferHigherConfidence,
+            ..Default::default()
+        });
+        let resolved = extractor.resolve_cross_types(entities.clone());
+        let keys: Vec<String> = resolved.iter().map(Entity::merge_key).collect();
+        assert_eq!(keys, vec!["technology:python", "technology:rust"]);
+        assert_eq!(resolved[0].mentions.len(), 1);
+
+        // Close confidences are too ambiguous to collapse
+        let extractor = EntityExtractor::with_config(EntityExtractorConfig {
+            cross_type_resolution: CrossTypeResolution::PreferHigherConfidence,
+            cross_type_confidence_gap: 0.5,
+            ..Default::default()
+        });
+        assert_eq!(extractor.resolve_cross_types(entities).len(), 3);
+    }
+
+    #[test]
+    fn test_code_elements_scoped_by_language() {
+        let code = |content: &str, language: Option<&str>| {
+            let mut chunk = Chunk::new(content.to_string(), ChunkType::Code);
+            chunk.metadata.language = language.map(str::to_string);
+            chunk
+        };
+        let code_elements = |chunks: Vec<Chunk>| -> Vec<String> {
+            EntityExtractor::new()
+                .extract(&chunks)
+                .into_iter()
+                .filter(|e| e.entity_type == EntityType::CodeElement)
+                .map(|e| e.name)
+                .collect()
+        };
+
+        assert!(code_elements(vec![make_chunk("What type Of food do you like?")]).is_empty());
+        assert!(code_elements(vec![make_chunk("Let me def Inately check.")]).is_empty());
+        assert!(code_elements(vec![code("struct Foo {}", None)]).is_empty());
+        assert_eq!(
+            code_elements(vec![code("struct Foo {}", Some("rust"))]),
+            vec!["Foo"]
+        );
+        assert_eq!(
+            code_elements(vec![code(
+                "class Parser:\n    def parse(self):\n        pass",
+                Some("python")
+            )]),
+            vec!["parse", "Parser"]
+        );
+        assert!(code_elements(vec![code("fn main() {}", Some("python"))]).is_empty());
+    }
+
+    #[test]
+    fn test_case_sensitive_matching() {
+        let mut chunk = Chunk::new("struct Foo {}\nfn foo() {}".to_string(), ChunkType::Code);
+        chunk.metadata.language = Some("rust".to_string());
+        let code_elements = |extractor: EntityExtractor| -> Vec<String> {
+            let mut names: Vec<String> = extractor
+                .extract(std::slice::from_ref(&chunk))
+                .into_iter()
+                .filter(|e| e.entity_type == EntityType::CodeElement)
+                .map(|e| e.normalized_name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(code_elements(EntityExtractor::new()), vec!["Foo", "foo"]);
+
+        // Prose entities still fold case
+        let rust = Entity::new("Rust", EntityType::Technology, 0.9);
+        assert_eq!(
+            rust.merge_key(),
+            Entity::new("rust", EntityType::Technology, 0.9).merge_key()
+        );
+
+        let case_insensitive = EntityExtractor::with_config(EntityExtractorConfig {
+            case_sensitive_types: Vec::new(),
+            ..Default::default()
+        });
+        assert_eq!(code_elements(case_insensitive), vec!["foo"]);
+    }
+
+    #[test]
+    fn test_confidence_weights() {
+        let chunks = vec![make_chunk("Built with Zorblax. Deployed with Docker.")];
+        let confidence = |extractor: EntityExtractor, name: &str| {
+            extractor
+                .extract(&chunks)
+                .into_iter()
+                .find(|e| e.entity_type == EntityType::Technology && e.normalized_name == name)
+                .map(|e| e.confidence)
+        };
+
+        assert_eq!(confidence(EntityExtractor::new(), "zorblax"), Some(0.7));
+
+        let tuned = || {
+            EntityExtractor::with_config(EntityExtractorConfig {
+                confidence_weights: ConfidenceWeights {
+                    technology_pattern: 0.55,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+        };
+        assert_eq!(confidence(tuned(), "zorblax"), Some(0.55));
+        // Dictionary matches keep their own weight
+        assert_eq!(confidence(tuned(), "docker"), Some(0.9));
+    }
 }
@@ -43,6 +43,38 @@ pub enum ContextError {
     /// Invalid context file format.
     #[error("invalid format: {0}")]
     InvalidFormat(String),
+
+    /// Parent node referenced by an operation does not exist.
+    #[error("parent node not found: {0}")]
+    ParentNotFound(String),
+
+    /// Domain referenced by an operation does not exist.
+    #[error("domain not found: {0}")]
+    DomainNotFound(String),
+
+    /// LLM request did not complete within the configured timeout.
+    #[error("llm request timed out after {0:?}")]
+    LlmTimeout(std::time::Duration),
+
+    /// Detected domain is below the configured minimum confidence.
+    #[error("domain {domain} detected with confidence {confidence} below minimum {min}")]
+    DomainConfidenceTooLow {
+        domain: String,
+        confidence: f32,
+        min: f32,
+    },
+
+    /// File exceeds the configured size limit.
+    #[error("file too large: {size} bytes (limit {limit})")]
+    FileTooLarge { size: u64, limit: u64 },
+
+    /// File content cannot be processed (e.g. not valid UTF-8).
+    #[error("unsupported format: {0}")]
+    UnsupportedFormat(String),
+
+    /// Binary (MessagePack or CBOR) encoding or decoding failed.
+    #[error("binary serialization error: {0}")]
+    BinarySerialization(String),
 }
 
 /// Storage-specific errors.
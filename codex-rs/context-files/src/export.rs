@@ -0,0 +1,284 @@
+//! Export of extracted knowledge graphs to external formats.
+//!
+//! Entities and relationships from a `PipelineResult` can be exported as
+//! GraphML (for graph tools such as Gephi or yEd) or as CSV tables.
+
+use std::fmt::Write;
+
+use crate::pipeline::PipelineResult;
+
+/// Column headers for the entities CSV export.
+const ENTITY_CSV_COLUMNS: &str = "id,name,normalized_name,entity_type,confidence,mention_count";
+
+/// Column headers for the relationships CSV export.
+const RELATIONSHIP_CSV_COLUMNS: &str =
+    "id,source_id,source_name,target_id,target_name,relationship_type,confidence,evidence_count";
+
+impl PipelineResult {
+    /// Export entities (nodes) and relationships (edges) as a GraphML document.
+    pub fn export_graphml(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str("  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n");
+        out.push_str(
+            "  <key id=\"entity_type\" for=\"node\" attr.name=\"entity_type\" attr.type=\"string\"/>\n",
+        );
+        out.push_str(
+            "  <key id=\"node_confidence\" for=\"node\" attr.name=\"confidence\" attr.type=\"double\"/>\n",
+        );
+        out.push_str(
+            "  <key id=\"relationship_type\" for=\"edge\" attr.name=\"relationship_type\" attr.type=\"string\"/>\n",
+        );
+        out.push_str(
+            "  <key id=\"edge_confidence\" for=\"edge\" attr.name=\"confidence\" attr.type=\"double\"/>\n",
+        );
+        out.push_str("  <graph id=\"G\" edgedefault=\"directed\">\n");
+
+        for entity in &self.all_entities {
+            let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(&entity.id));
+            let _ = writeln!(
+                out,
+                "      <data key=\"name\">{}</data>",
+                xml_escape(&entity.name)
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"entity_type\">{}</data>",
+                entity.entity_type.as_str()
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"node_confidence\">{}</data>",
+                entity.confidence
+            );
+            out.push_str("    </node>\n");
+        }
+
+        for rel in &self.all_relationships {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+                xml_escape(&rel.id),
+                xml_escape(&rel.source_id),
+                xml_escape(&rel.target_id)
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"relationship_type\">{}</data>",
+                rel.relationship_type.as_str()
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"edge_confidence\">{}</data>",
+                rel.confidence
+            );
+            out.push_str("    </edge>\n");
+        }
+
+        out.push_str("  </graph>\n");
+        out.push_str("</graphml>\n");
+        out
+    }
+
+    /// Export entities as CSV with a header row.
+    ///
+    /// Columns: `id,name,normalized_name,entity_type,confidence,mention_count`.
+    pub fn export_entities_csv(&self) -> String {
+        let mut out = String::new();
+        out.push_str(ENTITY_CSV_COLUMNS);
+        out.push('\n');
+
+        for entity in &self.all_entities {
+            let row = [
+                csv_escape(&entity.id),
+                csv_escape(&entity.name),
+                csv_escape(&entity.normalized_name),
+                entity.entity_type.as_str().to_string(),
+                entity.confidence.to_string(),
+                entity.mentions.len().to_string(),
+            ];
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Export relationships as CSV with a header row.
+    ///
+    /// Columns: `id,source_id,source_name,target_id,target_name,relationship_type,confidence,evidence_count`.
+    pub fn export_relationships_csv(&self) -> String {
+        let mut out = String::new();
+        out.push_str(RELATIONSHIP_CSV_COLUMNS);
+        out.push('\n');
+
+        for rel in &self.all_relationships {
+            let row = [
+                csv_escape(&rel.id),
+                csv_escape(&rel.source_id),
+                csv_escape(&rel.source_name),
+                csv_escape(&rel.target_id),
+                csv_escape(&rel.target_name),
+                rel.relationship_type.as_str().to_string(),
+                rel.confidence.to_string(),
+                rel.evidence.len().to_string(),
+            ];
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+/// Escape text for use in XML content and attribute values.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab/newline are not valid XML 1.0.
+            c if c.is_control() && c != '\t' && c != '\n' && c != '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape a CSV field, quoting it when needed (RFC 4180).
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::ContextPipeline;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn run_pipeline() -> PipelineResult {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("README.md"),
+            "# Tom & Jerry's \"Server\"\n\nCreated by John Smith. Built with Rust, \
+             uses Docker <and> PostgreSQL. Contact: john@example.com\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("notes.md"),
+            "# Notes\n\nJohn Smith uses Rust and Python for scripting.\n",
+        )
+        .unwrap();
+
+        ContextPipeline::new()
+            .process_directory(temp_dir.path())
+            .unwrap()
+    }
+
+    /// Check that tags are balanced and all entity references are known.
+    fn assert_well_formed_xml(xml: &str) {
+        let mut stack: Vec<&str> = Vec::new();
+        let mut rest = xml;
+
+        while let Some(start) = rest.find('<') {
+            let text = &rest[..start];
+            assert_valid_text(text);
+
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+
+            if tag.starts_with('?') {
+                continue;
+            }
+            assert!(!tag.contains('<'), "unescaped '<' in tag: {tag}");
+            assert_valid_text(tag);
+
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(stack.pop(), Some(name.trim()));
+            } else if !tag.ends_with('/') {
+                let name = tag.split_whitespace().next().unwrap();
+                stack.push(name);
+            }
+        }
+
+        assert!(rest.trim().is_empty());
+        assert!(stack.is_empty(), "unclosed tags: {stack:?}");
+    }
+
+    fn assert_valid_text(text: &str) {
+        for (i, _) in text.match_indices('&') {
+            let entity = &text[i..];
+            assert!(
+                ["&amp;", "&lt;", "&gt;", "&quot;", "&apos;"]
+                    .iter()
+                    .any(|e| entity.starts_with(e)),
+                "unescaped '&' in: {text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_export_graphml() {
+        let result = run_pipeline();
+        assert!(result.stats.total_entities > 0);
+        assert!(result.stats.total_relationships > 0);
+
+        let graphml = result.export_graphml();
+        assert_well_formed_xml(&graphml);
+
+        assert_eq!(
+            graphml.matches("<node ").count(),
+            result.stats.total_entities
+        );
+        assert_eq!(
+            graphml.matches("<edge ").count(),
+            result.stats.total_relationships
+        );
+
+        // Every edge endpoint refers to an exported node.
+        for rel in &result.all_relationships {
+            assert!(graphml.contains(&format!("<node id=\"{}\">", rel.source_id)));
+            assert!(graphml.contains(&format!("<node id=\"{}\">", rel.target_id)));
+        }
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let result = run_pipeline();
+
+        let entities = result.export_entities_csv();
+        let mut lines = entities.lines();
+        assert_eq!(lines.next(), Some(ENTITY_CSV_COLUMNS));
+        assert_eq!(lines.count(), result.stats.total_entities);
+
+        let relationships = result.export_relationships_csv();
+        let mut lines = relationships.lines();
+        assert_eq!(lines.next(), Some(RELATIONSHIP_CSV_COLUMNS));
+        assert_eq!(lines.count(), result.stats.total_relationships);
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(
+            xml_escape("Tom & \"Jerry\" <'x'>"),
+            "Tom &amp; &quot;Jerry&quot; &lt;&apos;x&apos;&gt;"
+        );
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_escape("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -3,47 +3,95 @@
 //! This module takes the output from entity and relationship extraction
 //! and generates structured context files that can be used for retrieval.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 
-use crate::context_file::ContextFile;
+use crate::context_file::{ContentReference, ContextFile, ReferenceType};
 use crate::entity::{Entity, EntityType};
+use crate::node::ContextNode;
 use crate::relationship::{Relationship, RelationshipType};
 
 /// Configuration for context file generation.
 #[derive(Debug, Clone)]
 pub struct GeneratorConfig {
-    /// Minimum number of entities to create a context file.
+    /// Minimum number of entities to create a type-based context file.
     pub min_entities_per_context: usize,
 
+    /// Per-type overrides for `min_entities_per_context`.
+    pub min_entities_by_type: HashMap<EntityType, usize>,
+
     /// Maximum number of entities per context file.
     pub max_entities_per_context: usize,
 
+    /// Minimum cluster confidence for a context to be generated.
+    ///
+    /// Clusters below it are dropped before they can claim their entities,
+    /// so later clustering methods may still cover them. 0.0 keeps all.
+    pub min_context_confidence: f32,
+
     /// Minimum relationship strength for clustering.
     pub min_relationship_strength: f32,
 
-    /// Whether to create type-based context files (e.g., "people", "technologies").
-    pub create_type_contexts: bool,
-
-    /// Whether to create relationship-based clusters.
-    pub create_relationship_clusters: bool,
+    /// Clustering methods to run, in priority order.
+    ///
+    /// A cluster is skipped when a cluster from an earlier method already
+    /// contains all of its entities. `SingleEntity` only picks up
+    /// high-confidence entities left out of earlier clusters.
+    pub cluster_methods: Vec<ClusterMethod>,
 
     /// Source identifier for generated context files.
     pub source_id: Option<String>,
+
+    /// Whether to add references to the source chunks mentioning each
+    /// context's entities.
+    pub track_references: bool,
+
+    /// Maximum number of source references per context file.
+    pub max_references_per_context: usize,
+
+    /// Whether contexts generated from a tree node are linked to it.
+    ///
+    /// When set, `ContextGenerator::generate_for_node` records the node on
+    /// each context file (`ContextFile::node_id`) and each concept on the
+    /// node (`ContextNode::concept_ids`).
+    pub link_source_nodes: bool,
 }
 
 impl Default for GeneratorConfig {
     fn default() -> Self {
         Self {
-            min_entities_per_context: 1,
+            min_entities_per_context: 2,
+            min_entities_by_type: HashMap::from([
+                (EntityType::Project, 1),
+                (EntityType::File, 3),
+                (EntityType::Url, 3),
+            ]),
             max_entities_per_context: 50,
+            min_context_confidence: 0.0,
             min_relationship_strength: 0.3,
-            create_type_contexts: true,
-            create_relationship_clusters: true,
+            cluster_methods: vec![
+                ClusterMethod::TypeBased,
+                ClusterMethod::RelationshipBased,
+                ClusterMethod::SingleEntity,
+            ],
             source_id: None,
+            track_references: true,
+            max_references_per_context: 20,
+            link_source_nodes: true,
         }
     }
 }
 
+impl GeneratorConfig {
+    /// Get the minimum number of entities needed for a context of `entity_type`.
+    pub fn min_entities_for(&self, entity_type: EntityType) -> usize {
+        self.min_entities_by_type
+            .get(&entity_type)
+            .copied()
+            .unwrap_or(self.min_entities_per_context)
+    }
+}
+
 /// A cluster of related entities that will become a context file.
 #[derive(Debug, Clone)]
 pub struct EntityCluster {
@@ -67,7 +115,7 @@ pub struct EntityCluster {
 }
 
 /// Method used to create a cluster.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ClusterMethod {
     /// Clustered by entity type.
     TypeBased,
@@ -128,64 +176,140 @@ impl ContextGenerator {
         let entity_map: HashMap<&str, &Entity> =
             entities.iter().map(|e| (e.id.as_str(), e)).collect();
 
-        // Create type-based clusters
-        if self.config.create_type_contexts {
-            let type_clusters = self.cluster_by_type(entities);
-            for cluster in type_clusters {
-                if let Some(ctx) = self.cluster_to_context(&cluster, &entity_map, relationships) {
-                    contexts.push(ctx);
-                }
+        // Entity sets of accepted clusters, used to skip overlapping clusters
+        let mut accepted: Vec<HashSet<&str>> = Vec::new();
+        let mut seen_methods = HashSet::new();
+
+        for &method in &self.config.cluster_methods {
+            if !seen_methods.insert(method) {
+                continue;
             }
-        }
 
-        // Create relationship-based clusters
-        if self.config.create_relationship_clusters {
-            let rel_clusters = self.cluster_by_relationships(entities, relationships);
-            for cluster in rel_clusters {
-                // Avoid duplicating entities already in type clusters
-                if self.config.create_type_contexts {
-                    // Only add relationship clusters that span multiple types
-                    let types: HashSet<_> = cluster
-                        .entity_ids
-                        .iter()
-                        .filter_map(|id| entity_map.get(id.as_str()))
-                        .map(|e| &e.entity_type)
-                        .collect();
-                    if types.len() <= 1 {
-                        continue;
-                    }
+            let clusters = match method {
+                ClusterMethod::TypeBased => self.cluster_by_type(entities),
+                ClusterMethod::RelationshipBased => {
+                    self.cluster_by_relationships(entities, relationships)
+                }
+                ClusterMethod::SourceBased => self.cluster_by_source(entities),
+                ClusterMethod::SingleEntity => self.single_entity_clusters(entities),
+            };
+
+            // Clusters only compete with those from earlier methods
+            let mut method_accepted = Vec::new();
+            for cluster in clusters {
+                if cluster.confidence < self.config.min_context_confidence {
+                    continue;
+                }
+                let ids: HashSet<&str> = cluster
+                    .entity_ids
+                    .iter()
+                    .filter_map(|id| entity_map.get_key_value(id.as_str()))
+                    .map(|(&id, _)| id)
+                    .collect();
+                if accepted.iter().any(|other| ids.is_subset(other)) {
+                    continue;
                 }
                 if let Some(ctx) = self.cluster_to_context(&cluster, &entity_map, relationships) {
                     contexts.push(ctx);
+                    method_accepted.push(ids);
                 }
             }
+            accepted.extend(method_accepted);
+        }
+
+        contexts
+    }
+
+    /// Generate context files from a tree node's entities.
+    ///
+    /// Contexts and the node are linked to each other when
+    /// `link_source_nodes` is set.
+    pub fn generate_for_node(&self, node: &mut ContextNode) -> Vec<GeneratedContext> {
+        let mut contexts = self.generate(&node.entities, &[]);
+        if !self.config.link_source_nodes {
+            return contexts;
+        }
+
+        for ctx in &mut contexts {
+            ctx.context_file.node_id = Some(node.id.clone());
+            if !node.concept_ids.contains(&ctx.context_file.concept) {
+                node.concept_ids.push(ctx.context_file.concept.clone());
+            }
         }
+        contexts
+    }
 
-        // Create single-entity contexts for high-confidence entities not in clusters
-        let clustered_ids: HashSet<String> = contexts
+    /// Create single-entity clusters for high-confidence entities.
+    fn single_entity_clusters(&self, entities: &[Entity]) -> Vec<EntityCluster> {
+        entities
             .iter()
-            .flat_map(|c| c.entities.iter().map(|e| e.id.clone()))
-            .collect();
+            .filter(|entity| entity.confidence >= 0.7)
+            .map(|entity| EntityCluster {
+                id: format!("single-{}", entity.id),
+                name: entity.normalized_name.clone(),
+                entity_ids: vec![entity.id.clone()],
+                primary_type: Some(entity.entity_type),
+                cluster_method: ClusterMethod::SingleEntity,
+                confidence: entity.confidence,
+            })
+            .collect()
+    }
 
-        let mut single_entity_contexts = Vec::new();
+    /// Cluster entities by the source they are mentioned in most often.
+    fn cluster_by_source(&self, entities: &[Entity]) -> Vec<EntityCluster> {
+        let mut source_groups: BTreeMap<&str, Vec<&Entity>> = BTreeMap::new();
         for entity in entities {
-            if !clustered_ids.contains(&entity.id) && entity.confidence >= 0.7 {
-                let cluster = EntityCluster {
-                    id: format!("single-{}", entity.id),
-                    name: entity.normalized_name.clone(),
-                    entity_ids: vec![entity.id.clone()],
-                    primary_type: Some(entity.entity_type.clone()),
-                    cluster_method: ClusterMethod::SingleEntity,
-                    confidence: entity.confidence,
+            if let Some(source) = entity.primary_source() {
+                source_groups.entry(source).or_default().push(entity);
+            }
+        }
+
+        // Name clusters by file name, falling back to the full source when
+        // file names collide
+        let mut name_counts: HashMap<&str, usize> = HashMap::new();
+        for source in source_groups.keys() {
+            *name_counts.entry(source_file_name(source)).or_default() += 1;
+        }
+
+        let mut clusters = Vec::new();
+
+        for (source, group) in source_groups {
+            if group.len() < self.config.min_entities_per_context {
+                continue;
+            }
+
+            let file_name = source_file_name(source);
+            let cluster_name = if name_counts.get(file_name).copied().unwrap_or(0) > 1 {
+                source
+            } else {
+                file_name
+            };
+            let avg_confidence =
+                group.iter().map(|e| e.confidence).sum::<f32>() / group.len() as f32;
+
+            // Split large groups
+            for (i, chunk) in group
+                .chunks(self.config.max_entities_per_context)
+                .enumerate()
+            {
+                let suffix = if i > 0 {
+                    format!("-{}", i + 1)
+                } else {
+                    String::new()
                 };
-                if let Some(ctx) = self.cluster_to_context(&cluster, &entity_map, relationships) {
-                    single_entity_contexts.push(ctx);
-                }
+
+                clusters.push(EntityCluster {
+                    id: format!("source-{source}{suffix}"),
+                    name: format!("{cluster_name}{suffix}"),
+                    entity_ids: chunk.iter().map(|e| e.id.clone()).collect(),
+                    primary_type: None,
+                    cluster_method: ClusterMethod::SourceBased,
+                    confidence: avg_confidence,
+                });
             }
         }
-        contexts.extend(single_entity_contexts);
 
-        contexts
+        clusters
     }
 
     /// Cluster entities by their type.
@@ -199,10 +323,14 @@ impl ContextGenerator {
                 .push(entity);
         }
 
+        // Visit types in a stable order
+        let mut type_groups: Vec<_> = type_groups.into_iter().collect();
+        type_groups.sort_by_key(|(entity_type, _)| entity_type.as_str());
+
         let mut clusters = Vec::new();
 
         for (entity_type, group) in type_groups {
-            if group.len() < self.config.min_entities_per_context {
+            if group.len() < self.config.min_entities_for(entity_type) {
                 continue;
             }
 
@@ -222,7 +350,7 @@ impl ContextGenerator {
                 };
 
                 clusters.push(EntityCluster {
-                    id: format!("type-{:?}{}", entity_type, suffix),
+                    id: format!("type-{}{}", entity_type.as_str(), suffix),
                     name: format!("{}{}", cluster_name, suffix),
                     entity_ids: chunk.iter().map(|e| e.id.clone()).collect(),
                     primary_type: Some(entity_type.clone()),
@@ -300,9 +428,13 @@ impl ContextGenerator {
             components.entry(root).or_default().push(i);
         }
 
-        // Create clusters from components with multiple entities
+        // Create clusters from components with multiple entities, ordered by
+        // their first entity
+        let mut components: Vec<Vec<usize>> = components.into_values().collect();
+        components.sort();
+
         let mut clusters = Vec::new();
-        for (_, indices) in components {
+        for indices in components {
             if indices.len() < 2 {
                 continue; // Skip single-entity components
             }
@@ -387,6 +519,12 @@ impl ContextGenerator {
         context_file.set_structured("entity_count", serde_json::json!(entities.len()));
         context_file.set_structured("confidence", serde_json::json!(cluster.confidence));
 
+        if self.config.track_references {
+            for reference in self.source_references(&entities) {
+                context_file.add_reference(reference);
+            }
+        }
+
         // Add related concepts
         for rel in &external_relationships {
             let related_name = if entity_id_set.contains(rel.source_id.as_str()) {
@@ -406,6 +544,41 @@ impl ContextGenerator {
         })
     }
 
+    /// Build references to the source chunks mentioning `entities`.
+    ///
+    /// Each chunk is referenced once, at its earliest mention, with the
+    /// confidence of the most confident entity mentioned there. Mentions
+    /// without a source file are skipped.
+    fn source_references(&self, entities: &[Entity]) -> Vec<ContentReference> {
+        let mut by_chunk: BTreeMap<(&str, &str), ContentReference> = BTreeMap::new();
+        for entity in entities {
+            for mention in &entity.mentions {
+                let Some(source) = mention.source.as_deref() else {
+                    continue;
+                };
+                let excerpt = mention.context.as_deref().unwrap_or(&mention.matched_text);
+                let reference = by_chunk
+                    .entry((source, mention.chunk_id.as_str()))
+                    .or_insert_with(|| {
+                        ContentReference::new(ReferenceType::File, source, entity.confidence)
+                            .with_location(&mention.chunk_id, mention.position)
+                            .with_excerpt(excerpt)
+                    });
+                if mention.position < reference.offset.unwrap_or(usize::MAX) {
+                    reference.offset = Some(mention.position);
+                    reference.excerpt = Some(excerpt.to_string());
+                }
+                reference.relevance = reference.relevance.max(entity.confidence);
+            }
+        }
+
+        let mut references: Vec<ContentReference> = by_chunk.into_values().collect();
+        // Stable sort keeps references of equal relevance in source order
+        references.sort_by(|a, b| b.relevance.total_cmp(&a.relevance));
+        references.truncate(self.config.max_references_per_context);
+        references
+    }
+
     /// Generate a summary for a context.
     fn generate_summary(
         &self,
@@ -462,10 +635,11 @@ impl ContextGenerator {
         if !relationships.is_empty() {
             let rel_types: HashSet<_> =
                 relationships.iter().map(|r| &r.relationship_type).collect();
-            let rel_descriptions: Vec<_> = rel_types
+            let mut rel_descriptions: Vec<_> = rel_types
                 .iter()
                 .map(|t| relationship_type_to_name(t))
                 .collect();
+            rel_descriptions.sort_unstable();
             parts.push(format!(
                 "Contains {} relationships: {}.",
                 relationships.len(),
@@ -477,9 +651,10 @@ impl ContextGenerator {
         if entities.len() <= 3 {
             for entity in entities {
                 if !entity.attributes.is_empty() {
-                    let attrs: Vec<_> = entity
-                        .attributes
-                        .iter()
+                    let mut attributes: Vec<_> = entity.attributes.iter().collect();
+                    attributes.sort();
+                    let attrs: Vec<_> = attributes
+                        .into_iter()
                         .take(3)
                         .map(|(k, v)| format!("{}: {}", k, v))
                         .collect();
@@ -498,6 +673,14 @@ impl Default for ContextGenerator {
     }
 }
 
+/// Get the file name of a source path, or the whole source if it has none.
+fn source_file_name(source: &str) -> &str {
+    Path::new(source)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(source)
+}
+
 /// Convert entity type to a concept name.
 fn type_to_concept_name(entity_type: &EntityType) -> String {
     match entity_type {
@@ -513,6 +696,7 @@ fn type_to_concept_name(entity_type: &EntityType) -> String {
         EntityType::Concept => "concepts".to_string(),
         EntityType::File => "files".to_string(),
         EntityType::CodeElement => "code-elements".to_string(),
+        EntityType::Task => "tasks".to_string(),
     }
 }
 
@@ -531,6 +715,7 @@ fn type_to_plural_name(entity_type: &EntityType) -> &'static str {
         EntityType::Concept => "concepts",
         EntityType::File => "files",
         EntityType::CodeElement => "code elements",
+        EntityType::Task => "tasks",
     }
 }
 
@@ -549,6 +734,7 @@ fn type_to_singular_name(entity_type: &EntityType) -> &'static str {
         EntityType::Concept => "concept",
         EntityType::File => "file",
         EntityType::CodeElement => "code element",
+        EntityType::Task => "task",
     }
 }
 
@@ -582,6 +768,7 @@ mod tests {
             id: id.to_string(),
             name: name.to_string(),
             normalized_name: name.to_lowercase().replace(' ', "-"),
+            display_name: name.to_string(),
             entity_type,
             confidence: 0.8,
             mentions: vec![EntityMention {
@@ -589,6 +776,7 @@ mod tests {
                 position: 0,
                 matched_text: name.to_string(),
                 context: Some(format!("Test context for {}", name)),
+                source: None,
             }],
             attributes: HashMap::new(),
         }
@@ -644,6 +832,173 @@ mod tests {
         assert_eq!(tech_ctx.unwrap().entities.len(), 2);
     }
 
+    #[test]
+    fn test_generate_for_node_links_node() {
+        let mut node = ContextNode::new(crate::node::NodeType::Document, "team.md");
+        node.entities = vec![
+            create_test_entity("p1", "Alice", EntityType::Person),
+            create_test_entity("p2", "Bob", EntityType::Person),
+        ];
+
+        let contexts = ContextGenerator::new().generate_for_node(&mut node);
+        let people = contexts
+            .iter()
+            .find(|c| c.context_file.concept == "people")
+            .unwrap();
+        assert_eq!(
+            people.context_file.node_id.as_deref(),
+            Some(node.id.as_str())
+        );
+        assert!(node.concept_ids.contains(&"people".to_string()));
+
+        let unlinked = ContextGenerator::with_config(GeneratorConfig {
+            link_source_nodes: false,
+            ..Default::default()
+        })
+        .generate_for_node(&mut node);
+        assert!(unlinked.iter().all(|c| c.context_file.node_id.is_none()));
+    }
+
+    #[test]
+    fn test_min_context_confidence() {
+        let mut entities = vec![
+            create_test_entity("p1", "Alice", EntityType::Person),
+            create_test_entity("p2", "Bob", EntityType::Person),
+            create_test_entity("t1", "Rust", EntityType::Technology),
+            create_test_entity("t2", "Python", EntityType::Technology),
+        ];
+        entities[0].confidence = 0.9;
+        entities[1].confidence = 0.9;
+        entities[2].confidence = 0.4;
+        entities[3].confidence = 0.4;
+        let concepts = |min_context_confidence: f32| -> Vec<String> {
+            let config = GeneratorConfig {
+                min_context_confidence,
+                cluster_methods: vec![ClusterMethod::TypeBased],
+                ..Default::default()
+            };
+            ContextGenerator::with_config(config)
+                .generate(&entities, &[])
+                .into_iter()
+                .map(|c| c.context_file.concept)
+                .collect()
+        };
+
+        assert_eq!(concepts(0.0), vec!["people", "technologies"]);
+        assert_eq!(concepts(0.5), vec!["people"]);
+        assert!(concepts(0.95).is_empty());
+    }
+
+    #[test]
+    fn test_type_context_thresholds() {
+        let entities = vec![
+            create_test_entity("f1", "main.rs", EntityType::File),
+            create_test_entity("proj1", "MyProject", EntityType::Project),
+        ];
+
+        let generator = ContextGenerator::new();
+        let contexts = generator.generate(&entities, &[]);
+        let concepts: Vec<&str> = contexts
+            .iter()
+            .filter(|c| c.cluster_method == ClusterMethod::TypeBased)
+            .map(|c| c.context_file.concept.as_str())
+            .collect();
+
+        assert_eq!(concepts, vec!["projects"]);
+    }
+
+    #[test]
+    fn test_source_based_clustering() {
+        let sourced = |id: &str, name: &str, entity_type, source: &str| {
+            let mut entity = create_test_entity(id, name, entity_type);
+            entity.mentions[0].source = Some(source.to_string());
+            entity
+        };
+        let entities = vec![
+            sourced("p1", "Alice", EntityType::Person, "/docs/a/README.md"),
+            sourced("t1", "Rust", EntityType::Technology, "/docs/a/README.md"),
+            sourced("t2", "Python", EntityType::Technology, "/docs/b/README.md"),
+            sourced("o1", "Acme", EntityType::Organization, "/docs/b/README.md"),
+            sourced("c1", "Caching", EntityType::Concept, "/docs/notes.md"),
+            create_test_entity("x1", "Unsourced", EntityType::Concept),
+        ];
+
+        let config = GeneratorConfig {
+            cluster_methods: vec![ClusterMethod::SourceBased],
+            ..Default::default()
+        };
+        let generator = ContextGenerator::with_config(config);
+        let contexts = generator.generate(&entities, &[]);
+
+        let groups: Vec<(&str, Vec<&str>)> = contexts
+            .iter()
+            .map(|c| {
+                assert_eq!(c.cluster_method, ClusterMethod::SourceBased);
+                let mut ids: Vec<&str> = c.entities.iter().map(|e| e.id.as_str()).collect();
+                ids.sort_unstable();
+                (c.context_file.concept.as_str(), ids)
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("/docs/a/README.md", vec!["p1", "t1"]),
+                ("/docs/b/README.md", vec!["o1", "t2"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_references() {
+        let sourced = |id: &str, name: &str, source: &str, chunk_id: &str, position| {
+            let mut entity = create_test_entity(id, name, EntityType::Technology);
+            entity.mentions[0].source = Some(source.to_string());
+            entity.mentions[0].chunk_id = chunk_id.to_string();
+            entity.mentions[0].position = position;
+            entity
+        };
+        let entities = vec![
+            sourced("t1", "Rust", "/docs/a.md", "chunk-a", 12),
+            sourced("t2", "Python", "/docs/b.md", "chunk-b", 40),
+            sourced("t3", "Go", "/docs/a.md", "chunk-a", 3),
+            create_test_entity("t4", "Unsourced", EntityType::Technology),
+        ];
+
+        let contexts = ContextGenerator::new().generate(&entities, &[]);
+        let tech_ctx = contexts
+            .iter()
+            .find(|c| c.context_file.concept == "technologies")
+            .unwrap();
+        let references: Vec<(&str, Option<&str>, Option<usize>)> = tech_ctx
+            .context_file
+            .content
+            .references
+            .iter()
+            .map(|r| {
+                assert_eq!(r.reference_type, ReferenceType::File);
+                (r.path.as_str(), r.chunk_id.as_deref(), r.offset)
+            })
+            .collect();
+        assert_eq!(
+            references,
+            vec![
+                ("/docs/a.md", Some("chunk-a"), Some(3)),
+                ("/docs/b.md", Some("chunk-b"), Some(40)),
+            ]
+        );
+
+        let generator = ContextGenerator::with_config(GeneratorConfig {
+            track_references: false,
+            ..Default::default()
+        });
+        let contexts = generator.generate(&entities, &[]);
+        assert!(
+            contexts
+                .iter()
+                .all(|c| c.context_file.content.references.is_empty())
+        );
+    }
+
     #[test]
     fn test_relationship_based_clustering() {
         let entities = vec![
@@ -657,9 +1012,10 @@ mod tests {
             create_test_relationship(&entities[1], &entities[2], RelationshipType::Uses),
         ];
 
-        let mut config = GeneratorConfig::default();
-        config.create_type_contexts = false;
-        config.create_relationship_clusters = true;
+        let config = GeneratorConfig {
+            cluster_methods: vec![ClusterMethod::RelationshipBased],
+            ..Default::default()
+        };
 
         let generator = ContextGenerator::with_config(config);
         let contexts = generator.generate(&entities, &relationships);
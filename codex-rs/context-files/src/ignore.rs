@@ -0,0 +1,135 @@
+//! `.codexignore` support for directory traversal.
+//!
+//! A `.codexignore` file uses gitignore syntax to exclude paths from context
+//! indexing without affecting git. Files are honored in the processed
+//! directory and in any subdirectory, with deeper files taking precedence.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
+use tracing::warn;
+
+/// Name of the ignore file honored during traversal.
+pub const IGNORE_FILE_NAME: &str = ".codexignore";
+
+/// Layered ignore rules collected while descending a directory tree.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    files: Vec<Arc<Gitignore>>,
+}
+
+impl IgnoreRules {
+    /// Create an empty rule set that ignores nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the rules that apply inside `dir`, starting from `dir` itself.
+    pub fn for_root(dir: &Path) -> Self {
+        Self::new().descend(dir)
+    }
+
+    /// Return the rules that apply inside `dir`, adding its ignore file if present.
+    pub fn descend(&self, dir: &Path) -> Self {
+        let path = dir.join(IGNORE_FILE_NAME);
+        if !path.is_file() {
+            return self.clone();
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        if let Some(e) = builder.add(&path) {
+            warn!("Failed to read {}: {e}", path.display());
+        }
+        self.with_builder(builder)
+    }
+
+    /// Return these rules with gitignore-style `contents` layered on top,
+    /// relative to `base`.
+    pub fn with_patterns(&self, base: &Path, contents: &str) -> Self {
+        let mut builder = GitignoreBuilder::new(base);
+        for line in contents.lines() {
+            if let Err(e) = builder.add_line(None, line) {
+                warn!("Invalid ignore pattern {line:?}: {e}");
+            }
+        }
+        self.with_builder(builder)
+    }
+
+    /// Return these rules with the patterns collected in `builder` on top.
+    fn with_builder(&self, builder: GitignoreBuilder) -> Self {
+        let mut rules = self.clone();
+        match builder.build() {
+            Ok(gitignore) if !gitignore.is_empty() => rules.files.push(Arc::new(gitignore)),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to build ignore rules: {e}"),
+        }
+        rules
+    }
+
+    /// Check whether a path should be skipped.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        // Deeper files come later and take precedence; within a file, the
+        // last matching pattern wins.
+        self.files
+            .iter()
+            .rev()
+            .filter(|file| path.starts_with(file.path()))
+            .map(|file| file.matched(path, is_dir))
+            .find(|m| !m.is_none())
+            .is_some_and(|m| m.is_ignore())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn rules(contents: &str) -> IgnoreRules {
+        IgnoreRules::new().with_patterns(Path::new("/root"), contents)
+    }
+
+    #[test]
+    fn test_gitignore_syntax() {
+        let rules = rules(
+            "# comment\n\
+             secrets.md\n\
+             *.log\n\
+             build/\n\
+             /top.md\n\
+             docs/**/draft-?.md\n\
+             !keep.log\n\
+             \\#literal.md\n\
+             report-[0-9].md\n\
+             /**/cache\n",
+        );
+        let ignored = |path: &str, is_dir| rules.is_ignored(&PathBuf::from(path), is_dir);
+
+        assert!(ignored("/root/secrets.md", false));
+        assert!(ignored("/root/nested/secrets.md", false));
+        assert!(ignored("/root/debug.log", false));
+        assert!(!ignored("/root/keep.log", false));
+        assert!(ignored("/root/build", true));
+        assert!(!ignored("/root/build", false));
+        assert!(ignored("/root/top.md", false));
+        assert!(!ignored("/root/nested/top.md", false));
+        assert!(ignored("/root/docs/a/b/draft-1.md", false));
+        assert!(ignored("/root/docs/draft-2.md", false));
+        assert!(!ignored("/root/docs/draft-10.md", false));
+        assert!(!ignored("/elsewhere/secrets.md", false));
+        assert!(ignored("/root/#literal.md", false));
+        assert!(ignored("/root/report-7.md", false));
+        assert!(!ignored("/root/report-x.md", false));
+        assert!(ignored("/root/cache", true));
+        assert!(ignored("/root/a/b/cache", true));
+    }
+
+    #[test]
+    fn test_deeper_rules_take_precedence() {
+        let rules = rules("*.md\n").with_patterns(Path::new("/root/keep"), "!*.md\n");
+
+        assert!(rules.is_ignored(Path::new("/root/notes.md"), false));
+        assert!(!rules.is_ignored(Path::new("/root/keep/notes.md"), false));
+    }
+}
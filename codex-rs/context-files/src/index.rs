@@ -4,12 +4,25 @@
 //! efficient retrieval based on keywords, tags, and relationships.
 
 use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
 
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tracing::{debug, info, warn};
 
 use crate::concept::{Concept, ConceptRelation, RelationType};
-use crate::error::Result;
+use crate::entity::edit_distance;
+use crate::error::{Result, StorageError};
+use crate::storage::ContextStore;
+
+/// Longest prefix (in chars) that also gets fuzzy completions.
+const MAX_FUZZY_PREFIX_LEN: usize = 4;
+
+/// Maximum edit distance between a prefix and a fuzzy completion's head.
+const MAX_FUZZY_DISTANCE: usize = 1;
+
+/// Format version of saved indexes; other versions are rebuilt on load.
+const INDEX_FORMAT_VERSION: u32 = 1;
 
 /// An inverted index for concept lookup.
 ///
@@ -42,6 +55,91 @@ impl ConceptIndex {
         Self::default()
     }
 
+    /// Build an index of every context file in a store.
+    ///
+    /// Each file contributes its concept, its tags, and a `RelatedTo`
+    /// relation to each of its related concepts.
+    pub fn from_store(store: &ContextStore) -> Self {
+        let mut files: Vec<_> = store.all().collect();
+        files.sort_by(|a, b| a.concept.cmp(&b.concept));
+
+        let mut index = Self::new();
+        for cf in files {
+            index.add_concept(Concept::new(&cf.concept));
+            for tag in &cf.metadata.tags {
+                index.add_tag(&cf.concept, tag);
+            }
+            for related in &cf.metadata.related_concepts {
+                index.add_relation(ConceptRelation::new(
+                    &cf.concept,
+                    related,
+                    RelationType::RelatedTo,
+                ));
+            }
+        }
+        index
+    }
+
+    /// Save the index, with a fingerprint of the store it was built from.
+    ///
+    /// The file is written to a temporary path first and then renamed, so
+    /// an interrupted save leaves any previous index intact.
+    pub fn save(&self, path: &Path, store: &ContextStore) -> Result<()> {
+        let saved = SavedIndex {
+            version: INDEX_FORMAT_VERSION,
+            fingerprint: IndexFingerprint::of(store),
+            index: self,
+        };
+        let content = serde_json::to_string(&saved)?;
+
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, content)
+            .map_err(|e| StorageError::WriteFile(format!("{}: {e}", temp_path.display())))?;
+        std::fs::rename(&temp_path, path)
+            .map_err(|e| StorageError::WriteFile(format!("{}: {e}", path.display())))?;
+
+        debug!("Saved concept index to {}", path.display());
+        Ok(())
+    }
+
+    /// Load a saved index, rebuilding it from the store if it is stale.
+    ///
+    /// The saved index is used only if it was built from the same set of
+    /// context files; a missing, unreadable, or outdated index is rebuilt
+    /// with [`Self::from_store`].
+    pub fn load(path: &Path, store: &ContextStore) -> Result<(Self, IndexLoad)> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok((Self::from_store(store), IndexLoad::Rebuilt));
+            }
+            Err(e) => {
+                return Err(StorageError::ReadFile(format!("{}: {e}", path.display())).into());
+            }
+        };
+
+        match serde_json::from_str::<SavedIndex<Self>>(&content) {
+            Ok(saved)
+                if saved.version == INDEX_FORMAT_VERSION
+                    && saved.fingerprint == IndexFingerprint::of(store) =>
+            {
+                debug!("Loaded concept index from {}", path.display());
+                Ok((saved.index, IndexLoad::Loaded))
+            }
+            Ok(_) => {
+                info!("Concept index at {} is stale, rebuilding", path.display());
+                Ok((Self::from_store(store), IndexLoad::Rebuilt))
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to parse concept index {}: {e}, rebuilding",
+                    path.display()
+                );
+                Ok((Self::from_store(store), IndexLoad::Rebuilt))
+            }
+        }
+    }
+
     /// Add a concept to the index.
     pub fn add_concept(&mut self, concept: Concept) {
         let name = concept.name.clone();
@@ -223,6 +321,65 @@ impl ConceptIndex {
         }
     }
 
+    /// Suggest completions for a partially typed query term.
+    ///
+    /// Keywords and tags starting with `prefix` are ranked by the number of
+    /// concepts they appear in. Short prefixes also get fuzzy completions
+    /// (terms whose head is one edit away from the prefix), ranked after
+    /// exact prefix matches. This scans the term lists once, so it is cheap
+    /// enough to call on every keystroke.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let prefix = prefix.trim().to_lowercase();
+        if prefix.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let prefix_len = prefix.chars().count();
+        let allow_fuzzy = prefix_len <= MAX_FUZZY_PREFIX_LEN;
+
+        // Term -> concepts containing it, across keywords and tags.
+        let mut terms: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for (term, names) in self.keyword_index.iter().chain(&self.tag_index) {
+            terms
+                .entry(term.as_str())
+                .or_default()
+                .extend(names.iter().map(String::as_str));
+        }
+
+        let mut suggestions: Vec<Suggestion> = terms
+            .into_iter()
+            .filter(|(_, names)| !names.is_empty())
+            .filter_map(|(term, names)| {
+                let fuzzy = if term.starts_with(&prefix) {
+                    false
+                } else if allow_fuzzy && term.chars().count() > prefix_len {
+                    let head: String = term.chars().take(prefix_len).collect();
+                    if edit_distance(&head, &prefix) > MAX_FUZZY_DISTANCE {
+                        return None;
+                    }
+                    true
+                } else {
+                    return None;
+                };
+
+                Some(Suggestion {
+                    term: term.to_string(),
+                    frequency: names.len(),
+                    fuzzy,
+                })
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| {
+            a.fuzzy
+                .cmp(&b.fuzzy)
+                .then_with(|| b.frequency.cmp(&a.frequency))
+                .then_with(|| a.term.cmp(&b.term))
+        });
+        suggestions.truncate(limit);
+        suggestions
+    }
+
     /// Extract keywords from a string for indexing.
     fn extract_keywords(text: &str) -> Vec<String> {
         text.to_lowercase()
@@ -243,6 +400,65 @@ impl ConceptIndex {
     }
 }
 
+/// How [`ConceptIndex::load`] produced an index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexLoad {
+    /// The saved index matched the store and was used as is.
+    Loaded,
+
+    /// The index was rebuilt from the store.
+    Rebuilt,
+}
+
+/// Identifies the set of context files an index was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct IndexFingerprint {
+    /// Number of context files.
+    count: usize,
+
+    /// Hash of each file's concept, ID, and version.
+    hash: u64,
+}
+
+impl IndexFingerprint {
+    /// Fingerprint the context files currently in a store.
+    fn of(store: &ContextStore) -> Self {
+        let mut files: Vec<_> = store
+            .all()
+            .map(|cf| (&cf.concept, &cf.id, cf.metadata.version))
+            .collect();
+        files.sort();
+
+        let mut hasher = DefaultHasher::new();
+        files.hash(&mut hasher);
+        Self {
+            count: files.len(),
+            hash: hasher.finish(),
+        }
+    }
+}
+
+/// On-disk form of a saved concept index.
+#[derive(Serialize, Deserialize)]
+struct SavedIndex<I> {
+    version: u32,
+    fingerprint: IndexFingerprint,
+    index: I,
+}
+
+/// A completion suggested for a partially typed query term.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suggestion {
+    /// The suggested term.
+    pub term: String,
+
+    /// Number of concepts the term appears in.
+    pub frequency: usize,
+
+    /// Whether the term is a fuzzy match rather than an exact prefix match.
+    pub fuzzy: bool,
+}
+
 /// Statistics about the concept index.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexStats {
@@ -289,4 +505,104 @@ mod tests {
         let children = index.get_children("hobbies");
         assert_eq!(children.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut store = ContextStore::new(temp_dir.path().join("context"))
+            .await
+            .unwrap();
+        for (concept, tag) in [
+            ("work-projects", "work"),
+            ("work-experience", "work"),
+            ("personal-projects", "home"),
+        ] {
+            let mut cf = crate::context_file::ContextFile::new(concept, "");
+            cf.metadata.tags.push(tag.to_string());
+            store.upsert(cf).await.unwrap();
+        }
+
+        let mut index = ConceptIndex::from_store(&store);
+        // Not derivable from the store, so only present if the save is used
+        index.add_relation(ConceptRelation::new(
+            "work-projects",
+            "personal-projects",
+            RelationType::DependsOn,
+        ));
+        let path = temp_dir.path().join("concepts.index");
+        index.save(&path, &store).unwrap();
+
+        let names = |concepts: Vec<&Concept>| {
+            let mut names: Vec<String> = concepts.iter().map(|c| c.name.clone()).collect();
+            names.sort();
+            names
+        };
+        let (loaded, how) = ConceptIndex::load(&path, &store).unwrap();
+        assert_eq!(how, IndexLoad::Loaded);
+        assert_eq!(
+            names(loaded.find_by_keyword("projects")),
+            names(index.find_by_keyword("projects"))
+        );
+        assert_eq!(
+            names(loaded.find_by_tag("work")),
+            names(index.find_by_tag("work"))
+        );
+        assert_eq!(loaded.get_related("personal-projects").len(), 1);
+        assert_eq!(loaded.stats().keyword_count, index.stats().keyword_count);
+
+        // Adding a context file makes the saved index stale
+        store
+            .upsert(crate::context_file::ContextFile::new("recipes", ""))
+            .await
+            .unwrap();
+        let (rebuilt, how) = ConceptIndex::load(&path, &store).unwrap();
+        assert_eq!(how, IndexLoad::Rebuilt);
+        assert!(rebuilt.contains("recipes"));
+        assert!(rebuilt.get_related("personal-projects").is_empty());
+
+        let (missing, how) =
+            ConceptIndex::load(&temp_dir.path().join("missing.index"), &store).unwrap();
+        assert_eq!(how, IndexLoad::Rebuilt);
+        assert_eq!(missing.stats().concept_count, 4);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut index = ConceptIndex::new();
+        index.add_concept(Concept::new("context-files"));
+        index.add_concept(Concept::new("context-window"));
+        index.add_concept(Concept::new("context-tree"));
+        index.add_concept(Concept::new("concept-maps"));
+        index.add_concept(Concept::new("concept-art"));
+        index.add_concept(Concept::new("config"));
+        index.add_concept(Concept::new("cooking"));
+
+        let terms: Vec<(String, usize)> = index
+            .suggest("con", 10)
+            .into_iter()
+            .filter(|s| !s.fuzzy)
+            .map(|s| (s.term, s.frequency))
+            .collect();
+        assert_eq!(
+            terms,
+            vec![
+                ("context".to_string(), 3),
+                ("concept".to_string(), 2),
+                ("config".to_string(), 1),
+            ]
+        );
+
+        // Exact prefix matches rank ahead of fuzzy completions.
+        let suggestions = index.suggest("con", 10);
+        assert!(suggestions.iter().any(|s| s.fuzzy && s.term == "cooking"));
+        assert!(!suggestions[..3].iter().any(|s| s.fuzzy));
+
+        // Typos in short prefixes still complete.
+        let suggestions = index.suggest("cin", 1);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].term, "context");
+        assert!(suggestions[0].fuzzy);
+
+        assert!(index.suggest("", 10).is_empty());
+    }
 }
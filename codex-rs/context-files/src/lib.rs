@@ -53,6 +53,7 @@ pub mod concept;
 pub mod context_file;
 pub mod error;
 pub mod extraction;
+pub mod id;
 pub mod index;
 pub mod query;
 pub mod retrieval;
@@ -61,10 +62,16 @@ pub mod sync;
 
 // Context generation pipeline modules
 pub mod chunker;
+pub mod config_file;
+pub mod encoding;
 pub mod entity;
+pub mod export;
 pub mod generator;
+pub mod ignore;
+pub mod links;
 pub mod pipeline;
 pub mod relationship;
+pub mod snapshot;
 
 // Agentic context system modules
 pub mod agent;
@@ -79,20 +86,29 @@ pub use concept::{Concept, ConceptRelation, RelationType};
 pub use context_file::{ContentReference, ContextFile, ContextMetadata, ReferenceType};
 pub use error::{ContextError, Result};
 pub use extraction::ConceptExtractor;
-pub use index::ConceptIndex;
+pub use id::{IdGenerator, SeededIdGenerator, UuidIdGenerator};
+pub use index::{ConceptIndex, IndexLoad, Suggestion};
 pub use query::{Query, QueryIntent, QueryResult};
-pub use retrieval::RetrievalEngine;
+pub use retrieval::{ChunkResult, RetrievalEngine};
 pub use storage::ContextStore;
 pub use sync::SyncManager;
 
 // Pipeline re-exports
-pub use chunker::{Chunk, ChunkMetadata, ChunkType, ChunkerConfig, SemanticChunker};
-pub use entity::{Entity, EntityExtractor, EntityExtractorConfig, EntityMention, EntityType};
+pub use chunker::{Chunk, ChunkMetadata, ChunkReader, ChunkType, ChunkerConfig, SemanticChunker};
+pub use config_file::{ConfigFileParser, ConfigFormat, ParsedConfig};
+pub use encoding::DecodedText;
+pub use entity::{
+    ConfidenceWeights, CrossTypeConflict, CrossTypeResolution, Entity, EntityExtractor,
+    EntityExtractorConfig, EntityMention, EntityType, Singularization,
+};
 pub use generator::{
     ClusterMethod, ContextGenerator, EntityCluster, GeneratedContext, GeneratorConfig,
 };
+pub use ignore::{IGNORE_FILE_NAME, IgnoreRules};
+pub use links::{MarkdownLinkExtractor, ParsedLinks};
 pub use pipeline::{
-    ContextPipeline, DocumentResult, PipelineBuilder, PipelineConfig, PipelineResult, PipelineStats,
+    ContextPipeline, DocumentResult, FileOrder, IncrementalResult, PipelineBuilder, PipelineConfig,
+    PipelineResult, PipelineStats, ProgressHandle, QualityMetrics,
 };
 pub use relationship::{
     EvidenceType, Relationship, RelationshipEvidence, RelationshipExtractor,
@@ -100,11 +116,22 @@ pub use relationship::{
 };
 
 // Agentic system re-exports
-pub use agent::{AgentBuilder, AgentConfig, AgentQueryResult, ContextAgent, ProcessingResult};
-pub use llm::{AnalysisContext, LlmAnalyzer, LlmConfig};
+pub use agent::{
+    AgentBuilder, AgentConfig, AgentQueryOptions, AgentQueryResult, ContextAgent,
+    FacetedQueryResult, NodeTypePolicy, ProcessingResult, QueryFacets, RelatedResult,
+    SharedContextAgent,
+};
+pub use llm::{AnalysisContext, LlmAnalyzer, LlmClient, LlmConfig, PromptTemplates, SummaryStyle};
 pub use node::{
-    ContextNode, CrossLinkType, DocumentAnalysis, DomainDetection, NodeType, RelatedNode,
+    ContextNode, CrossLinkType, DocumentAnalysis, DocumentHeading, DomainDetection, NodeSummary,
+    NodeType, RelatedNode,
+};
+pub use optimizer::{
+    BranchCompression, MergeGroup, OptimizationAnalysis, OptimizationPlan, OptimizationResult,
+    OptimizerConfig, TreeOptimizer,
+};
+pub use tree::{
+    ContextTree, CrossDomainLink, SearchConfig, TreeIndex, TreeInvariantViolation, TreeMutation,
+    TreeStats,
 };
-pub use optimizer::{OptimizationAnalysis, OptimizationResult, OptimizerConfig, TreeOptimizer};
-pub use tree::{ContextTree, TreeStats};
-pub use tree_storage::{TreeStore, TreeVisualization};
+pub use tree_storage::{TreeStore, TreeStoreFormat, TreeVisualization};
@@ -0,0 +1,249 @@
+//! Extraction of explicit links from markdown documents.
+//!
+//! Markdown links (`[text](target)`) state a reference outright, so they
+//! don't need to be inferred from prose. `MarkdownLinkExtractor` turns links
+//! to files that exist next to the document into `References` edges between
+//! the document and the target file, and links to external URLs into
+//! `References` edges to a URL entity.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use tracing::debug;
+
+use crate::chunker::{Chunk, ChunkType};
+use crate::entity::{Entity, EntityMention, EntityType};
+use crate::relationship::{EvidenceType, Relationship, RelationshipEvidence, RelationshipType};
+
+/// Confidence of entities and relationships read from explicit links.
+const LINK_CONFIDENCE: f32 = 0.9;
+
+/// Markdown inline link: `[text](target)` or `[text](<target> "title")`.
+const LINK_PATTERN: &str = r#"\[([^\]]*)\]\(<?([^)\s>]+)>?(?:\s+"[^"]*")?\)"#;
+
+/// Entities and relationships extracted from a document's links.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedLinks {
+    /// The linking document followed by each distinct link target.
+    pub entities: Vec<Entity>,
+
+    /// `References` relationships from the document to each target.
+    pub relationships: Vec<Relationship>,
+}
+
+impl ParsedLinks {
+    /// Add these entities to `entities`, reusing any already present.
+    ///
+    /// Returns the relationships pointed at the surviving entity IDs.
+    pub fn merge_into(self, entities: &mut Vec<Entity>) -> Vec<Relationship> {
+        let mut id_map: HashMap<String, String> = HashMap::new();
+        for entity in self.entities {
+            let key = entity.merge_key();
+            match entities.iter().find(|e| e.merge_key() == key) {
+                Some(existing) => {
+                    id_map.insert(entity.id, existing.id.clone());
+                }
+                None => entities.push(entity),
+            }
+        }
+
+        self.relationships
+            .into_iter()
+            .map(|mut rel| {
+                if let Some(id) = id_map.get(&rel.source_id) {
+                    rel.source_id = id.clone();
+                }
+                if let Some(id) = id_map.get(&rel.target_id) {
+                    rel.target_id = id.clone();
+                }
+                rel
+            })
+            .collect()
+    }
+}
+
+/// Extractor for links in markdown documents.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownLinkExtractor;
+
+impl MarkdownLinkExtractor {
+    /// Create a new link extractor.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Check whether `source` is a markdown file.
+    pub fn is_markdown(source: &Path) -> bool {
+        source
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "md" | "markdown"))
+    }
+
+    /// Extract links from the chunks of the markdown document at `source`.
+    ///
+    /// Relative link targets are resolved against the document's directory
+    /// and only kept if they name an existing file. Links inside code
+    /// blocks, images, and same-document anchors are ignored.
+    pub fn extract(&self, source: &Path, chunks: &[Chunk]) -> ParsedLinks {
+        let Ok(re) = regex_lite::Regex::new(LINK_PATTERN) else {
+            return ParsedLinks::default();
+        };
+        let base_dir = source.parent().unwrap_or(Path::new(""));
+
+        // One entity and relationship per target, in a stable order
+        let mut targets: BTreeMap<String, (Entity, Vec<RelationshipEvidence>)> = BTreeMap::new();
+        for chunk in chunks.iter().filter(|c| c.chunk_type != ChunkType::Code) {
+            for caps in re.captures_iter(&chunk.content) {
+                let (Some(link), Some(target)) = (caps.get(0), caps.get(2)) else {
+                    continue;
+                };
+                if chunk.content[..link.start()].ends_with('!') {
+                    continue;
+                }
+                let Some((name, entity_type)) = resolve_target(base_dir, target.as_str()) else {
+                    continue;
+                };
+
+                let (entity, evidence) = targets.entry(name.clone()).or_insert_with(|| {
+                    (Entity::new(&name, entity_type, LINK_CONFIDENCE), Vec::new())
+                });
+                // Chunk overlap repeats text, so identical links count once
+                if evidence.iter().any(|e| e.text == link.as_str()) {
+                    continue;
+                }
+                entity.add_mention(EntityMention {
+                    chunk_id: chunk.id.clone(),
+                    position: target.start(),
+                    matched_text: target.as_str().to_string(),
+                    context: Some(link.as_str().to_string()),
+                    source: chunk.source.clone(),
+                });
+                evidence.push(RelationshipEvidence {
+                    evidence_type: EvidenceType::Structural,
+                    text: link.as_str().to_string(),
+                    chunk_id: Some(chunk.id.clone()),
+                    confidence_contribution: LINK_CONFIDENCE,
+                });
+            }
+        }
+
+        let mut parsed = ParsedLinks::default();
+        if targets.is_empty() {
+            return parsed;
+        }
+        debug!("Found {} link targets in {source:?}", targets.len());
+
+        let document = Entity::new(file_name(source), EntityType::File, LINK_CONFIDENCE);
+        for (target, evidence) in targets.into_values() {
+            let mut rel = Relationship::new(
+                &document,
+                &target,
+                RelationshipType::References,
+                LINK_CONFIDENCE,
+            );
+            for item in evidence {
+                rel.add_evidence(item);
+            }
+            parsed.relationships.push(rel);
+            parsed.entities.push(target);
+        }
+        parsed.entities.insert(0, document);
+
+        parsed
+    }
+}
+
+/// Resolve a link target to an entity name and type.
+///
+/// External `http(s)` links become URL entities; relative or absolute paths
+/// become file entities named by their canonical path when the file exists.
+fn resolve_target(base_dir: &Path, target: &str) -> Option<(String, EntityType)> {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        return Some((target.to_string(), EntityType::Url));
+    }
+    // Other schemes (mailto:, ftp:) aren't files in the repository
+    if target.contains("://") || target.starts_with("mailto:") {
+        return None;
+    }
+
+    let path = target.split(['#', '?']).next().unwrap_or_default();
+    if path.is_empty() {
+        return None;
+    }
+    let path = Path::new(path);
+    let resolved = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base_dir.join(path)
+    };
+    if !resolved.is_file() {
+        return None;
+    }
+    Some((file_name(&resolved), EntityType::File))
+}
+
+/// Name a file entity by its canonical path, falling back to the path given.
+fn file_name(path: &Path) -> String {
+    path.canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(path))
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::SemanticChunker;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_extract_markdown_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let readme = temp_dir.path().join("README.md");
+        let setup = temp_dir.path().join("docs").join("setup.md");
+        std::fs::create_dir(temp_dir.path().join("docs")).unwrap();
+        std::fs::write(&setup, "# Setup").unwrap();
+
+        let content = "# Project\n\n\
+                       Follow the [setup](./docs/setup.md) guide, see [Rust](https://www.rust-lang.org),\n\
+                       or jump to [usage](#usage). [Old notes](./notes.md) were removed.\n\n\
+                       ![logo](./docs/setup.md)\n";
+        let source = readme.to_string_lossy().to_string();
+        let chunks = SemanticChunker::new().chunk_with_source(content, &source);
+        let parsed = MarkdownLinkExtractor::new().extract(&readme, &chunks);
+
+        let setup_name = setup.canonicalize().unwrap().to_string_lossy().to_string();
+        let references: Vec<(&str, RelationshipType)> = parsed
+            .relationships
+            .iter()
+            .map(|r| (r.target_name.as_str(), r.relationship_type))
+            .collect();
+        assert_eq!(
+            references,
+            vec![
+                (setup_name.as_str(), RelationshipType::References),
+                ("https://www.rust-lang.org", RelationshipType::References),
+            ]
+        );
+        assert_eq!(parsed.relationships[0].evidence.len(), 1);
+
+        let types: Vec<EntityType> = parsed.entities.iter().map(|e| e.entity_type).collect();
+        assert_eq!(
+            types,
+            vec![EntityType::File, EntityType::File, EntityType::Url]
+        );
+
+        // Targets already extracted from prose are reused
+        let mut entities = vec![Entity::new(
+            "https://www.rust-lang.org",
+            EntityType::Url,
+            0.9,
+        )];
+        let url_id = entities[0].id.clone();
+        let relationships = parsed.merge_into(&mut entities);
+        assert_eq!(entities.len(), 3);
+        assert_eq!(relationships[1].target_id, url_id);
+    }
+}
@@ -5,13 +5,22 @@
 //! heuristic fallbacks when the LLM is unavailable.
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
-use tracing::info;
+use async_trait::async_trait;
+use tracing::{debug, warn};
 
-use crate::chunker::SemanticChunker;
+use crate::chunker::{ChunkType, SemanticChunker};
 use crate::entity::{Entity, EntityExtractor, EntityType};
-use crate::error::Result;
-use crate::node::{ContextNode, CrossLinkType, DocumentAnalysis, DomainDetection, RelatedNode};
+use crate::error::{ContextError, Result};
+use crate::node::{
+    ContextNode, CrossLinkType, DocumentAnalysis, DocumentHeading, DomainDetection, RelatedNode,
+};
+
+/// Maximum number of entities listed in a summary's highlights line.
+const MAX_SUMMARY_HIGHLIGHTS: usize = 5;
 
 /// Configuration for the LLM analyzer.
 #[derive(Debug, Clone)]
@@ -27,6 +36,48 @@ pub struct LlmConfig {
 
     /// Known domains for detection.
     pub known_domains: Vec<String>,
+
+    /// Minimum number of keyword matches for heuristic domain detection.
+    pub min_domain_score: usize,
+
+    /// Domain preference order used to break ties in heuristic detection.
+    ///
+    /// Domains already in the tree win first, then domains in this list (in
+    /// order), then the alphabetically first domain.
+    pub domain_priority: Vec<String>,
+
+    /// Domain for folders that match no known domain (None = "other").
+    pub fallback_domain: Option<String>,
+
+    /// Minimum detection confidence for placing a folder in the tree.
+    ///
+    /// Detections below this, including the fallback domain, are rejected
+    /// with `ContextError::DomainConfidenceTooLow`.
+    pub min_domain_confidence: f32,
+
+    /// Maximum time to wait for each LLM request.
+    ///
+    /// A request that times out is handled like a failed one: heuristics
+    /// are used when `fallback_to_heuristic` is set.
+    pub request_timeout: Duration,
+
+    /// Prompt templates sent to the LLM client.
+    pub prompts: PromptTemplates,
+
+    /// How heuristic document summaries are built.
+    pub summary_style: SummaryStyle,
+}
+
+/// How heuristic document summaries are built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SummaryStyle {
+    /// The document's first paragraph.
+    #[default]
+    FirstParagraph,
+
+    /// The first paragraph followed by a `Mentions: ...` line naming the
+    /// highest-confidence extracted entities.
+    WithEntityHighlights,
 }
 
 impl Default for LlmConfig {
@@ -45,7 +96,93 @@ impl Default for LlmConfig {
                 "health".to_string(),
                 "travel".to_string(),
             ],
+            min_domain_score: 2,
+            domain_priority: Vec::new(),
+            fallback_domain: None,
+            min_domain_confidence: 0.0,
+            request_timeout: Duration::from_secs(30),
+            prompts: PromptTemplates::default(),
+            summary_style: SummaryStyle::default(),
+        }
+    }
+}
+
+/// Prompt templates for each kind of LLM request.
+///
+/// Templates contain `{placeholder}` markers that are replaced when the
+/// prompt is rendered; unknown placeholders are left as written. Override
+/// them to tune or translate prompts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplates {
+    /// Document analysis prompt.
+    ///
+    /// Placeholders: `{content}`, `{file_path}`, `{file_extension}`,
+    /// `{parent_folder}`, `{existing_domains}`, `{content_hint}`.
+    pub analysis: String,
+
+    /// Domain detection prompt.
+    ///
+    /// Placeholders: `{domains}`, `{extensions}`, `{summary}`.
+    pub domain_detection: String,
+
+    /// Relationship finding prompt.
+    ///
+    /// Placeholders: `{node}`, `{candidates}`. Reserved for LLM relationship
+    /// finding, which currently uses heuristics.
+    pub relationships: String,
+
+    /// Summarization prompt for a collection of child nodes.
+    ///
+    /// Placeholders: `{children}`.
+    pub summarization: String,
+}
+
+impl Default for PromptTemplates {
+    fn default() -> Self {
+        Self {
+            analysis: "Summarize the following document in one or two sentences.\n\n{content}"
+                .to_string(),
+            domain_detection: "Choose the domain that best fits this folder from: {domains}. \
+                               Reply with the domain name only.\n\n{summary}"
+                .to_string(),
+            relationships: "List the candidates related to {node}, one per line.\n\n{candidates}"
+                .to_string(),
+            summarization: "Write a short overview of a collection containing:\n{children}"
+                .to_string(),
+        }
+    }
+}
+
+impl PromptTemplates {
+    /// Replace `{key}` placeholders in `template` with their values.
+    ///
+    /// Substitution is a single pass, so placeholder-like text inside the
+    /// values (e.g. document content) is never expanded.
+    pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let substitution = after.find('}').and_then(|end| {
+                values
+                    .iter()
+                    .find(|(key, _)| *key == &after[..end])
+                    .map(|(_, value)| (*value, end))
+            });
+            match substitution {
+                Some((value, end)) => {
+                    rendered.push_str(value);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = after;
+                }
+            }
         }
+        rendered.push_str(rest);
+        rendered
     }
 }
 
@@ -68,6 +205,13 @@ pub struct AnalysisContext {
     pub content_hint: Option<String>,
 }
 
+/// A language model that completes text prompts.
+#[async_trait]
+pub trait LlmClient: Send + Sync {
+    /// Complete `prompt`, returning the model's response text.
+    async fn complete(&self, prompt: &str) -> Result<String>;
+}
+
 /// LLM-powered analyzer for document analysis and domain detection.
 ///
 /// The analyzer can operate in two modes:
@@ -77,8 +221,8 @@ pub struct LlmAnalyzer {
     config: LlmConfig,
     entity_extractor: EntityExtractor,
     chunker: SemanticChunker,
-    // TODO: Add ModelClient when integrating with codex-core
-    // client: Option<ModelClient>,
+    client: Option<Arc<dyn LlmClient>>,
+    fallbacks: AtomicUsize,
 }
 
 impl Default for LlmAnalyzer {
@@ -94,9 +238,17 @@ impl LlmAnalyzer {
             config,
             entity_extractor: EntityExtractor::new(),
             chunker: SemanticChunker::new(),
+            client: None,
+            fallbacks: AtomicUsize::new(0),
         }
     }
 
+    /// Use `client` for LLM-based analysis.
+    pub fn with_client(mut self, client: Arc<dyn LlmClient>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
     /// Create an analyzer with heuristic-only mode.
     pub fn heuristic_only() -> Self {
         let mut config = LlmConfig::default();
@@ -106,8 +258,32 @@ impl LlmAnalyzer {
 
     /// Check if LLM is available.
     pub fn is_llm_available(&self) -> bool {
-        // TODO: Check if ModelClient is connected
-        false
+        self.client.is_some()
+    }
+
+    /// Number of LLM calls that failed or timed out and fell back to heuristics.
+    pub fn llm_fallbacks(&self) -> usize {
+        self.fallbacks.load(Ordering::Relaxed)
+    }
+
+    /// Send a prompt to the LLM client, waiting at most `request_timeout`.
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let Some(client) = &self.client else {
+            return Err(ContextError::Query("no LLM client configured".to_string()));
+        };
+        tokio::time::timeout(self.config.request_timeout, client.complete(prompt))
+            .await
+            .unwrap_or(Err(ContextError::LlmTimeout(self.config.request_timeout)))
+    }
+
+    /// Handle a failed LLM call, using `heuristic` when fallback is enabled.
+    fn fall_back<T>(&self, error: ContextError, heuristic: impl FnOnce() -> T) -> Result<T> {
+        if !self.config.fallback_to_heuristic {
+            return Err(error);
+        }
+        warn!("LLM request failed, using heuristics: {error}");
+        self.fallbacks.fetch_add(1, Ordering::Relaxed);
+        Ok(heuristic())
     }
 
     /// Analyze a document and extract structured information.
@@ -131,10 +307,43 @@ impl LlmAnalyzer {
         content: &str,
         context: &AnalysisContext,
     ) -> Result<DocumentAnalysis> {
-        // TODO: Implement LLM-based analysis using ModelClient
-        // For now, fall back to heuristics
-        info!("LLM analysis not yet implemented, using heuristics");
-        Ok(self.analyze_with_heuristics(content, context))
+        let excerpt: String = content
+            .chars()
+            .take(self.config.max_analysis_tokens * 4)
+            .collect();
+        let existing_domains = context.existing_domains.join(", ");
+        let prompt = PromptTemplates::render(
+            &self.config.prompts.analysis,
+            &[
+                ("content", excerpt.as_str()),
+                ("file_path", context.file_path.as_deref().unwrap_or("")),
+                (
+                    "file_extension",
+                    context.file_extension.as_deref().unwrap_or(""),
+                ),
+                (
+                    "parent_folder",
+                    context.parent_folder.as_deref().unwrap_or(""),
+                ),
+                ("existing_domains", existing_domains.as_str()),
+                (
+                    "content_hint",
+                    context.content_hint.as_deref().unwrap_or(""),
+                ),
+            ],
+        );
+
+        match self.complete(&prompt).await {
+            Ok(summary) => {
+                // Entities, topics, and headings still come from heuristics.
+                let mut analysis = self.analyze_with_heuristics(content, context);
+                if !summary.trim().is_empty() {
+                    analysis.summary = summary.trim().to_string();
+                }
+                Ok(analysis)
+            }
+            Err(e) => self.fall_back(e, || self.analyze_with_heuristics(content, context)),
+        }
     }
 
     /// Analyze document using heuristic methods.
@@ -150,11 +359,28 @@ impl LlmAnalyzer {
         let entities = self.entity_extractor.extract(&chunks);
 
         // Generate summary from first paragraph
-        let summary = self.generate_heuristic_summary(content, &context.file_path);
+        let mut summary = self.generate_heuristic_summary(content, &context.file_path);
+        if self.config.summary_style == SummaryStyle::WithEntityHighlights
+            && let Some(highlights) = entity_highlights(&entities)
+        {
+            summary = format!("{summary}\n{highlights}");
+        }
 
         // Extract topics from entities and content
         let topics = self.extract_topics(&entities, content);
 
+        // Collect section headings
+        let headings = chunks
+            .iter()
+            .filter(|chunk| chunk.chunk_type == ChunkType::Section)
+            .filter_map(|chunk| {
+                Some(DocumentHeading {
+                    title: chunk.metadata.title.clone()?,
+                    level: chunk.metadata.heading_level?,
+                })
+            })
+            .collect();
+
         // Detect domain from content and context
         let suggested_domain = if let Some(ref ext) = context.file_extension {
             let extensions = vec![ext.clone()];
@@ -175,6 +401,7 @@ impl LlmAnalyzer {
             summary,
             entities,
             topics,
+            headings,
             suggested_domain,
             confidence,
         }
@@ -409,11 +636,36 @@ impl LlmAnalyzer {
         }
 
         // Return domain with highest score if it meets threshold
-        scores
+        let best = scores.values().copied().max()?;
+        if best < self.config.min_domain_score {
+            return None;
+        }
+
+        let tied: Vec<&str> = scores
             .into_iter()
-            .max_by_key(|(_, count)| *count)
-            .filter(|(_, count)| *count >= 2)
-            .map(|(domain, _)| domain.to_string())
+            .filter(|(_, count)| *count == best)
+            .map(|(domain, _)| domain)
+            .collect();
+
+        self.break_domain_tie(&tied, &context.existing_domains)
+            .map(ToString::to_string)
+    }
+
+    /// Pick one domain among equally scored candidates.
+    ///
+    /// Prefers domains already in the tree, then the configured priority
+    /// order, then alphabetical order.
+    fn break_domain_tie<'a>(&self, candidates: &[&'a str], existing: &[String]) -> Option<&'a str> {
+        candidates.iter().copied().min_by_key(|domain| {
+            let is_new = !existing.iter().any(|e| e.eq_ignore_ascii_case(domain));
+            let priority = self
+                .config
+                .domain_priority
+                .iter()
+                .position(|p| p.eq_ignore_ascii_case(domain))
+                .unwrap_or(usize::MAX);
+            (is_new, priority, *domain)
+        })
     }
 
     /// Calculate confidence score.
@@ -450,31 +702,81 @@ impl LlmAnalyzer {
         file_extensions: &[String],
         existing_domains: &[String],
     ) -> Result<DomainDetection> {
-        if self.is_llm_available() {
-            self.detect_domain_with_llm(folder_summary, existing_domains)
-                .await
+        let detection = if self.is_llm_available() {
+            self.detect_domain_with_llm(folder_summary, file_extensions, existing_domains)
+                .await?
         } else if self.config.fallback_to_heuristic {
-            Ok(
-                self.detect_domain_heuristic_full(
-                    folder_summary,
-                    file_extensions,
-                    existing_domains,
-                ),
-            )
+            self.detect_domain_heuristic_full(folder_summary, file_extensions, existing_domains)
         } else {
-            Ok(DomainDetection::new("other", 0.3).as_new())
+            self.fallback_detection()
+        };
+
+        if detection.confidence < self.config.min_domain_confidence {
+            return Err(ContextError::DomainConfidenceTooLow {
+                domain: detection.domain,
+                confidence: detection.confidence,
+                min: self.config.min_domain_confidence,
+            });
         }
+        Ok(detection)
+    }
+
+    /// Detect a single document's domain with heuristics only.
+    pub(crate) fn detect_document_domain(
+        &self,
+        content: &str,
+        extension: Option<&str>,
+    ) -> DomainDetection {
+        let extensions: Vec<String> = extension.map(str::to_lowercase).into_iter().collect();
+        self.detect_domain_heuristic_full(content, &extensions, &[])
+    }
+
+    /// Detection for folders that match no known domain.
+    pub(crate) fn fallback_detection(&self) -> DomainDetection {
+        let domain = self.config.fallback_domain.as_deref().unwrap_or("other");
+        DomainDetection::new(domain, 0.3).as_new()
     }
 
     /// Detect domain using LLM.
     async fn detect_domain_with_llm(
         &self,
-        _folder_summary: &str,
-        _existing_domains: &[String],
+        folder_summary: &str,
+        file_extensions: &[String],
+        existing_domains: &[String],
     ) -> Result<DomainDetection> {
-        // TODO: Implement LLM-based domain detection
-        info!("LLM domain detection not yet implemented");
-        Ok(DomainDetection::new("other", 0.3).as_new())
+        let mut domains: Vec<&str> = existing_domains
+            .iter()
+            .chain(&self.config.known_domains)
+            .map(String::as_str)
+            .collect();
+        domains.sort_unstable();
+        domains.dedup();
+        let prompt = PromptTemplates::render(
+            &self.config.prompts.domain_detection,
+            &[
+                ("domains", domains.join(", ").as_str()),
+                ("extensions", file_extensions.join(", ").as_str()),
+                ("summary", folder_summary),
+            ],
+        );
+
+        match self.complete(&prompt).await {
+            Ok(response) => {
+                let domain = response.trim().to_lowercase();
+                if domain.is_empty() {
+                    return Ok(self.fallback_detection());
+                }
+                let detection = DomainDetection::new(&domain, 0.7);
+                Ok(if existing_domains.contains(&domain) {
+                    detection
+                } else {
+                    detection.as_new()
+                })
+            }
+            Err(e) => self.fall_back(e, || {
+                self.detect_domain_heuristic_full(folder_summary, file_extensions, existing_domains)
+            }),
+        }
     }
 
     /// Detect domain using full heuristics.
@@ -524,8 +826,8 @@ impl LlmAnalyzer {
             };
         }
 
-        // Default to "other"
-        DomainDetection::new("other", 0.3).as_new()
+        // Default to the fallback domain
+        self.fallback_detection()
     }
 
     /// Detect coding subcategory from file extensions.
@@ -576,12 +878,12 @@ impl LlmAnalyzer {
     /// Find relationships using LLM.
     async fn find_relationships_with_llm(
         &self,
-        _node: &ContextNode,
-        _candidates: &[ContextNode],
+        node: &ContextNode,
+        candidates: &[ContextNode],
     ) -> Result<Vec<RelatedNode>> {
         // TODO: Implement LLM-based relationship finding
-        info!("LLM relationship finding not yet implemented");
-        Ok(Vec::new())
+        debug!("LLM relationship finding not yet implemented, using heuristics");
+        Ok(self.find_relationships_heuristic(node, candidates))
     }
 
     /// Find relationships using heuristics.
@@ -661,21 +963,31 @@ impl LlmAnalyzer {
         if self.is_llm_available() {
             self.summarize_with_llm(children).await
         } else if self.config.fallback_to_heuristic {
-            Ok(self.summarize_heuristic(children))
+            Ok(Self::summarize_heuristic(children))
         } else {
             Ok(String::new())
         }
     }
 
     /// Summarize using LLM.
-    async fn summarize_with_llm(&self, _children: &[ContextNode]) -> Result<String> {
-        // TODO: Implement LLM-based summarization
-        info!("LLM summarization not yet implemented");
-        Ok(String::new())
+    async fn summarize_with_llm(&self, children: &[ContextNode]) -> Result<String> {
+        let listing: Vec<String> = children
+            .iter()
+            .map(|c| format!("- {} ({}): {}", c.name, c.node_type.label(), c.summary))
+            .collect();
+        let prompt = PromptTemplates::render(
+            &self.config.prompts.summarization,
+            &[("children", listing.join("\n").as_str())],
+        );
+
+        match self.complete(&prompt).await {
+            Ok(summary) => Ok(summary.trim().to_string()),
+            Err(e) => self.fall_back(e, || Self::summarize_heuristic(children)),
+        }
     }
 
     /// Summarize using heuristics.
-    fn summarize_heuristic(&self, children: &[ContextNode]) -> String {
+    pub(crate) fn summarize_heuristic(children: &[ContextNode]) -> String {
         if children.is_empty() {
             return String::new();
         }
@@ -704,10 +1016,17 @@ impl LlmAnalyzer {
         let mut parts = Vec::new();
 
         if project_count > 0 {
+            let mut project_names: Vec<&str> = children
+                .iter()
+                .filter(|c| c.node_type == NodeType::Project)
+                .map(|c| c.name.as_str())
+                .collect();
+            project_names.sort_unstable();
             parts.push(format!(
-                "{} project{}",
+                "{} project{} ({})",
                 project_count,
-                if project_count > 1 { "s" } else { "" }
+                if project_count > 1 { "s" } else { "" },
+                project_names.join(", ")
             ));
         }
         if doc_count > 0 {
@@ -743,11 +1062,81 @@ impl LlmAnalyzer {
 
 use crate::node::NodeType;
 
+/// Build a `Mentions: ...` line from the highest-confidence entities.
+///
+/// Ties go to the more often mentioned entity. Tasks are skipped since
+/// their names are whole sentences. Returns `None` if nothing qualifies.
+fn entity_highlights(entities: &[Entity]) -> Option<String> {
+    let mut ranked: Vec<&Entity> = entities
+        .iter()
+        .filter(|e| e.entity_type != EntityType::Task)
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.confidence
+            .total_cmp(&a.confidence)
+            .then(b.mentions.len().cmp(&a.mentions.len()))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let mut names: Vec<&str> = Vec::new();
+    for entity in ranked {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(&entity.name)) {
+            names.push(&entity.name);
+        }
+        if names.len() == MAX_SUMMARY_HIGHLIGHTS {
+            break;
+        }
+    }
+
+    (!names.is_empty()).then(|| format!("Mentions: {}", names.join(", ")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn test_domain_tie_prefers_existing_domain() {
+        let analyzer = LlmAnalyzer::heuristic_only();
+        // Two work keywords and two cooking keywords.
+        let content = "Meeting about the deadline. Recipe: bake the bread.";
+
+        let fresh = AnalysisContext::default();
+        for _ in 0..20 {
+            assert_eq!(
+                analyzer.detect_domain_heuristic(content, &fresh),
+                Some("cooking".to_string())
+            );
+        }
+
+        let context = AnalysisContext {
+            existing_domains: vec!["work".to_string()],
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            assert_eq!(
+                analyzer.detect_domain_heuristic(content, &context),
+                Some("work".to_string())
+            );
+        }
+
+        let prioritized = LlmAnalyzer::new(LlmConfig {
+            domain_priority: vec!["work".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            prioritized.detect_domain_heuristic(content, &fresh),
+            Some("work".to_string())
+        );
+
+        let strict = LlmAnalyzer::new(LlmConfig {
+            min_domain_score: 3,
+            ..Default::default()
+        });
+        assert_eq!(strict.detect_domain_heuristic(content, &fresh), None);
+    }
+
     #[tokio::test]
     async fn test_analyze_document_heuristic() {
         let analyzer = LlmAnalyzer::heuristic_only();
@@ -775,6 +1164,31 @@ It handles HTTP requests and connects to a PostgreSQL database.
         assert!(analysis.confidence > 0.0);
     }
 
+    #[tokio::test]
+    async fn test_summary_entity_highlights() {
+        let content = "# Deploys\n\nThe service is built with Rust and shipped with Docker.\n";
+        let context = AnalysisContext::default();
+
+        let plain = LlmAnalyzer::heuristic_only()
+            .analyze_document(content, &context)
+            .await
+            .unwrap();
+        assert!(!plain.summary.contains("Mentions:"));
+
+        let analyzer = LlmAnalyzer::new(LlmConfig {
+            summary_style: SummaryStyle::WithEntityHighlights,
+            ..Default::default()
+        });
+        let analysis = analyzer.analyze_document(content, &context).await.unwrap();
+
+        let (first_paragraph, highlights) = analysis.summary.split_once('\n').unwrap();
+        assert_eq!(first_paragraph, plain.summary);
+        let highlights = highlights.to_lowercase();
+        assert!(highlights.starts_with("mentions: "), "{highlights}");
+        assert!(highlights.contains("rust"), "{highlights}");
+        assert!(highlights.contains("docker"), "{highlights}");
+    }
+
     #[tokio::test]
     async fn test_detect_domain_coding() {
         let analyzer = LlmAnalyzer::heuristic_only();
@@ -863,6 +1277,94 @@ It handles HTTP requests and connects to a PostgreSQL database.
         );
     }
 
+    /// LLM client whose requests never complete.
+    struct HangingClient;
+
+    #[async_trait]
+    impl LlmClient for HangingClient {
+        async fn complete(&self, _prompt: &str) -> Result<String> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout_falls_back_to_heuristics() {
+        let config = LlmConfig {
+            request_timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let analyzer = LlmAnalyzer::new(config.clone()).with_client(Arc::new(HangingClient));
+        assert!(analyzer.is_llm_available());
+
+        let content = "# Notes\n\nBuilt with Rust and Docker.";
+        let context = AnalysisContext::default();
+        let analysis = tokio::time::timeout(
+            Duration::from_secs(5),
+            analyzer.analyze_document(content, &context),
+        )
+        .await
+        .expect("analysis should not hang")
+        .unwrap();
+
+        let heuristic = LlmAnalyzer::heuristic_only().analyze_with_heuristics(content, &context);
+        assert_eq!(analysis.summary, heuristic.summary);
+        assert_eq!(analyzer.llm_fallbacks(), 1);
+
+        let strict = LlmAnalyzer::new(LlmConfig {
+            fallback_to_heuristic: false,
+            ..config
+        })
+        .with_client(Arc::new(HangingClient));
+        let err = strict.summarize_children(&[]).await.unwrap_err();
+        assert!(matches!(err, ContextError::LlmTimeout(_)));
+        assert_eq!(strict.llm_fallbacks(), 0);
+    }
+
+    /// LLM client that records prompts and replies with a fixed summary.
+    #[derive(Default)]
+    struct RecordingClient {
+        prompts: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl LlmClient for RecordingClient {
+        async fn complete(&self, prompt: &str) -> Result<String> {
+            self.prompts.lock().unwrap().push(prompt.to_string());
+            Ok("A recipe.".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_analysis_prompt() {
+        let client = Arc::new(RecordingClient::default());
+        let prompts = PromptTemplates {
+            analysis: "Résume {file_path} ({parent_folder}, {missing}):\n{content}".to_string(),
+            ..Default::default()
+        };
+        let analyzer = LlmAnalyzer::new(LlmConfig {
+            prompts,
+            ..Default::default()
+        })
+        .with_client(client.clone());
+
+        let context = AnalysisContext {
+            file_path: Some("/recipes/soup.md".to_string()),
+            parent_folder: Some("recipes".to_string()),
+            ..Default::default()
+        };
+        let analysis = analyzer
+            .analyze_document("Simmer the {file_path} stock.", &context)
+            .await
+            .unwrap();
+        assert_eq!(analysis.summary, "A recipe.");
+
+        let prompts = client.prompts.lock().unwrap();
+        assert_eq!(
+            prompts.as_slice(),
+            ["Résume /recipes/soup.md (recipes, {missing}):\nSimmer the {file_path} stock."]
+        );
+    }
+
     #[tokio::test]
     async fn test_summarize_children() {
         let analyzer = LlmAnalyzer::heuristic_only();
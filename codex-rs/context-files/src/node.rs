@@ -29,6 +29,10 @@ pub struct ContextNode {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<PathBuf>,
 
+    /// Project-relative path used to tell apart files with the same name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_path: Option<String>,
+
     /// Depth in the tree (0 = root).
     pub depth: u32,
 
@@ -43,6 +47,10 @@ pub struct ContextNode {
     #[serde(default)]
     pub keywords: Vec<String>,
 
+    /// Concepts of the context files generated from this node.
+    #[serde(default)]
+    pub concept_ids: Vec<String>,
+
     /// Parent node ID (None for root).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_id: Option<String>,
@@ -64,6 +72,14 @@ pub struct ContextNode {
     /// Number of times this node has been accessed.
     #[serde(default)]
     pub access_count: u32,
+
+    /// When `access_count` was last decayed, if ever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_decayed_at: Option<DateTime<Utc>>,
+
+    /// Whether this node is kept by cleanup such as pruning empty containers.
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 impl ContextNode {
@@ -74,16 +90,20 @@ impl ContextNode {
             node_type,
             name: name.into(),
             path: None,
+            display_path: None,
             depth: 0,
             summary: String::new(),
             entities: Vec::new(),
             keywords: Vec::new(),
+            concept_ids: Vec::new(),
             parent_id: None,
             children: Vec::new(),
             related_nodes: Vec::new(),
             confidence: 1.0,
             last_updated: Utc::now(),
             access_count: 0,
+            access_decayed_at: None,
+            pinned: false,
         }
     }
 
@@ -135,6 +155,17 @@ impl ContextNode {
         node
     }
 
+    /// Get the name to show for this node, preferring its display path.
+    pub fn display_name(&self) -> &str {
+        self.display_path.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Set the ID for this node, replacing the generated one.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = id.into();
+        self
+    }
+
     /// Set the summary for this node.
     pub fn with_summary(mut self, summary: impl Into<String>) -> Self {
         self.summary = summary.into();
@@ -161,11 +192,13 @@ impl ContextNode {
     /// Add a related node link.
     pub fn add_related(&mut self, related: RelatedNode) {
         self.related_nodes.push(related);
+        self.touch();
     }
 
     /// Add an entity to this node.
     pub fn add_entity(&mut self, entity: Entity) {
         self.entities.push(entity);
+        self.touch();
     }
 
     /// Add a keyword to this node.
@@ -173,9 +206,15 @@ impl ContextNode {
         let kw = keyword.into().to_lowercase();
         if !self.keywords.contains(&kw) {
             self.keywords.push(kw);
+            self.touch();
         }
     }
 
+    /// Mark this node as modified now.
+    pub fn touch(&mut self) {
+        self.last_updated = Utc::now();
+    }
+
     /// Record an access to this node.
     pub fn record_access(&mut self) {
         self.access_count += 1;
@@ -199,6 +238,52 @@ impl ContextNode {
             .and_then(|p| p.extension())
             .and_then(|ext| ext.to_str())
     }
+
+    /// Get a lightweight summary of this node without its entities, keywords,
+    /// or cross-links.
+    pub fn to_summary(&self) -> NodeSummary {
+        NodeSummary {
+            id: self.id.clone(),
+            node_type: self.node_type,
+            name: self.display_name().to_string(),
+            summary: self.summary.clone(),
+            parent_id: self.parent_id.clone(),
+            children: self.children.clone(),
+            entity_count: self.entities.len(),
+            related_count: self.related_nodes.len(),
+        }
+    }
+}
+
+/// A compact view of a `ContextNode` for transferring the tree skeleton.
+///
+/// Clients can fetch the full node by `id` when they need its details.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeSummary {
+    /// ID of the node.
+    pub id: String,
+
+    /// The type of node.
+    pub node_type: NodeType,
+
+    /// Name to show for the node.
+    pub name: String,
+
+    /// Brief summary of the node's content.
+    pub summary: String,
+
+    /// Parent node ID (None for root).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+
+    /// Child node IDs.
+    pub children: Vec<String>,
+
+    /// Number of entities on the full node.
+    pub entity_count: usize,
+
+    /// Number of cross-links on the full node.
+    pub related_count: usize,
 }
 
 /// The type of a context node in the hierarchy.
@@ -355,6 +440,10 @@ pub struct DocumentAnalysis {
     /// Detected topics/themes.
     pub topics: Vec<String>,
 
+    /// Section headings in document order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headings: Vec<DocumentHeading>,
+
     /// Suggested domain if not already known.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub suggested_domain: Option<String>,
@@ -369,12 +458,42 @@ impl Default for DocumentAnalysis {
             summary: String::new(),
             entities: Vec::new(),
             topics: Vec::new(),
+            headings: Vec::new(),
             suggested_domain: None,
             confidence: 0.0,
         }
     }
 }
 
+impl DocumentAnalysis {
+    /// Get heading titles at or above `max_level`, most prominent first.
+    ///
+    /// Headings of the same level keep their document order.
+    pub fn prominent_headings(&self, max_level: u8, limit: usize) -> Vec<&str> {
+        let mut headings: Vec<&DocumentHeading> = self
+            .headings
+            .iter()
+            .filter(|h| h.level <= max_level && !h.title.is_empty())
+            .collect();
+        headings.sort_by_key(|h| h.level);
+        headings
+            .into_iter()
+            .take(limit)
+            .map(|h| h.title.as_str())
+            .collect()
+    }
+}
+
+/// A section heading found in a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentHeading {
+    /// Heading text without markup.
+    pub title: String,
+
+    /// Heading level (1 is most prominent).
+    pub level: u8,
+}
+
 /// Result of domain detection for a folder.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DomainDetection {
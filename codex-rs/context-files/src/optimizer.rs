@@ -3,11 +3,12 @@
 //! The `TreeOptimizer` provides algorithms for optimizing the context tree
 //! by merging redundant nodes, pruning stale content, and compressing depth.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
 use chrono::{Duration, Utc};
 use tracing::{debug, info};
 
+use crate::entity::Entity;
 use crate::error::Result;
 use crate::llm::LlmAnalyzer;
 use crate::node::{ContextNode, NodeType};
@@ -28,6 +29,14 @@ pub struct OptimizerConfig {
     /// Minimum nodes to trigger sibling merging.
     pub min_siblings_for_merge: usize,
 
+    /// Minimum similarity (0.0 to 1.0) for sibling file references to merge.
+    ///
+    /// Similarity is the overlap (Jaccard index) of two nodes' keywords and
+    /// entities. Siblings are grouped with any sibling at least this
+    /// similar, and each group is merged separately. At 0.0 all file
+    /// references under a parent merge together.
+    pub min_sibling_similarity: f32,
+
     /// Maximum depth before forcing compression.
     pub max_depth_threshold: u32,
 
@@ -48,6 +57,7 @@ impl Default for OptimizerConfig {
             min_access_count: 2,
             target_depth_reduction: 2,
             min_siblings_for_merge: 5,
+            min_sibling_similarity: 0.0,
             max_depth_threshold: 8,
             prune_file_refs: true,
             merge_siblings: true,
@@ -78,6 +88,59 @@ pub struct OptimizationResult {
     pub created_node_ids: Vec<String>,
 }
 
+/// A dry run of an optimization pass, listing the exact nodes it would change.
+#[derive(Debug, Default, Clone)]
+pub struct OptimizationPlan {
+    /// IDs of stale nodes to prune.
+    pub prune: Vec<String>,
+
+    /// Groups of sibling file references to merge into summary nodes.
+    pub merges: Vec<MergeGroup>,
+
+    /// Deep branches to compress into their top node.
+    pub compressions: Vec<BranchCompression>,
+}
+
+impl OptimizationPlan {
+    /// Check whether the plan changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.prune.is_empty() && self.merges.is_empty() && self.compressions.is_empty()
+    }
+
+    /// Get the IDs of every node the plan removes.
+    pub fn removed_node_ids(&self) -> Vec<&str> {
+        self.prune
+            .iter()
+            .chain(self.merges.iter().flat_map(|m| &m.node_ids))
+            .chain(self.compressions.iter().flat_map(|c| &c.descendant_ids))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Sibling nodes to be merged into a single summary node.
+#[derive(Debug, Clone)]
+pub struct MergeGroup {
+    /// ID of the parent the merged node is added to.
+    pub parent_id: String,
+
+    /// IDs of the nodes being merged.
+    pub node_ids: Vec<String>,
+
+    /// Proposed summary for the merged node.
+    pub summary: String,
+}
+
+/// A deep branch to be folded into its top node.
+#[derive(Debug, Clone)]
+pub struct BranchCompression {
+    /// ID of the node that absorbs the branch.
+    pub node_id: String,
+
+    /// IDs of the descendants being removed.
+    pub descendant_ids: Vec<String>,
+}
+
 /// Tree optimizer for managing context tree depth and efficiency.
 pub struct TreeOptimizer {
     config: OptimizerConfig,
@@ -96,10 +159,115 @@ impl TreeOptimizer {
     }
 
     /// Run a full optimization pass on the tree.
+    ///
+    /// Equivalent to executing the plan returned by [`TreeOptimizer::plan`].
     pub async fn optimize(
         &self,
         tree: &mut ContextTree,
         analyzer: &LlmAnalyzer,
+    ) -> Result<OptimizationResult> {
+        let plan = self.plan(tree);
+        self.execute_plan(tree, analyzer, &plan).await
+    }
+
+    /// Work out exactly which nodes an optimization pass would change,
+    /// without mutating the tree.
+    pub fn plan(&self, tree: &ContextTree) -> OptimizationPlan {
+        let prune = if self.config.prune_file_refs {
+            self.stale_node_ids(tree)
+        } else {
+            Vec::new()
+        };
+        let pruned: HashSet<&str> = prune.iter().map(String::as_str).collect();
+
+        // Merging replaces file references with a summary node at the same
+        // depth, so only pruning affects which branches are too deep.
+        let mut compressions = Vec::new();
+        let max_depth = tree
+            .all_nodes()
+            .filter(|n| !pruned.contains(n.id.as_str()))
+            .map(|n| n.depth)
+            .max()
+            .unwrap_or(0);
+        if self.config.compress_deep_branches && max_depth > self.config.max_depth_threshold {
+            for node in tree.nodes_at_depth(self.config.max_depth_threshold) {
+                let mut descendant_ids: Vec<String> = tree
+                    .get_descendants(&node.id)
+                    .into_iter()
+                    .filter(|d| !pruned.contains(d.id.as_str()))
+                    .map(|d| d.id.clone())
+                    .collect();
+                if descendant_ids.is_empty() {
+                    continue;
+                }
+                descendant_ids.sort();
+                compressions.push(BranchCompression {
+                    node_id: node.id.clone(),
+                    descendant_ids,
+                });
+            }
+            compressions.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        }
+
+        // Branches being compressed absorb their file references directly
+        let compressed: HashSet<&str> = compressions
+            .iter()
+            .flat_map(|c| std::iter::once(&c.node_id).chain(&c.descendant_ids))
+            .map(String::as_str)
+            .collect();
+
+        let mut merges = Vec::new();
+        if self.config.merge_siblings {
+            for parent in tree.all_nodes() {
+                if compressed.contains(parent.id.as_str()) {
+                    continue;
+                }
+                let file_refs: Vec<ContextNode> = parent
+                    .children
+                    .iter()
+                    .filter(|id| !pruned.contains(id.as_str()))
+                    .filter_map(|id| tree.get(id))
+                    .filter(|n| n.node_type == NodeType::FileReference)
+                    .cloned()
+                    .collect();
+                if file_refs.len() < self.config.min_siblings_for_merge.max(2) {
+                    continue;
+                }
+                for group in self.similar_groups(file_refs) {
+                    if group.len() < self.config.min_siblings_for_merge.max(2) {
+                        continue;
+                    }
+                    let mut node_ids: Vec<String> = group.iter().map(|n| n.id.clone()).collect();
+                    node_ids.sort();
+                    merges.push(MergeGroup {
+                        parent_id: parent.id.clone(),
+                        node_ids,
+                        summary: LlmAnalyzer::summarize_heuristic(&group),
+                    });
+                }
+            }
+            merges.sort_by(|a, b| {
+                a.parent_id
+                    .cmp(&b.parent_id)
+                    .then_with(|| a.node_ids.cmp(&b.node_ids))
+            });
+        }
+
+        OptimizationPlan {
+            prune,
+            merges,
+            compressions,
+        }
+    }
+
+    /// Apply a plan from [`TreeOptimizer::plan`] to the tree.
+    ///
+    /// Nodes in the plan that no longer exist are skipped.
+    pub async fn execute_plan(
+        &self,
+        tree: &mut ContextTree,
+        analyzer: &LlmAnalyzer,
+        plan: &OptimizationPlan,
     ) -> Result<OptimizationResult> {
         let mut result = OptimizationResult::default();
 
@@ -109,25 +277,43 @@ impl TreeOptimizer {
         let initial_count = tree.node_count();
 
         // Phase 1: Prune stale leaf nodes
-        if self.config.prune_file_refs {
-            let pruned = self.prune_stale_nodes(tree);
-            result.nodes_pruned += pruned.len();
-            result.removed_node_ids.extend(pruned);
+        for id in &plan.prune {
+            if tree.remove(id).is_some() {
+                debug!("Pruned stale node: {}", id);
+                result.nodes_pruned += 1;
+                result.removed_node_ids.push(id.clone());
+            }
         }
 
         // Phase 2: Merge similar siblings
-        if self.config.merge_siblings {
-            let merged = self.merge_similar_siblings(tree, analyzer).await;
-            result.nodes_merged += merged.0;
-            result.removed_node_ids.extend(merged.1);
-            result.created_node_ids.extend(merged.2);
+        for group in &plan.merges {
+            let file_refs: Vec<ContextNode> = group
+                .node_ids
+                .iter()
+                .filter_map(|id| tree.get(id).cloned())
+                .collect();
+            if file_refs.len() < 2 {
+                continue;
+            }
+            let merged_node = self
+                .merge_file_refs(&file_refs, &group.summary, analyzer)
+                .await;
+            result.created_node_ids.push(merged_node.id.clone());
+            tree.add_child(&group.parent_id, merged_node).ok();
+            for node in &file_refs {
+                tree.remove(&node.id);
+                result.removed_node_ids.push(node.id.clone());
+            }
+            result.nodes_merged += 1;
         }
 
         // Phase 3: Compress deep branches
-        if self.config.compress_deep_branches {
-            let compressed = self.compress_deep_branches(tree, analyzer).await;
-            result.nodes_merged += compressed.0;
-            result.removed_node_ids.extend(compressed.1);
+        for compression in &plan.compressions {
+            let removed = self.compress_branch(tree, compression, analyzer).await;
+            if !removed.is_empty() {
+                result.nodes_merged += 1;
+                result.removed_node_ids.extend(removed);
+            }
         }
 
         // Calculate results
@@ -135,7 +321,7 @@ impl TreeOptimizer {
         let final_count = tree.node_count();
 
         result.depth_reduced_by = initial_depth.saturating_sub(final_depth);
-        result.storage_saved_bytes = (initial_count - final_count) * 500; // Rough estimate
+        result.storage_saved_bytes = initial_count.saturating_sub(final_count) * 500; // Rough estimate
 
         info!(
             "Optimization complete: pruned {}, merged {}, depth reduced by {}",
@@ -145,13 +331,50 @@ impl TreeOptimizer {
         Ok(result)
     }
 
-    /// Prune stale nodes that haven't been accessed recently.
-    fn prune_stale_nodes(&self, tree: &mut ContextTree) -> Vec<String> {
+    /// Split sibling nodes into groups of similar nodes.
+    ///
+    /// Two nodes share a group when they are linked by a chain of nodes at
+    /// least `min_sibling_similarity` similar to each other. Groups keep the
+    /// order of their first node.
+    fn similar_groups(&self, nodes: Vec<ContextNode>) -> Vec<Vec<ContextNode>> {
+        if self.config.min_sibling_similarity <= 0.0 {
+            return vec![nodes];
+        }
+
+        let terms: Vec<HashSet<String>> = nodes.iter().map(merge_terms).collect();
+        let mut group_of: Vec<usize> = (0..nodes.len()).collect();
+        for i in 0..nodes.len() {
+            for j in i + 1..nodes.len() {
+                let (a, b) = (group_of[i], group_of[j]);
+                if a == b
+                    || term_similarity(&terms[i], &terms[j]) < self.config.min_sibling_similarity
+                {
+                    continue;
+                }
+                for group in &mut group_of {
+                    if *group == b {
+                        *group = a;
+                    }
+                }
+            }
+        }
+
+        let mut groups: Vec<(usize, Vec<ContextNode>)> = Vec::new();
+        for (node, group) in nodes.into_iter().zip(group_of) {
+            match groups.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, members)) => members.push(node),
+                None => groups.push((group, vec![node])),
+            }
+        }
+        groups.into_iter().map(|(_, members)| members).collect()
+    }
+
+    /// Find stale file reference leaves that haven't been accessed recently.
+    fn stale_node_ids(&self, tree: &ContextTree) -> Vec<String> {
         let now = Utc::now();
         let cutoff = now - Duration::days(self.config.max_idle_days as i64);
 
-        // Find stale leaf nodes
-        let stale_ids: Vec<String> = tree
+        let mut stale_ids: Vec<String> = tree
             .get_leaves()
             .iter()
             .filter(|node| {
@@ -165,96 +388,19 @@ impl TreeOptimizer {
             })
             .map(|node| node.id.clone())
             .collect();
-
-        // Remove stale nodes
-        for id in &stale_ids {
-            tree.remove(id);
-            debug!("Pruned stale node: {}", id);
-        }
-
+        stale_ids.sort();
         stale_ids
     }
 
-    /// Merge similar sibling nodes.
-    async fn merge_similar_siblings(
-        &self,
-        tree: &mut ContextTree,
-        analyzer: &LlmAnalyzer,
-    ) -> (usize, Vec<String>, Vec<String>) {
-        let mut merged_count = 0;
-        let mut removed_ids = Vec::new();
-        let mut created_ids = Vec::new();
-
-        // Get all non-leaf nodes
-        let parent_ids: Vec<String> = tree
-            .all_nodes()
-            .filter(|n| !n.children.is_empty())
-            .map(|n| n.id.clone())
-            .collect();
-
-        for parent_id in parent_ids {
-            // Get children
-            let children: Vec<ContextNode> = {
-                let parent = match tree.get(&parent_id) {
-                    Some(p) => p,
-                    None => continue,
-                };
-
-                parent
-                    .children
-                    .iter()
-                    .filter_map(|id| tree.get(id).cloned())
-                    .collect()
-            };
-
-            // Check if we should merge
-            if children.len() < self.config.min_siblings_for_merge {
-                continue;
-            }
-
-            // Group by node type
-            let mut by_type: HashMap<NodeType, Vec<ContextNode>> = HashMap::new();
-            for child in children {
-                by_type.entry(child.node_type).or_default().push(child);
-            }
-
-            // Merge file references if there are many
-            if let Some(file_refs) = by_type.get(&NodeType::FileReference) {
-                if file_refs.len() >= self.config.min_siblings_for_merge {
-                    let (merged, removed) = self
-                        .merge_file_refs(tree, &parent_id, file_refs, analyzer)
-                        .await;
-                    if let Some(merged_node) = merged {
-                        created_ids.push(merged_node.id.clone());
-                        tree.add_child(&parent_id, merged_node).ok();
-                        merged_count += 1;
-                    }
-                    for id in &removed {
-                        tree.remove(id);
-                    }
-                    removed_ids.extend(removed);
-                }
-            }
-        }
-
-        (merged_count, removed_ids, created_ids)
-    }
-
     /// Merge multiple file references into a summary node.
+    ///
+    /// `fallback_summary` is used when the analyzer produces no summary.
     async fn merge_file_refs(
         &self,
-        _tree: &ContextTree,
-        _parent_id: &str,
         file_refs: &[ContextNode],
+        fallback_summary: &str,
         analyzer: &LlmAnalyzer,
-    ) -> (Option<ContextNode>, Vec<String>) {
-        if file_refs.len() < 2 {
-            return (None, Vec::new());
-        }
-
-        // Collect IDs to remove
-        let removed_ids: Vec<String> = file_refs.iter().map(|n| n.id.clone()).collect();
-
+    ) -> ContextNode {
         // Create summary node
         let summary = analyzer
             .summarize_children(file_refs)
@@ -262,10 +408,12 @@ impl TreeOptimizer {
             .unwrap_or_default();
 
         let mut merged_node = ContextNode::new(NodeType::Document, "Files Summary");
-        merged_node.summary = if summary.is_empty() {
-            format!("Summary of {} files", file_refs.len())
-        } else {
+        merged_node.summary = if !summary.is_empty() {
             summary
+        } else if !fallback_summary.is_empty() {
+            fallback_summary.to_string()
+        } else {
+            format!("Summary of {} files", file_refs.len())
         };
 
         // Collect keywords from all merged nodes
@@ -285,89 +433,64 @@ impl TreeOptimizer {
             file_refs.len()
         );
 
-        (Some(merged_node), removed_ids)
+        merged_node
     }
 
-    /// Compress branches that are too deep.
-    async fn compress_deep_branches(
+    /// Compress a deep branch into its top node, returning the removed IDs.
+    async fn compress_branch(
         &self,
         tree: &mut ContextTree,
+        compression: &BranchCompression,
         analyzer: &LlmAnalyzer,
-    ) -> (usize, Vec<String>) {
-        let mut compressed_count = 0;
-        let mut removed_ids = Vec::new();
-
-        let max_depth = tree.max_depth();
-
-        // Only compress if we exceed threshold
-        if max_depth <= self.config.max_depth_threshold {
-            return (0, Vec::new());
-        }
-
-        // Find nodes at excessive depth
-        let deep_nodes: Vec<ContextNode> = tree
-            .nodes_at_depth(self.config.max_depth_threshold)
-            .into_iter()
-            .cloned()
+    ) -> Vec<String> {
+        let descendants: Vec<ContextNode> = compression
+            .descendant_ids
+            .iter()
+            .filter_map(|id| tree.get(id).cloned())
             .collect();
 
-        for node in deep_nodes {
-            // Get descendants
-            let descendants: Vec<ContextNode> = tree
-                .get_descendants(&node.id)
-                .into_iter()
-                .cloned()
-                .collect();
-
-            if descendants.is_empty() {
-                continue;
-            }
-
-            // Compress descendants into the node's summary
-            let summary = analyzer
-                .summarize_children(&descendants)
-                .await
-                .unwrap_or_default();
-
-            if let Some(target_node) = tree.get_mut(&node.id) {
-                // Append compressed summary
-                if !summary.is_empty() {
-                    target_node.summary =
-                        format!("{}\n\nCompressed: {}", target_node.summary, summary);
-                }
+        if descendants.is_empty() || tree.get(&compression.node_id).is_none() {
+            return Vec::new();
+        }
 
-                // Collect keywords and entities from descendants
-                for desc in &descendants {
-                    target_node.keywords.extend(desc.keywords.clone());
-                    target_node.entities.extend(desc.entities.clone());
-                }
+        // Compress descendants into the node's summary
+        let summary = analyzer
+            .summarize_children(&descendants)
+            .await
+            .unwrap_or_default();
 
-                // Deduplicate keywords
-                let keywords: HashSet<String> = target_node.keywords.drain(..).collect();
-                target_node.keywords = keywords.into_iter().collect();
+        if let Some(target_node) = tree.get_mut(&compression.node_id) {
+            // Append compressed summary
+            if !summary.is_empty() {
+                target_node.summary = format!("{}\n\nCompressed: {}", target_node.summary, summary);
             }
 
-            // Remove descendants
+            // Collect keywords and entities from descendants
             for desc in &descendants {
-                removed_ids.push(desc.id.clone());
+                target_node.keywords.extend(desc.keywords.clone());
+                target_node.entities.extend(desc.entities.clone());
             }
-            compressed_count += 1;
+
+            // Deduplicate keywords
+            let keywords: HashSet<String> = target_node.keywords.drain(..).collect();
+            target_node.keywords = keywords.into_iter().collect();
+            target_node.touch();
         }
+        tree.refresh_entity_index(&compression.node_id);
 
-        // Actually remove the nodes
+        // Remove descendants
+        let removed_ids: Vec<String> = descendants.into_iter().map(|d| d.id).collect();
         for id in &removed_ids {
             tree.remove(id);
         }
 
-        if compressed_count > 0 {
-            debug!(
-                "Compressed {} deep branches, removed {} nodes",
-                compressed_count,
-                removed_ids.len()
-            );
-        }
+        debug!(
+            "Compressed branch {}, removed {} nodes",
+            compression.node_id,
+            removed_ids.len()
+        );
 
-        (compressed_count, removed_ids)
+        removed_ids
     }
 
     /// Get recommendations for optimization without making changes.
@@ -419,6 +542,24 @@ impl TreeOptimizer {
     }
 }
 
+/// Terms compared when deciding whether siblings are similar enough to merge.
+fn merge_terms(node: &ContextNode) -> HashSet<String> {
+    node.keywords
+        .iter()
+        .map(|k| k.to_lowercase())
+        .chain(node.entities.iter().map(Entity::merge_key))
+        .collect()
+}
+
+/// Jaccard index of two term sets (0.0 when both are empty).
+fn term_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
 /// Analysis of potential optimizations.
 #[derive(Debug, Default)]
 pub struct OptimizationAnalysis {
@@ -533,8 +674,8 @@ mod tests {
         assert!(analysis.excessive_depth);
     }
 
-    #[test]
-    fn test_prune_stale_nodes() {
+    #[tokio::test]
+    async fn test_prune_stale_nodes() {
         let mut tree = ContextTree::new();
         let domain_id = tree.ensure_domain("coding");
 
@@ -567,12 +708,59 @@ mod tests {
         });
 
         let initial_count = tree.node_count();
-        let pruned = optimizer.prune_stale_nodes(&mut tree);
+        let plan = optimizer.plan(&tree);
+        let result = optimizer
+            .execute_plan(&mut tree, &LlmAnalyzer::heuristic_only(), &plan)
+            .await
+            .unwrap();
 
-        assert_eq!(pruned.len(), 3);
+        assert_eq!(plan.prune.len(), 3);
+        assert_eq!(result.nodes_pruned, 3);
         assert_eq!(tree.node_count(), initial_count - 3);
     }
 
+    #[tokio::test]
+    async fn test_plan_matches_optimize() {
+        let mut tree = create_test_tree();
+        let domain_id = tree.ensure_domain("coding");
+        let mut stale = ContextNode::file_reference("old.rs", PathBuf::from("/old.rs"));
+        stale.last_updated = Utc::now() - Duration::days(60);
+        let stale_id = tree.add_child(&domain_id, stale).unwrap();
+
+        let mut parent_id = tree.ensure_domain("research");
+        for i in 0..6 {
+            let node = ContextNode::new(NodeType::Module, format!("level{i}"));
+            parent_id = tree.add_child(&parent_id, node).unwrap();
+        }
+
+        let optimizer = TreeOptimizer::new(OptimizerConfig {
+            max_depth_threshold: 5,
+            ..Default::default()
+        });
+        let before = tree.node_count();
+        let plan = optimizer.plan(&tree);
+
+        // Planning doesn't touch the tree
+        assert_eq!(tree.node_count(), before);
+        assert_eq!(plan.prune, vec![stale_id]);
+        assert_eq!(plan.merges.len(), 1);
+        assert_eq!(plan.merges[0].node_ids.len(), 10);
+        assert!(plan.merges[0].summary.contains("10 files"));
+        assert_eq!(plan.compressions.len(), 1);
+        assert_eq!(plan.compressions[0].descendant_ids.len(), 2);
+
+        let result = optimizer
+            .optimize(&mut tree, &LlmAnalyzer::heuristic_only())
+            .await
+            .unwrap();
+        let mut planned = plan.removed_node_ids();
+        planned.sort_unstable();
+        let mut removed: Vec<&str> = result.removed_node_ids.iter().map(String::as_str).collect();
+        removed.sort_unstable();
+        assert_eq!(removed, planned);
+        assert!(planned.iter().all(|id| tree.get(id).is_none()));
+    }
+
     #[tokio::test]
     async fn test_optimize_full() {
         let mut tree = create_test_tree();
@@ -593,6 +781,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merge_only_similar_siblings() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let project = ContextNode::project("shop", PathBuf::from("/shop"));
+        let project_id = tree.add_child(&domain_id, project).unwrap();
+
+        let files: [(&str, &[&str]); 5] = [
+            ("invoice.rs", &["billing", "invoice"]),
+            ("logger.rs", &["logging"]),
+            ("tax.rs", &["billing", "invoice", "tax"]),
+            ("auth.rs", &["auth", "session"]),
+            ("receipt.rs", &["billing", "invoice"]),
+        ];
+        let mut ids = Vec::new();
+        for (name, keywords) in files {
+            let mut file = ContextNode::file_reference(name, PathBuf::from("/shop").join(name));
+            file.keywords = keywords.iter().map(ToString::to_string).collect();
+            ids.push(tree.add_child(&project_id, file).unwrap());
+        }
+
+        let optimizer = TreeOptimizer::new(OptimizerConfig {
+            min_siblings_for_merge: 3,
+            min_sibling_similarity: 0.5,
+            ..Default::default()
+        });
+        let plan = optimizer.plan(&tree);
+
+        let mut similar = vec![ids[0].clone(), ids[2].clone(), ids[4].clone()];
+        similar.sort();
+        assert_eq!(plan.merges.len(), 1);
+        assert_eq!(plan.merges[0].parent_id, project_id);
+        assert_eq!(plan.merges[0].node_ids, similar);
+
+        // Without a similarity threshold every file reference merges
+        let optimizer = TreeOptimizer::new(OptimizerConfig {
+            min_siblings_for_merge: 3,
+            ..Default::default()
+        });
+        assert_eq!(optimizer.plan(&tree).merges[0].node_ids.len(), 5);
+    }
+
     #[test]
     fn test_optimization_result_default() {
         let result = OptimizationResult::default();
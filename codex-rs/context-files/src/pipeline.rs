@@ -3,16 +3,31 @@
 //! This module provides the main entry point for processing documents
 //! and generating context files using the full extraction pipeline.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 
 use crate::chunker::{Chunk, ChunkerConfig, SemanticChunker};
+use crate::config_file::ConfigFileParser;
 use crate::context_file::ContextFile;
-use crate::entity::{Entity, EntityExtractor, EntityExtractorConfig};
+use crate::encoding::{DEFAULT_FALLBACK_ENCODING, decode, encoding_for_label};
+use crate::entity::{Entity, EntityExtractor, EntityExtractorConfig, sort_entities};
 use crate::error::{ContextError, Result};
 use crate::generator::{ContextGenerator, GeneratedContext, GeneratorConfig};
-use crate::relationship::{Relationship, RelationshipExtractor, RelationshipExtractorConfig};
+use crate::id::IdGenerator;
+use crate::ignore::IgnoreRules;
+use crate::links::MarkdownLinkExtractor;
+use crate::llm::LlmAnalyzer;
+use crate::relationship::{
+    Relationship, RelationshipExtractor, RelationshipExtractorConfig, sort_relationships,
+};
 use crate::storage::ContextStore;
 
 /// Configuration for the context extraction pipeline.
@@ -21,12 +36,31 @@ pub struct PipelineConfig {
     /// Chunker configuration.
     pub chunker: ChunkerConfig,
 
+    /// Chunker configurations by file extension (lowercase, without the
+    /// dot). Files with other extensions, and in-memory documents, use
+    /// `chunker`.
+    pub chunker_profiles: HashMap<String, ChunkerConfig>,
+
     /// Entity extractor configuration.
     pub entity: EntityExtractorConfig,
 
     /// Relationship extractor configuration.
     pub relationship: RelationshipExtractorConfig,
 
+    /// Whether to extract relationships between entities.
+    ///
+    /// Relationship extraction is the most expensive stage. When disabled,
+    /// documents have no relationships and only entity-based contexts are
+    /// generated.
+    pub extract_relationships: bool,
+
+    /// Whether to derive entities from file and directory names.
+    ///
+    /// When enabled, `billing/payment-service.rs` contributes a "billing"
+    /// concept and a "payment service" project, at lower confidence than
+    /// entities found in content.
+    pub path_entities: bool,
+
     /// Context generator configuration.
     pub generator: GeneratorConfig,
 
@@ -36,19 +70,65 @@ pub struct PipelineConfig {
     /// Directories to skip.
     pub skip_directories: Vec<String>,
 
+    /// Top-level subdirectories to restrict traversal to.
+    ///
+    /// When set and non-empty, only these subdirectories of the processed
+    /// directory are traversed (files directly in it are still processed).
+    /// `skip_directories` still applies within them.
+    pub include_directories: Option<Vec<String>>,
+
     /// Maximum file size to process (in bytes).
     pub max_file_size: usize,
 
     /// Whether to process hidden files.
     pub process_hidden: bool,
+
+    /// Label of the encoding used for files that are neither UTF-8 nor UTF-16.
+    ///
+    /// When `None`, or when the fallback cannot decode a file, invalid bytes
+    /// are replaced (lossy UTF-8).
+    pub fallback_encoding: Option<String>,
+
+    /// Checkpoint file recording processed files, for resumable runs.
+    ///
+    /// When set, files already recorded with the same modification time and
+    /// size are skipped on later runs, and progress is saved as files are
+    /// processed. A resumed run's result covers only the files it processed;
+    /// skipped files are counted in `files_skipped` but contribute no
+    /// documents, entities, or contexts. The checkpoint file itself is never
+    /// processed.
+    pub checkpoint_path: Option<PathBuf>,
+
+    /// Order in which files in a directory are processed.
+    pub order_by: FileOrder,
+}
+
+/// Order in which the pipeline processes a directory's files.
+///
+/// With streaming output, files processed first are available to queries
+/// soonest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileOrder {
+    /// By path.
+    #[default]
+    Alphabetical,
+
+    /// Most recently modified first.
+    RecentFirst,
+
+    /// Smallest first.
+    SizeAscending,
 }
 
 impl Default for PipelineConfig {
     fn default() -> Self {
         Self {
             chunker: ChunkerConfig::default(),
+            chunker_profiles: HashMap::new(),
             entity: EntityExtractorConfig::default(),
             relationship: RelationshipExtractorConfig::default(),
+            extract_relationships: true,
+            path_entities: false,
             generator: GeneratorConfig::default(),
             file_extensions: vec![
                 "md".to_string(),
@@ -74,14 +154,18 @@ impl Default for PipelineConfig {
                 ".venv".to_string(),
                 "venv".to_string(),
             ],
+            include_directories: None,
             max_file_size: 1024 * 1024, // 1MB
             process_hidden: false,
+            fallback_encoding: Some(DEFAULT_FALLBACK_ENCODING.to_string()),
+            checkpoint_path: None,
+            order_by: FileOrder::Alphabetical,
         }
     }
 }
 
 /// Result of processing a single document.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DocumentResult {
     /// Source file path.
     pub source: PathBuf,
@@ -94,11 +178,20 @@ pub struct DocumentResult {
 
     /// Relationships extracted.
     pub relationships: Vec<Relationship>,
+
+    /// Whether the document had no content (empty or whitespace-only).
+    pub empty: bool,
+
+    /// Encoding the file was decoded from (`None` for in-memory documents).
+    pub encoding: Option<String>,
 }
 
 /// Result of running the full pipeline.
 #[derive(Debug)]
 pub struct PipelineResult {
+    /// Directory that was processed.
+    pub root: PathBuf,
+
     /// All processed documents.
     pub documents: Vec<DocumentResult>,
 
@@ -118,18 +211,51 @@ pub struct PipelineResult {
     pub stats: PipelineStats,
 }
 
+/// Result of `ContextPipeline::process_directory_incremental`.
+#[derive(Debug, Default)]
+pub struct IncrementalResult {
+    /// Files not in the manifest that were processed.
+    pub added: Vec<PathBuf>,
+
+    /// Files whose content changed since the manifest was written.
+    pub changed: Vec<PathBuf>,
+
+    /// Files in the manifest that no longer exist. Callers holding nodes
+    /// for these files should remove them.
+    pub removed: Vec<PathBuf>,
+
+    /// Number of files skipped because they were unchanged.
+    pub unchanged: usize,
+
+    /// Documents for the added and changed files.
+    pub documents: Vec<DocumentResult>,
+
+    /// Files that failed to process. They are retried on the next run.
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl IncrementalResult {
+    /// Whether the run found nothing added, changed, or removed.
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
 /// Statistics about the pipeline run.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct PipelineStats {
     /// Number of files processed.
     pub files_processed: usize,
 
-    /// Number of files skipped.
+    /// Number of files skipped (already recorded in the checkpoint).
     pub files_skipped: usize,
 
     /// Number of files with errors.
     pub files_with_errors: usize,
 
+    /// Number of empty or whitespace-only files.
+    pub files_empty: usize,
+
     /// Total chunks created.
     pub total_chunks: usize,
 
@@ -146,13 +272,310 @@ pub struct PipelineStats {
     pub processing_time_ms: u64,
 }
 
+/// Number of equal-width buckets in `QualityMetrics` confidence histograms.
+const CONFIDENCE_BUCKETS: usize = 5;
+
+/// Encodings files are decoded from without falling back.
+const DETECTED_ENCODINGS: &[&str] = &["UTF-8", "UTF-16LE", "UTF-16BE"];
+
+/// Aggregate signals about extraction quality for a pipeline run.
+///
+/// Only counts, averages, and rates are recorded, never file contents,
+/// paths, or entity names, so metrics can be reported off-machine.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QualityMetrics {
+    /// Number of files processed.
+    pub files_processed: usize,
+
+    /// Number of empty or whitespace-only files.
+    pub files_empty: usize,
+
+    /// Number of files with errors.
+    pub files_with_errors: usize,
+
+    /// Total entities after deduplication.
+    pub total_entities: usize,
+
+    /// Average entities per processed file.
+    pub avg_entities_per_file: f64,
+
+    /// Average relationships per processed file.
+    pub avg_relationships_per_file: f64,
+
+    /// Average chunks per processed file.
+    pub avg_chunks_per_file: f64,
+
+    /// Entity counts by confidence, in buckets of 0.2 from low to high.
+    pub entity_confidence: [usize; CONFIDENCE_BUCKETS],
+
+    /// Processed file counts by the confidence of their heuristically
+    /// detected domain, in buckets of 0.2 from low to high.
+    pub domain_confidence: [usize; CONFIDENCE_BUCKETS],
+
+    /// Fraction of decoded files that needed the fallback encoding.
+    pub encoding_fallback_rate: f64,
+
+    /// Fraction of attempted files that failed to process.
+    pub error_rate: f64,
+}
+
+impl PipelineResult {
+    /// Compute aggregate quality metrics for this run.
+    pub fn quality_metrics(&self) -> QualityMetrics {
+        let stats = &self.stats;
+        let per_file = |total: usize| {
+            if stats.files_processed == 0 {
+                0.0
+            } else {
+                total as f64 / stats.files_processed as f64
+            }
+        };
+
+        let bucket = |confidence: f32| {
+            let bucket = (confidence.clamp(0.0, 1.0) * CONFIDENCE_BUCKETS as f32) as usize;
+            bucket.min(CONFIDENCE_BUCKETS - 1)
+        };
+        let mut entity_confidence = [0; CONFIDENCE_BUCKETS];
+        for entity in &self.all_entities {
+            entity_confidence[bucket(entity.confidence)] += 1;
+        }
+
+        let analyzer = LlmAnalyzer::default();
+        let mut domain_confidence = [0; CONFIDENCE_BUCKETS];
+        for document in self.documents.iter().filter(|d| !d.empty) {
+            let content: Vec<&str> = document.chunks.iter().map(|c| c.content.as_str()).collect();
+            let extension = document.source.extension().and_then(|ext| ext.to_str());
+            let detection = analyzer.detect_document_domain(&content.join("\n"), extension);
+            domain_confidence[bucket(detection.confidence)] += 1;
+        }
+
+        let encodings: Vec<&str> = self
+            .documents
+            .iter()
+            .filter_map(|d| d.encoding.as_deref())
+            .collect();
+        let fallbacks = encodings
+            .iter()
+            .filter(|e| !DETECTED_ENCODINGS.contains(e))
+            .count();
+        let rate = |count: usize, total: usize| {
+            if total == 0 {
+                0.0
+            } else {
+                count as f64 / total as f64
+            }
+        };
+        let attempted = stats.files_processed + stats.files_empty + stats.files_with_errors;
+
+        QualityMetrics {
+            files_processed: stats.files_processed,
+            files_empty: stats.files_empty,
+            files_with_errors: stats.files_with_errors,
+            total_entities: stats.total_entities,
+            avg_entities_per_file: per_file(stats.total_entities),
+            avg_relationships_per_file: per_file(stats.total_relationships),
+            avg_chunks_per_file: per_file(stats.total_chunks),
+            entity_confidence,
+            domain_confidence,
+            encoding_fallback_rate: rate(fallbacks, encodings.len()),
+            error_rate: rate(stats.files_with_errors, attempted),
+        }
+    }
+}
+
+/// One line of JSON-lines pipeline output.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonLine<'a> {
+    /// A processed document.
+    Document(&'a DocumentResult),
+
+    /// Statistics for the run, written last.
+    Summary(&'a PipelineStats),
+}
+
+/// Live progress of a directory run, shared between threads.
+///
+/// Create a handle before starting `process_directory_with_progress` and
+/// poll a clone of it from another thread. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressHandle {
+    inner: Arc<ProgressState>,
+}
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    files_done: AtomicUsize,
+    files_total: AtomicUsize,
+    started: Mutex<Option<Instant>>,
+}
+
+impl ProgressHandle {
+    /// Create a handle with no progress recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of files finished so far, including skipped and failed files.
+    pub fn files_done(&self) -> usize {
+        self.inner.files_done.load(Ordering::Acquire)
+    }
+
+    /// Number of files found for this run (0 until traversal finishes).
+    pub fn files_total(&self) -> usize {
+        self.inner.files_total.load(Ordering::Acquire)
+    }
+
+    /// Percentage of files finished (0.0 to 100.0).
+    pub fn percent_complete(&self) -> f32 {
+        let total = self.files_total();
+        if total == 0 {
+            return 0.0;
+        }
+        self.files_done().min(total) as f32 / total as f32 * 100.0
+    }
+
+    /// Time since file processing started.
+    pub fn elapsed(&self) -> Duration {
+        self.started().map(|s| s.elapsed()).unwrap_or_default()
+    }
+
+    /// Estimated time remaining, based on the average time per finished file.
+    ///
+    /// Returns `None` until at least one file has finished.
+    pub fn eta(&self) -> Option<Duration> {
+        let started = self.started()?;
+        let done = self.files_done();
+        if done == 0 {
+            return None;
+        }
+        let remaining = self.files_total().saturating_sub(done);
+        let per_file = started.elapsed() / done as u32;
+        Some(per_file * remaining as u32)
+    }
+
+    fn started(&self) -> Option<Instant> {
+        *self
+            .inner
+            .started
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Reset progress for a run over `total` files.
+    fn start(&self, total: usize) {
+        *self
+            .inner
+            .started
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+        self.inner.files_done.store(0, Ordering::Release);
+        self.inner.files_total.store(total, Ordering::Release);
+    }
+
+    /// Record one more finished file.
+    fn file_done(&self) {
+        self.inner.files_done.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Number of processed files between checkpoint saves.
+const CHECKPOINT_INTERVAL: usize = 50;
+
+/// State of a file when it was last processed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ManifestEntry {
+    /// Modification time, if the platform reports one.
+    modified: Option<SystemTime>,
+
+    /// Size in bytes.
+    size: u64,
+
+    /// SHA-256 of the file's bytes, recorded by incremental runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_hash: Option<String>,
+}
+
+impl ManifestEntry {
+    /// Record a file's current metadata, with an optional content hash.
+    fn new(metadata: &std::fs::Metadata, content_hash: Option<String>) -> Self {
+        Self {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            content_hash,
+        }
+    }
+}
+
+/// Files processed by earlier pipeline runs over a directory.
+///
+/// Serves both as the checkpoint for resumable runs and as the manifest for
+/// incremental runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FileManifest {
+    files: BTreeMap<PathBuf, ManifestEntry>,
+}
+
+impl FileManifest {
+    /// Load a manifest, starting fresh if it is missing or unreadable.
+    fn load(path: &Path) -> Self {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable manifest {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    /// Save the manifest, replacing the previous file atomically.
+    fn save(&self, path: &Path) -> Result<()> {
+        write_atomically(path, serde_json::to_string(self)?.as_bytes())
+    }
+
+    /// Whether the file is recorded with its current modification time and
+    /// size.
+    fn is_current(&self, file: &Path, metadata: &std::fs::Metadata) -> bool {
+        let modified = metadata.modified().ok();
+        self.files.get(file).is_some_and(|entry| {
+            modified.is_some() && entry.modified == modified && entry.size == metadata.len()
+        })
+    }
+}
+
+/// Hash file contents for change detection.
+///
+/// Uses SHA-256 so hashes saved in a manifest stay valid across Rust
+/// versions.
+fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Write `contents` to `path` via a temporary file, so readers never see a
+/// partial write.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Callback receiving quality metrics after each directory run.
+type QualityReporter = Arc<dyn Fn(&QualityMetrics) + Send + Sync>;
+
 /// The main context extraction pipeline.
 pub struct ContextPipeline {
     config: PipelineConfig,
     chunker: SemanticChunker,
+    profile_chunkers: HashMap<String, SemanticChunker>,
     entity_extractor: EntityExtractor,
     relationship_extractor: RelationshipExtractor,
     context_generator: ContextGenerator,
+    quality_reporter: Option<QualityReporter>,
+    relationship_runs: AtomicUsize,
 }
 
 impl ContextPipeline {
@@ -165,13 +588,54 @@ impl ContextPipeline {
     pub fn with_config(config: PipelineConfig) -> Self {
         Self {
             chunker: SemanticChunker::with_config(config.chunker.clone()),
+            profile_chunkers: config
+                .chunker_profiles
+                .iter()
+                .map(|(ext, profile)| (ext.clone(), SemanticChunker::with_config(profile.clone())))
+                .collect(),
             entity_extractor: EntityExtractor::with_config(config.entity.clone()),
             relationship_extractor: RelationshipExtractor::with_config(config.relationship.clone()),
             context_generator: ContextGenerator::with_config(config.generator.clone()),
+            quality_reporter: None,
+            relationship_runs: AtomicUsize::new(0),
             config,
         }
     }
 
+    /// Number of documents relationship extraction has run on.
+    pub fn relationship_extractions(&self) -> usize {
+        self.relationship_runs.load(Ordering::Relaxed)
+    }
+
+    /// Report quality metrics to `reporter` after each directory run.
+    ///
+    /// The reporter receives `PipelineResult::quality_metrics`, which holds
+    /// only numeric aggregates.
+    pub fn with_quality_reporter(
+        mut self,
+        reporter: impl Fn(&QualityMetrics) + Send + Sync + 'static,
+    ) -> Self {
+        self.quality_reporter = Some(Arc::new(reporter));
+        self
+    }
+
+    /// Use `id_generator` for chunk and entity IDs.
+    ///
+    /// Files are processed in order, so a seeded generator makes repeated
+    /// runs over the same inputs produce the same IDs.
+    pub fn with_id_generator(self, id_generator: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            chunker: self.chunker.with_id_generator(Arc::clone(&id_generator)),
+            profile_chunkers: self
+                .profile_chunkers
+                .into_iter()
+                .map(|(ext, chunker)| (ext, chunker.with_id_generator(Arc::clone(&id_generator))))
+                .collect(),
+            entity_extractor: self.entity_extractor.with_id_generator(id_generator),
+            ..self
+        }
+    }
+
     /// Process a single document and return extracted information.
     pub fn process_document(&self, content: &str, source: Option<&Path>) -> Result<DocumentResult> {
         let source_path = source.map(|p| p.to_path_buf()).unwrap_or_default();
@@ -179,20 +643,72 @@ impl ContextPipeline {
 
         debug!("Processing document: {:?}", source_path);
 
+        if content.trim().is_empty() {
+            debug!("Document is empty: {:?}", source_path);
+            return Ok(DocumentResult {
+                source: source_path,
+                chunks: Vec::new(),
+                entities: Vec::new(),
+                relationships: Vec::new(),
+                empty: true,
+                encoding: None,
+            });
+        }
+
         // Step 1: Chunk the document
         let chunks = if let Some(ref src) = source_str {
-            self.chunker.chunk_with_source(content, src)
+            self.chunker_for(src).chunk_with_source(content, src)
         } else {
             self.chunker.chunk(content)
         };
         debug!("Created {} chunks", chunks.len());
 
+        // Manifests state their metadata exactly, so parse them directly
+        // instead of running prose extraction over them.
+        let parsed_config = source_str
+            .as_deref()
+            .and_then(ConfigFileParser::for_source)
+            .and_then(|parser| parser.parse(content, &chunks));
+        if let Some(parsed) = parsed_config {
+            debug!(
+                "Parsed {} entities from config file {source_path:?}",
+                parsed.entities.len()
+            );
+            let relationships = if self.config.extract_relationships {
+                parsed.relationships
+            } else {
+                Vec::new()
+            };
+            return Ok(DocumentResult {
+                source: source_path,
+                chunks,
+                entities: parsed.entities,
+                relationships,
+                empty: false,
+                encoding: None,
+            });
+        }
+
         // Step 2: Extract entities from chunks
-        let entities = self.entity_extractor.extract(&chunks);
+        let mut entities = self.entity_extractor.extract(&chunks);
         debug!("Extracted {} entities", entities.len());
 
         // Step 3: Extract relationships
-        let relationships = self.relationship_extractor.extract(&entities, &chunks);
+        let mut relationships = if self.config.extract_relationships {
+            self.relationship_runs.fetch_add(1, Ordering::Relaxed);
+            self.relationship_extractor.extract(&entities, &chunks)
+        } else {
+            Vec::new()
+        };
+
+        // Markdown links are explicit references to files and URLs
+        if self.config.extract_relationships
+            && let Some(path) = source
+            && MarkdownLinkExtractor::is_markdown(path)
+        {
+            let links = MarkdownLinkExtractor::new().extract(path, &chunks);
+            relationships.extend(links.merge_into(&mut entities));
+        }
         debug!("Extracted {} relationships", relationships.len());
 
         Ok(DocumentResult {
@@ -200,51 +716,65 @@ impl ContextPipeline {
             chunks,
             entities,
             relationships,
+            empty: false,
+            encoding: None,
         })
     }
 
+    /// The chunker for a source, chosen by its extension.
+    fn chunker_for(&self, source: &str) -> &SemanticChunker {
+        Path::new(source)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.profile_chunkers.get(&ext.to_lowercase()))
+            .unwrap_or(&self.chunker)
+    }
+
     /// Process a directory of files.
     pub fn process_directory(&self, dir: &Path) -> Result<PipelineResult> {
+        self.process_directory_with_progress(dir, &ProgressHandle::new())
+    }
+
+    /// Process a directory of files, reporting progress to `progress`.
+    pub fn process_directory_with_progress(
+        &self,
+        dir: &Path,
+        progress: &ProgressHandle,
+    ) -> Result<PipelineResult> {
         let start_time = std::time::Instant::now();
 
         info!("Processing directory: {:?}", dir);
 
         let mut documents = Vec::new();
-        let mut errors = Vec::new();
-        let mut stats = PipelineStats::default();
-
-        // Collect files to process
-        let files = self.collect_files(dir)?;
-        info!("Found {} files to process", files.len());
-
-        for file_path in files {
-            match self.process_file(&file_path) {
-                Ok(doc_result) => {
-                    stats.total_chunks += doc_result.chunks.len();
-                    stats.total_entities += doc_result.entities.len();
-                    stats.total_relationships += doc_result.relationships.len();
-                    stats.files_processed += 1;
-                    documents.push(doc_result);
-                }
-                Err(e) => {
-                    warn!("Failed to process {:?}: {}", file_path, e);
-                    errors.push((file_path, e.to_string()));
-                    stats.files_with_errors += 1;
-                }
-            }
-        }
+        let (mut stats, errors) = self.for_each_document(dir, progress, |doc_result| {
+            documents.push(doc_result);
+            Ok(())
+        })?;
 
         // Aggregate all entities and relationships
-        let mut all_entities: Vec<Entity> =
-            documents.iter().flat_map(|d| d.entities.clone()).collect();
-        let mut all_relationships: Vec<Relationship> = documents
-            .iter()
+        let content_documents = || documents.iter().filter(|d| !d.empty);
+        let mut all_entities: Vec<Entity> = content_documents()
+            .flat_map(|d| d.entities.clone())
+            .collect();
+        let mut all_relationships: Vec<Relationship> = content_documents()
             .flat_map(|d| d.relationships.clone())
             .collect();
 
-        // Deduplicate across documents
-        all_entities = deduplicate_entities(all_entities);
+        // Deduplicate across documents, pointing relationships at the
+        // surviving entity IDs
+        let id_map;
+        (all_entities, id_map) = deduplicate_entities(all_entities);
+        for rel in &mut all_relationships {
+            if let Some(id) = id_map.get(&rel.source_id) {
+                rel.source_id = id.clone();
+            }
+            if let Some(id) = id_map.get(&rel.target_id) {
+                rel.target_id = id.clone();
+            }
+        }
         all_relationships = deduplicate_relationships(all_relationships);
+        sort_entities(&mut all_entities);
+        sort_relationships(&mut all_relationships);
 
         stats.total_entities = all_entities.len();
         stats.total_relationships = all_relationships.len();
@@ -266,46 +796,348 @@ impl ContextPipeline {
             stats.processing_time_ms
         );
 
-        Ok(PipelineResult {
+        let result = PipelineResult {
+            root: dir.to_path_buf(),
             documents,
             all_entities,
             all_relationships,
             contexts,
             errors,
             stats,
-        })
+        };
+        if let Some(reporter) = &self.quality_reporter {
+            reporter(&result.quality_metrics());
+        }
+        Ok(result)
+    }
+
+    /// Process only the files in `dir` that changed since the last run.
+    ///
+    /// `manifest_path` records each indexed file's modification time, size,
+    /// and content hash. A file whose size and modification time match its
+    /// entry is skipped without being read; otherwise it is hashed and
+    /// processed only if its content differs. Files in the manifest that no
+    /// longer exist are reported as removed. The manifest is rewritten at
+    /// the end of the run.
+    pub fn process_directory_incremental(
+        &self,
+        dir: &Path,
+        manifest_path: &Path,
+    ) -> Result<IncrementalResult> {
+        info!("Incrementally processing directory: {:?}", dir);
+
+        let mut result = IncrementalResult::default();
+        let mut manifest = FileManifest::load(manifest_path);
+        let files: Vec<PathBuf> = self
+            .collect_files(dir, &mut result.errors)?
+            .into_iter()
+            .filter(|file| file != manifest_path)
+            .collect();
+
+        let current: HashSet<&PathBuf> = files.iter().collect();
+        result.removed = manifest
+            .files
+            .keys()
+            .filter(|file| file.starts_with(dir) && !current.contains(file))
+            .cloned()
+            .collect();
+        for file in &result.removed {
+            manifest.files.remove(file);
+        }
+
+        for file_path in &files {
+            let metadata = match std::fs::metadata(file_path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    result.errors.push((file_path.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            if manifest.is_current(file_path, &metadata) {
+                result.unchanged += 1;
+                continue;
+            }
+
+            let bytes = match std::fs::read(file_path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    result.errors.push((file_path.clone(), e.to_string()));
+                    continue;
+                }
+            };
+            let entry = ManifestEntry::new(&metadata, Some(content_hash(&bytes)));
+            let previous = manifest.files.get(file_path);
+            let is_new = previous.is_none();
+            if previous.is_some_and(|p| p.content_hash == entry.content_hash) {
+                debug!("Content unchanged: {:?}", file_path);
+                result.unchanged += 1;
+                manifest.files.insert(file_path.clone(), entry);
+                continue;
+            }
+
+            match self.process_file(dir, file_path) {
+                Ok(doc_result) => {
+                    result.documents.push(doc_result);
+                    if is_new {
+                        result.added.push(file_path.clone());
+                    } else {
+                        result.changed.push(file_path.clone());
+                    }
+                    manifest.files.insert(file_path.clone(), entry);
+                }
+                Err(e) => {
+                    warn!("Failed to process {:?}: {}", file_path, e);
+                    result.errors.push((file_path.clone(), e.to_string()));
+                }
+            }
+        }
+
+        manifest.save(manifest_path)?;
+
+        info!(
+            "Incremental run: {} added, {} changed, {} removed, {} unchanged",
+            result.added.len(),
+            result.changed.len(),
+            result.removed.len(),
+            result.unchanged
+        );
+        Ok(result)
+    }
+
+    /// Process a directory, writing one JSON object per line.
+    ///
+    /// Each document is written as a `{"type": "document", ...}` line as soon
+    /// as it is processed, followed by a final `{"type": "summary", ...}`
+    /// line with the run statistics. Empty documents are counted in the
+    /// summary's `files_empty` but not written, so there is one document
+    /// line per processed file. Documents are not kept in memory, so
+    /// entities are not deduplicated across documents and no contexts are
+    /// generated.
+    pub fn process_directory_jsonl(
+        &self,
+        dir: &Path,
+        mut writer: impl Write,
+    ) -> Result<PipelineStats> {
+        let start_time = Instant::now();
+
+        info!("Processing directory as JSON lines: {:?}", dir);
+
+        let (mut stats, _errors) =
+            self.for_each_document(dir, &ProgressHandle::new(), |doc_result| {
+                if doc_result.empty {
+                    return Ok(());
+                }
+                serde_json::to_writer(&mut writer, &JsonLine::Document(&doc_result))?;
+                writer.write_all(b"\n")?;
+                Ok(())
+            })?;
+        stats.processing_time_ms = start_time.elapsed().as_millis() as u64;
+
+        serde_json::to_writer(&mut writer, &JsonLine::Summary(&stats))?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+
+        Ok(stats)
+    }
+
+    /// Process each file in a directory, passing documents to `on_document`.
+    ///
+    /// Files recorded in the checkpoint, and the checkpoint itself, are
+    /// skipped. Returns per-file stats and the files that failed to process.
+    fn for_each_document(
+        &self,
+        dir: &Path,
+        progress: &ProgressHandle,
+        mut on_document: impl FnMut(DocumentResult) -> Result<()>,
+    ) -> Result<(PipelineStats, Vec<(PathBuf, String)>)> {
+        let mut errors = Vec::new();
+        let mut stats = PipelineStats::default();
+
+        let checkpoint_path = self.config.checkpoint_path.as_deref();
+
+        // Collect files to process
+        let files: Vec<PathBuf> = self
+            .collect_files(dir, &mut errors)?
+            .into_iter()
+            .filter(|file| Some(file.as_path()) != checkpoint_path)
+            .collect();
+        info!("Found {} files to process", files.len());
+        progress.start(files.len());
+
+        let mut checkpoint = checkpoint_path.map(FileManifest::load).unwrap_or_default();
+        let mut unsaved = 0;
+
+        for file_path in files {
+            let metadata = std::fs::metadata(&file_path).ok();
+            if metadata
+                .as_ref()
+                .is_some_and(|m| checkpoint.is_current(&file_path, m))
+            {
+                debug!("Skipping checkpointed file: {:?}", file_path);
+                stats.files_skipped += 1;
+                progress.file_done();
+                continue;
+            }
+
+            match self.process_file(dir, &file_path) {
+                Ok(doc_result) if doc_result.empty => {
+                    debug!("Skipping empty file: {:?}", file_path);
+                    stats.files_empty += 1;
+                    on_document(doc_result)?;
+                }
+                Ok(doc_result) => {
+                    stats.total_chunks += doc_result.chunks.len();
+                    stats.total_entities += doc_result.entities.len();
+                    stats.total_relationships += doc_result.relationships.len();
+                    stats.files_processed += 1;
+                    on_document(doc_result)?;
+                }
+                Err(e) => {
+                    warn!("Failed to process {:?}: {}", file_path, e);
+                    errors.push((file_path, e.to_string()));
+                    stats.files_with_errors += 1;
+                    progress.file_done();
+                    continue;
+                }
+            }
+            progress.file_done();
+
+            if let (Some(path), Some(metadata)) = (checkpoint_path, &metadata) {
+                checkpoint
+                    .files
+                    .insert(file_path, ManifestEntry::new(metadata, None));
+                unsaved += 1;
+                if unsaved >= CHECKPOINT_INTERVAL {
+                    checkpoint.save(path)?;
+                    unsaved = 0;
+                }
+            }
+        }
+
+        if let Some(path) = checkpoint_path
+            && unsaved > 0
+        {
+            checkpoint.save(path)?;
+        }
+
+        Ok((stats, errors))
     }
 
-    /// Process a single file.
-    fn process_file(&self, path: &Path) -> Result<DocumentResult> {
+    /// Process a single file found under `root`.
+    fn process_file(&self, root: &Path, path: &Path) -> Result<DocumentResult> {
         let metadata = std::fs::metadata(path)?;
 
-        if metadata.len() > self.config.max_file_size as u64 {
-            return Err(ContextError::InvalidFormat(format!(
-                "File too large: {} bytes",
-                metadata.len()
-            )));
+        let limit = self.config.max_file_size as u64;
+        if metadata.len() > limit {
+            return Err(ContextError::FileTooLarge {
+                size: metadata.len(),
+                limit,
+            });
         }
 
-        let content = std::fs::read_to_string(path)?;
-        self.process_document(&content, Some(path))
+        let bytes = std::fs::read(path)?;
+        let fallback = self.config.fallback_encoding.as_deref().and_then(|label| {
+            let encoding = encoding_for_label(label);
+            if encoding.is_none() {
+                warn!("Unknown fallback encoding: {label}");
+            }
+            encoding
+        });
+        let decoded = decode(&bytes, fallback).ok_or_else(|| {
+            ContextError::UnsupportedFormat(format!("{} is not a text file", path.display()))
+        })?;
+        if decoded.lossy {
+            warn!(
+                "Replaced invalid {} sequences in {}",
+                decoded.encoding,
+                path.display()
+            );
+        }
+
+        let mut result = self.process_document(&decoded.text, Some(path))?;
+        result.encoding = Some(decoded.encoding.to_string());
+        if self.config.path_entities && !result.empty {
+            self.add_path_entities(root, &mut result);
+        }
+        Ok(result)
+    }
+
+    /// Merge entities named by the document's path into its entities.
+    ///
+    /// The path is taken relative to `root`, so directories above the
+    /// processed directory don't contribute.
+    fn add_path_entities(&self, root: &Path, result: &mut DocumentResult) {
+        let Some(chunk_id) = result.chunks.first().map(|c| c.id.clone()) else {
+            return;
+        };
+        let relative = result.source.strip_prefix(root).unwrap_or(&result.source);
+        let source = result.source.to_string_lossy().to_string();
+
+        let path_entities = self.entity_extractor.extract_from_path(relative, &chunk_id);
+        debug!(
+            "Derived {} entities from path {relative:?}",
+            path_entities.len()
+        );
+        for mut entity in path_entities
+            .into_iter()
+            .filter(|e| e.confidence >= self.config.entity.min_confidence)
+        {
+            for mention in &mut entity.mentions {
+                mention.source = Some(source.clone());
+            }
+            let key = entity.merge_key();
+            match result.entities.iter_mut().find(|e| e.merge_key() == key) {
+                Some(existing) => existing.merge(entity),
+                None => result.entities.push(entity),
+            }
+        }
+        sort_entities(&mut result.entities);
     }
 
-    /// Collect files to process from a directory.
-    fn collect_files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+    /// Collect files to process from a directory, in `order_by` order.
+    ///
+    /// Subdirectories that can't be read are recorded in `errors` and
+    /// skipped; only an unreadable `dir` itself is an error. Files whose
+    /// metadata can't be read sort last, and ties are broken by path.
+    fn collect_files(
+        &self,
+        dir: &Path,
+        errors: &mut Vec<(PathBuf, String)>,
+    ) -> Result<Vec<PathBuf>> {
         let mut files = Vec::new();
 
-        self.collect_files_recursive(dir, &mut files)?;
+        self.collect_files_recursive(dir, true, &IgnoreRules::new(), &mut files, errors)?;
+        files.sort();
+        match self.config.order_by {
+            FileOrder::Alphabetical => {}
+            FileOrder::RecentFirst => files.sort_by_cached_key(|path| {
+                let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+                (modified.is_none(), std::cmp::Reverse(modified))
+            }),
+            FileOrder::SizeAscending => files.sort_by_cached_key(|path| {
+                let size = std::fs::metadata(path).map(|m| m.len()).ok();
+                (size.is_none(), size)
+            }),
+        }
 
         Ok(files)
     }
 
     /// Recursively collect files.
-    fn collect_files_recursive(&self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    fn collect_files_recursive(
+        &self,
+        dir: &Path,
+        top_level: bool,
+        ignore: &IgnoreRules,
+        files: &mut Vec<PathBuf>,
+        errors: &mut Vec<(PathBuf, String)>,
+    ) -> Result<()> {
         if !dir.is_dir() {
             return Ok(());
         }
 
+        let ignore = ignore.descend(dir);
+
         for entry in std::fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
@@ -319,7 +1151,13 @@ impl ContextPipeline {
                 continue;
             }
 
-            if path.is_dir() {
+            // Honor .codexignore before the include list
+            let is_dir = path.is_dir();
+            if ignore.is_ignored(&path, is_dir) {
+                continue;
+            }
+
+            if is_dir {
                 // Skip configured directories
                 if self
                     .config
@@ -328,7 +1166,18 @@ impl ContextPipeline {
                 {
                     continue;
                 }
-                self.collect_files_recursive(&path, files)?;
+                // Restrict top-level traversal to the include list
+                if top_level
+                    && let Some(include) = &self.config.include_directories
+                    && !include.is_empty()
+                    && !include.iter().any(|d| d == file_name)
+                {
+                    continue;
+                }
+                if let Err(e) = self.collect_files_recursive(&path, false, &ignore, files, errors) {
+                    warn!("Failed to read directory {:?}: {}", path, e);
+                    errors.push((path, e.to_string()));
+                }
             } else if path.is_file() {
                 // Check extension
                 if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -372,15 +1221,15 @@ impl Default for ContextPipeline {
 }
 
 /// Deduplicate entities by normalized name.
-fn deduplicate_entities(entities: Vec<Entity>) -> Vec<Entity> {
-    use std::collections::HashMap;
-
+fn deduplicate_entities(entities: Vec<Entity>) -> (Vec<Entity>, HashMap<String, String>) {
     let mut seen: HashMap<String, Entity> = HashMap::new();
+    let mut id_map: HashMap<String, String> = HashMap::new();
 
     for entity in entities {
-        let key = format!("{:?}:{}", entity.entity_type, entity.normalized_name);
+        let key = entity.merge_key();
 
         if let Some(existing) = seen.get_mut(&key) {
+            id_map.insert(entity.id.clone(), existing.id.clone());
             // Merge mentions
             existing.mentions.extend(entity.mentions);
             // Keep higher confidence
@@ -391,18 +1240,17 @@ fn deduplicate_entities(entities: Vec<Entity>) -> Vec<Entity> {
             for (k, v) in entity.attributes {
                 existing.attributes.entry(k).or_insert(v);
             }
+            existing.resolve_display_name();
         } else {
             seen.insert(key, entity);
         }
     }
 
-    seen.into_values().collect()
+    (seen.into_values().collect(), id_map)
 }
 
 /// Deduplicate relationships.
 fn deduplicate_relationships(relationships: Vec<Relationship>) -> Vec<Relationship> {
-    use std::collections::HashMap;
-
     let mut seen: HashMap<String, Relationship> = HashMap::new();
 
     for rel in relationships {
@@ -469,6 +1317,39 @@ impl PipelineBuilder {
         self
     }
 
+    /// Chunk files with the given extensions using `config`.
+    pub fn with_chunker_profile(mut self, extensions: Vec<String>, config: ChunkerConfig) -> Self {
+        for extension in extensions {
+            self.config
+                .chunker_profiles
+                .insert(extension.to_lowercase(), config.clone());
+        }
+        self
+    }
+
+    /// Chunk source code with `ChunkerConfig::code` and Markdown and text
+    /// with `ChunkerConfig::prose`.
+    pub fn with_default_chunker_profiles(self) -> Self {
+        let code = ["rs", "py", "js", "ts", "tsx", "jsx"].map(String::from);
+        let prose = ["md", "txt"].map(String::from);
+        self.with_chunker_profile(code.to_vec(), ChunkerConfig::code())
+            .with_chunker_profile(prose.to_vec(), ChunkerConfig::prose())
+    }
+
+    /// Record processed files in a checkpoint so later runs can resume.
+    ///
+    /// Results of resumed runs include only the files not yet recorded.
+    pub fn with_checkpoint(mut self, path: PathBuf) -> Self {
+        self.config.checkpoint_path = Some(path);
+        self
+    }
+
+    /// Restrict traversal to the given top-level subdirectories.
+    pub fn with_include_dirs(mut self, dirs: Vec<String>) -> Self {
+        self.config.include_directories = Some(dirs);
+        self
+    }
+
     /// Set maximum file size.
     pub fn with_max_file_size(mut self, size: usize) -> Self {
         self.config.max_file_size = size;
@@ -481,6 +1362,30 @@ impl PipelineBuilder {
         self
     }
 
+    /// Enable or disable relationship extraction.
+    pub fn with_relationships(mut self, extract: bool) -> Self {
+        self.config.extract_relationships = extract;
+        self
+    }
+
+    /// Enable or disable deriving entities from file and directory names.
+    pub fn with_path_entities(mut self, extract: bool) -> Self {
+        self.config.path_entities = extract;
+        self
+    }
+
+    /// Set the order in which a directory's files are processed.
+    pub fn with_file_order(mut self, order: FileOrder) -> Self {
+        self.config.order_by = order;
+        self
+    }
+
+    /// Set the encoding label used for non-UTF-8 files (`None` for lossy UTF-8).
+    pub fn with_fallback_encoding(mut self, label: Option<String>) -> Self {
+        self.config.fallback_encoding = label;
+        self
+    }
+
     /// Set source identifier for generated contexts.
     pub fn with_source_id(mut self, source_id: String) -> Self {
         self.config.generator.source_id = Some(source_id);
@@ -502,6 +1407,9 @@ impl Default for PipelineBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chunker::ChunkType;
+    use crate::generator::ClusterMethod;
+    use crate::relationship::RelationshipType;
     use tempfile::TempDir;
 
     #[test]
@@ -543,6 +1451,27 @@ This project uses Rust and Python for data processing.
         assert!(techs.len() >= 2);
     }
 
+    #[test]
+    fn test_process_document_config_file() {
+        let pipeline = ContextPipeline::new();
+        let content = "[package]\nname = \"dataflow\"\n\n[dependencies]\ntokio = \"1\"\n";
+
+        let result = pipeline
+            .process_document(content, Some(Path::new("Cargo.toml")))
+            .unwrap();
+
+        let project = result
+            .entities
+            .iter()
+            .find(|e| e.entity_type == crate::entity::EntityType::Project)
+            .unwrap();
+        assert_eq!(project.name, "dataflow");
+        assert!(result.relationships.iter().any(|r| {
+            r.relationship_type == crate::relationship::RelationshipType::DependsOn
+                && r.target_name == "tokio"
+        }));
+    }
+
     #[test]
     fn test_process_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -568,6 +1497,231 @@ This project uses Rust and Python for data processing.
         assert!(!result.contexts.is_empty());
     }
 
+    #[test]
+    fn test_chunker_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut code = String::from("fn handler() {\n");
+        for i in 0..180 {
+            code.push_str(&format!("    let value_{i:03} = compute({i:03});\n"));
+        }
+        code.push_str("}\n");
+        std::fs::write(temp_dir.path().join("handler.rs"), &code).unwrap();
+        let prose = "The kitchen notes cover weeknight dinners. ".repeat(10);
+        let notes = vec![prose.trim_end(); 15].join("\n\n");
+        std::fs::write(temp_dir.path().join("notes.md"), &notes).unwrap();
+        assert!(code.len().abs_diff(notes.len()) < 1000);
+
+        let pipeline = PipelineBuilder::new()
+            .with_default_chunker_profiles()
+            .build();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+        let chunks = |name: &str| {
+            let document = result
+                .documents
+                .iter()
+                .find(|d| d.source.ends_with(name))
+                .unwrap();
+            document.chunks.clone()
+        };
+
+        // The whole function fits the code profile's larger chunks
+        let code_chunks = chunks("handler.rs");
+        assert_eq!(code_chunks.len(), 1);
+        assert!(!code_chunks[0].within_limit(ChunkerConfig::default().max_tokens));
+        assert!(code_chunks[0].within_limit(ChunkerConfig::code().max_tokens));
+
+        // Prose is split into paragraph chunks within the prose profile
+        let prose_chunks = chunks("notes.md");
+        assert!(prose_chunks.len() > 1);
+        let prose_max = ChunkerConfig::prose().max_tokens;
+        assert!(prose_chunks.iter().all(|c| c.within_limit(prose_max)));
+    }
+
+    #[test]
+    fn test_path_entities() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("billing")).unwrap();
+        std::fs::write(
+            temp_dir.path().join("billing").join("payment-service.rs"),
+            "fn charge() {}\n",
+        )
+        .unwrap();
+
+        let path_entities = |pipeline: ContextPipeline| {
+            let result = pipeline.process_directory(temp_dir.path()).unwrap();
+            result
+                .all_entities
+                .iter()
+                .map(|e| (e.normalized_name.clone(), e.entity_type))
+                .filter(|(name, _)| name == "billing" || name == "payment service")
+                .collect::<Vec<_>>()
+        };
+
+        assert!(path_entities(ContextPipeline::new()).is_empty());
+
+        let mut found = path_entities(PipelineBuilder::new().with_path_entities(true).build());
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            found,
+            vec![
+                ("billing".to_string(), crate::entity::EntityType::Concept),
+                (
+                    "payment service".to_string(),
+                    crate::entity::EntityType::Project
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_seeded_ids_are_reproducible() {
+        use crate::id::SeededIdGenerator;
+        use crate::tree::ContextTree;
+
+        let content = "# Search\n\nBuilt with Rust and Tokio by Alice Smith.\n\n\
+                       The indexer uses Redis for caching since 2024-03-01.";
+        let run = || {
+            let id_generator: Arc<dyn IdGenerator> = Arc::new(SeededIdGenerator::new(42));
+            let pipeline = ContextPipeline::new().with_id_generator(Arc::clone(&id_generator));
+            let result = pipeline
+                .process_document(content, Some(Path::new("/notes/search.md")))
+                .unwrap();
+            let mut tree = ContextTree::new().with_id_generator(id_generator);
+            let domain_id = tree.ensure_domain("coding");
+
+            let mut ids: Vec<String> = result.chunks.into_iter().map(|c| c.id).collect();
+            ids.extend(result.entities.into_iter().map(|e| e.id));
+            ids.push(tree.root().id.clone());
+            ids.push(domain_id);
+            ids
+        };
+
+        let first = run();
+        assert!(first.len() > 4);
+        assert_eq!(first, run());
+    }
+
+    #[test]
+    fn test_quality_metrics() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("README.md"),
+            "# Secret Project\nCreated by Alice.\nUses Rust and Python.",
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("notes.md"), "Uses Go.").unwrap();
+        std::fs::write(temp_dir.path().join("empty.md"), "").unwrap();
+        std::fs::write(temp_dir.path().join("legacy.txt"), b"Caf\xe9 menu").unwrap();
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reported);
+        let pipeline = ContextPipeline::new()
+            .with_quality_reporter(move |metrics| sink.lock().unwrap().push(metrics.clone()));
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+        let metrics = result.quality_metrics();
+
+        assert_eq!(*reported.lock().unwrap(), vec![metrics.clone()]);
+        assert_eq!(metrics.files_processed, result.stats.files_processed);
+        assert_eq!(metrics.files_empty, result.stats.files_empty);
+        assert_eq!(metrics.total_entities, result.stats.total_entities);
+        assert_eq!(
+            metrics.entity_confidence.iter().sum::<usize>(),
+            result.stats.total_entities
+        );
+        assert_eq!(
+            metrics.domain_confidence.iter().sum::<usize>(),
+            result.stats.files_processed
+        );
+        // "Secret Project" reads as work (0.6); the rest fall back (0.3)
+        assert_eq!(metrics.domain_confidence, [0, 2, 0, 1, 0]);
+        assert_eq!(
+            metrics.avg_entities_per_file,
+            result.stats.total_entities as f64 / result.stats.files_processed as f64
+        );
+        assert_eq!(metrics.encoding_fallback_rate, 0.25);
+
+        // Nothing but numbers, so no content or paths can leak
+        let json = serde_json::to_value(&metrics).unwrap();
+        let fields = json.as_object().unwrap();
+        assert!(fields.values().all(|value| match value {
+            serde_json::Value::Number(_) => true,
+            serde_json::Value::Array(items) => items.iter().all(serde_json::Value::is_number),
+            _ => false,
+        }));
+        assert!(!json.to_string().contains("Alice"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_directory_with_unreadable_entries() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("README.md"),
+            "# My Project\nCreated by Bob.\nUses Rust.",
+        )
+        .unwrap();
+        // Binary content fails to decode whatever the caller's privileges
+        let binary_file = temp_dir.path().join("image.md");
+        std::fs::write(&binary_file, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01").unwrap();
+        let locked_dir = temp_dir.path().join("private");
+        std::fs::create_dir(&locked_dir).unwrap();
+        std::fs::write(locked_dir.join("notes.md"), "Uses Go.").unwrap();
+        std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // Permissions aren't enforced when running as root
+        let dir_locked = std::fs::read_dir(&locked_dir).is_err();
+
+        let pipeline = ContextPipeline::new();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+
+        // Restore access so the temp dir can be cleaned up
+        std::fs::set_permissions(&locked_dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut failed: Vec<&Path> = result.errors.iter().map(|(p, _)| p.as_path()).collect();
+        failed.sort();
+        let mut expected = vec![binary_file.as_path()];
+        if dir_locked {
+            expected.push(locked_dir.as_path());
+        }
+        assert_eq!(failed, expected);
+        assert_eq!(result.stats.files_with_errors, 1);
+        assert_eq!(result.stats.files_processed, if dir_locked { 1 } else { 2 });
+        assert!(!result.all_entities.is_empty());
+    }
+
+    #[test]
+    fn test_empty_documents() {
+        let temp_dir = TempDir::new().unwrap();
+
+        std::fs::write(temp_dir.path().join("empty.md"), "").unwrap();
+        std::fs::write(temp_dir.path().join("blank.txt"), "  \n\t\n   \n").unwrap();
+        std::fs::write(
+            temp_dir.path().join("README.md"),
+            "# My Project\nCreated by Bob.\nUses Rust.",
+        )
+        .unwrap();
+
+        let pipeline = ContextPipeline::new();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+
+        assert_eq!(result.stats.files_processed, 1);
+        assert_eq!(result.stats.files_empty, 2);
+        assert_eq!(result.stats.files_with_errors, 0);
+        assert!(result.errors.is_empty());
+
+        let empty: Vec<_> = result.documents.iter().filter(|d| d.empty).collect();
+        assert_eq!(empty.len(), 2);
+        assert!(
+            empty
+                .iter()
+                .all(|d| d.chunks.is_empty() && d.entities.is_empty())
+        );
+
+        let doc = pipeline.process_document(" \n\n ", None).unwrap();
+        assert!(doc.empty);
+    }
+
     #[test]
     fn test_pipeline_builder() {
         let pipeline = PipelineBuilder::new()
@@ -581,6 +1735,82 @@ This project uses Rust and Python for data processing.
         assert_eq!(pipeline.config.file_extensions.len(), 2);
     }
 
+    #[test]
+    fn test_source_file_prose_is_weighted_as_code() {
+        let config = PipelineConfig {
+            relationship: RelationshipExtractorConfig {
+                min_confidence: 0.0,
+                cooccurrence_threshold: 0.0,
+                use_patterns: false,
+                use_type_inference: false,
+                chunk_type_weights: HashMap::from([
+                    (ChunkType::Code, 2.0),
+                    (ChunkType::Paragraph, 0.6),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pipeline = ContextPipeline::with_config(config);
+        let prose = "Built with Rust, see https://example.com/docs for details.\n";
+
+        // Every chunk of a source file counts as code, prose included
+        let confidence = |source: &str| {
+            let result = pipeline
+                .process_document(prose, Some(Path::new(source)))
+                .unwrap();
+            let is_code = source.ends_with(".rs");
+            assert!(
+                result
+                    .chunks
+                    .iter()
+                    .all(|c| (c.chunk_type == ChunkType::Code) == is_code)
+            );
+            result
+                .relationships
+                .iter()
+                .find(|r| r.relationship_type == RelationshipType::RelatedTo)
+                .map(|r| r.confidence)
+                .unwrap()
+        };
+        let code = confidence("bindings.rs");
+        let paragraph = confidence("bindings.md");
+
+        assert!(code > paragraph, "code {code} <= paragraph {paragraph}");
+    }
+
+    #[test]
+    fn test_skip_relationships() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("README.md"),
+            "# Server\n\nCreated by John Smith. Built with Rust, uses Docker and PostgreSQL.\n\n\
+             Jane Doe maintains the Python and Redis scripts.\n",
+        )
+        .unwrap();
+
+        let full_pipeline = ContextPipeline::new();
+        let full = full_pipeline.process_directory(temp_dir.path()).unwrap();
+        assert!(full.stats.total_relationships > 0);
+        assert_eq!(full_pipeline.relationship_extractions(), 1);
+
+        let pipeline = PipelineBuilder::new().with_relationships(false).build();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+
+        // The extractor is never invoked, not just filtered afterwards
+        assert_eq!(pipeline.relationship_extractions(), 0);
+        assert_eq!(result.stats.total_relationships, 0);
+        assert!(result.documents.iter().all(|d| d.relationships.is_empty()));
+        assert_eq!(result.stats.total_entities, full.stats.total_entities);
+        assert!(!result.contexts.is_empty());
+        assert!(
+            result
+                .contexts
+                .iter()
+                .all(|ctx| ctx.cluster_method != ClusterMethod::RelationshipBased)
+        );
+    }
+
     #[test]
     fn test_skip_directories() {
         let temp_dir = TempDir::new().unwrap();
@@ -604,6 +1834,325 @@ This project uses Rust and Python for data processing.
         assert_eq!(result.stats.files_processed, 1);
     }
 
+    #[test]
+    fn test_file_order() {
+        use std::time::{Duration, SystemTime};
+
+        let temp_dir = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        for (name, content, age_secs) in [
+            ("a.md", "# Oldest and largest file", 300),
+            ("b.md", "# Newest", 0),
+            ("c.md", "# Middle file", 100),
+        ] {
+            let path = temp_dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age_secs))
+                .unwrap();
+        }
+
+        let collect = |order: FileOrder| -> Vec<String> {
+            PipelineBuilder::new()
+                .with_file_order(order)
+                .build()
+                .collect_files(temp_dir.path(), &mut Vec::new())
+                .unwrap()
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            collect(FileOrder::Alphabetical),
+            vec!["a.md", "b.md", "c.md"]
+        );
+        assert_eq!(
+            collect(FileOrder::RecentFirst),
+            vec!["b.md", "c.md", "a.md"]
+        );
+        assert_eq!(
+            collect(FileOrder::SizeAscending),
+            vec!["b.md", "c.md", "a.md"]
+        );
+    }
+
+    #[test]
+    fn test_include_directories() {
+        let temp_dir = TempDir::new().unwrap();
+
+        for dir in ["docs", "src", "scratch"] {
+            let path = temp_dir.path().join(dir);
+            std::fs::create_dir(&path).unwrap();
+            std::fs::write(path.join("notes.md"), format!("# {dir}\nUses Rust.")).unwrap();
+        }
+
+        // Skip list still applies inside included directories
+        let nested = temp_dir.path().join("src").join("node_modules");
+        std::fs::create_dir(&nested).unwrap();
+        std::fs::write(nested.join("dep.js"), "// skipped").unwrap();
+
+        let pipeline = PipelineBuilder::new()
+            .with_include_dirs(vec!["docs".to_string(), "src".to_string()])
+            .build();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+
+        assert_eq!(result.stats.files_processed, 2);
+        assert!(
+            result
+                .documents
+                .iter()
+                .all(|d| !d.source.starts_with(temp_dir.path().join("scratch")))
+        );
+
+        // An empty include list preserves the default behavior
+        let pipeline = PipelineBuilder::new().with_include_dirs(Vec::new()).build();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+        assert_eq!(result.stats.files_processed, 3);
+    }
+
+    #[test]
+    fn test_codexignore() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        std::fs::write(docs.join("notes.md"), "# Notes\nUses Rust.").unwrap();
+        std::fs::write(docs.join("secrets.md"), "# Secrets\nUses Python.").unwrap();
+        std::fs::write(temp_dir.path().join("secrets.md"), "# Secrets").unwrap();
+        std::fs::write(temp_dir.path().join(".codexignore"), "secrets.md\n").unwrap();
+
+        // Ignored files stay excluded even inside included directories
+        let pipeline = PipelineBuilder::new()
+            .with_include_dirs(vec!["docs".to_string()])
+            .build();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+
+        assert_eq!(result.stats.files_processed, 1);
+        assert!(
+            result
+                .documents
+                .iter()
+                .all(|d| d.source.file_name().unwrap() != "secrets.md")
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        std::fs::write(docs.join("a.md"), "# A\nUses Rust.").unwrap();
+        std::fs::write(docs.join("b.md"), "# B\nUses Python.").unwrap();
+        let checkpoint = temp_dir.path().join("checkpoint.json");
+
+        let pipeline = PipelineBuilder::new()
+            .with_checkpoint(checkpoint.clone())
+            .build();
+
+        let first = pipeline.process_directory(&docs).unwrap();
+        assert_eq!(first.stats.files_processed, 2);
+        assert!(checkpoint.exists());
+        drop(first);
+
+        // A re-run resumes from the checkpoint and skips finished files
+        let second = pipeline.process_directory(&docs).unwrap();
+        assert_eq!(second.stats.files_processed, 0);
+        assert_eq!(second.stats.files_skipped, 2);
+        // Only files processed in this run are returned
+        assert!(second.documents.is_empty());
+        assert!(second.all_entities.is_empty());
+
+        // A changed file is processed again
+        let changed = std::fs::File::options()
+            .write(true)
+            .open(docs.join("a.md"))
+            .unwrap();
+        changed
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(60))
+            .unwrap();
+        drop(changed);
+
+        let third = pipeline.process_directory(&docs).unwrap();
+        assert_eq!(third.stats.files_processed, 1);
+        assert_eq!(third.stats.files_skipped, 1);
+        assert_eq!(third.documents.len(), 1);
+        assert!(third.documents[0].source.ends_with("a.md"));
+    }
+
+    #[test]
+    fn test_checkpoint_inside_directory_is_not_processed() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.md"), "# A\nUses Rust.").unwrap();
+        let checkpoint = temp_dir.path().join("checkpoint.json");
+
+        let pipeline = PipelineBuilder::new()
+            .with_extensions(vec!["md".to_string(), "json".to_string()])
+            .with_checkpoint(checkpoint.clone())
+            .build();
+
+        pipeline.process_directory(temp_dir.path()).unwrap();
+        assert!(checkpoint.exists());
+
+        let second = pipeline.process_directory(temp_dir.path()).unwrap();
+        assert_eq!(second.stats.files_processed, 0);
+        assert_eq!(second.stats.files_skipped, 1);
+    }
+
+    #[test]
+    fn test_incremental_processing() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        std::fs::write(docs.join("a.md"), "# A\n\nBuilt with Rust.").unwrap();
+        std::fs::write(docs.join("b.md"), "# B\n\nBuilt with Python.").unwrap();
+        let manifest = temp_dir.path().join("manifest.json");
+        let pipeline = ContextPipeline::new();
+
+        let first = pipeline
+            .process_directory_incremental(&docs, &manifest)
+            .unwrap();
+        assert_eq!(first.added, vec![docs.join("a.md"), docs.join("b.md")]);
+        assert_eq!(first.documents.len(), 2);
+        assert!(manifest.exists());
+
+        // Nothing changed, so nothing is read or processed
+        let second = pipeline
+            .process_directory_incremental(&docs, &manifest)
+            .unwrap();
+        assert!(second.is_unchanged());
+        assert!(second.documents.is_empty());
+        assert_eq!(second.unchanged, 2);
+
+        // Only the edited file is processed again
+        std::fs::write(docs.join("a.md"), "# A\n\nBuilt with Rust and Docker.").unwrap();
+        let third = pipeline
+            .process_directory_incremental(&docs, &manifest)
+            .unwrap();
+        assert!(third.added.is_empty());
+        assert_eq!(third.changed, vec![docs.join("a.md")]);
+        assert_eq!(third.documents.len(), 1);
+        assert!(third.documents[0].source.ends_with("a.md"));
+        assert_eq!(third.unchanged, 1);
+
+        // Deleted files are reported so their nodes can be removed
+        std::fs::remove_file(docs.join("b.md")).unwrap();
+        let fourth = pipeline
+            .process_directory_incremental(&docs, &manifest)
+            .unwrap();
+        assert_eq!(fourth.removed, vec![docs.join("b.md")]);
+        assert!(fourth.documents.is_empty());
+    }
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        // Manifests persist hashes, so they must not depend on the toolchain
+        assert_eq!(
+            content_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_utf16_file_is_decoded() {
+        let temp_dir = TempDir::new().unwrap();
+        let text = "# Server\n\nCreated by John Smith. Built with Rust and Docker.\n";
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        std::fs::write(temp_dir.path().join("README.md"), bytes).unwrap();
+
+        let pipeline = ContextPipeline::new();
+        let result = pipeline.process_directory(temp_dir.path()).unwrap();
+
+        assert!(result.errors.is_empty());
+        assert_eq!(result.stats.files_processed, 1);
+        assert_eq!(result.documents[0].encoding.as_deref(), Some("UTF-16LE"));
+        let names: Vec<&str> = result
+            .all_entities
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert!(names.contains(&"Rust"), "entities: {names:?}");
+        assert!(names.contains(&"John Smith"), "entities: {names:?}");
+    }
+
+    #[test]
+    fn test_process_directory_jsonl() {
+        let temp_dir = TempDir::new().unwrap();
+        for (name, text) in [
+            ("a.md", "# Server\n\nBuilt with Rust and Docker.\n"),
+            ("b.md", "# Notes\n\nJohn Smith uses Python.\n"),
+            ("c.txt", "Deployed with Kubernetes.\n"),
+            ("empty.md", "  \n"),
+        ] {
+            std::fs::write(temp_dir.path().join(name), text).unwrap();
+        }
+
+        let mut output = Vec::new();
+        let pipeline = ContextPipeline::new();
+        let stats = pipeline
+            .process_directory_jsonl(temp_dir.path(), &mut output)
+            .unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let (summary, documents) = lines.split_last().unwrap();
+
+        assert_eq!(stats.files_processed, 3);
+        assert_eq!(stats.files_empty, 1);
+        assert_eq!(documents.len(), stats.files_processed);
+        assert!(documents.iter().all(|line| line["type"] == "document"));
+        assert!(documents[0]["chunks"].is_array());
+        assert_eq!(summary["type"], "summary");
+        assert_eq!(summary["files_processed"], 3);
+        assert_eq!(summary["files_empty"], 1);
+    }
+
+    #[test]
+    fn test_progress_handle() {
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..100 {
+            std::fs::write(
+                temp_dir.path().join(format!("doc{i}.md")),
+                format!("# Doc {i}\n\nCreated by John Smith. Uses Rust and Docker."),
+            )
+            .unwrap();
+        }
+
+        let pipeline = ContextPipeline::new();
+        let progress = ProgressHandle::new();
+        assert_eq!(progress.eta(), None);
+
+        let mut samples = Vec::new();
+        let result = std::thread::scope(|scope| {
+            let run = scope
+                .spawn(|| pipeline.process_directory_with_progress(temp_dir.path(), &progress));
+            while !run.is_finished() {
+                samples.push((progress.files_done(), progress.eta()));
+                std::thread::yield_now();
+            }
+            run.join().unwrap().unwrap()
+        });
+
+        assert!(samples.windows(2).all(|w| w[0].0 <= w[1].0));
+        assert!(
+            samples
+                .iter()
+                .filter(|(done, _)| *done > 0)
+                .all(|(_, eta)| eta.is_some())
+        );
+        assert_eq!(result.stats.files_processed, 100);
+        assert_eq!(progress.files_done(), 100);
+        assert_eq!(progress.files_total(), 100);
+        assert_eq!(progress.percent_complete(), 100.0);
+        assert_eq!(progress.eta(), Some(Duration::ZERO));
+    }
+
     #[test]
     fn test_entity_deduplication() {
         use crate::entity::{EntityMention, EntityType};
@@ -613,6 +2162,7 @@ This project uses Rust and Python for data processing.
                 id: "1".to_string(),
                 name: "Rust".to_string(),
                 normalized_name: "rust".to_string(),
+                display_name: "Rust".to_string(),
                 entity_type: EntityType::Technology,
                 confidence: 0.8,
                 mentions: vec![EntityMention {
@@ -620,6 +2170,7 @@ This project uses Rust and Python for data processing.
                     position: 0,
                     matched_text: "Rust".to_string(),
                     context: Some("Uses Rust".to_string()),
+                    source: None,
                 }],
                 attributes: std::collections::HashMap::new(),
             },
@@ -627,6 +2178,7 @@ This project uses Rust and Python for data processing.
                 id: "2".to_string(),
                 name: "rust".to_string(),
                 normalized_name: "rust".to_string(),
+                display_name: "rust".to_string(),
                 entity_type: EntityType::Technology,
                 confidence: 0.9,
                 mentions: vec![EntityMention {
@@ -634,14 +2186,17 @@ This project uses Rust and Python for data processing.
                     position: 10,
                     matched_text: "rust".to_string(),
                     context: Some("built with rust".to_string()),
+                    source: None,
                 }],
                 attributes: std::collections::HashMap::new(),
             },
         ];
 
-        let deduped = deduplicate_entities(entities);
+        let (deduped, id_map) = deduplicate_entities(entities);
         assert_eq!(deduped.len(), 1);
         assert_eq!(deduped[0].mentions.len(), 2);
         assert_eq!(deduped[0].confidence, 0.9); // Higher confidence kept
+        assert_eq!(id_map.len(), 1);
+        assert!(id_map.values().all(|id| *id == deduped[0].id));
     }
 }
@@ -3,10 +3,12 @@
 //! The query module handles parsing natural language queries,
 //! identifying intent, and mapping queries to relevant concepts.
 
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::context_file::ContextFile;
 use crate::error::Result;
+use crate::node::NodeType;
 
 /// A parsed query with identified intent and concepts.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +50,28 @@ impl Query {
         }
     }
 
+    /// Parse a query that mixes `key:value` filters with free text.
+    ///
+    /// Recognized keys are `domain`, `type`, `before`, `after`, and
+    /// `confidence`, e.g. `domain:coding type:project after:2024-01-01 rust`.
+    /// Tokens with an unknown key or a value that doesn't parse are kept as
+    /// free text. The free text is parsed as with [`Query::parse`].
+    pub fn parse_structured(text: &str) -> Self {
+        let mut filters = QueryFilters::default();
+        let mut terms = Vec::new();
+
+        for token in text.split_whitespace() {
+            let applied = token
+                .split_once(':')
+                .is_some_and(|(key, value)| filters.apply(key, value));
+            if !applied {
+                terms.push(token);
+            }
+        }
+
+        Self::parse(terms.join(" ")).with_filters(filters)
+    }
+
     /// Add identified concepts to the query.
     pub fn with_concepts(mut self, concepts: Vec<String>) -> Self {
         self.concepts = concepts;
@@ -171,6 +195,75 @@ pub struct QueryFilters {
 
     /// Minimum relevance score.
     pub min_relevance: Option<f32>,
+
+    /// Filter by domain names.
+    #[serde(default)]
+    pub domains: Vec<String>,
+
+    /// Filter by node types.
+    #[serde(default)]
+    pub node_types: Vec<NodeType>,
+
+    /// Minimum confidence of matched nodes.
+    pub min_confidence: Option<f32>,
+}
+
+impl QueryFilters {
+    /// Apply a `key:value` filter token, returning `false` if the key is
+    /// unknown or the value is invalid.
+    fn apply(&mut self, key: &str, value: &str) -> bool {
+        if value.is_empty() {
+            return false;
+        }
+        match key.to_lowercase().as_str() {
+            "domain" => self.domains.push(value.to_lowercase()),
+            "type" => match parse_node_type(value) {
+                Some(node_type) => self.node_types.push(node_type),
+                None => return false,
+            },
+            "before" => match parse_date(value) {
+                Some(date) => self.date_to = Some(date),
+                None => return false,
+            },
+            "after" => match parse_date(value) {
+                Some(date) => self.date_from = Some(date),
+                None => return false,
+            },
+            "confidence" => match value.parse::<f32>() {
+                Ok(confidence) if (0.0..=1.0).contains(&confidence) => {
+                    self.min_confidence = Some(confidence);
+                }
+                _ => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Parse a node type name such as `project` or `file_reference`.
+fn parse_node_type(value: &str) -> Option<NodeType> {
+    match value.to_lowercase().replace('-', "_").as_str() {
+        "root" => Some(NodeType::Root),
+        "domain" => Some(NodeType::Domain),
+        "category" => Some(NodeType::Category),
+        "project" => Some(NodeType::Project),
+        "module" => Some(NodeType::Module),
+        "document" => Some(NodeType::Document),
+        "file" | "file_reference" => Some(NodeType::FileReference),
+        _ => None,
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a `YYYY-MM-DD` date (as midnight UTC).
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
 }
 
 /// The result of a query.
@@ -289,6 +382,31 @@ mod tests {
         assert_eq!(query.intent, QueryIntent::ContentGeneration);
     }
 
+    #[test]
+    fn test_parse_structured() {
+        let query = Query::parse_structured("domain:coding type:project async");
+        assert_eq!(query.filters.domains, vec!["coding"]);
+        assert_eq!(query.filters.node_types, vec![NodeType::Project]);
+        assert_eq!(query.keywords, vec!["async"]);
+        assert_eq!(query.text, "async");
+
+        let query = Query::parse_structured(
+            "after:2024-01-01 before:2024-06-30T12:00:00Z confidence:0.7 lang:rust type:widget",
+        );
+        assert_eq!(
+            query.filters.date_from.map(|d| d.to_rfc3339()),
+            Some("2024-01-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            query.filters.date_to.map(|d| d.to_rfc3339()),
+            Some("2024-06-30T12:00:00+00:00".to_string())
+        );
+        assert_eq!(query.filters.min_confidence, Some(0.7));
+        assert!(query.filters.node_types.is_empty());
+        assert_eq!(query.text, "lang:rust type:widget");
+        assert_eq!(query.keywords, vec!["lang", "rust", "type", "widget"]);
+    }
+
     #[test]
     fn test_keyword_extraction() {
         let query = Query::parse("Tell me about my projects and research");
@@ -7,9 +7,78 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::chunker::Chunk;
+use crate::chunker::{Chunk, ChunkType};
 use crate::entity::{Entity, EntityType};
 
+/// Relationship patterns: (pattern, source_group, target_group, relationship_type).
+const RELATIONSHIP_PATTERNS: &[(&str, usize, usize, RelationshipType)] = &[
+    // Dependency patterns
+    (
+        r"(\S+)\s+depends\s+on\s+(\S+)",
+        1,
+        2,
+        RelationshipType::DependsOn,
+    ),
+    (
+        r"(\S+)\s+requires\s+(\S+)",
+        1,
+        2,
+        RelationshipType::DependsOn,
+    ),
+    (r"(\S+)\s+uses\s+(\S+)", 1, 2, RelationshipType::Uses),
+    (r"built\s+with\s+(\S+)", 0, 1, RelationshipType::Uses),
+    // Authorship patterns
+    (
+        r"(\S+)\s+(?:created|wrote|authored)\s+by\s+(\S+)",
+        1,
+        2,
+        RelationshipType::CreatedBy,
+    ),
+    (
+        r"(\S+)\s+maintains?\s+(\S+)",
+        1,
+        2,
+        RelationshipType::Maintains,
+    ),
+    // Containment patterns
+    (
+        r"(\S+)\s+contains?\s+(\S+)",
+        1,
+        2,
+        RelationshipType::Contains,
+    ),
+    (
+        r"(\S+)\s+includes?\s+(\S+)",
+        1,
+        2,
+        RelationshipType::Contains,
+    ),
+    // Implementation patterns
+    (
+        r"(\S+)\s+implements?\s+(\S+)",
+        1,
+        2,
+        RelationshipType::Implements,
+    ),
+    (r"(\S+)\s+extends?\s+(\S+)", 1, 2, RelationshipType::Extends),
+];
+
+/// Default words marking a date as when something happened or is due.
+const DEFAULT_TEMPORAL_CUES: &[&str] = &[
+    "deadline",
+    "due",
+    "released",
+    "launched",
+    "shipped",
+    "scheduled",
+    "started",
+    "completed",
+    "ended",
+];
+
+/// Confidence of `OccurredOn` relationships inferred from temporal cues.
+const TEMPORAL_CONFIDENCE: f32 = 0.6;
+
 /// A relationship between two entities.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Relationship {
@@ -195,8 +264,24 @@ pub struct RelationshipExtractorConfig {
     /// Minimum co-occurrence score to create relationship.
     pub cooccurrence_threshold: f32,
 
+    /// Multipliers applied to co-occurrence scores by chunk type.
+    ///
+    /// Chunk types without an entry weigh 1.0.
+    pub chunk_type_weights: HashMap<ChunkType, f32>,
+
     /// Whether to infer relationships from entity types.
     pub use_type_inference: bool,
+
+    /// Whether to link dates to the projects and concepts they qualify.
+    ///
+    /// In chunks containing one of `temporal_cues`, each date gets an
+    /// `OccurredOn` relationship from the nearest project or concept.
+    pub use_temporal_inference: bool,
+
+    /// Words marking a date as an event time ("deadline", "released").
+    ///
+    /// Matched case-insensitively against whole words.
+    pub temporal_cues: Vec<String>,
 }
 
 impl Default for RelationshipExtractorConfig {
@@ -206,7 +291,13 @@ impl Default for RelationshipExtractorConfig {
             use_patterns: true,
             use_cooccurrence: true,
             cooccurrence_threshold: 0.3,
+            chunk_type_weights: HashMap::new(),
             use_type_inference: true,
+            use_temporal_inference: false,
+            temporal_cues: DEFAULT_TEMPORAL_CUES
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
         }
     }
 }
@@ -238,13 +329,20 @@ impl RelationshipExtractor {
 
         // Pattern-based relationship extraction
         if self.config.use_patterns {
-            relationships.extend(self.extract_pattern_relationships(entities, chunks));
+            relationships.extend(self.extract_pattern_relationships(
+                entities,
+                chunks,
+                &chunk_entities,
+            ));
         }
 
         // Co-occurrence based relationships
         if self.config.use_cooccurrence {
-            relationships
-                .extend(self.extract_cooccurrence_relationships(entities, &chunk_entities));
+            relationships.extend(self.extract_cooccurrence_relationships(
+                entities,
+                chunks,
+                &chunk_entities,
+            ));
         }
 
         // Type-inference based relationships
@@ -252,6 +350,11 @@ impl RelationshipExtractor {
             relationships.extend(self.infer_type_relationships(entities, &chunk_entities));
         }
 
+        // Dates qualifying events ("released on 2024-06-01")
+        if self.config.use_temporal_inference {
+            relationships.extend(self.infer_temporal_relationships(chunks, &chunk_entities));
+        }
+
         // Deduplicate and filter
         self.deduplicate_relationships(relationships)
     }
@@ -275,65 +378,25 @@ impl RelationshipExtractor {
     }
 
     /// Extract relationships using pattern matching.
+    ///
+    /// Source entities are drawn only from those mentioned in the matching
+    /// chunk, via `chunk_entities`, rather than scanning every entity.
     fn extract_pattern_relationships(
         &self,
         entities: &[Entity],
         chunks: &[Chunk],
+        chunk_entities: &HashMap<String, Vec<&Entity>>,
     ) -> Vec<Relationship> {
         let mut relationships = Vec::new();
 
-        // Relationship patterns: (pattern, source_group, target_group, relationship_type)
-        let patterns: Vec<(&str, usize, usize, RelationshipType)> = vec![
-            // Dependency patterns
-            (
-                r"(\S+)\s+depends\s+on\s+(\S+)",
-                1,
-                2,
-                RelationshipType::DependsOn,
-            ),
-            (
-                r"(\S+)\s+requires\s+(\S+)",
-                1,
-                2,
-                RelationshipType::DependsOn,
-            ),
-            (r"(\S+)\s+uses\s+(\S+)", 1, 2, RelationshipType::Uses),
-            (r"built\s+with\s+(\S+)", 0, 1, RelationshipType::Uses),
-            // Authorship patterns
-            (
-                r"(\S+)\s+(?:created|wrote|authored)\s+by\s+(\S+)",
-                1,
-                2,
-                RelationshipType::CreatedBy,
-            ),
-            (
-                r"(\S+)\s+maintains?\s+(\S+)",
-                1,
-                2,
-                RelationshipType::Maintains,
-            ),
-            // Containment patterns
-            (
-                r"(\S+)\s+contains?\s+(\S+)",
-                1,
-                2,
-                RelationshipType::Contains,
-            ),
-            (
-                r"(\S+)\s+includes?\s+(\S+)",
-                1,
-                2,
-                RelationshipType::Contains,
-            ),
-            // Implementation patterns
-            (
-                r"(\S+)\s+implements?\s+(\S+)",
-                1,
-                2,
-                RelationshipType::Implements,
-            ),
-            (r"(\S+)\s+extends?\s+(\S+)", 1, 2, RelationshipType::Extends),
-        ];
+        let patterns: Vec<(regex_lite::Regex, usize, RelationshipType)> = RELATIONSHIP_PATTERNS
+            .iter()
+            .filter_map(|(pattern, _source_group, target_group, rel_type)| {
+                regex_lite::Regex::new(pattern)
+                    .ok()
+                    .map(|re| (re, *target_group, *rel_type))
+            })
+            .collect();
 
         // Build entity name lookup for matching
         let entity_lookup: HashMap<String, &Entity> = entities
@@ -342,39 +405,41 @@ impl RelationshipExtractor {
             .collect();
 
         for chunk in chunks {
-            for (pattern, _source_group, target_group, rel_type) in &patterns {
-                if let Ok(re) = regex_lite::Regex::new(pattern) {
-                    for cap in re.captures_iter(&chunk.content) {
-                        if let Some(target_match) = cap.get(*target_group) {
-                            let target_text = target_match.as_str().to_lowercase();
-
-                            // Try to find matching entities
-                            if let Some(target_entity) = entity_lookup.get(&target_text) {
-                                // Look for source entity in same chunk
-                                for source_entity in entities {
-                                    if source_entity.id != target_entity.id
-                                        && source_entity
-                                            .mentions
-                                            .iter()
-                                            .any(|m| m.chunk_id == chunk.id)
-                                    {
-                                        let mut rel = Relationship::new(
-                                            source_entity,
-                                            target_entity,
-                                            *rel_type,
-                                            0.8,
-                                        );
-                                        rel.add_evidence(RelationshipEvidence {
-                                            evidence_type: EvidenceType::PatternMatch,
-                                            text: cap.get(0).unwrap().as_str().to_string(),
-                                            chunk_id: Some(chunk.id.clone()),
-                                            confidence_contribution: 0.8,
-                                        });
-                                        relationships.push(rel);
-                                    }
-                                }
-                            }
+            // Without entities in this chunk there is no source to link from
+            let Some(chunk_local) = chunk_entities.get(&chunk.id) else {
+                continue;
+            };
+
+            // An entity mentioned several times in a chunk appears once per
+            // mention; those entries are adjacent, so skip repeats
+            let mut sources: Vec<&Entity> = chunk_local.clone();
+            sources.dedup_by(|a, b| a.id == b.id);
+
+            for (re, target_group, rel_type) in &patterns {
+                for cap in re.captures_iter(&chunk.content) {
+                    let Some(target_match) = cap.get(*target_group) else {
+                        continue;
+                    };
+                    let target_text = target_match.as_str().to_lowercase();
+
+                    // Try to find matching entities
+                    let Some(target_entity) = entity_lookup.get(&target_text) else {
+                        continue;
+                    };
+
+                    for source_entity in &sources {
+                        if source_entity.id == target_entity.id {
+                            continue;
                         }
+                        let mut rel =
+                            Relationship::new(source_entity, target_entity, *rel_type, 0.8);
+                        rel.add_evidence(RelationshipEvidence {
+                            evidence_type: EvidenceType::PatternMatch,
+                            text: cap.get(0).unwrap().as_str().to_string(),
+                            chunk_id: Some(chunk.id.clone()),
+                            confidence_contribution: 0.8,
+                        });
+                        relationships.push(rel);
                     }
                 }
             }
@@ -387,12 +452,18 @@ impl RelationshipExtractor {
     fn extract_cooccurrence_relationships(
         &self,
         entities: &[Entity],
+        chunks: &[Chunk],
         chunk_entities: &HashMap<String, Vec<&Entity>>,
     ) -> Vec<Relationship> {
         let mut relationships = Vec::new();
         let mut pair_scores: HashMap<(String, String), f32> = HashMap::new();
         let mut pair_chunks: HashMap<(String, String), Vec<String>> = HashMap::new();
 
+        let chunk_types: HashMap<&str, ChunkType> = chunks
+            .iter()
+            .map(|c| (c.id.as_str(), c.chunk_type))
+            .collect();
+
         // Calculate co-occurrence scores
         for (chunk_id, entities_in_chunk) in chunk_entities {
             let n = entities_in_chunk.len();
@@ -400,6 +471,12 @@ impl RelationshipExtractor {
                 continue;
             }
 
+            let weight = chunk_types
+                .get(chunk_id.as_str())
+                .and_then(|t| self.config.chunk_type_weights.get(t))
+                .copied()
+                .unwrap_or(1.0);
+
             // Each pair of entities in the same chunk gets a score
             for i in 0..n {
                 for j in (i + 1)..n {
@@ -411,14 +488,18 @@ impl RelationshipExtractor {
                         continue;
                     }
 
-                    let key = if e1.id < e2.id {
+                    // Order the pair by type and name so the relationship
+                    // direction doesn't depend on generated IDs
+                    let key = if (e1.entity_type.as_str(), &e1.normalized_name)
+                        < (e2.entity_type.as_str(), &e2.normalized_name)
+                    {
                         (e1.id.clone(), e2.id.clone())
                     } else {
                         (e2.id.clone(), e1.id.clone())
                     };
 
                     // Score based on chunk size (smaller chunk = stronger relationship)
-                    let score = 1.0 / (1.0 + (n as f32).ln());
+                    let score = weight / (1.0 + (n as f32).ln());
 
                     *pair_scores.entry(key.clone()).or_insert(0.0) += score;
                     pair_chunks.entry(key).or_default().push(chunk_id.clone());
@@ -527,15 +608,148 @@ impl RelationshipExtractor {
                     relationships.push(rel);
                 }
             }
+
+            // Version + preceding Technology/Project = VersionOf
+            relationships.extend(self.infer_version_relationships(chunk_id, entities_in_chunk));
+        }
+
+        relationships
+    }
+
+    /// Link each version mention to the closest technology or project
+    /// mentioned before it in the same chunk (e.g., "Rust 1.75", "React v18").
+    fn infer_version_relationships(
+        &self,
+        chunk_id: &str,
+        entities_in_chunk: &[&Entity],
+    ) -> Vec<Relationship> {
+        let mut relationships = Vec::new();
+
+        let mentions_in_chunk = |entity: &'_ Entity| {
+            entity
+                .mentions
+                .iter()
+                .filter(|m| m.chunk_id == chunk_id)
+                .map(|m| (m.position, m.position + m.matched_text.len()))
+                .collect::<Vec<_>>()
+        };
+
+        let subjects: Vec<(&Entity, usize)> = entities_in_chunk
+            .iter()
+            .filter(|e| matches!(e.entity_type, EntityType::Technology | EntityType::Project))
+            .flat_map(|e| {
+                mentions_in_chunk(e)
+                    .into_iter()
+                    .map(move |(_, end)| (*e, end))
+            })
+            .collect();
+
+        for version in entities_in_chunk
+            .iter()
+            .filter(|e| e.entity_type == EntityType::Version)
+        {
+            for (start, _) in mentions_in_chunk(version) {
+                let Some((subject, _)) = subjects
+                    .iter()
+                    .filter(|(_, end)| *end <= start)
+                    .max_by_key(|(_, end)| *end)
+                else {
+                    continue;
+                };
+
+                let mut rel = Relationship::new(version, subject, RelationshipType::VersionOf, 0.7);
+                rel.add_evidence(RelationshipEvidence {
+                    evidence_type: EvidenceType::TypeInference,
+                    text: format!("{} {}", subject.name, version.name),
+                    chunk_id: Some(chunk_id.to_string()),
+                    confidence_contribution: 0.7,
+                });
+                relationships.push(rel);
+            }
+        }
+
+        relationships
+    }
+
+    /// Link each date in a chunk with a temporal cue to the project or
+    /// concept mentioned nearest to it.
+    fn infer_temporal_relationships(
+        &self,
+        chunks: &[Chunk],
+        chunk_entities: &HashMap<String, Vec<&Entity>>,
+    ) -> Vec<Relationship> {
+        let mut relationships = Vec::new();
+
+        for chunk in chunks {
+            let Some(entities_in_chunk) = chunk_entities.get(&chunk.id) else {
+                continue;
+            };
+            let Some(cue) = chunk
+                .content
+                .split(|c: char| !c.is_alphanumeric())
+                .find(|word| {
+                    self.config
+                        .temporal_cues
+                        .iter()
+                        .any(|cue| cue.eq_ignore_ascii_case(word))
+                })
+            else {
+                continue;
+            };
+
+            let positions = |entity: &Entity| -> Vec<usize> {
+                entity
+                    .mentions
+                    .iter()
+                    .filter(|m| m.chunk_id == chunk.id)
+                    .map(|m| m.position)
+                    .collect()
+            };
+            let subjects: Vec<(&Entity, usize)> = entities_in_chunk
+                .iter()
+                .filter(|e| matches!(e.entity_type, EntityType::Project | EntityType::Concept))
+                .flat_map(|e| positions(e).into_iter().map(move |p| (*e, p)))
+                .collect();
+
+            for date in entities_in_chunk
+                .iter()
+                .filter(|e| e.entity_type == EntityType::Date)
+            {
+                for position in positions(date) {
+                    let Some((subject, _)) =
+                        subjects.iter().min_by_key(|(_, p)| p.abs_diff(position))
+                    else {
+                        continue;
+                    };
+
+                    let mut rel = Relationship::new(
+                        subject,
+                        date,
+                        RelationshipType::OccurredOn,
+                        TEMPORAL_CONFIDENCE,
+                    );
+                    rel.add_evidence(RelationshipEvidence {
+                        evidence_type: EvidenceType::TypeInference,
+                        text: format!("{} {cue} {}", subject.name, date.name),
+                        chunk_id: Some(chunk.id.clone()),
+                        confidence_contribution: TEMPORAL_CONFIDENCE,
+                    });
+                    relationships.push(rel);
+                }
+            }
         }
 
         relationships
     }
 
     /// Deduplicate relationships and combine evidence.
-    fn deduplicate_relationships(&self, relationships: Vec<Relationship>) -> Vec<Relationship> {
+    fn deduplicate_relationships(&self, mut relationships: Vec<Relationship>) -> Vec<Relationship> {
         let mut unique: HashMap<(String, String, String), Relationship> = HashMap::new();
 
+        // Combine strongest evidence first so the boosted confidence doesn't
+        // depend on extraction order
+        relationships.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+
         for rel in relationships {
             let key = (
                 rel.source_id.clone(),
@@ -555,13 +769,24 @@ impl RelationshipExtractor {
                 .or_insert(rel);
         }
 
-        unique
+        let mut relationships: Vec<Relationship> = unique
             .into_values()
             .filter(|r| r.confidence >= self.config.min_confidence)
-            .collect()
+            .collect();
+        sort_relationships(&mut relationships);
+        relationships
     }
 }
 
+/// Sort relationships into a stable order by source, type, then target.
+pub(crate) fn sort_relationships(relationships: &mut [Relationship]) {
+    relationships.sort_by(|a, b| {
+        (&a.source_name, a.relationship_type.as_str(), &a.target_name)
+            .cmp(&(&b.source_name, b.relationship_type.as_str(), &b.target_name))
+            .then_with(|| b.confidence.total_cmp(&a.confidence))
+    });
+}
+
 impl Default for RelationshipExtractor {
     fn default() -> Self {
         Self::new()
@@ -594,11 +819,29 @@ mod tests {
                 position: 0,
                 matched_text: name.to_string(),
                 context: None,
+                source: None,
             });
         }
         entity
     }
 
+    fn make_entity_at(
+        name: &str,
+        entity_type: EntityType,
+        chunk_id: &str,
+        position: usize,
+    ) -> Entity {
+        let mut entity = Entity::new(name, entity_type, 0.9);
+        entity.add_mention(crate::entity::EntityMention {
+            chunk_id: chunk_id.to_string(),
+            position,
+            matched_text: name.to_string(),
+            context: None,
+            source: None,
+        });
+        entity
+    }
+
     #[test]
     fn test_cooccurrence_relationships() {
         let extractor = RelationshipExtractor::new();
@@ -664,4 +907,229 @@ mod tests {
         // Should have only one relationship (deduplicated)
         assert!(rust_rels.len() <= 2);
     }
+
+    #[test]
+    fn test_version_relationships() {
+        let extractor = RelationshipExtractor::new();
+
+        let content = "Using Rust 1.75 with tokio 1.35";
+        let chunks = vec![make_chunk("chunk1", content)];
+
+        let at = |text: &str| content.find(text).unwrap();
+        let entities = vec![
+            make_entity_at("Rust", EntityType::Technology, "chunk1", at("Rust")),
+            make_entity_at("1.75", EntityType::Version, "chunk1", at("1.75")),
+            make_entity_at("tokio", EntityType::Technology, "chunk1", at("tokio")),
+            make_entity_at("1.35", EntityType::Version, "chunk1", at("1.35")),
+        ];
+
+        let relationships = extractor.extract(&entities, &chunks);
+
+        let mut version_of: Vec<_> = relationships
+            .iter()
+            .filter(|r| r.relationship_type == RelationshipType::VersionOf)
+            .map(|r| (r.source_name.as_str(), r.target_name.as_str()))
+            .collect();
+        version_of.sort();
+
+        assert_eq!(version_of, vec![("1.35", "tokio"), ("1.75", "Rust")]);
+    }
+
+    #[test]
+    fn test_temporal_relationships() {
+        let content = "Project X released on 2024-06-01";
+        let chunks = vec![make_chunk("chunk1", content)];
+        let at = |text: &str| content.find(text).unwrap();
+        let entities = vec![
+            make_entity_at("Project X", EntityType::Project, "chunk1", at("Project X")),
+            make_entity_at("2024-06-01", EntityType::Date, "chunk1", at("2024-06-01")),
+        ];
+        let occurred_on = |extractor: RelationshipExtractor| -> Vec<(String, String)> {
+            extractor
+                .extract(&entities, &chunks)
+                .into_iter()
+                .filter(|r| r.relationship_type == RelationshipType::OccurredOn)
+                .map(|r| (r.source_name, r.target_name))
+                .collect()
+        };
+
+        assert!(occurred_on(RelationshipExtractor::new()).is_empty());
+
+        let extractor = RelationshipExtractor::with_config(RelationshipExtractorConfig {
+            use_temporal_inference: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            occurred_on(extractor),
+            vec![("Project X".to_string(), "2024-06-01".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_chunk_type_weighted_cooccurrence() {
+        let config = RelationshipExtractorConfig {
+            min_confidence: 0.0,
+            use_patterns: false,
+            use_type_inference: false,
+            chunk_type_weights: HashMap::from([
+                (ChunkType::Code, 2.0),
+                (ChunkType::Paragraph, 0.6),
+            ]),
+            ..Default::default()
+        };
+        let extractor = RelationshipExtractor::with_config(config);
+
+        let confidence = |chunk_type: ChunkType, content: &str| {
+            let mut chunk = make_chunk("chunk1", content);
+            chunk.chunk_type = chunk_type;
+            let entities = vec![
+                make_entity("Alice", EntityType::Person, &["chunk1"]),
+                make_entity("Rust", EntityType::Technology, &["chunk1"]),
+            ];
+            let relationships = extractor.extract(&entities, &[chunk]);
+            relationships
+                .iter()
+                .find(|r| r.relationship_type == RelationshipType::RelatedTo)
+                .map(|r| r.confidence)
+                .unwrap()
+        };
+
+        let code = confidence(ChunkType::Code, "// Alice: port this to Rust");
+        let paragraph = confidence(
+            ChunkType::Paragraph,
+            &"Alice wrote a long design note that eventually mentions Rust. ".repeat(20),
+        );
+
+        assert!(code > paragraph, "code {code} <= paragraph {paragraph}");
+    }
+
+    /// The original all-entities scan, kept as a reference for the indexed path.
+    fn pattern_relationships_full_scan(entities: &[Entity], chunks: &[Chunk]) -> Vec<Relationship> {
+        let lookup: HashMap<String, &Entity> = entities
+            .iter()
+            .map(|e| (e.normalized_name.clone(), e))
+            .collect();
+        let mut relationships = Vec::new();
+        for chunk in chunks {
+            for (pattern, _, target_group, rel_type) in RELATIONSHIP_PATTERNS {
+                let re = regex_lite::Regex::new(pattern).unwrap();
+                for cap in re.captures_iter(&chunk.content) {
+                    let Some(target) = cap
+                        .get(*target_group)
+                        .and_then(|m| lookup.get(&m.as_str().to_lowercase()))
+                    else {
+                        continue;
+                    };
+                    for source in entities {
+                        if source.id != target.id
+                            && source.mentions.iter().any(|m| m.chunk_id == chunk.id)
+                        {
+                            let mut rel = Relationship::new(source, target, *rel_type, 0.8);
+                            rel.add_evidence(RelationshipEvidence {
+                                evidence_type: EvidenceType::PatternMatch,
+                                text: cap.get(0).unwrap().as_str().to_string(),
+                                chunk_id: Some(chunk.id.clone()),
+                                confidence_contribution: 0.8,
+                            });
+                            relationships.push(rel);
+                        }
+                    }
+                }
+            }
+        }
+        relationships
+    }
+
+    fn pattern_summary(relationships: &[Relationship]) -> Vec<(String, String, String, String)> {
+        relationships
+            .iter()
+            .map(|r| {
+                (
+                    r.source_id.clone(),
+                    r.target_id.clone(),
+                    r.relationship_type.as_str().to_string(),
+                    r.evidence[0].text.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pattern_relationships_match_full_scan() {
+        let chunks = vec![
+            make_chunk(
+                "c1",
+                "Server depends on tokio. Server uses serde and uses tokio.",
+            ),
+            make_chunk(
+                "c2",
+                "The parser was created by Alice. Alice maintains parser.",
+            ),
+            make_chunk("c3", "Nothing relevant here uses anything."),
+            make_chunk("c4", "Built with rust. App extends server."),
+        ];
+        let mut server = make_entity("Server", EntityType::Project, &["c1", "c1", "c4"]);
+        server.add_mention(crate::entity::EntityMention {
+            chunk_id: "c1".to_string(),
+            position: 40,
+            matched_text: "Server".to_string(),
+            context: None,
+            source: None,
+        });
+        let entities = vec![
+            server,
+            make_entity("tokio", EntityType::Technology, &["c1"]),
+            make_entity("serde", EntityType::Technology, &["c1"]),
+            make_entity("parser", EntityType::Project, &["c2"]),
+            make_entity("Alice", EntityType::Person, &["c2"]),
+            make_entity("rust", EntityType::Technology, &["c4"]),
+            make_entity("App", EntityType::Project, &["c4"]),
+        ];
+
+        let extractor = RelationshipExtractor::new();
+        let chunk_entities = extractor.build_chunk_entity_map(&entities);
+        let indexed = extractor.extract_pattern_relationships(&entities, &chunks, &chunk_entities);
+        let expected = pattern_relationships_full_scan(&entities, &chunks);
+
+        assert!(!expected.is_empty());
+        assert_eq!(pattern_summary(&indexed), pattern_summary(&expected));
+    }
+
+    #[test]
+    fn test_pattern_relationships_scale() {
+        // Many entities, each mentioned in one chunk: only chunk-local
+        // entities should be considered as sources.
+        let mut chunks = Vec::new();
+        let mut entities = Vec::new();
+        for i in 0..400 {
+            let id = format!("c{i}");
+            chunks.push(make_chunk(
+                &id,
+                &format!("module{i} uses lib{i} and depends on lib{i} too"),
+            ));
+            entities.push(make_entity(
+                &format!("module{i}"),
+                EntityType::Project,
+                &[&id],
+            ));
+            entities.push(make_entity(
+                &format!("lib{i}"),
+                EntityType::Technology,
+                &[&id],
+            ));
+        }
+
+        let extractor = RelationshipExtractor::new();
+        let chunk_entities = extractor.build_chunk_entity_map(&entities);
+        let start = std::time::Instant::now();
+        let relationships =
+            extractor.extract_pattern_relationships(&entities, &chunks, &chunk_entities);
+        let elapsed = start.elapsed();
+
+        assert_eq!(relationships.len(), 800);
+        assert!(
+            elapsed < std::time::Duration::from_secs(5),
+            "pattern extraction took {elapsed:?}"
+        );
+    }
 }
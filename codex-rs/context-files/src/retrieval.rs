@@ -8,6 +8,7 @@ use std::time::Instant;
 
 use tracing::{debug, info};
 
+use crate::chunker::Chunk;
 use crate::context_file::ContextFile;
 use crate::error::Result;
 use crate::extraction::ConceptExtractor;
@@ -35,6 +36,10 @@ pub struct RetrievalConfig {
 
     /// Whether to expand query to related concepts.
     pub expand_related: bool,
+
+    /// Collapse chunk matches from the same source into a single result,
+    /// keeping the best-scoring chunk.
+    pub dedupe_by_source: bool,
 }
 
 impl Default for RetrievalConfig {
@@ -46,6 +51,7 @@ impl Default for RetrievalConfig {
             min_relevance: 0.3,
             max_results: 10,
             expand_related: true,
+            dedupe_by_source: false,
         }
     }
 }
@@ -61,6 +67,31 @@ impl Default for RetrievalConfig {
 pub struct RetrievalEngine {
     config: RetrievalConfig,
     extractor: ConceptExtractor,
+    /// Indexed chunks grouped by source, ordered by start offset.
+    chunks: HashMap<String, Vec<Chunk>>,
+}
+
+/// A chunk matched by `RetrievalEngine::retrieve_chunks`, with its neighbors.
+#[derive(Debug, Clone)]
+pub struct ChunkResult {
+    /// The matching chunk.
+    pub chunk: Chunk,
+
+    /// Relevance score (fraction of query keywords matched).
+    pub score: f32,
+
+    /// Chunks immediately preceding the match in the same source.
+    pub before: Vec<Chunk>,
+
+    /// Chunks immediately following the match in the same source.
+    pub after: Vec<Chunk>,
+
+    /// Content of the preceding chunks, the match, and the following chunks.
+    pub context: String,
+
+    /// Other matching chunks from the same source that were collapsed into
+    /// this result (only set when `dedupe_by_source` is enabled).
+    pub additional_matches: usize,
 }
 
 impl RetrievalEngine {
@@ -69,6 +100,7 @@ impl RetrievalEngine {
         Self {
             config,
             extractor: ConceptExtractor::with_defaults(),
+            chunks: HashMap::new(),
         }
     }
 
@@ -196,6 +228,91 @@ impl RetrievalEngine {
         Ok(result)
     }
 
+    /// Index chunks for chunk-level retrieval.
+    ///
+    /// Chunks are grouped by source and ordered by offset so neighbors can
+    /// be returned as context.
+    pub fn index_chunks(&mut self, chunks: impl IntoIterator<Item = Chunk>) {
+        for chunk in chunks {
+            let source = chunk.source.clone().unwrap_or_default();
+            self.chunks.entry(source).or_default().push(chunk);
+        }
+
+        for source_chunks in self.chunks.values_mut() {
+            source_chunks.sort_by_key(|c| (c.start_offset, c.end_offset));
+        }
+    }
+
+    /// Retrieve the best-matching chunks, each with up to `window` adjacent
+    /// chunks from the same source on either side.
+    pub fn retrieve_chunks(&self, query_text: &str, window: usize) -> Vec<ChunkResult> {
+        let query = Query::parse(query_text);
+        if query.keywords.is_empty() {
+            return Vec::new();
+        }
+
+        let mut matches: Vec<(&str, &[Chunk], usize, f32)> = Vec::new();
+        for (source, source_chunks) in &self.chunks {
+            for (position, chunk) in source_chunks.iter().enumerate() {
+                let content = chunk.content.to_lowercase();
+                let matched = query
+                    .keywords
+                    .iter()
+                    .filter(|k| content.contains(k.as_str()))
+                    .count();
+                let score = matched as f32 / query.keywords.len() as f32;
+                if matched > 0 && score >= self.config.min_relevance {
+                    matches.push((source, source_chunks, position, score));
+                }
+            }
+        }
+
+        matches.sort_by(|a, b| {
+            b.3.partial_cmp(&a.3)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(b.0))
+                .then(a.2.cmp(&b.2))
+        });
+
+        // Keep the best match per source, counting the rest
+        let mut additional: HashMap<&str, usize> = HashMap::new();
+        if self.config.dedupe_by_source {
+            matches.retain(|(source, ..)| match additional.get_mut(source) {
+                Some(count) => {
+                    *count += 1;
+                    false
+                }
+                None => {
+                    additional.insert(source, 0);
+                    true
+                }
+            });
+        }
+        matches.truncate(self.config.max_results);
+
+        matches
+            .into_iter()
+            .map(|(source, source_chunks, position, score)| {
+                let start = position.saturating_sub(window);
+                let end = (position + window + 1).min(source_chunks.len());
+                let context = source_chunks[start..end]
+                    .iter()
+                    .map(|c| c.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                ChunkResult {
+                    chunk: source_chunks[position].clone(),
+                    score,
+                    before: source_chunks[start..position].to_vec(),
+                    after: source_chunks[position + 1..end].to_vec(),
+                    context,
+                    additional_matches: additional.get(source).copied().unwrap_or(0),
+                }
+            })
+            .collect()
+    }
+
     /// Perform semantic similarity search.
     ///
     /// This requires embeddings to be generated for both the query
@@ -308,6 +425,100 @@ mod tests {
         assert_eq!(engine.config.max_results, 10);
     }
 
+    #[test]
+    fn test_retrieve_chunks_with_neighbors() {
+        let paragraphs = [
+            "Install the toolchain first.",
+            "Configure the database connection pool.",
+            "Run the migrations afterwards.",
+            "Deploy to production.",
+        ];
+        let mut offset = 0;
+        let mut chunks = Vec::new();
+        for text in paragraphs {
+            chunks.push(
+                Chunk::new(text, crate::chunker::ChunkType::Paragraph)
+                    .with_source("guide.md")
+                    .with_offsets(offset, offset + text.len()),
+            );
+            offset += text.len() + 2;
+        }
+        chunks.reverse();
+        chunks.push(
+            Chunk::new("Unrelated notes.", crate::chunker::ChunkType::Paragraph)
+                .with_source("other.md"),
+        );
+
+        let mut engine = RetrievalEngine::with_defaults();
+        engine.index_chunks(chunks);
+
+        let results = engine.retrieve_chunks("database connection", 1);
+        assert_eq!(results.len(), 1);
+
+        let result = &results[0];
+        assert_eq!(result.chunk.content, paragraphs[1]);
+        assert_eq!(result.before.len(), 1);
+        assert_eq!(result.before[0].content, paragraphs[0]);
+        assert_eq!(result.after.len(), 1);
+        assert_eq!(result.after[0].content, paragraphs[2]);
+        assert_eq!(
+            result.context,
+            format!(
+                "{}\n\n{}\n\n{}",
+                paragraphs[0], paragraphs[1], paragraphs[2]
+            )
+        );
+
+        let results = engine.retrieve_chunks("toolchain", 2);
+        assert!(results[0].before.is_empty());
+        assert_eq!(results[0].after.len(), 2);
+    }
+
+    #[test]
+    fn test_retrieve_chunks_dedupe_by_source() {
+        let chunk = |text: &str, source: &str, offset: usize| {
+            Chunk::new(text, crate::chunker::ChunkType::Paragraph)
+                .with_source(source)
+                .with_offsets(offset, offset + text.len())
+        };
+        let chunks = vec![
+            chunk("Tune the database pool size.", "guide.md", 0),
+            chunk("The database connection pool is shared.", "guide.md", 100),
+            chunk("Close each database connection.", "guide.md", 200),
+            chunk("Unrelated notes.", "guide.md", 300),
+            chunk("A database connection per request.", "faq.md", 0),
+        ];
+
+        let mut engine = RetrievalEngine::with_defaults();
+        engine.index_chunks(chunks.clone());
+        assert_eq!(engine.retrieve_chunks("database connection", 0).len(), 4);
+
+        let mut engine = RetrievalEngine::new(RetrievalConfig {
+            dedupe_by_source: true,
+            ..Default::default()
+        });
+        engine.index_chunks(chunks);
+
+        let results = engine.retrieve_chunks("database connection", 0);
+        assert_eq!(results.len(), 2);
+
+        let guide = results
+            .iter()
+            .find(|r| r.chunk.source.as_deref() == Some("guide.md"))
+            .unwrap();
+        assert_eq!(
+            guide.chunk.content,
+            "The database connection pool is shared."
+        );
+        assert_eq!(guide.additional_matches, 2);
+
+        let faq = results
+            .iter()
+            .find(|r| r.chunk.source.as_deref() == Some("faq.md"))
+            .unwrap();
+        assert_eq!(faq.additional_matches, 0);
+    }
+
     #[test]
     fn test_query_builder() {
         let query = QueryBuilder::new("Find my projects")
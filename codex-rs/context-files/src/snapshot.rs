@@ -0,0 +1,236 @@
+//! Deterministic snapshots of pipeline results.
+//!
+//! A snapshot is a stable, human-readable rendering of a `PipelineResult`
+//! intended for golden-file tests. Collections are sorted and generated IDs,
+//! timestamps, and the processed directory are redacted so snapshots are
+//! identical across runs and machines.
+
+use std::collections::BTreeMap;
+
+use serde_json::{Value, json};
+
+use crate::entity::sort_entities;
+use crate::pipeline::PipelineResult;
+use crate::relationship::sort_relationships;
+
+/// Placeholder for the processed directory in snapshot paths.
+const ROOT_PLACEHOLDER: &str = "[root]";
+
+/// Placeholder for generated UUIDs.
+const UUID_PLACEHOLDER: &str = "[uuid]";
+
+/// Replacement for RFC 3339 timestamps.
+const ZERO_TIMESTAMP: &str = "1970-01-01T00:00:00Z";
+
+impl PipelineResult {
+    /// Render a deterministic, redacted snapshot of this result.
+    ///
+    /// UUIDs are replaced with `[uuid]`, timestamps are zeroed, the
+    /// processed directory is replaced with `[root]`, and floats are
+    /// rounded to three decimals.
+    pub fn to_snapshot(&self) -> String {
+        let mut documents: Vec<&_> = self.documents.iter().collect();
+        documents.sort_by(|a, b| a.source.cmp(&b.source));
+        let documents: Vec<Value> = documents
+            .into_iter()
+            .map(|doc| {
+                let chunks: Vec<Value> = doc
+                    .chunks
+                    .iter()
+                    .map(|chunk| {
+                        json!({
+                            "chunk_type": chunk.chunk_type,
+                            "start_offset": chunk.start_offset,
+                            "end_offset": chunk.end_offset,
+                            "title": chunk.metadata.title,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "source": doc.source.to_string_lossy(),
+                    "empty": doc.empty,
+                    "chunks": chunks,
+                    "entities": doc.entities.len(),
+                    "relationships": doc.relationships.len(),
+                })
+            })
+            .collect();
+
+        let mut entities = self.all_entities.clone();
+        sort_entities(&mut entities);
+        let entities: Vec<Value> = entities
+            .iter()
+            .map(|entity| {
+                let mut attributes: Vec<(&String, &String)> = entity.attributes.iter().collect();
+                attributes.sort();
+                json!({
+                    "name": entity.name,
+                    "normalized_name": entity.normalized_name,
+                    "display_name": entity.display_name,
+                    "entity_type": entity.entity_type.as_str(),
+                    "confidence": round(entity.confidence),
+                    "mentions": entity.mentions.len(),
+                    "attributes": attributes,
+                })
+            })
+            .collect();
+
+        let mut relationships = self.all_relationships.clone();
+        sort_relationships(&mut relationships);
+        let relationships: Vec<Value> = relationships
+            .iter()
+            .map(|rel| {
+                json!({
+                    "source": rel.source_name,
+                    "relationship_type": rel.relationship_type.as_str(),
+                    "target": rel.target_name,
+                    "confidence": round(rel.confidence),
+                    "evidence": rel.evidence.len(),
+                })
+            })
+            .collect();
+
+        let mut contexts: Vec<Value> = self
+            .contexts
+            .iter()
+            .map(|ctx| {
+                let file = &ctx.context_file;
+                let mut entity_names: Vec<&str> =
+                    ctx.entities.iter().map(|e| e.name.as_str()).collect();
+                entity_names.sort_unstable();
+                let mut related_concepts = file.metadata.related_concepts.clone();
+                related_concepts.sort();
+                let mut tags = file.metadata.tags.clone();
+                tags.sort();
+                let mut references: Vec<Value> = file
+                    .content
+                    .references
+                    .iter()
+                    .map(|r| {
+                        json!({
+                            "reference_type": r.reference_type,
+                            "path": r.path,
+                            "relevance": round(r.relevance),
+                        })
+                    })
+                    .collect();
+                references.sort_by_key(Value::to_string);
+                let structured: BTreeMap<&String, &Value> =
+                    file.content.structured.iter().collect();
+
+                json!({
+                    "concept": file.concept,
+                    "summary": file.summary,
+                    "cluster_method": format!("{:?}", ctx.cluster_method),
+                    "entities": entity_names,
+                    "internal_relationships": ctx.internal_relationships.len(),
+                    "external_relationships": ctx.external_relationships.len(),
+                    "related_concepts": related_concepts,
+                    "tags": tags,
+                    "structured": structured,
+                    "references": references,
+                })
+            })
+            .collect();
+        contexts.sort_by_key(Value::to_string);
+
+        let mut errors: Vec<(String, &String)> = self
+            .errors
+            .iter()
+            .map(|(path, error)| (path.to_string_lossy().to_string(), error))
+            .collect();
+        errors.sort();
+
+        let stats = &self.stats;
+        let snapshot = json!({
+            "stats": {
+                "files_processed": stats.files_processed,
+                "files_skipped": stats.files_skipped,
+                "files_with_errors": stats.files_with_errors,
+                "files_empty": stats.files_empty,
+                "total_chunks": stats.total_chunks,
+                "total_entities": stats.total_entities,
+                "total_relationships": stats.total_relationships,
+                "total_contexts": stats.total_contexts,
+                "processing_time_ms": 0,
+            },
+            "documents": documents,
+            "entities": entities,
+            "relationships": relationships,
+            "contexts": contexts,
+            "errors": errors,
+        });
+
+        let rendered = serde_json::to_string_pretty(&snapshot).unwrap_or_default();
+        self.redact(&rendered)
+    }
+
+    /// Redact run-specific values from rendered snapshot text.
+    fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+
+        // Match the root as it appears inside JSON strings.
+        let root = serde_json::to_string(&self.root.to_string_lossy()).unwrap_or_default();
+        let root = root.trim_matches('"');
+        if !root.is_empty() {
+            text = text.replace(root, ROOT_PLACEHOLDER);
+        }
+
+        if let Ok(uuid_re) = regex_lite::Regex::new(
+            r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}",
+        ) {
+            text = uuid_re.replace_all(&text, UUID_PLACEHOLDER).into_owned();
+        }
+
+        if let Ok(timestamp_re) = regex_lite::Regex::new(
+            r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:\d{2})",
+        ) {
+            text = timestamp_re.replace_all(&text, ZERO_TIMESTAMP).into_owned();
+        }
+
+        text
+    }
+}
+
+/// Round a score to three decimals so float noise doesn't churn snapshots.
+fn round(value: f32) -> String {
+    format!("{value:.3}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pipeline::ContextPipeline;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_is_deterministic_and_redacted() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("README.md"),
+            "# Server\n\nCreated by John Smith on 2024-01-15T10:30:00Z. Uses Rust and Docker.\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("notes.md"),
+            "# Notes\n\nJohn Smith uses Python. See 123e4567-e89b-12d3-a456-426614174000.\n",
+        )
+        .unwrap();
+
+        let pipeline = ContextPipeline::new();
+        let first = pipeline.process_directory(temp_dir.path()).unwrap();
+        let second = pipeline.process_directory(temp_dir.path()).unwrap();
+
+        let snapshot = first.to_snapshot();
+        assert_eq!(snapshot, second.to_snapshot());
+
+        assert!(snapshot.contains("[root]/README.md"));
+        assert!(!snapshot.contains(&temp_dir.path().to_string_lossy().to_string()));
+        assert!(!snapshot.contains("2024-01-15T10:30:00Z"));
+        assert!(!snapshot.contains("123e4567-e89b-12d3-a456-426614174000"));
+        for entity in &first.all_entities {
+            assert!(!snapshot.contains(&entity.id));
+        }
+    }
+}
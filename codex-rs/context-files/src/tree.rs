@@ -3,13 +3,55 @@
 //! The `ContextTree` manages a hierarchy of context nodes representing
 //! the user's knowledge organized by domains, categories, and projects.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
 
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
+use crate::entity::{Entity, EntityType};
 use crate::error::{ContextError, Result};
-use crate::node::{ContextNode, CrossLinkType, DomainDetection, NodeType, RelatedNode};
+use crate::id::{IdGenerator, default_id_generator};
+use crate::node::{
+    ContextNode, CrossLinkType, DomainDetection, NodeSummary, NodeType, RelatedNode,
+};
+
+/// Strength of a cross-link for a technology shared by exactly two nodes.
+const MAX_CROSS_LINK_STRENGTH: f32 = 0.7;
+
+/// Default minimum strength for cross-links created by `build_cross_links`.
+pub const DEFAULT_MIN_CROSS_LINK_STRENGTH: f32 = 0.1;
+
+/// Default minimum confidence a shared technology entity needs on both
+/// nodes before `build_cross_links` links them.
+pub const DEFAULT_MIN_CROSS_LINK_CONFIDENCE: f32 = 0.5;
+
+/// Weights applied to query terms in `ContextTree::search_scored_with`.
+///
+/// Terms matched in a node's name, summary, or keywords count as prose
+/// matches. Terms matched only through keywords naming one of the node's
+/// code elements (functions, types) are weaker topical evidence and count
+/// as code matches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchConfig {
+    /// Weight of a term matched in prose.
+    pub prose_weight: f32,
+
+    /// Weight of a term matched only through code element keywords.
+    pub code_weight: f32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            prose_weight: 1.0,
+            code_weight: 0.5,
+        }
+    }
+}
 
 /// The main hierarchical context tree.
 ///
@@ -23,11 +65,74 @@ pub struct ContextTree {
     /// ID of the root node.
     root_id: String,
 
+    /// Stable identity of the tree's owner (e.g. a user ID), if known.
+    root_identity: Option<String>,
+
     /// Index from domain names to domain node IDs.
     domain_index: HashMap<String, String>,
 
     /// Index from file paths to node IDs.
     path_index: HashMap<String, String>,
+
+    /// Index from (entity type, normalized name) to IDs of nodes mentioning it.
+    entity_index: HashMap<(EntityType, String), BTreeSet<String>>,
+
+    /// Maximum cross-links per node, not counting user-defined links.
+    max_related_per_node: Option<usize>,
+
+    /// Optional sink receiving structural mutation events.
+    audit_log: Option<AuditSink>,
+
+    /// Source of IDs for nodes the tree creates itself.
+    id_generator: Arc<dyn IdGenerator>,
+}
+
+/// A structural change to the tree, reported to the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TreeMutation {
+    /// A node was inserted into the tree.
+    NodeInserted {
+        /// ID of the inserted node.
+        id: String,
+        /// Type of the inserted node.
+        node_type: NodeType,
+        /// ID of the node's parent, if any.
+        parent_id: Option<String>,
+    },
+    /// A node was removed from the tree.
+    NodeRemoved {
+        /// ID of the removed node.
+        id: String,
+        /// Type of the removed node.
+        node_type: NodeType,
+    },
+    /// A new domain was created under the root.
+    DomainCreated {
+        /// ID of the domain node.
+        id: String,
+        /// Name of the domain.
+        name: String,
+    },
+    /// A cross-link was added between two nodes.
+    CrossLinkAdded {
+        /// ID of the node holding the link.
+        from_id: String,
+        /// ID of the linked node.
+        to_id: String,
+        /// Type of the cross-link.
+        link_type: CrossLinkType,
+    },
+}
+
+/// Shared callback receiving tree mutation events.
+#[derive(Clone)]
+struct AuditSink(Arc<Mutex<dyn FnMut(TreeMutation) + Send>>);
+
+impl fmt::Debug for AuditSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AuditSink")
+    }
 }
 
 impl Default for ContextTree {
@@ -48,11 +153,116 @@ impl ContextTree {
         Self {
             nodes,
             root_id,
+            root_identity: None,
             domain_index: HashMap::new(),
             path_index: HashMap::new(),
+            entity_index: HashMap::new(),
+            max_related_per_node: None,
+            audit_log: None,
+            id_generator: default_id_generator(),
         }
     }
 
+    /// Set the name of the root node.
+    pub fn with_root_name(mut self, name: impl Into<String>) -> Self {
+        self.root_mut().name = name.into();
+        self
+    }
+
+    /// Set the identity of the tree's owner (e.g. a user ID).
+    ///
+    /// The identity is persisted with the tree so trees from different
+    /// users can be told apart.
+    pub fn with_root_identity(mut self, identity: impl Into<String>) -> Self {
+        self.root_identity = Some(identity.into());
+        self
+    }
+
+    /// Keep at most `max` cross-links per node.
+    ///
+    /// When a node is full, a new link replaces the node's weakest link if
+    /// it is stronger and is dropped otherwise. User-defined links neither
+    /// count toward the limit nor get dropped.
+    pub fn with_max_related_per_node(mut self, max: usize) -> Self {
+        self.max_related_per_node = Some(max);
+        self
+    }
+
+    /// Set the generator for IDs of nodes the tree creates (root, domains,
+    /// categories) and for `next_id`.
+    ///
+    /// If the tree holds only its root, the root is given a new ID from the
+    /// generator so a seeded generator makes the whole tree reproducible.
+    pub fn with_id_generator(mut self, id_generator: Arc<dyn IdGenerator>) -> Self {
+        self.id_generator = id_generator;
+        if self.nodes.len() == 1
+            && let Some(mut root) = self.nodes.remove(&self.root_id)
+        {
+            root.id = self.id_generator.next_id();
+            self.root_id = root.id.clone();
+            self.nodes.insert(root.id.clone(), root);
+        }
+        self
+    }
+
+    /// Produce an ID for a new node from the tree's generator.
+    pub fn next_id(&self) -> String {
+        self.id_generator.next_id()
+    }
+
+    /// Get the identity of the tree's owner, if set.
+    pub fn root_identity(&self) -> Option<&str> {
+        self.root_identity.as_deref()
+    }
+
+    /// Check whether two trees belong to the same owner.
+    ///
+    /// Trees without an identity never match, since their owners are unknown.
+    pub fn same_identity(&self, other: &ContextTree) -> bool {
+        self.root_identity.is_some() && self.root_identity == other.root_identity
+    }
+
+    /// Attach an audit log sink that receives every structural mutation.
+    ///
+    /// Events are delivered in order from `insert`, `remove`, `ensure_domain`
+    /// and `add_cross_link`. Clones of the tree share the same sink.
+    pub fn with_audit_log(mut self, sink: impl FnMut(TreeMutation) + Send + 'static) -> Self {
+        self.audit_log = Some(AuditSink(Arc::new(Mutex::new(sink))));
+        self
+    }
+
+    /// Send an event to the audit log, if one is attached.
+    fn record(&self, event: impl FnOnce() -> TreeMutation) {
+        if let Some(AuditSink(sink)) = &self.audit_log {
+            let mut sink = sink.lock().unwrap_or_else(PoisonError::into_inner);
+            sink(event());
+        }
+    }
+
+    /// Rebuild a tree from stored nodes and the ID of its root.
+    ///
+    /// Lookup indexes are rebuilt from the nodes (see `reindex`). The caller
+    /// is responsible for ensuring the root node is present (see
+    /// `ensure_root`).
+    pub(crate) fn from_nodes(
+        root_id: impl Into<String>,
+        nodes: impl IntoIterator<Item = ContextNode>,
+    ) -> Self {
+        let mut tree = Self {
+            nodes: nodes.into_iter().map(|n| (n.id.clone(), n)).collect(),
+            root_id: root_id.into(),
+            root_identity: None,
+            domain_index: HashMap::new(),
+            path_index: HashMap::new(),
+            entity_index: HashMap::new(),
+            max_related_per_node: None,
+            audit_log: None,
+            id_generator: default_id_generator(),
+        };
+        tree.reindex();
+        tree
+    }
+
     /// Get the root node.
     pub fn root(&self) -> &ContextNode {
         match self.nodes.get(&self.root_id) {
@@ -97,7 +307,7 @@ impl ContextTree {
     pub fn ensure_root(&mut self) {
         if !self.nodes.contains_key(&self.root_id) {
             warn!("Tree missing root node, creating new one");
-            let root = ContextNode::root();
+            let root = ContextNode::root().with_id(self.next_id());
             self.root_id = root.id.clone();
             self.nodes.insert(root.id.clone(), root);
         }
@@ -126,6 +336,165 @@ impl ContextTree {
         self.nodes.values()
     }
 
+    /// Get nodes modified after `since`, newest first.
+    pub fn changed_since(&self, since: DateTime<Utc>) -> Vec<&ContextNode> {
+        let mut nodes: Vec<&ContextNode> = self
+            .nodes
+            .values()
+            .filter(|node| node.last_updated > since)
+            .collect();
+        nodes.sort_by(|a, b| {
+            b.last_updated
+                .cmp(&a.last_updated)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        nodes
+    }
+
+    /// Get nodes that mention an entity, ordered by node ID.
+    pub fn nodes_with_entity(
+        &self,
+        normalized_name: &str,
+        entity_type: EntityType,
+    ) -> Vec<&ContextNode> {
+        let key = (entity_type, normalized_name.to_string());
+        let Some(ids) = self.entity_index.get(&key) else {
+            return Vec::new();
+        };
+
+        // Entities changed through `get_mut` may leave stale entries behind.
+        ids.iter()
+            .filter_map(|id| self.nodes.get(id))
+            .filter(|node| {
+                node.entities
+                    .iter()
+                    .any(|e| e.entity_type == entity_type && e.normalized_name == normalized_name)
+            })
+            .collect()
+    }
+
+    /// Re-index a node's entities after changing them through `get_mut`.
+    pub fn refresh_entity_index(&mut self, id: &str) {
+        self.unindex_entities(id);
+        if let Some(node) = self.nodes.get(id) {
+            let keys: Vec<(EntityType, String)> = node
+                .entities
+                .iter()
+                .map(|e| (e.entity_type, e.normalized_name.clone()))
+                .collect();
+            for key in keys {
+                self.entity_index
+                    .entry(key)
+                    .or_default()
+                    .insert(id.to_string());
+            }
+        }
+    }
+
+    /// Drop every entity index entry pointing at a node.
+    fn unindex_entities(&mut self, id: &str) {
+        self.entity_index.retain(|_, ids| {
+            ids.remove(id);
+            !ids.is_empty()
+        });
+    }
+
+    /// Rebuild every lookup index from the nodes.
+    ///
+    /// Recovers lookups by path, domain, and entity after the indexes drift
+    /// from the nodes, for example when paths or names are changed through
+    /// `get_mut`.
+    pub fn reindex(&mut self) {
+        self.domain_index.clear();
+        self.path_index.clear();
+        for (id, node) in &self.nodes {
+            if let Some(ref path) = node.path {
+                self.path_index
+                    .insert(path.to_string_lossy().to_string(), id.clone());
+            }
+            if node.node_type == NodeType::Domain {
+                self.domain_index
+                    .insert(node.name.to_lowercase(), id.clone());
+            }
+        }
+        self.rebuild_entity_index();
+    }
+
+    /// Rebuild the entity index from scratch.
+    fn rebuild_entity_index(&mut self) {
+        self.entity_index.clear();
+        for (id, node) in &self.nodes {
+            for entity in &node.entities {
+                self.entity_index
+                    .entry((entity.entity_type, entity.normalized_name.clone()))
+                    .or_default()
+                    .insert(id.clone());
+            }
+        }
+    }
+
+    /// Apply `f` to every entity on every node in the tree.
+    ///
+    /// Nodes that carry entities are marked as updated.
+    pub fn for_each_entity_mut(&mut self, mut f: impl FnMut(&mut Entity)) {
+        for node in self.nodes.values_mut() {
+            if node.entities.is_empty() {
+                continue;
+            }
+            node.entities.iter_mut().for_each(&mut f);
+            node.touch();
+        }
+        self.rebuild_entity_index();
+    }
+
+    /// Keep only the entities matching `pred`, across every node in the tree.
+    ///
+    /// Returns the number of entities removed. Nodes that lose entities are
+    /// marked as updated.
+    pub fn retain_entities(&mut self, pred: impl Fn(&Entity) -> bool) -> usize {
+        let mut removed = 0;
+        for node in self.nodes.values_mut() {
+            let before = node.entities.len();
+            node.entities.retain(&pred);
+            if node.entities.len() < before {
+                removed += before - node.entities.len();
+                node.touch();
+            }
+        }
+        if removed > 0 {
+            self.rebuild_entity_index();
+        }
+        removed
+    }
+
+    /// Exponentially decay every node's access count by its age.
+    ///
+    /// A count halves for every `half_life_days` since the node was last
+    /// updated, so relevance reflects recent usage rather than lifetime
+    /// totals. Time already accounted for by an earlier decay is not decayed
+    /// again, so this can be called on a schedule. A half-life of zero
+    /// disables decay.
+    pub fn decay_access_counts(&mut self, half_life_days: u32) {
+        if half_life_days == 0 {
+            return;
+        }
+        let now = Utc::now();
+        let half_life_secs = f64::from(half_life_days) * 86_400.0;
+
+        for node in self.nodes.values_mut() {
+            if node.access_count == 0 {
+                continue;
+            }
+            let since = node
+                .access_decayed_at
+                .map_or(node.last_updated, |decayed| decayed.max(node.last_updated));
+            let elapsed_secs = (now - since).num_seconds().max(0) as f64;
+            let factor = 0.5_f64.powf(elapsed_secs / half_life_secs);
+            node.access_count = (f64::from(node.access_count) * factor).round() as u32;
+            node.access_decayed_at = Some(now);
+        }
+    }
+
     /// Get the total number of nodes.
     pub fn node_count(&self) -> usize {
         self.nodes.len()
@@ -135,20 +504,42 @@ impl ContextTree {
     pub fn insert(&mut self, node: ContextNode) -> String {
         let id = node.id.clone();
 
+        // Replacing a node drops the entities it used to mention
+        if self.nodes.contains_key(&id) {
+            self.unindex_entities(&id);
+        }
+        self.index_node(&node);
+
+        self.record(|| TreeMutation::NodeInserted {
+            id: id.clone(),
+            node_type: node.node_type,
+            parent_id: node.parent_id.clone(),
+        });
+
+        self.nodes.insert(id.clone(), node);
+        id
+    }
+
+    /// Add the path, domain, and entity index entries for a node.
+    fn index_node(&mut self, node: &ContextNode) {
         // Update path index if node has a path
         if let Some(ref path) = node.path {
             let path_str = path.to_string_lossy().to_string();
-            self.path_index.insert(path_str, id.clone());
+            self.path_index.insert(path_str, node.id.clone());
         }
 
         // Update domain index if node is a domain
         if node.node_type == NodeType::Domain {
             let name = node.name.to_lowercase();
-            self.domain_index.insert(name, id.clone());
+            self.domain_index.insert(name, node.id.clone());
         }
 
-        self.nodes.insert(id.clone(), node);
-        id
+        for entity in &node.entities {
+            self.entity_index
+                .entry((entity.entity_type, entity.normalized_name.clone()))
+                .or_default()
+                .insert(node.id.clone());
+        }
     }
 
     /// Remove a node from the tree.
@@ -176,9 +567,258 @@ impl ContextTree {
             self.domain_index.remove(&name);
         }
 
+        // Remove from entity index
+        for entity in &node.entities {
+            let key = (entity.entity_type, entity.normalized_name.clone());
+            if let Some(ids) = self.entity_index.get_mut(&key) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.entity_index.remove(&key);
+                }
+            }
+        }
+
+        self.record(|| TreeMutation::NodeRemoved {
+            id: node.id.clone(),
+            node_type: node.node_type,
+        });
+
         Some(node)
     }
 
+    /// Remove domain, category, project, and module nodes that have no
+    /// children.
+    ///
+    /// Repeats until no empty containers remain, so a domain emptied by
+    /// pruning its categories is removed too. Pinned nodes are kept.
+    /// Returns the number of nodes removed.
+    pub fn prune_empty_containers(&mut self) -> usize {
+        let mut removed = 0;
+        loop {
+            let empty: Vec<String> = self
+                .nodes
+                .values()
+                .filter(|n| {
+                    n.children.is_empty()
+                        && !n.pinned
+                        && matches!(
+                            n.node_type,
+                            NodeType::Domain
+                                | NodeType::Category
+                                | NodeType::Project
+                                | NodeType::Module
+                        )
+                })
+                .map(|n| n.id.clone())
+                .collect();
+            if empty.is_empty() {
+                return removed;
+            }
+
+            for id in empty {
+                if self.remove(&id).is_some() {
+                    removed += 1;
+                }
+            }
+        }
+    }
+
+    /// Find nodes that can't be reached from the root through child links.
+    ///
+    /// Such nodes are left behind when a parent is removed without its
+    /// children. Returns their IDs in sorted order.
+    pub fn find_orphans(&self) -> Vec<String> {
+        let mut reachable = HashSet::new();
+        let mut to_visit = vec![self.root_id.as_str()];
+        while let Some(id) = to_visit.pop() {
+            if !reachable.insert(id) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get(id) {
+                to_visit.extend(node.children.iter().map(String::as_str));
+            }
+        }
+
+        let mut orphans: Vec<String> = self
+            .nodes
+            .keys()
+            .filter(|id| !reachable.contains(id.as_str()))
+            .cloned()
+            .collect();
+        orphans.sort();
+        orphans
+    }
+
+    /// Remove nodes unreachable from the root (see `find_orphans`).
+    ///
+    /// Cross-links from remaining nodes to removed ones are dropped too.
+    /// Returns the number of nodes removed.
+    pub fn remove_orphans(&mut self) -> usize {
+        let orphans = self.find_orphans();
+        if orphans.is_empty() {
+            return 0;
+        }
+
+        for id in &orphans {
+            self.remove(id);
+        }
+        let removed: HashSet<&str> = orphans.iter().map(String::as_str).collect();
+        for node in self.nodes.values_mut() {
+            node.related_nodes
+                .retain(|link| !removed.contains(link.node_id.as_str()));
+        }
+
+        info!("Removed {} orphaned nodes", orphans.len());
+        orphans.len()
+    }
+
+    /// Check the tree's structural invariants, returning every violation.
+    ///
+    /// Checks that the root exists, that parent and child links exist and
+    /// agree, that each node is one level deeper than its parent, and that
+    /// the lookup indexes match the nodes. A healthy tree returns an empty
+    /// list. Violations are grouped by check and ordered by node ID.
+    pub fn validate(&self) -> Vec<TreeInvariantViolation> {
+        let mut violations = Vec::new();
+        if !self.nodes.contains_key(&self.root_id) {
+            violations.push(TreeInvariantViolation::MissingRoot {
+                root_id: self.root_id.clone(),
+            });
+        }
+
+        let mut nodes: Vec<&ContextNode> = self.nodes.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        for node in &nodes {
+            for child_id in &node.children {
+                if !self.nodes.contains_key(child_id) {
+                    violations.push(TreeInvariantViolation::MissingChild {
+                        parent_id: node.id.clone(),
+                        child_id: child_id.clone(),
+                    });
+                }
+            }
+
+            let Some(parent_id) = &node.parent_id else {
+                continue;
+            };
+            let Some(parent) = self.nodes.get(parent_id) else {
+                violations.push(TreeInvariantViolation::MissingParent {
+                    node_id: node.id.clone(),
+                    parent_id: parent_id.clone(),
+                });
+                continue;
+            };
+            if !parent.children.contains(&node.id) {
+                violations.push(TreeInvariantViolation::NotAChildOfParent {
+                    node_id: node.id.clone(),
+                    parent_id: parent_id.clone(),
+                });
+            }
+            if node.depth != parent.depth + 1 {
+                violations.push(TreeInvariantViolation::DepthMismatch {
+                    node_id: node.id.clone(),
+                    depth: node.depth,
+                    expected: parent.depth + 1,
+                });
+            }
+        }
+
+        self.check_index(
+            TreeIndex::Path,
+            &self.path_index,
+            &nodes,
+            &mut violations,
+            |node| {
+                node.path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().to_string())
+            },
+        );
+        self.check_index(
+            TreeIndex::Domain,
+            &self.domain_index,
+            &nodes,
+            &mut violations,
+            |node| (node.node_type == NodeType::Domain).then(|| node.name.to_lowercase()),
+        );
+
+        let entity_key =
+            |(entity_type, name): &(EntityType, String)| format!("{}:{name}", entity_type.as_str());
+        let mut entity_entries: Vec<_> = self.entity_index.iter().collect();
+        entity_entries.sort_by_key(|&(key, _)| entity_key(key));
+        for (key, node_ids) in entity_entries {
+            for node_id in node_ids {
+                let mentions = self.nodes.get(node_id).is_some_and(|node| {
+                    node.entities
+                        .iter()
+                        .any(|e| e.entity_type == key.0 && e.normalized_name == key.1)
+                });
+                if !mentions {
+                    violations.push(TreeInvariantViolation::StaleIndexEntry {
+                        index: TreeIndex::Entity,
+                        key: entity_key(key),
+                        node_id: node_id.clone(),
+                    });
+                }
+            }
+        }
+        for node in &nodes {
+            for entity in &node.entities {
+                let key = (entity.entity_type, entity.normalized_name.clone());
+                let indexed = self
+                    .entity_index
+                    .get(&key)
+                    .is_some_and(|ids| ids.contains(&node.id));
+                if !indexed {
+                    violations.push(TreeInvariantViolation::MissingIndexEntry {
+                        index: TreeIndex::Entity,
+                        key: entity_key(&key),
+                        node_id: node.id.clone(),
+                    });
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Check a name-to-node index against the nodes, where `key_of` gives
+    /// the key a node should be indexed under.
+    fn check_index(
+        &self,
+        index: TreeIndex,
+        entries: &HashMap<String, String>,
+        nodes: &[&ContextNode],
+        violations: &mut Vec<TreeInvariantViolation>,
+        key_of: impl Fn(&ContextNode) -> Option<String>,
+    ) {
+        let mut sorted: Vec<(&String, &String)> = entries.iter().collect();
+        sorted.sort();
+        for (key, node_id) in sorted {
+            let current = self.nodes.get(node_id).and_then(&key_of);
+            if current.as_ref() != Some(key) {
+                violations.push(TreeInvariantViolation::StaleIndexEntry {
+                    index,
+                    key: key.clone(),
+                    node_id: node_id.clone(),
+                });
+            }
+        }
+
+        for &node in nodes {
+            if let Some(key) = key_of(node)
+                && !entries.contains_key(&key)
+            {
+                violations.push(TreeInvariantViolation::MissingIndexEntry {
+                    index,
+                    key,
+                    node_id: node.id.clone(),
+                });
+            }
+        }
+    }
+
     /// Get or create a domain node.
     ///
     /// If the domain already exists, returns its ID.
@@ -192,7 +832,7 @@ impl ContextTree {
         }
 
         // Create new domain node
-        let mut domain_node = ContextNode::domain(domain);
+        let mut domain_node = ContextNode::domain(domain).with_id(self.next_id());
         domain_node.parent_id = Some(self.root_id.clone());
         let domain_id = domain_node.id.clone();
 
@@ -205,6 +845,11 @@ impl ContextTree {
         self.domain_index.insert(domain_lower, domain_id.clone());
         self.nodes.insert(domain_id.clone(), domain_node);
 
+        self.record(|| TreeMutation::DomainCreated {
+            id: domain_id.clone(),
+            name: domain.to_string(),
+        });
+
         info!("Created new domain: {}", domain);
         domain_id
     }
@@ -269,6 +914,75 @@ impl ContextTree {
         descendants
     }
 
+    /// Get a skeleton of the tree as node summaries.
+    ///
+    /// Nodes are listed depth-first from the root, with children in order.
+    pub fn summary_view(&self) -> Vec<NodeSummary> {
+        let mut view = Vec::with_capacity(self.nodes.len());
+        let mut to_visit = vec![self.root_id.as_str()];
+
+        while let Some(id) = to_visit.pop() {
+            if let Some(node) = self.nodes.get(id) {
+                view.push(node.to_summary());
+                to_visit.extend(node.children.iter().rev().map(String::as_str));
+            }
+        }
+
+        view
+    }
+
+    /// Export the tree as nested JSON, starting from the root.
+    ///
+    /// Each node is its summary with `children` holding the child nodes
+    /// themselves instead of their IDs. Cross-links stay as node IDs under
+    /// `related` so the output has no cycles.
+    pub fn to_nested_json(&self) -> serde_json::Value {
+        self.nested_json(&self.root_id, None)
+    }
+
+    /// Export the tree as nested JSON, stopping `max_depth` levels below
+    /// the root.
+    ///
+    /// Nodes at the cap keep their `child_count` but get an empty
+    /// `children` list and `truncated: true` if they have any children.
+    pub fn to_nested_json_with_max_depth(&self, max_depth: u32) -> serde_json::Value {
+        self.nested_json(&self.root_id, Some(max_depth))
+    }
+
+    fn nested_json(&self, node_id: &str, remaining_depth: Option<u32>) -> serde_json::Value {
+        let Some(node) = self.nodes.get(node_id) else {
+            return serde_json::Value::Null;
+        };
+
+        let truncated = remaining_depth == Some(0) && !node.children.is_empty();
+        let children: Vec<serde_json::Value> = if remaining_depth == Some(0) {
+            Vec::new()
+        } else {
+            node.children
+                .iter()
+                .filter(|id| self.nodes.contains_key(id.as_str()))
+                .map(|id| self.nested_json(id, remaining_depth.map(|d| d - 1)))
+                .collect()
+        };
+        let related: Vec<&str> = node
+            .related_nodes
+            .iter()
+            .map(|r| r.node_id.as_str())
+            .collect();
+
+        let mut value = serde_json::to_value(node.to_summary()).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            object.insert("path".to_string(), serde_json::json!(node.path));
+            object.insert("child_count".to_string(), node.children.len().into());
+            object.insert("children".to_string(), children.into());
+            object.insert("related".to_string(), serde_json::json!(related));
+            if truncated {
+                object.insert("truncated".to_string(), true.into());
+            }
+        }
+        value
+    }
+
     /// Get all leaf nodes (nodes with no children).
     pub fn get_leaves(&self) -> Vec<&ContextNode> {
         self.nodes.values().filter(|n| n.is_leaf()).collect()
@@ -282,9 +996,10 @@ impl ContextTree {
     /// Add a node as a child of another node.
     pub fn add_child(&mut self, parent_id: &str, mut child: ContextNode) -> Result<String> {
         // Check parent exists
-        let parent = self.nodes.get(parent_id).ok_or_else(|| {
-            ContextError::InvalidFormat(format!("Parent node not found: {}", parent_id))
-        })?;
+        let parent = self
+            .nodes
+            .get(parent_id)
+            .ok_or_else(|| ContextError::ParentNotFound(parent_id.to_string()))?;
 
         // Set child's parent and depth
         child.parent_id = Some(parent_id.to_string());
@@ -303,63 +1018,263 @@ impl ContextTree {
         Ok(child_id)
     }
 
+    /// Add many nodes as children of one parent.
+    ///
+    /// Produces the same tree as calling `add_child` for each node in order,
+    /// but nodes replacing existing IDs are dropped from the entity index in
+    /// a single pass and the parent's children are appended once. Returns
+    /// the child IDs in order.
+    pub fn insert_batch(
+        &mut self,
+        parent_id: &str,
+        children: Vec<ContextNode>,
+    ) -> Result<Vec<String>> {
+        let depth = self
+            .nodes
+            .get(parent_id)
+            .ok_or_else(|| ContextError::ParentNotFound(parent_id.to_string()))?
+            .depth
+            + 1;
+
+        // Replacing nodes drops the entities they used to mention
+        let replaced: HashSet<&str> = children
+            .iter()
+            .map(|child| child.id.as_str())
+            .filter(|id| self.nodes.contains_key(*id))
+            .collect();
+        if !replaced.is_empty() {
+            self.entity_index.retain(|_, ids| {
+                ids.retain(|id| !replaced.contains(id.as_str()));
+                !ids.is_empty()
+            });
+        }
+
+        let mut child_ids = Vec::with_capacity(children.len());
+        for mut child in children {
+            child.parent_id = Some(parent_id.to_string());
+            child.depth = depth;
+            self.index_node(&child);
+
+            let id = child.id.clone();
+            self.record(|| TreeMutation::NodeInserted {
+                id: id.clone(),
+                node_type: child.node_type,
+                parent_id: child.parent_id.clone(),
+            });
+            self.nodes.insert(id.clone(), child);
+            child_ids.push(id);
+        }
+
+        if let Some(parent) = self.nodes.get_mut(parent_id) {
+            parent.children.extend(child_ids.iter().cloned());
+        }
+        debug!("Inserted {} children under {parent_id}", child_ids.len());
+
+        Ok(child_ids)
+    }
+
     /// Build cross-links between related nodes.
     ///
     /// This finds nodes that share common attributes (technologies, authors, etc.)
-    /// and creates cross-links between them.
+    /// and creates cross-links between them, dropping links weaker than
+    /// [`DEFAULT_MIN_CROSS_LINK_STRENGTH`].
     pub fn build_cross_links(&mut self) {
-        let node_ids: Vec<String> = self.nodes.keys().cloned().collect();
+        self.build_cross_links_with_threshold(DEFAULT_MIN_CROSS_LINK_STRENGTH);
+    }
 
-        // Build technology index
-        let mut tech_index: HashMap<String, Vec<String>> = HashMap::new();
-        for id in &node_ids {
-            if let Some(node) = self.nodes.get(id) {
-                for entity in &node.entities {
-                    if entity.entity_type == crate::entity::EntityType::Technology {
-                        tech_index
-                            .entry(entity.normalized_name.clone())
-                            .or_default()
-                            .push(id.clone());
-                    }
+    /// Build cross-links, dropping links weaker than `min_strength`.
+    ///
+    /// Link strength is IDF-weighted: a technology shared by only two nodes
+    /// links them strongly, while one shared by nearly every node (e.g. "git")
+    /// produces weak links. Nodes sharing several technologies keep the
+    /// strongest link.
+    pub fn build_cross_links_with_threshold(&mut self, min_strength: f32) {
+        self.build_cross_links_with_options(min_strength, DEFAULT_MIN_CROSS_LINK_CONFIDENCE);
+    }
+
+    /// Build cross-links, ignoring technology entities below `min_confidence`.
+    ///
+    /// Both nodes must carry the shared technology with at least
+    /// `min_confidence`, and the IDF-weighted strength is scaled by the lower
+    /// of the two entity confidences, so guessed technologies link weakly.
+    /// Links weaker than `min_strength` are dropped.
+    pub fn build_cross_links_with_options(&mut self, min_strength: f32, min_confidence: f32) {
+        // Build technology index with each node's best confidence (sorted for
+        // deterministic link order)
+        let mut tech_index: BTreeMap<String, BTreeMap<String, f32>> = BTreeMap::new();
+        for (id, node) in &self.nodes {
+            for entity in &node.entities {
+                if entity.entity_type == EntityType::Technology
+                    && entity.confidence >= min_confidence
+                {
+                    let confidence = tech_index
+                        .entry(entity.normalized_name.clone())
+                        .or_default()
+                        .entry(id.clone())
+                        .or_insert(entity.confidence);
+                    *confidence = confidence.max(entity.confidence);
                 }
             }
         }
 
-        // Create cross-links for shared technologies
-        for (_tech, ids) in tech_index {
-            if ids.len() > 1 {
-                for i in 0..ids.len() {
-                    for j in (i + 1)..ids.len() {
-                        let id_a = &ids[i];
-                        let id_b = &ids[j];
-
-                        // Don't link nodes in the same branch
-                        if !self.are_in_same_branch(id_a, id_b) {
-                            // Add bidirectional links
-                            let link_a =
-                                RelatedNode::new(id_b.clone(), CrossLinkType::SameTechnology, 0.7);
-                            let link_b =
-                                RelatedNode::new(id_a.clone(), CrossLinkType::SameTechnology, 0.7);
-
-                            if let Some(node_a) = self.nodes.get_mut(id_a) {
-                                if !node_a.related_nodes.iter().any(|r| r.node_id == *id_b) {
-                                    node_a.add_related(link_a);
-                                }
-                            }
-                            if let Some(node_b) = self.nodes.get_mut(id_b) {
-                                if !node_b.related_nodes.iter().any(|r| r.node_id == *id_a) {
-                                    node_b.add_related(link_b);
-                                }
-                            }
-                        }
+        let tech_node_count = {
+            let mut ids: Vec<&String> = tech_index.values().flat_map(|ids| ids.keys()).collect();
+            ids.sort();
+            ids.dedup();
+            ids.len()
+        };
+        // IDF of a technology shared by exactly two nodes, used to scale
+        // strengths into (0, MAX_CROSS_LINK_STRENGTH].
+        let max_idf = (tech_node_count as f32 / 2.0).ln();
+
+        // Strongest shared technology per node pair
+        let mut pair_links: BTreeMap<(String, String), (f32, String)> = BTreeMap::new();
+        for (tech, ids) in &tech_index {
+            if ids.len() < 2 {
+                continue;
+            }
+
+            let base_strength = if max_idf > 0.0 {
+                let idf = (tech_node_count as f32 / ids.len() as f32).ln();
+                MAX_CROSS_LINK_STRENGTH * idf / max_idf
+            } else {
+                MAX_CROSS_LINK_STRENGTH
+            };
+            if base_strength < min_strength {
+                debug!("Skipping cross-links for common technology {tech} ({base_strength:.2})");
+                continue;
+            }
+
+            let ids: Vec<(&String, f32)> = ids.iter().map(|(id, c)| (id, *c)).collect();
+            for i in 0..ids.len() {
+                for j in (i + 1)..ids.len() {
+                    let ((id_a, confidence_a), (id_b, confidence_b)) = (ids[i], ids[j]);
+                    // Don't link nodes in the same branch
+                    if self.are_in_same_branch(id_a, id_b) {
+                        continue;
+                    }
+                    let strength = base_strength * confidence_a.min(confidence_b);
+                    if strength < min_strength {
+                        continue;
+                    }
+                    let entry = pair_links
+                        .entry((id_a.clone(), id_b.clone()))
+                        .or_insert((strength, tech.clone()));
+                    if strength > entry.0 {
+                        *entry = (strength, tech.clone());
                     }
                 }
             }
         }
 
+        // Add bidirectional links
+        for ((id_a, id_b), (strength, tech)) in pair_links {
+            let reason = format!("Both use {tech}");
+            let link_a = RelatedNode::new(id_b.clone(), CrossLinkType::SameTechnology, strength)
+                .with_reason(reason.clone());
+            let link_b = RelatedNode::new(id_a.clone(), CrossLinkType::SameTechnology, strength)
+                .with_reason(reason);
+
+            self.add_cross_link(&id_a, link_a);
+            self.add_cross_link(&id_b, link_b);
+        }
+
         debug!("Built cross-links for tree");
     }
 
+    /// Add a cross-link from one node to another.
+    ///
+    /// Returns `false` if the source node does not exist, already links to
+    /// the target, or is full of stronger links (see
+    /// `with_max_related_per_node`).
+    pub fn add_cross_link(&mut self, from_id: &str, link: RelatedNode) -> bool {
+        let Some(node) = self.nodes.get_mut(from_id) else {
+            return false;
+        };
+        if node.related_nodes.iter().any(|r| r.node_id == link.node_id) {
+            return false;
+        }
+
+        if let Some(max) = self.max_related_per_node
+            && link.relationship != CrossLinkType::UserDefined
+        {
+            let capped = || {
+                node.related_nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, r)| r.relationship != CrossLinkType::UserDefined)
+            };
+            if capped().count() >= max {
+                let weakest = capped()
+                    .min_by(|(_, a), (_, b)| a.strength.total_cmp(&b.strength))
+                    .map(|(i, r)| (i, r.strength));
+                match weakest {
+                    Some((i, strength)) if link.strength > strength => {
+                        let dropped = node.related_nodes.remove(i);
+                        debug!(
+                            "Dropped cross-link {from_id} -> {} to stay within {max}",
+                            dropped.node_id
+                        );
+                    }
+                    _ => return false,
+                }
+            }
+        }
+
+        let event = TreeMutation::CrossLinkAdded {
+            from_id: from_id.to_string(),
+            to_id: link.node_id.clone(),
+            link_type: link.relationship,
+        };
+        node.add_related(link);
+        self.record(|| event);
+        true
+    }
+
+    /// Get cross-links whose endpoints are in different domains.
+    ///
+    /// Links are ordered by domain names, then node IDs. Nodes outside any
+    /// domain are ignored.
+    pub fn cross_domain_links(&self) -> Vec<CrossDomainLink> {
+        let mut links = Vec::new();
+
+        for node in self.nodes.values() {
+            if node.related_nodes.is_empty() {
+                continue;
+            }
+            let Some(from_domain) = self.get_domain_for_node(&node.id) else {
+                continue;
+            };
+
+            for related in &node.related_nodes {
+                let Some(to_domain) = self.get_domain_for_node(&related.node_id) else {
+                    continue;
+                };
+                if to_domain.id == from_domain.id {
+                    continue;
+                }
+                links.push(CrossDomainLink {
+                    from_id: node.id.clone(),
+                    to_id: related.node_id.clone(),
+                    from_domain: from_domain.name.clone(),
+                    to_domain: to_domain.name.clone(),
+                    link_type: related.relationship,
+                    strength: related.strength,
+                    reason: related.reason.clone(),
+                });
+            }
+        }
+
+        links.sort_by(|a, b| {
+            a.from_domain
+                .cmp(&b.from_domain)
+                .then_with(|| a.to_domain.cmp(&b.to_domain))
+                .then_with(|| a.from_id.cmp(&b.from_id))
+                .then_with(|| a.to_id.cmp(&b.to_id))
+        });
+        links
+    }
+
     /// Check if two nodes are in the same branch (one is an ancestor of the other).
     fn are_in_same_branch(&self, id_a: &str, id_b: &str) -> bool {
         let ancestry_a: Vec<String> = self
@@ -413,7 +1328,7 @@ impl ContextTree {
         }
 
         // Create new category node
-        let category_node = ContextNode::category(category);
+        let category_node = ContextNode::category(category).with_id(self.next_id());
         self.add_child(domain_id, category_node)
     }
 
@@ -448,6 +1363,30 @@ impl ContextTree {
     /// Returns nodes that match ANY of the search terms (more lenient).
     /// Nodes are scored by how many terms they match and sorted by relevance.
     pub fn search(&self, query: &str) -> Vec<&ContextNode> {
+        self.search_scored(query)
+            .into_iter()
+            .map(|(node, _)| node)
+            .collect()
+    }
+
+    /// Search for nodes by keyword, returning each match with its score.
+    ///
+    /// Uses the default `SearchConfig`; see `search_scored_with`.
+    pub fn search_scored(&self, query: &str) -> Vec<(&ContextNode, f32)> {
+        self.search_scored_with(query, &SearchConfig::default())
+    }
+
+    /// Search for nodes by keyword with the given term weights.
+    ///
+    /// The score is the weighted fraction of meaningful query terms the node
+    /// matches (0.0 to 1.0), where each term counts with the weight of its
+    /// strongest match. Queries with no meaningful terms return top-level
+    /// content nodes with a score of 0.0.
+    pub fn search_scored_with(
+        &self,
+        query: &str,
+        config: &SearchConfig,
+    ) -> Vec<(&ContextNode, f32)> {
         let query_lower = query.to_lowercase();
 
         // Filter out common stop words for better matching
@@ -470,37 +1409,152 @@ impl ContextTree {
                 .values()
                 .filter(|n| matches!(n.node_type, NodeType::Project | NodeType::Document))
                 .take(10)
+                .map(|n| (n, 0.0))
                 .collect();
         }
 
-        // Score nodes by how many terms they match
-        let mut scored: Vec<(&ContextNode, usize)> = self.nodes
+        // Score nodes by the weighted terms they match
+        let max_weight = config.prose_weight.max(config.code_weight);
+        let term_count = terms.len() as f32;
+        let mut scored: Vec<(&ContextNode, f32)> = self
+            .nodes
             .values()
             .filter_map(|node| {
                 let name_lower = node.name.to_lowercase();
                 let summary_lower = node.summary.to_lowercase();
+                let code_elements: HashSet<String> = node
+                    .entities
+                    .iter()
+                    .filter(|e| e.entity_type == EntityType::CodeElement)
+                    .flat_map(|e| [e.name.to_lowercase(), e.normalized_name.clone()])
+                    .collect();
+                let (code_keywords, prose_keywords): (Vec<String>, Vec<String>) = node
+                    .keywords
+                    .iter()
+                    .map(|k| k.to_lowercase())
+                    .partition(|k| code_elements.contains(k));
+
+                let weight: f32 = terms
+                    .iter()
+                    .map(|term| {
+                        if name_lower.contains(term)
+                            || summary_lower.contains(term)
+                            || prose_keywords.iter().any(|k| k.contains(term))
+                        {
+                            config.prose_weight
+                        } else if code_keywords.iter().any(|k| k.contains(term)) {
+                            config.code_weight
+                        } else {
+                            0.0
+                        }
+                    })
+                    .sum();
 
-                let match_count = terms.iter().filter(|term| {
-                    name_lower.contains(*term)
-                        || summary_lower.contains(*term)
-                        || node.keywords.iter().any(|k| k.to_lowercase().contains(*term))
-                }).count();
-
-                if match_count > 0 {
-                    Some((node, match_count))
-                } else {
-                    None
-                }
+                (weight > 0.0).then(|| (node, weight / (term_count * max_weight)))
             })
             .collect();
 
-        // Sort by match count (descending)
-        scored.sort_by(|a, b| b.1.cmp(&a.1));
-
-        scored.into_iter().map(|(node, _)| node).collect()
+        // Sort by score (descending)
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored
     }
 }
 
+/// A cross-link whose endpoints belong to different domains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossDomainLink {
+    /// ID of the node holding the link.
+    pub from_id: String,
+
+    /// ID of the linked node.
+    pub to_id: String,
+
+    /// Domain of the node holding the link.
+    pub from_domain: String,
+
+    /// Domain of the linked node.
+    pub to_domain: String,
+
+    /// Type of the cross-link.
+    pub link_type: CrossLinkType,
+
+    /// Strength of the cross-link (0.0 to 1.0).
+    pub strength: f32,
+
+    /// Explanation of the link, if any.
+    pub reason: Option<String>,
+}
+
+/// A lookup index kept by `ContextTree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeIndex {
+    /// Domain names to domain nodes.
+    Domain,
+    /// File paths to nodes.
+    Path,
+    /// Entities to the nodes mentioning them.
+    Entity,
+}
+
+/// A broken structural invariant, reported by `ContextTree::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeInvariantViolation {
+    /// The root node is not in the tree.
+    MissingRoot {
+        /// ID the tree expects its root to have.
+        root_id: String,
+    },
+    /// A node lists a child that is not in the tree.
+    MissingChild {
+        /// ID of the node listing the child.
+        parent_id: String,
+        /// ID of the missing child.
+        child_id: String,
+    },
+    /// A node's parent is not in the tree.
+    MissingParent {
+        /// ID of the node.
+        node_id: String,
+        /// ID of the missing parent.
+        parent_id: String,
+    },
+    /// A node's parent does not list it as a child.
+    NotAChildOfParent {
+        /// ID of the node.
+        node_id: String,
+        /// ID of its parent.
+        parent_id: String,
+    },
+    /// A node's depth is not one more than its parent's.
+    DepthMismatch {
+        /// ID of the node.
+        node_id: String,
+        /// Depth recorded on the node.
+        depth: u32,
+        /// Depth implied by its parent.
+        expected: u32,
+    },
+    /// An index entry points at a missing node or one that no longer
+    /// matches the key.
+    StaleIndexEntry {
+        /// Index holding the entry.
+        index: TreeIndex,
+        /// Key of the entry.
+        key: String,
+        /// Node the entry points at.
+        node_id: String,
+    },
+    /// A node is missing from an index it belongs in.
+    MissingIndexEntry {
+        /// Index lacking the entry.
+        index: TreeIndex,
+        /// Key the node should be found under.
+        key: String,
+        /// ID of the node.
+        node_id: String,
+    },
+}
+
 /// Statistics about the context tree.
 #[derive(Debug, Default, Clone)]
 pub struct TreeStats {
@@ -539,6 +1593,63 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn test_decay_access_counts() {
+        let mut tree = ContextTree::new();
+        let mut ids = Vec::new();
+        for age_days in [0, 30, 60] {
+            let mut node = ContextNode::new(NodeType::Project, format!("p{age_days}"));
+            node.access_count = 1000;
+            node.last_updated = Utc::now() - chrono::Duration::days(age_days);
+            ids.push(tree.insert(node));
+        }
+
+        tree.decay_access_counts(30);
+        let counts: Vec<u32> = ids
+            .iter()
+            .map(|id| tree.get(id).unwrap().access_count)
+            .collect();
+        assert_eq!(counts, vec![1000, 500, 250]);
+
+        // Decaying again right away doesn't decay the same age twice
+        tree.decay_access_counts(30);
+        let again: Vec<u32> = ids
+            .iter()
+            .map(|id| tree.get(id).unwrap().access_count)
+            .collect();
+        assert_eq!(again, counts);
+    }
+
+    #[test]
+    fn test_search_prefers_prose_matches() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+
+        let mut prose = ContextNode::new(NodeType::Project, "indexer");
+        prose.add_keyword("parser");
+        let prose_id = tree.add_child(&domain_id, prose).unwrap();
+
+        let mut code = ContextNode::new(NodeType::Project, "renderer");
+        code.add_keyword("parser");
+        code.add_entity(Entity::new("parser", EntityType::CodeElement, 0.9));
+        let code_id = tree.add_child(&domain_id, code).unwrap();
+
+        let results = tree.search_scored("parser");
+        let ranked: Vec<(&str, f32)> = results.iter().map(|(n, s)| (n.id.as_str(), *s)).collect();
+        assert_eq!(
+            ranked,
+            vec![(prose_id.as_str(), 1.0), (code_id.as_str(), 0.5)]
+        );
+
+        // Equal weights make the matches tie
+        let flat = SearchConfig {
+            prose_weight: 1.0,
+            code_weight: 1.0,
+        };
+        let results = tree.search_scored_with("parser", &flat);
+        assert!(results.iter().all(|(_, score)| *score == 1.0));
+    }
+
     #[test]
     fn test_new_tree() {
         let tree = ContextTree::new();
@@ -546,6 +1657,381 @@ mod tests {
         assert_eq!(tree.root().node_type, NodeType::Root);
     }
 
+    #[test]
+    fn test_audit_log_records_mutations_in_order() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = Arc::clone(&events);
+        let mut tree =
+            ContextTree::new().with_audit_log(move |event| sink_events.lock().unwrap().push(event));
+        let root_id = tree.root().id.clone();
+
+        let domain_id = tree.ensure_domain("coding");
+        let a = tree
+            .add_child(&domain_id, ContextNode::project("a", PathBuf::from("/a")))
+            .unwrap();
+        let b = tree
+            .add_child(&domain_id, ContextNode::project("b", PathBuf::from("/b")))
+            .unwrap();
+        assert!(tree.add_cross_link(&a, RelatedNode::new(&b, CrossLinkType::SameTechnology, 0.7)));
+        assert!(!tree.add_cross_link(&a, RelatedNode::new(&b, CrossLinkType::SameTechnology, 0.7)));
+        tree.remove(&b);
+
+        let events = events.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            vec![
+                TreeMutation::DomainCreated {
+                    id: domain_id.clone(),
+                    name: "coding".to_string(),
+                },
+                TreeMutation::NodeInserted {
+                    id: a.clone(),
+                    node_type: NodeType::Project,
+                    parent_id: Some(domain_id.clone()),
+                },
+                TreeMutation::NodeInserted {
+                    id: b.clone(),
+                    node_type: NodeType::Project,
+                    parent_id: Some(domain_id.clone()),
+                },
+                TreeMutation::CrossLinkAdded {
+                    from_id: a,
+                    to_id: b.clone(),
+                    link_type: CrossLinkType::SameTechnology,
+                },
+                TreeMutation::NodeRemoved {
+                    id: b,
+                    node_type: NodeType::Project,
+                },
+            ]
+        );
+        assert!(tree.get(&root_id).is_some());
+    }
+
+    #[test]
+    fn test_cross_link_strength_weighting() {
+        use crate::entity::{Entity, EntityType};
+
+        let mut tree = ContextTree::new();
+        let mut ids = Vec::new();
+        for (i, techs) in [
+            &["git", "tokio"][..],
+            &["git", "tokio"],
+            &["git", "serde"],
+            &["git", "react"],
+            &["git", "django"],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let domain_id = tree.ensure_domain(&format!("domain-{i}"));
+            let mut node = ContextNode::project(format!("p{i}"), PathBuf::from(format!("/p{i}")));
+            for tech in techs {
+                node.entities
+                    .push(Entity::new(*tech, EntityType::Technology, 0.9));
+            }
+            ids.push(tree.add_child(&domain_id, node).unwrap());
+        }
+
+        let link_strength = |tree: &ContextTree, from: &str, to: &str| {
+            tree.get(from)
+                .unwrap()
+                .related_nodes
+                .iter()
+                .find(|r| r.node_id == to)
+                .map(|r| r.strength)
+        };
+
+        // Without a threshold, the ubiquitous "git" links everything weakly.
+        let mut dense = tree.clone();
+        dense.build_cross_links_with_threshold(0.0);
+        let rare = link_strength(&dense, &ids[0], &ids[1]).unwrap();
+        let common = link_strength(&dense, &ids[2], &ids[3]).unwrap();
+        assert!(rare > common, "rare {rare} <= common {common}");
+
+        // The default threshold drops links that only share "git".
+        tree.build_cross_links();
+        assert_eq!(link_strength(&tree, &ids[0], &ids[1]), Some(rare));
+        assert_eq!(link_strength(&tree, &ids[1], &ids[0]), Some(rare));
+        assert_eq!(link_strength(&tree, &ids[2], &ids[3]), None);
+        assert!(tree.get(&ids[4]).unwrap().related_nodes.is_empty());
+    }
+
+    #[test]
+    fn test_max_related_per_node() {
+        let mut tree = ContextTree::new().with_max_related_per_node(3);
+        let domain_id = tree.ensure_domain("coding");
+        let hub = ContextNode::project("hub", PathBuf::from("/hub"));
+        let hub = tree.add_child(&domain_id, hub).unwrap();
+        let mut others = Vec::new();
+        for i in 0..7 {
+            let node = ContextNode::project(format!("p{i}"), PathBuf::from(format!("/p{i}")));
+            others.push(tree.add_child(&domain_id, node).unwrap());
+        }
+
+        let pinned = RelatedNode::new(&others[0], CrossLinkType::UserDefined, 0.05);
+        assert!(tree.add_cross_link(&hub, pinned));
+        for (i, strength) in [0.3, 0.6, 0.1, 0.5, 0.2, 0.4].into_iter().enumerate() {
+            let link = RelatedNode::new(&others[i + 1], CrossLinkType::SameTechnology, strength);
+            tree.add_cross_link(&hub, link);
+        }
+
+        let mut kept: Vec<(&str, f32)> = tree
+            .get(&hub)
+            .unwrap()
+            .related_nodes
+            .iter()
+            .map(|r| (r.node_id.as_str(), r.strength))
+            .collect();
+        kept.sort_by(|a, b| b.1.total_cmp(&a.1));
+        assert_eq!(
+            kept,
+            vec![
+                (others[2].as_str(), 0.6),
+                (others[4].as_str(), 0.5),
+                (others[6].as_str(), 0.4),
+                (others[0].as_str(), 0.05),
+            ]
+        );
+
+        // A full node rejects weaker links
+        let weak = RelatedNode::new(&others[5], CrossLinkType::SameTechnology, 0.3);
+        assert!(!tree.add_cross_link(&hub, weak));
+    }
+
+    #[test]
+    fn test_cross_link_min_confidence() {
+        use crate::entity::{Entity, EntityType};
+
+        let mut tree = ContextTree::new();
+        let mut ids = Vec::new();
+        for (i, (tech, confidence)) in [
+            ("tokio", 0.9),
+            ("tokio", 0.8),
+            ("kafka", 0.3),
+            ("kafka", 0.9),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let domain_id = tree.ensure_domain(&format!("domain-{i}"));
+            let mut node = ContextNode::project(format!("p{i}"), PathBuf::from(format!("/p{i}")));
+            node.add_entity(Entity::new(tech, EntityType::Technology, confidence));
+            ids.push(tree.add_child(&domain_id, node).unwrap());
+        }
+        let link = |tree: &ContextTree, from: &str, to: &str| {
+            tree.get(from)
+                .unwrap()
+                .related_nodes
+                .iter()
+                .find(|r| r.node_id == to)
+                .map(|r| r.strength)
+        };
+
+        let mut lenient = tree.clone();
+        tree.build_cross_links();
+
+        // Strength is scaled by the weaker of the two confidences
+        let strength = link(&tree, &ids[0], &ids[1]).unwrap();
+        assert!((strength - MAX_CROSS_LINK_STRENGTH * 0.8).abs() < 1e-6);
+
+        // The low-confidence "kafka" guess doesn't link
+        assert_eq!(link(&tree, &ids[2], &ids[3]), None);
+        assert!(tree.get(&ids[3]).unwrap().related_nodes.is_empty());
+
+        // Lowering the minimum confidence links it weakly
+        lenient.build_cross_links_with_options(DEFAULT_MIN_CROSS_LINK_STRENGTH, 0.2);
+        let strength = link(&lenient, &ids[2], &ids[3]).unwrap();
+        assert!((strength - MAX_CROSS_LINK_STRENGTH * 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_summary_view() {
+        use crate::entity::{Entity, EntityMention, EntityType};
+
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let mut project =
+            ContextNode::project("server", PathBuf::from("/server")).with_summary("An HTTP server");
+        let mut tokio = Entity::new("tokio", EntityType::Technology, 0.9);
+        tokio.add_mention(EntityMention {
+            chunk_id: "chunk-1".to_string(),
+            position: 3,
+            matched_text: "tokio".to_string(),
+            context: Some("uses tokio for async IO".to_string()),
+            source: Some("/server/main.rs".to_string()),
+        });
+        project.add_entity(tokio);
+        project.add_entity(Entity::new("hyper", EntityType::Technology, 0.9));
+        let project_id = tree.add_child(&domain_id, project).unwrap();
+        let doc_id = tree
+            .add_child(
+                &project_id,
+                ContextNode::document("main.rs", PathBuf::from("/server/main.rs")),
+            )
+            .unwrap();
+
+        let view = tree.summary_view();
+        let ids: Vec<&str> = view.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                tree.root().id.as_str(),
+                domain_id.as_str(),
+                project_id.as_str(),
+                doc_id.as_str()
+            ]
+        );
+
+        let project = &view[2];
+        assert_eq!(project.name, "server");
+        assert_eq!(project.summary, "An HTTP server");
+        assert_eq!(project.parent_id.as_deref(), Some(domain_id.as_str()));
+        assert_eq!(project.children, vec![doc_id]);
+        assert_eq!(project.entity_count, 2);
+
+        let json = serde_json::to_value(project).unwrap();
+        assert!(json.get("entities").is_none());
+        assert!(!json.to_string().contains("chunk-1"));
+    }
+
+    #[test]
+    fn test_to_nested_json() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let server_id = tree
+            .add_child(
+                &domain_id,
+                ContextNode::project("server", PathBuf::from("/server")),
+            )
+            .unwrap();
+        let client_id = tree
+            .add_child(
+                &domain_id,
+                ContextNode::project("client", PathBuf::from("/client")),
+            )
+            .unwrap();
+        let doc_id = tree
+            .add_child(
+                &server_id,
+                ContextNode::document("main.rs", PathBuf::from("/server/main.rs")),
+            )
+            .unwrap();
+        assert!(tree.add_cross_link(
+            &server_id,
+            RelatedNode::new(&client_id, CrossLinkType::SameTechnology, 0.5),
+        ));
+
+        let nested = tree.to_nested_json();
+        assert_eq!(nested["id"], tree.root().id.as_str());
+        let domains = nested["children"].as_array().unwrap();
+        assert_eq!(domains.len(), 1);
+        assert_eq!(domains[0]["id"], domain_id.as_str());
+
+        let projects = domains[0]["children"].as_array().unwrap();
+        let project_ids: Vec<&str> = projects.iter().map(|p| p["id"].as_str().unwrap()).collect();
+        assert_eq!(project_ids, vec![server_id.as_str(), client_id.as_str()]);
+        assert_eq!(projects[0]["related"], serde_json::json!([client_id]));
+        assert_eq!(projects[0]["children"][0]["id"], doc_id.as_str());
+        assert_eq!(projects[0]["children"][0]["path"], "/server/main.rs");
+        assert!(nested.get("truncated").is_none());
+
+        let capped = tree.to_nested_json_with_max_depth(1);
+        let domain = &capped["children"][0];
+        assert_eq!(domain["id"], domain_id.as_str());
+        assert_eq!(domain["children"], serde_json::json!([]));
+        assert_eq!(domain["child_count"], 2);
+        assert_eq!(domain["truncated"], true);
+
+        let root_only = tree.to_nested_json_with_max_depth(0);
+        assert_eq!(root_only["children"], serde_json::json!([]));
+        assert_eq!(root_only["truncated"], true);
+    }
+
+    #[test]
+    fn test_insert_batch_matches_add_child() {
+        use crate::entity::{Entity, EntityType};
+
+        let mut sequential = ContextTree::new();
+        let domain_id = sequential.ensure_domain("coding");
+        let project_id = sequential
+            .add_child(
+                &domain_id,
+                ContextNode::project("server", PathBuf::from("/server")),
+            )
+            .unwrap();
+        let mut batched = sequential.clone();
+
+        let docs: Vec<ContextNode> = ["main.rs", "lib.rs", "config.rs"]
+            .into_iter()
+            .map(|name| {
+                let mut doc = ContextNode::document(name, PathBuf::from(format!("/server/{name}")));
+                doc.add_entity(Entity::new("tokio", EntityType::Technology, 0.9));
+                doc
+            })
+            .collect();
+
+        let sequential_ids: Vec<String> = docs
+            .iter()
+            .map(|doc| sequential.add_child(&project_id, doc.clone()).unwrap())
+            .collect();
+        let batched_ids = batched.insert_batch(&project_id, docs).unwrap();
+        assert_eq!(batched_ids, sequential_ids);
+
+        let structure = |tree: &ContextTree| {
+            let mut nodes: Vec<(String, Option<String>, u32, Vec<String>)> = tree
+                .all_nodes()
+                .map(|n| {
+                    (
+                        n.id.clone(),
+                        n.parent_id.clone(),
+                        n.depth,
+                        n.children.clone(),
+                    )
+                })
+                .collect();
+            nodes.sort();
+            nodes
+        };
+        assert_eq!(structure(&batched), structure(&sequential));
+        assert_eq!(
+            batched
+                .get_by_path(Path::new("/server/lib.rs"))
+                .map(|n| &n.id),
+            Some(&batched_ids[1])
+        );
+        assert_eq!(
+            batched
+                .nodes_with_entity("tokio", EntityType::Technology)
+                .len(),
+            3
+        );
+
+        assert!(matches!(
+            batched.insert_batch("missing", Vec::new()),
+            Err(ContextError::ParentNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_reindex() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let path = PathBuf::from("/projects/app/main.rs");
+        let file = ContextNode::file_reference("main.rs", path.clone());
+        let file_id = tree.add_child(&domain_id, file).unwrap();
+
+        tree.domain_index.remove("coding");
+        tree.path_index
+            .insert(path.to_string_lossy().to_string(), "missing".to_string());
+        assert!(tree.get_domain("coding").is_none());
+        assert!(tree.get_by_path(&path).is_none());
+
+        tree.reindex();
+        assert_eq!(tree.get_domain("coding").unwrap().id, domain_id);
+        assert_eq!(tree.get_by_path(&path).unwrap().id, file_id);
+    }
+
     #[test]
     fn test_ensure_domain() {
         let mut tree = ContextTree::new();
@@ -577,6 +2063,123 @@ mod tests {
         assert!(domain_node.children.contains(&project_id));
     }
 
+    #[test]
+    fn test_add_child_missing_parent() {
+        let mut tree = ContextTree::new();
+        let node = ContextNode::document("orphan.md", PathBuf::from("/orphan.md"));
+
+        let err = tree.add_child("no-such-parent", node).unwrap_err();
+        assert!(matches!(err, ContextError::ParentNotFound(ref id) if id == "no-such-parent"));
+        assert_eq!(tree.node_count(), 1);
+    }
+
+    #[test]
+    fn test_retain_entities_tree_wide() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+
+        let mut first = ContextNode::document("a.md", PathBuf::from("/a.md"));
+        first.add_entity(Entity::new("alice@example.com", EntityType::Email, 0.9));
+        first.add_entity(Entity::new("Rust", EntityType::Technology, 0.9));
+        let mut second = ContextNode::document("b.md", PathBuf::from("/b.md"));
+        second.add_entity(Entity::new("bob@example.com", EntityType::Email, 0.9));
+        second.add_entity(Entity::new("Alice Smith", EntityType::Person, 0.8));
+        tree.add_child(&domain_id, first).unwrap();
+        tree.add_child(&domain_id, second).unwrap();
+
+        tree.for_each_entity_mut(|entity| entity.confidence = 0.5);
+        let removed = tree.retain_entities(|entity| entity.entity_type != EntityType::Email);
+        assert_eq!(removed, 2);
+
+        let remaining: Vec<&Entity> = tree.all_nodes().flat_map(|n| &n.entities).collect();
+        assert!(remaining.iter().all(|e| e.entity_type != EntityType::Email));
+        let mut names: Vec<&str> = remaining.iter().map(|e| e.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["Alice Smith", "Rust"]);
+        assert!(remaining.iter().all(|e| e.confidence == 0.5));
+    }
+
+    #[test]
+    fn test_nodes_with_entity() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+
+        let mut first = ContextNode::document("a.md", PathBuf::from("/a.md"));
+        first.add_entity(Entity::new("Rust", EntityType::Technology, 0.9));
+        let mut second = ContextNode::document("b.md", PathBuf::from("/b.md"));
+        second.add_entity(Entity::new("Python", EntityType::Technology, 0.9));
+        let mut third = ContextNode::document("c.md", PathBuf::from("/c.md"));
+        third.add_entity(Entity::new("Rust", EntityType::Technology, 0.8));
+        third.add_entity(Entity::new("Rust", EntityType::Concept, 0.5));
+        let first_id = tree.add_child(&domain_id, first).unwrap();
+        tree.add_child(&domain_id, second).unwrap();
+        let third_id = tree.add_child(&domain_id, third).unwrap();
+
+        let mut expected = vec![first_id.as_str(), third_id.as_str()];
+        expected.sort_unstable();
+        let ids: Vec<&str> = tree
+            .nodes_with_entity("rust", EntityType::Technology)
+            .iter()
+            .map(|n| n.id.as_str())
+            .collect();
+        assert_eq!(ids, expected);
+        assert!(
+            tree.nodes_with_entity("go", EntityType::Technology)
+                .is_empty()
+        );
+
+        tree.remove(&first_id);
+        let ids: Vec<String> = tree
+            .nodes_with_entity("rust", EntityType::Technology)
+            .iter()
+            .map(|n| n.id.clone())
+            .collect();
+        assert_eq!(ids, vec![third_id]);
+
+        tree.retain_entities(|e| e.entity_type != EntityType::Technology);
+        assert!(
+            tree.nodes_with_entity("rust", EntityType::Technology)
+                .is_empty()
+        );
+        assert_eq!(tree.nodes_with_entity("rust", EntityType::Concept).len(), 1);
+    }
+
+    #[test]
+    fn test_cross_domain_links() {
+        let mut tree = ContextTree::new();
+        let coding_id = tree.ensure_domain("coding");
+        let work_id = tree.ensure_domain("work");
+
+        let project = ContextNode::project("server", PathBuf::from("/code/server"));
+        let project_id = tree.add_child(&coding_id, project).unwrap();
+        let tool = ContextNode::project("cli", PathBuf::from("/code/cli"));
+        let tool_id = tree.add_child(&coding_id, tool).unwrap();
+        let doc = ContextNode::document("plan.md", PathBuf::from("/work/plan.md"));
+        let doc_id = tree.add_child(&work_id, doc).unwrap();
+
+        let link = RelatedNode::new(&project_id, CrossLinkType::References, 0.6)
+            .with_reason("Plan references the server");
+        tree.add_cross_link(&doc_id, link);
+        tree.add_cross_link(
+            &project_id,
+            RelatedNode::new(&tool_id, CrossLinkType::SameTechnology, 0.5),
+        );
+
+        let links = tree.cross_domain_links();
+        assert_eq!(
+            links,
+            vec![CrossDomainLink {
+                from_id: doc_id,
+                to_id: project_id,
+                from_domain: "work".to_string(),
+                to_domain: "coding".to_string(),
+                link_type: CrossLinkType::References,
+                strength: 0.6,
+                reason: Some("Plan references the server".to_string()),
+            }]
+        );
+    }
+
     #[test]
     fn test_get_ancestry() {
         let mut tree = ContextTree::new();
@@ -671,6 +2274,164 @@ mod tests {
         assert!(!domain.children.contains(&project_id));
     }
 
+    #[test]
+    fn test_find_and_remove_orphans() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let project_id = tree
+            .add_child(
+                &domain_id,
+                ContextNode::project("app", PathBuf::from("/app")),
+            )
+            .unwrap();
+        assert!(tree.find_orphans().is_empty());
+
+        // A node claiming a parent that doesn't list it as a child
+        let mut orphan = ContextNode::document("stray.md", PathBuf::from("/app/stray.md"));
+        orphan.parent_id = Some(project_id.clone());
+        let orphan_id = tree.insert(orphan);
+        let child_id = tree
+            .add_child(
+                &orphan_id,
+                ContextNode::document("nested.md", PathBuf::from("/app/nested.md")),
+            )
+            .unwrap();
+        assert!(tree.add_cross_link(
+            &project_id,
+            RelatedNode::new(&orphan_id, CrossLinkType::References, 0.5),
+        ));
+
+        let mut expected = vec![orphan_id.clone(), child_id.clone()];
+        expected.sort();
+        assert_eq!(tree.find_orphans(), expected);
+
+        assert_eq!(tree.remove_orphans(), 2);
+        assert!(tree.find_orphans().is_empty());
+        assert!(tree.get(&orphan_id).is_none());
+        assert!(tree.get(&child_id).is_none());
+        assert!(tree.get_by_path(Path::new("/app/stray.md")).is_none());
+        assert!(tree.get(&project_id).unwrap().related_nodes.is_empty());
+        assert_eq!(tree.remove_orphans(), 0);
+    }
+
+    #[test]
+    fn test_validate_healthy_tree() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let category_id = tree.ensure_category(&domain_id, "rust").unwrap();
+        let mut project = ContextNode::project("app", PathBuf::from("/app"));
+        project.add_entity(Entity::new("tokio", EntityType::Technology, 0.9));
+        let project_id = tree.add_child(&category_id, project).unwrap();
+        tree.add_child(
+            &project_id,
+            ContextNode::document("README.md", PathBuf::from("/app/README.md")),
+        )
+        .unwrap();
+
+        assert_eq!(tree.validate(), Vec::new());
+    }
+
+    #[test]
+    fn test_validate_reports_violations() {
+        let mut tree = ContextTree::new();
+        let domain_id = tree.ensure_domain("coding");
+        let project_id = tree
+            .add_child(
+                &domain_id,
+                ContextNode::project("app", PathBuf::from("/app")),
+            )
+            .unwrap();
+        let doc_id = tree
+            .add_child(
+                &project_id,
+                ContextNode::document("README.md", PathBuf::from("/app/README.md")),
+            )
+            .unwrap();
+
+        let doc = tree.get_mut(&doc_id).unwrap();
+        doc.depth = 7;
+        doc.path = Some(PathBuf::from("/app/moved.md"));
+        tree.get_mut(&project_id)
+            .unwrap()
+            .children
+            .push("ghost".to_string());
+
+        let violations = tree.validate();
+        let expected = [
+            TreeInvariantViolation::MissingChild {
+                parent_id: project_id.clone(),
+                child_id: "ghost".to_string(),
+            },
+            TreeInvariantViolation::DepthMismatch {
+                node_id: doc_id.clone(),
+                depth: 7,
+                expected: 3,
+            },
+            TreeInvariantViolation::StaleIndexEntry {
+                index: TreeIndex::Path,
+                key: "/app/README.md".to_string(),
+                node_id: doc_id.clone(),
+            },
+            TreeInvariantViolation::MissingIndexEntry {
+                index: TreeIndex::Path,
+                key: "/app/moved.md".to_string(),
+                node_id: doc_id.clone(),
+            },
+        ];
+        assert_eq!(violations.len(), expected.len(), "{violations:?}");
+        for violation in &expected {
+            assert!(violations.contains(violation), "{violations:?}");
+        }
+
+        let root_id = tree.root().id.clone();
+        tree.remove(&root_id);
+        let violations = tree.validate();
+        assert_eq!(
+            violations[0],
+            TreeInvariantViolation::MissingRoot {
+                root_id: root_id.clone()
+            }
+        );
+        assert!(violations.contains(&TreeInvariantViolation::MissingParent {
+            node_id: domain_id,
+            parent_id: root_id,
+        }));
+    }
+
+    #[test]
+    fn test_prune_empty_containers() {
+        let mut tree = ContextTree::new();
+        let cooking_id = tree.ensure_domain("cooking");
+        let category_id = tree
+            .add_child(&cooking_id, ContextNode::category("recipes"))
+            .unwrap();
+        let project_id = tree
+            .add_child(
+                &category_id,
+                ContextNode::project("soups", PathBuf::from("/soups")),
+            )
+            .unwrap();
+
+        let coding_id = tree.ensure_domain("coding");
+        let project = ContextNode::project("app", PathBuf::from("/app"));
+        let app_id = tree.add_child(&coding_id, project).unwrap();
+        let doc = ContextNode::document("readme", PathBuf::from("/app/README.md"));
+        tree.add_child(&app_id, doc).unwrap();
+
+        let pinned_id = tree.ensure_domain("travel");
+        tree.get_mut(&pinned_id).unwrap().pinned = true;
+
+        tree.remove(&project_id);
+        assert_eq!(tree.prune_empty_containers(), 2);
+
+        assert!(tree.get(&category_id).is_none());
+        assert!(tree.get_domain("cooking").is_none());
+        assert!(tree.get_domain("coding").is_some());
+        assert!(tree.get(&app_id).is_some());
+        assert!(tree.get_domain("travel").is_some());
+        assert_eq!(tree.prune_empty_containers(), 0);
+    }
+
     #[test]
     fn test_get_by_path() {
         let mut tree = ContextTree::new();
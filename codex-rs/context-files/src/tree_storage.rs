@@ -23,6 +23,10 @@ struct TreeData {
     /// ID of the root node.
     root_id: String,
 
+    /// Identity of the tree's owner, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    root_identity: Option<String>,
+
     /// All nodes in the tree.
     nodes: Vec<ContextNode>,
 
@@ -37,6 +41,7 @@ impl TreeData {
         Self {
             version: Self::CURRENT_VERSION,
             root_id: tree.root().id.clone(),
+            root_identity: tree.root_identity().map(str::to_string),
             nodes: tree.all_nodes().cloned().collect(),
             domain_index: tree
                 .list_domains()
@@ -63,28 +68,100 @@ impl TreeData {
             return Ok(ContextTree::new());
         }
 
-        let mut tree = ContextTree::new();
+        let tree = ContextTree::from_nodes(self.root_id, self.nodes);
+        Ok(match self.root_identity {
+            Some(identity) => tree.with_root_identity(identity),
+            None => tree,
+        })
+    }
+}
+
+/// Magic bytes starting a tree file in a binary format.
+///
+/// The byte after the magic identifies the format. JSON files are written
+/// without a header so they stay plain, human-readable JSON.
+const BINARY_MAGIC: &[u8; 4] = b"CTXT";
+
+/// Serialization format for saved trees.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TreeStoreFormat {
+    /// Pretty-printed JSON, readable and diffable.
+    #[default]
+    Json,
+    /// MessagePack, smaller and faster to parse.
+    MessagePack,
+    /// CBOR, smaller and faster to parse.
+    Cbor,
+}
+
+impl TreeStoreFormat {
+    /// All formats, in the order a store looks for saved trees.
+    const ALL: [Self; 3] = [Self::Json, Self::MessagePack, Self::Cbor];
 
-        // Clear the default root
-        let default_root_id = tree.root().id.clone();
+    /// File extension of trees saved in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::MessagePack => "msgpack",
+            Self::Cbor => "cbor",
+        }
+    }
 
-        // Insert all nodes
-        for node in self.nodes {
-            tree.insert(node);
+    /// Byte identifying a binary format in the file header.
+    fn header_byte(self) -> Option<u8> {
+        match self {
+            Self::Json => None,
+            Self::MessagePack => Some(1),
+            Self::Cbor => Some(2),
         }
+    }
 
-        // Remove the default root if it's different from the stored one
-        if default_root_id != self.root_id {
-            tree.remove(&default_root_id);
+    /// Detect the format of a saved tree from its leading bytes.
+    fn detect(bytes: &[u8]) -> Result<(Self, &[u8])> {
+        let Some(rest) = bytes.strip_prefix(BINARY_MAGIC) else {
+            return Ok((Self::Json, bytes));
+        };
+        match rest.split_first() {
+            Some((1, body)) => Ok((Self::MessagePack, body)),
+            Some((2, body)) => Ok((Self::Cbor, body)),
+            Some((byte, _)) => Err(ContextError::UnsupportedFormat(format!(
+                "unknown tree file format {byte}"
+            ))),
+            None => Err(ContextError::UnsupportedFormat(
+                "truncated tree file header".to_string(),
+            )),
         }
+    }
 
-        // Final safety check - ensure root exists
-        if tree.get(&self.root_id).is_none() {
-            warn!("Tree reconstruction failed, creating fresh tree");
-            return Ok(ContextTree::new());
+    /// Encode tree data, including the header for binary formats.
+    fn encode(self, data: &TreeData) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        if let Some(byte) = self.header_byte() {
+            bytes.extend_from_slice(BINARY_MAGIC);
+            bytes.push(byte);
+        }
+        match self {
+            Self::Json => serde_json::to_writer_pretty(&mut bytes, data)?,
+            // Named fields, since skipped optional fields would break
+            // MessagePack's positional struct encoding
+            Self::MessagePack => rmp_serde::encode::write_named(&mut bytes, data)
+                .map_err(|e| ContextError::BinarySerialization(format!("MessagePack: {e}")))?,
+            Self::Cbor => ciborium::into_writer(data, &mut bytes)
+                .map_err(|e| ContextError::BinarySerialization(format!("CBOR: {e}")))?,
         }
+        Ok(bytes)
+    }
 
-        Ok(tree)
+    /// Decode tree data from a file, detecting its format.
+    fn decode(bytes: &[u8]) -> Result<TreeData> {
+        let (format, body) = Self::detect(bytes)?;
+        Ok(match format {
+            Self::Json => serde_json::from_slice(body)?,
+            Self::MessagePack => rmp_serde::from_slice(body)
+                .map_err(|e| ContextError::BinarySerialization(format!("MessagePack: {e}")))?,
+            Self::Cbor => ciborium::from_reader(body)
+                .map_err(|e| ContextError::BinarySerialization(format!("CBOR: {e}")))?,
+        })
     }
 }
 
@@ -94,6 +171,9 @@ impl TreeData {
 pub struct TreeStore {
     /// Base directory for storage.
     base_path: PathBuf,
+
+    /// Format used when saving the tree.
+    format: TreeStoreFormat,
 }
 
 impl TreeStore {
@@ -101,9 +181,25 @@ impl TreeStore {
     pub fn new(base_path: impl Into<PathBuf>) -> Self {
         Self {
             base_path: base_path.into(),
+            format: TreeStoreFormat::default(),
         }
     }
 
+    /// Set the format used when saving the tree.
+    ///
+    /// Trees are saved as `tree.<extension>` for the format, replacing a
+    /// tree saved in another format. Loading detects the format of the saved
+    /// file, so a store can read trees saved in any format.
+    pub fn with_format(mut self, format: TreeStoreFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Get the format used when saving the tree.
+    pub fn format(&self) -> TreeStoreFormat {
+        self.format
+    }
+
     /// Create a tree store in the default location (~/.codex/context).
     pub fn default_location() -> Result<Self> {
         let home = dirs::home_dir().ok_or_else(|| {
@@ -133,12 +229,49 @@ impl TreeStore {
 
     /// Get the path to the main tree file.
     fn tree_file_path(&self) -> PathBuf {
-        self.base_path.join("tree.json")
+        self.tree_file_path_for(self.format)
+    }
+
+    /// Get the path a tree saved in `format` is stored at.
+    fn tree_file_path_for(&self, format: TreeStoreFormat) -> PathBuf {
+        self.base_path.join(format!("tree.{}", format.extension()))
     }
 
     /// Get the path to a backup file.
     fn backup_path(&self) -> PathBuf {
-        self.base_path.join("tree.json.bak")
+        self.backup_path_for(self.format)
+    }
+
+    /// Get the path to the backup of a tree saved in `format`.
+    fn backup_path_for(&self, format: TreeStoreFormat) -> PathBuf {
+        self.base_path
+            .join(format!("tree.{}.bak", format.extension()))
+    }
+
+    /// Get the path to the temporary file used for atomic saves.
+    fn temp_path(&self) -> PathBuf {
+        self.base_path
+            .join(format!("tree.{}.tmp", self.format.extension()))
+    }
+
+    /// Find the saved tree file, preferring this store's format.
+    fn saved_tree_path(&self) -> Option<PathBuf> {
+        std::iter::once(self.format)
+            .chain(TreeStoreFormat::ALL)
+            .map(|format| self.tree_file_path_for(format))
+            .find(|path| path.exists())
+    }
+
+    /// Remove trees saved in other formats, so a load never finds a stale one.
+    fn remove_other_formats(&self) -> Result<()> {
+        for format in TreeStoreFormat::ALL {
+            let path = self.tree_file_path_for(format);
+            if format != self.format && path.exists() {
+                fs::remove_file(&path).map_err(ContextError::Io)?;
+                debug!("Removed tree saved as {format:?} at {}", path.display());
+            }
+        }
+        Ok(())
     }
 
     /// Save the context tree to disk.
@@ -156,11 +289,11 @@ impl TreeStore {
 
         // Serialize tree
         let data = TreeData::from_tree(tree);
-        let json = serde_json::to_string_pretty(&data)
-            .map_err(|e| ContextError::InvalidFormat(format!("Failed to serialize tree: {}", e)))?;
+        let bytes = self.format.encode(&data)?;
 
         // Write to file
-        fs::write(&tree_path, json).map_err(ContextError::Io)?;
+        fs::write(&tree_path, bytes).map_err(ContextError::Io)?;
+        self.remove_other_formats()?;
 
         info!(
             "Saved context tree ({} nodes) to {}",
@@ -171,23 +304,73 @@ impl TreeStore {
         Ok(())
     }
 
-    /// Load the context tree from disk.
-    pub fn load(&self) -> Result<ContextTree> {
+    /// Save the context tree to disk atomically.
+    ///
+    /// The tree is written to a temporary file and renamed into place, so a
+    /// crash mid-save leaves the previously saved tree intact.
+    pub fn save_atomic(&self, tree: &ContextTree) -> Result<()> {
+        self.save_atomic_with(tree, |_| Ok(()))
+    }
+
+    /// Atomic save with a hook that runs after the temp file is written and
+    /// before it is renamed into place.
+    fn save_atomic_with(
+        &self,
+        tree: &ContextTree,
+        before_rename: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<()> {
+        self.ensure_dir()?;
+
         let tree_path = self.tree_file_path();
+        let temp_path = self.temp_path();
+
+        let data = TreeData::from_tree(tree);
+        let bytes = self.format.encode(&data)?;
 
-        if !tree_path.exists() {
+        let result = write_synced(&temp_path, &bytes)
+            .and_then(|()| before_rename(&temp_path))
+            .and_then(|()| {
+                if tree_path.exists() {
+                    fs::copy(&tree_path, self.backup_path()).map_err(ContextError::Io)?;
+                }
+                fs::rename(&temp_path, &tree_path).map_err(ContextError::Io)
+            })
+            .and_then(|()| self.remove_other_formats());
+
+        if let Err(e) = result {
+            if temp_path.exists()
+                && let Err(cleanup_err) = fs::remove_file(&temp_path)
+            {
+                warn!(
+                    "Failed to remove temp file {}: {cleanup_err}",
+                    temp_path.display()
+                );
+            }
+            return Err(e);
+        }
+
+        info!(
+            "Atomically saved context tree ({} nodes) to {}",
+            tree.node_count(),
+            tree_path.display()
+        );
+
+        Ok(())
+    }
+
+    /// Load the context tree from disk.
+    pub fn load(&self) -> Result<ContextTree> {
+        let Some(tree_path) = self.saved_tree_path() else {
             info!(
-                "No existing tree found at {}, creating new tree",
-                tree_path.display()
+                "No existing tree found in {}, creating new tree",
+                self.base_path.display()
             );
             return Ok(ContextTree::new());
-        }
+        };
 
-        let json = fs::read_to_string(&tree_path).map_err(ContextError::Io)?;
+        let bytes = fs::read(&tree_path).map_err(ContextError::Io)?;
 
-        let data: TreeData = serde_json::from_str(&json).map_err(|e| {
-            ContextError::InvalidFormat(format!("Failed to deserialize tree: {}", e))
-        })?;
+        let data = TreeStoreFormat::decode(&bytes)?;
 
         // Check version
         if data.version != TreeData::CURRENT_VERSION {
@@ -199,7 +382,12 @@ impl TreeStore {
             // In the future, we could migrate old versions here
         }
 
-        let tree = data.into_tree()?;
+        let mut tree = data.into_tree()?;
+
+        if !tree.has_valid_root() {
+            warn!("Loaded tree has no valid root, recovering");
+            tree.ensure_root();
+        }
 
         info!(
             "Loaded context tree ({} nodes) from {}",
@@ -212,20 +400,21 @@ impl TreeStore {
 
     /// Check if a tree exists at the storage location.
     pub fn exists(&self) -> bool {
-        self.tree_file_path().exists()
+        self.saved_tree_path().is_some()
     }
 
-    /// Delete the stored tree.
+    /// Delete the stored tree, in any format.
     pub fn delete(&self) -> Result<()> {
-        let tree_path = self.tree_file_path();
-        let backup_path = self.backup_path();
-
-        if tree_path.exists() {
-            fs::remove_file(&tree_path).map_err(ContextError::Io)?;
-        }
-
-        if backup_path.exists() {
-            fs::remove_file(&backup_path).map_err(ContextError::Io)?;
+        for format in TreeStoreFormat::ALL {
+            let paths = [
+                self.tree_file_path_for(format),
+                self.backup_path_for(format),
+            ];
+            for path in paths {
+                if path.exists() {
+                    fs::remove_file(&path).map_err(ContextError::Io)?;
+                }
+            }
         }
 
         info!("Deleted stored tree at {}", self.base_path.display());
@@ -244,8 +433,7 @@ impl TreeStore {
         }
 
         let node_path = nodes_dir.join(format!("{}.json", node.id));
-        let json = serde_json::to_string_pretty(node)
-            .map_err(|e| ContextError::InvalidFormat(format!("Failed to serialize node: {}", e)))?;
+        let json = serde_json::to_string_pretty(node).map_err(ContextError::Serialization)?;
 
         fs::write(&node_path, json).map_err(ContextError::Io)?;
 
@@ -262,9 +450,7 @@ impl TreeStore {
         }
 
         let json = fs::read_to_string(&node_path).map_err(ContextError::Io)?;
-        let node: ContextNode = serde_json::from_str(&json).map_err(|e| {
-            ContextError::InvalidFormat(format!("Failed to deserialize node: {}", e))
-        })?;
+        let node: ContextNode = serde_json::from_str(&json).map_err(ContextError::Serialization)?;
 
         Ok(Some(node))
     }
@@ -306,6 +492,15 @@ impl TreeStore {
     }
 }
 
+/// Write a file and flush it to disk.
+fn write_synced(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut file = fs::File::create(path).map_err(ContextError::Io)?;
+    file.write_all(contents).map_err(ContextError::Io)?;
+    file.sync_all().map_err(ContextError::Io)
+}
+
 /// A visualization of the tree structure.
 #[derive(Debug, Default)]
 pub struct TreeVisualization {
@@ -360,6 +555,89 @@ mod tests {
         assert!(loaded_tree.get_domain("coding").is_some());
     }
 
+    #[test]
+    fn test_save_and_load_each_format() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut tree = ContextTree::new().with_root_identity("user-alice");
+        let domain_id = tree.ensure_domain("coding");
+        for i in 0..20 {
+            let mut project =
+                ContextNode::project(format!("project-{i}"), PathBuf::from(format!("/p/{i}")));
+            project.summary = format!("Project {i} built with Rust and tokio");
+            project.add_keyword("rust");
+            tree.add_child(&domain_id, project).unwrap();
+        }
+
+        let mut sizes = HashMap::new();
+        let mut loaded = Vec::new();
+        for format in [
+            TreeStoreFormat::Json,
+            TreeStoreFormat::MessagePack,
+            TreeStoreFormat::Cbor,
+        ] {
+            // Load with a default store to check the format is detected
+            let path = temp_dir.path().join(format!("{format:?}"));
+            TreeStore::new(&path)
+                .with_format(format)
+                .save(&tree)
+                .unwrap();
+            let store = TreeStore::new(&path);
+            let saved = store.saved_tree_path().unwrap();
+            assert_eq!(saved, path.join(format!("tree.{}", format.extension())));
+            sizes.insert(format, fs::metadata(saved).unwrap().len());
+
+            let data = TreeData::from_tree(&store.load().unwrap());
+            let mut nodes = serde_json::to_value(data.nodes).unwrap();
+            nodes
+                .as_array_mut()
+                .unwrap()
+                .sort_by_key(|n| n["id"].to_string());
+            loaded.push(nodes);
+        }
+
+        assert!(sizes[&TreeStoreFormat::MessagePack] < sizes[&TreeStoreFormat::Json]);
+        assert!(sizes[&TreeStoreFormat::Cbor] < sizes[&TreeStoreFormat::Json]);
+        assert_eq!(loaded[1], loaded[0]);
+        assert_eq!(loaded[2], loaded[0]);
+        assert_eq!(loaded[0].as_array().unwrap().len(), tree.node_count());
+    }
+
+    #[test]
+    fn test_save_replaces_other_formats() {
+        let temp_dir = TempDir::new().unwrap();
+        let json_store = TreeStore::new(temp_dir.path());
+        let cbor_store = TreeStore::new(temp_dir.path()).with_format(TreeStoreFormat::Cbor);
+
+        let mut tree = ContextTree::new();
+        tree.ensure_domain("coding");
+        json_store.save(&tree).unwrap();
+
+        tree.ensure_domain("cooking");
+        cbor_store.save_atomic(&tree).unwrap();
+        assert!(!temp_dir.path().join("tree.json").exists());
+        assert!(temp_dir.path().join("tree.cbor").exists());
+
+        // A store in another format still finds the saved tree
+        let loaded = json_store.load().unwrap();
+        assert!(loaded.get_domain("cooking").is_some());
+
+        json_store.delete().unwrap();
+        assert!(!cbor_store.exists());
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_binary_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TreeStore::new(temp_dir.path());
+        fs::write(store.tree_file_path(), b"CTXT\x09{}").unwrap();
+
+        assert!(matches!(
+            store.load(),
+            Err(ContextError::UnsupportedFormat(_))
+        ));
+    }
+
     #[test]
     fn test_save_creates_backup() {
         let temp_dir = TempDir::new().unwrap();
@@ -375,6 +653,43 @@ mod tests {
         assert!(store.backup_path().exists());
     }
 
+    #[test]
+    fn test_save_atomic_and_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TreeStore::new(temp_dir.path());
+
+        let mut tree = ContextTree::new();
+        tree.ensure_domain("coding");
+        store.save_atomic(&tree).unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.root().id, tree.root().id);
+        assert!(loaded.get_domain("coding").is_some());
+        assert!(!store.temp_path().exists());
+    }
+
+    #[test]
+    fn test_save_atomic_failure_keeps_previous_tree() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TreeStore::new(temp_dir.path());
+
+        let mut tree = ContextTree::new();
+        tree.ensure_domain("coding");
+        store.save_atomic(&tree).unwrap();
+
+        tree.ensure_domain("cooking");
+        let result = store.save_atomic_with(&tree, |_| {
+            Err(ContextError::Io(std::io::Error::other("simulated crash")))
+        });
+        assert!(result.is_err());
+        assert!(!store.temp_path().exists());
+
+        let loaded = store.load().unwrap();
+        assert!(loaded.has_valid_root());
+        assert!(loaded.get_domain("coding").is_some());
+        assert!(loaded.get_domain("cooking").is_none());
+    }
+
     #[test]
     fn test_load_nonexistent() {
         let temp_dir = TempDir::new().unwrap();
@@ -458,5 +773,27 @@ mod tests {
         let restored = data.into_tree().unwrap();
 
         assert_eq!(restored.node_count(), tree.node_count());
+        assert_eq!(restored.root().id, tree.root().id);
+        assert!(restored.get_domain("test-domain").is_some());
+    }
+
+    #[test]
+    fn test_root_name_and_identity_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = TreeStore::new(temp_dir.path());
+
+        let tree = ContextTree::new()
+            .with_root_name("Alice's Knowledge")
+            .with_root_identity("user-alice");
+        store.save(&tree).unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.root().name, "Alice's Knowledge");
+        assert_eq!(loaded.root_identity(), Some("user-alice"));
+        assert!(loaded.same_identity(&tree));
+
+        let other = ContextTree::new().with_root_identity("user-bob");
+        assert!(!loaded.same_identity(&other));
+        assert!(!ContextTree::new().same_identity(&ContextTree::new()));
     }
 }
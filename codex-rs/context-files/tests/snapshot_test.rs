@@ -0,0 +1,22 @@
+//! Golden snapshot tests for the context extraction pipeline.
+//!
+//! Behavioral changes to chunking, extraction, or generation show up as
+//! snapshot diffs. Review them with `cargo insta review` and commit the
+//! updated `.snap` files when the change is intended.
+
+use std::path::PathBuf;
+
+use codex_context_files::ContextPipeline;
+
+/// Get the path to the test fixtures directory.
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+#[test]
+fn test_pipeline_fixtures_snapshot() {
+    let pipeline = ContextPipeline::new();
+    let result = pipeline.process_directory(&fixtures_dir()).unwrap();
+
+    insta::assert_snapshot!("pipeline_fixtures", result.to_snapshot());
+}
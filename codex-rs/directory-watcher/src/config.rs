@@ -1,9 +1,13 @@
 //! Configuration types for directory watching.
 
+use std::collections::HashSet;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use crate::event::FileEventKind;
+
 /// Configuration for a watched directory.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirectoryConfig {
@@ -27,6 +31,16 @@ pub struct DirectoryConfig {
 
     /// Whether to follow symbolic links.
     pub follow_symlinks: bool,
+
+    /// Event kinds to emit for this directory (defaults to all kinds).
+    #[serde(default = "default_event_kinds")]
+    pub event_kinds: HashSet<FileEventKind>,
+
+    /// Minimum time between two events of the same kind for the same path,
+    /// in milliseconds (0 = no debouncing). Events held back within the
+    /// window are coalesced into one emitted when it closes.
+    #[serde(default)]
+    pub debounce_ms: u64,
 }
 
 impl DirectoryConfig {
@@ -40,6 +54,8 @@ impl DirectoryConfig {
             priority: 5,
             max_depth: None,
             follow_symlinks: false,
+            event_kinds: default_event_kinds(),
+            debounce_ms: 0,
         }
     }
 
@@ -73,6 +89,23 @@ impl DirectoryConfig {
         self
     }
 
+    /// Only emit events of the given kinds.
+    pub fn with_event_kinds(mut self, kinds: impl IntoIterator<Item = FileEventKind>) -> Self {
+        self.event_kinds = kinds.into_iter().collect();
+        self
+    }
+
+    /// Set the debounce window for repeated events on the same path.
+    pub fn with_debounce(mut self, window: Duration) -> Self {
+        self.debounce_ms = u64::try_from(window.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Get the debounce window.
+    pub fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+
     /// Disable the directory.
     pub fn disabled(mut self) -> Self {
         self.enabled = false;
@@ -127,6 +160,28 @@ impl DirectoryConfig {
 
         false
     }
+
+    /// Map an event kind to the kind delivered to subscribers, if any.
+    ///
+    /// Renames are decomposed when the rename kind itself isn't subscribed:
+    /// the old path is reported as `Deleted` and the new path as `Created`.
+    pub fn subscribed_kind(&self, kind: FileEventKind) -> Option<FileEventKind> {
+        if self.event_kinds.contains(&kind) {
+            return Some(kind);
+        }
+
+        let fallback = match kind {
+            FileEventKind::RenamedFrom => FileEventKind::Deleted,
+            FileEventKind::RenamedTo => FileEventKind::Created,
+            _ => return None,
+        };
+        self.event_kinds.contains(&fallback).then_some(fallback)
+    }
+}
+
+/// Default event kinds: everything.
+fn default_event_kinds() -> HashSet<FileEventKind> {
+    FileEventKind::ALL.into_iter().collect()
 }
 
 /// How to watch a directory.
@@ -174,4 +229,28 @@ mod tests {
         assert!(config.should_exclude(Path::new("/test/node_modules/package/index.js")));
         assert!(!config.should_exclude(Path::new("/test/src/main.rs")));
     }
+
+    #[test]
+    fn test_subscribed_kind() {
+        let config = DirectoryConfig::new("/test");
+        assert_eq!(
+            config.subscribed_kind(FileEventKind::Accessed),
+            Some(FileEventKind::Accessed)
+        );
+
+        let config = DirectoryConfig::new("/test")
+            .with_event_kinds([FileEventKind::Created, FileEventKind::Deleted]);
+        assert_eq!(config.subscribed_kind(FileEventKind::Modified), None);
+        assert_eq!(
+            config.subscribed_kind(FileEventKind::RenamedFrom),
+            Some(FileEventKind::Deleted)
+        );
+        assert_eq!(
+            config.subscribed_kind(FileEventKind::RenamedTo),
+            Some(FileEventKind::Created)
+        );
+
+        let config = DirectoryConfig::new("/test").with_event_kinds([FileEventKind::Modified]);
+        assert_eq!(config.subscribed_kind(FileEventKind::RenamedTo), None);
+    }
 }
@@ -19,6 +19,10 @@ pub struct FileEvent {
 
     /// Additional attributes.
     pub attributes: FileAttributes,
+
+    /// Watched root the event originated from (if known).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<PathBuf>,
 }
 
 impl FileEvent {
@@ -29,6 +33,7 @@ impl FileEvent {
             path: path.into(),
             timestamp: Utc::now(),
             attributes: FileAttributes::default(),
+            root: None,
         }
     }
 
@@ -38,6 +43,12 @@ impl FileEvent {
         self
     }
 
+    /// Tag the event with the watched root it originated from.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
     /// Check if this is a file event (not directory).
     pub fn is_file(&self) -> bool {
         self.attributes.is_file
@@ -50,7 +61,7 @@ impl FileEvent {
 }
 
 /// Kind of file event.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum FileEventKind {
     /// File was created.
@@ -78,6 +89,20 @@ pub enum FileEventKind {
     Unknown,
 }
 
+impl FileEventKind {
+    /// Every event kind.
+    pub const ALL: [FileEventKind; 8] = [
+        Self::Created,
+        Self::Modified,
+        Self::Deleted,
+        Self::RenamedFrom,
+        Self::RenamedTo,
+        Self::MetadataChanged,
+        Self::Accessed,
+        Self::Unknown,
+    ];
+}
+
 impl From<notify::EventKind> for FileEventKind {
     fn from(kind: notify::EventKind) -> Self {
         match kind {
@@ -2,8 +2,8 @@
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use tokio::sync::{RwLock, mpsc};
@@ -13,6 +13,12 @@ use crate::config::{DirectoryConfig, WatchMode};
 use crate::error::{Result, WatcherError};
 use crate::event::{FileAttributes, FileEvent, FileEventKind};
 
+/// Number of tracked paths above which expired debounce entries are pruned.
+const DEBOUNCE_PRUNE_THRESHOLD: usize = 1024;
+
+/// How often debounced trailing events are checked for emission.
+const DEBOUNCE_FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Directory watcher that monitors file system changes.
 pub struct DirectoryWatcher {
     /// Watched directories.
@@ -29,6 +35,9 @@ pub struct DirectoryWatcher {
 
     /// Whether the watcher is running.
     running: Arc<RwLock<bool>>,
+
+    /// Task emitting debounced trailing events while running.
+    flush_task: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl DirectoryWatcher {
@@ -42,6 +51,7 @@ impl DirectoryWatcher {
             event_tx,
             event_rx: Arc::new(RwLock::new(event_rx)),
             running: Arc::new(RwLock::new(false)),
+            flush_task: None,
         }
     }
 
@@ -87,6 +97,41 @@ impl DirectoryWatcher {
         Ok(())
     }
 
+    /// Add a root to watch, starting to watch it immediately if the watcher
+    /// is running.
+    ///
+    /// Each root keeps its own excludes, event kinds, and debounce window, and
+    /// events are tagged with the root they originated from.
+    pub async fn add_root(&mut self, config: DirectoryConfig) -> Result<()> {
+        let path = config.path.clone();
+        let watch = config.enabled && config.watch_mode == WatchMode::Realtime;
+        let mode = recursive_mode(&config);
+        self.add(config).await?;
+
+        if watch && let Some(ref mut w) = self.watcher {
+            if let Err(e) = w.watch(&path, mode) {
+                self.configs.write().await.remove(&path);
+                return Err(e.into());
+            }
+            debug!("Started watching: {}", path.display());
+        }
+
+        Ok(())
+    }
+
+    /// Remove a root, stopping any active watch on it.
+    pub async fn remove_root(&mut self, path: &Path) -> Result<()> {
+        self.remove(path).await?;
+
+        if let Some(ref mut w) = self.watcher
+            && let Err(e) = w.unwatch(path)
+        {
+            warn!("Failed to unwatch {}: {e}", path.display());
+        }
+
+        Ok(())
+    }
+
     /// Start watching all configured directories.
     pub async fn start(&mut self) -> Result<()> {
         if *self.running.read().await {
@@ -95,53 +140,41 @@ impl DirectoryWatcher {
 
         let event_tx = self.event_tx.clone();
         let configs = self.configs.clone();
+        let debouncer = Arc::new(Mutex::new(Debouncer::default()));
+        let flush_debouncer = Arc::clone(&debouncer);
 
         // Create the notify watcher
         let watcher = notify::recommended_watcher(
-            move |res: std::result::Result<notify::Event, notify::Error>| {
-                match res {
-                    Ok(event) => {
-                        let kind = FileEventKind::from(event.kind);
-
-                        for path in event.paths {
-                            // Check if path should be excluded
-                            let should_process = {
-                                let configs_guard = configs.blocking_read();
-                                !configs_guard.values().any(|c| c.should_exclude(&path))
-                            };
-
-                            if should_process {
-                                let file_event = FileEvent::new(kind, &path).with_attributes(
-                                    FileAttributes::from_path(&path).with_mime_type(),
-                                );
-
-                                if let Err(e) = event_tx.blocking_send(file_event) {
-                                    error!("Failed to send file event: {e}");
-                                }
-                            }
+            move |res: std::result::Result<notify::Event, notify::Error>| match res {
+                Ok(event) => {
+                    let mut debouncer = debouncer.lock().unwrap_or_else(PoisonError::into_inner);
+                    let file_events = route_event(&configs.blocking_read(), &mut debouncer, event);
+                    drop(debouncer);
+
+                    for file_event in file_events {
+                        if let Err(e) = event_tx.blocking_send(file_event) {
+                            error!("Failed to send file event: {e}");
                         }
                     }
-                    Err(e) => {
-                        error!("Watch error: {e}");
-                    }
+                }
+                Err(e) => {
+                    error!("Watch error: {e}");
                 }
             },
         )?;
 
         self.watcher = Some(watcher);
+        self.flush_task = Some(tokio::spawn(flush_debounced(
+            flush_debouncer,
+            self.event_tx.clone(),
+        )));
 
         // Add all realtime directories to the watcher
         let configs = self.configs.read().await;
         for (path, config) in configs.iter() {
             if config.enabled && config.watch_mode == WatchMode::Realtime {
                 if let Some(ref mut w) = self.watcher {
-                    let mode = if config.max_depth == Some(0) {
-                        RecursiveMode::NonRecursive
-                    } else {
-                        RecursiveMode::Recursive
-                    };
-
-                    match w.watch(path, mode) {
+                    match w.watch(path, recursive_mode(config)) {
                         Ok(_) => debug!("Started watching: {}", path.display()),
                         Err(e) => warn!("Failed to watch {}: {e}", path.display()),
                     }
@@ -164,6 +197,9 @@ impl DirectoryWatcher {
             }
         }
 
+        if let Some(task) = self.flush_task.take() {
+            task.abort();
+        }
         self.watcher = None;
         *self.running.write().await = false;
         info!("Directory watcher stopped");
@@ -214,12 +250,7 @@ impl DirectoryWatcher {
         if was_running && config.enabled && config.watch_mode == WatchMode::Realtime {
             // Re-add to watcher
             if let Some(ref mut w) = self.watcher {
-                let mode = if config.max_depth == Some(0) {
-                    RecursiveMode::NonRecursive
-                } else {
-                    RecursiveMode::Recursive
-                };
-                w.watch(&path, mode)?;
+                w.watch(&path, recursive_mode(&config))?;
             }
         }
 
@@ -252,6 +283,166 @@ impl Default for DirectoryWatcher {
     }
 }
 
+/// Notify recursion mode for a directory config.
+fn recursive_mode(config: &DirectoryConfig) -> RecursiveMode {
+    if config.max_depth == Some(0) {
+        RecursiveMode::NonRecursive
+    } else {
+        RecursiveMode::Recursive
+    }
+}
+
+/// Convert a notify event into the file events subscribers asked for.
+///
+/// Each path is handled by the root that contains it: paths matching that
+/// root's excludes are dropped, kinds are filtered through its `event_kinds`,
+/// repeats within its debounce window are held back for `Debouncer::flush`,
+/// and the emitted event is tagged with the root.
+fn route_event(
+    configs: &HashMap<PathBuf, DirectoryConfig>,
+    debouncer: &mut Debouncer,
+    event: notify::Event,
+) -> Vec<FileEvent> {
+    let kind = FileEventKind::from(event.kind);
+    let now = Instant::now();
+
+    event
+        .paths
+        .into_iter()
+        .filter_map(|path| {
+            let Some(config) = owning_config(configs, &path) else {
+                let attributes = FileAttributes::from_path(&path).with_mime_type();
+                return Some(FileEvent::new(kind, path).with_attributes(attributes));
+            };
+            if config.should_exclude(&path) {
+                return None;
+            }
+            let kind = config.subscribed_kind(kind)?;
+            if !debouncer.allow(&path, kind, &config.path, config.debounce(), now) {
+                return None;
+            }
+            Some(root_event(kind, path, &config.path))
+        })
+        .collect()
+}
+
+/// Build an event for `path` under a watched `root`.
+fn root_event(kind: FileEventKind, path: PathBuf, root: &Path) -> FileEvent {
+    let attributes = FileAttributes::from_path(&path).with_mime_type();
+    FileEvent::new(kind, path)
+        .with_attributes(attributes)
+        .with_root(root)
+}
+
+/// Periodically emit debounced trailing events until the receiver closes.
+async fn flush_debounced(debouncer: Arc<Mutex<Debouncer>>, event_tx: mpsc::Sender<FileEvent>) {
+    loop {
+        tokio::time::sleep(DEBOUNCE_FLUSH_INTERVAL).await;
+        let events = debouncer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .flush(Instant::now());
+        for event in events {
+            if event_tx.send(event).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Tracks when events were last emitted so roots can debounce repeats.
+///
+/// The first event for a path and kind is emitted immediately. Repeats
+/// within the debounce window are coalesced into one trailing event, emitted
+/// by `flush` once the window closes, so subscribers always see the final
+/// state of a file after a burst of writes.
+#[derive(Debug, Default)]
+struct Debouncer {
+    /// Last emission time and debounce window per path and kind.
+    last_emitted: HashMap<(PathBuf, FileEventKind), (Instant, Duration)>,
+
+    /// Suppressed events awaiting emission, with when they are due and the
+    /// root they belong to.
+    pending: HashMap<(PathBuf, FileEventKind), (Instant, PathBuf)>,
+}
+
+impl Debouncer {
+    /// Check whether an event may be emitted now, recording it if so.
+    ///
+    /// A suppressed event is queued for `flush` at the end of the window.
+    fn allow(
+        &mut self,
+        path: &Path,
+        kind: FileEventKind,
+        root: &Path,
+        window: Duration,
+        now: Instant,
+    ) -> bool {
+        if window.is_zero() {
+            return true;
+        }
+
+        let key = (path.to_path_buf(), kind);
+        if let Some((last, _)) = self.last_emitted.get(&key)
+            && now.duration_since(*last) < window
+        {
+            let due = *last + window;
+            self.pending
+                .entry(key)
+                .or_insert_with(|| (due, root.to_path_buf()));
+            return false;
+        }
+
+        if self.last_emitted.len() >= DEBOUNCE_PRUNE_THRESHOLD {
+            let pending = &self.pending;
+            self.last_emitted.retain(|key, (last, window)| {
+                now.duration_since(*last) < *window || pending.contains_key(key)
+            });
+        }
+        self.pending.remove(&key);
+        self.last_emitted.insert(key, (now, window));
+        true
+    }
+
+    /// Take the trailing events whose window has closed by `now`.
+    ///
+    /// Each emission starts a new window for its path and kind.
+    fn flush(&mut self, now: Instant) -> Vec<FileEvent> {
+        let mut due: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, (at, _))| *at <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        due.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut events = Vec::with_capacity(due.len());
+        for key in due {
+            let Some((_, root)) = self.pending.remove(&key) else {
+                continue;
+            };
+            if let Some(emitted) = self.last_emitted.get_mut(&key) {
+                emitted.0 = now;
+            }
+            let (path, kind) = key;
+            events.push(root_event(kind, path, &root));
+        }
+        events
+    }
+}
+
+/// Find the most specific watched directory containing `path`.
+fn owning_config<'a>(
+    configs: &'a HashMap<PathBuf, DirectoryConfig>,
+    path: &Path,
+) -> Option<&'a DirectoryConfig> {
+    configs
+        .iter()
+        .filter(|(root, _)| path.starts_with(root))
+        .max_by_key(|(root, _)| root.components().count())
+        .map(|(_, config)| config)
+}
+
 /// Statistics about the directory watcher.
 #[derive(Debug, Clone)]
 pub struct WatcherStats {
@@ -358,6 +549,157 @@ mod tests {
         assert_eq!(dirs.len(), 1);
     }
 
+    #[test]
+    fn test_route_event_filters_kinds() {
+        use notify::event::{CreateKind, DataChange, ModifyKind, RemoveKind, RenameMode};
+        use notify::{Event, EventKind};
+
+        let root = PathBuf::from("/watched");
+        let file = root.join("notes.md");
+        let mut configs = HashMap::new();
+        configs.insert(
+            root.clone(),
+            DirectoryConfig::new(&root).with_event_kinds([FileEventKind::Modified]),
+        );
+
+        fn kinds(configs: &HashMap<PathBuf, DirectoryConfig>, event: Event) -> Vec<FileEventKind> {
+            route_event(configs, &mut Debouncer::default(), event)
+                .into_iter()
+                .map(|e| e.kind)
+                .collect()
+        }
+
+        let created = Event::new(EventKind::Create(CreateKind::File)).add_path(file.clone());
+        let modified = Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            .add_path(file.clone());
+        let deleted = Event::new(EventKind::Remove(RemoveKind::File)).add_path(file.clone());
+        assert!(kinds(&configs, created).is_empty());
+        assert_eq!(kinds(&configs, modified), vec![FileEventKind::Modified]);
+        assert!(kinds(&configs, deleted).is_empty());
+
+        // Renames decompose into delete + create when renames aren't subscribed
+        configs.insert(
+            root.clone(),
+            DirectoryConfig::new(&root)
+                .with_event_kinds([FileEventKind::Created, FileEventKind::Deleted]),
+        );
+        let renamed_from =
+            Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::From))).add_path(file);
+        let renamed_to = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::To)))
+            .add_path(root.join("renamed.md"));
+        assert_eq!(kinds(&configs, renamed_from), vec![FileEventKind::Deleted]);
+        assert_eq!(kinds(&configs, renamed_to), vec![FileEventKind::Created]);
+    }
+
+    #[test]
+    fn test_route_event_per_root_rules() {
+        use notify::event::{DataChange, ModifyKind};
+        use notify::{Event, EventKind};
+
+        let docs = PathBuf::from("/docs");
+        let code = PathBuf::from("/code");
+        let mut configs = HashMap::new();
+        configs.insert(
+            docs.clone(),
+            DirectoryConfig::new(&docs).exclude("**/*.log"),
+        );
+        configs.insert(
+            code.clone(),
+            DirectoryConfig::new(&code)
+                .exclude("**/*.md")
+                .with_debounce(Duration::from_secs(60)),
+        );
+
+        let modified = |path: PathBuf| {
+            Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content))).add_path(path)
+        };
+        let mut debouncer = Debouncer::default();
+        let mut route = |path: PathBuf| -> Vec<(PathBuf, Option<PathBuf>)> {
+            route_event(&configs, &mut debouncer, modified(path))
+                .into_iter()
+                .map(|e| (e.path, e.root))
+                .collect()
+        };
+
+        // Each root only applies its own excludes
+        assert_eq!(
+            route(docs.join("notes.md")),
+            vec![(docs.join("notes.md"), Some(docs.clone()))]
+        );
+        assert!(route(docs.join("debug.log")).is_empty());
+        assert!(route(code.join("README.md")).is_empty());
+        assert_eq!(
+            route(code.join("debug.log")),
+            vec![(code.join("debug.log"), Some(code.clone()))]
+        );
+
+        // Only the code root debounces repeated events
+        assert!(route(code.join("debug.log")).is_empty());
+        assert_eq!(route(docs.join("notes.md")).len(), 1);
+    }
+
+    #[test]
+    fn test_debouncer_emits_trailing_event() {
+        let root = PathBuf::from("/watched");
+        let file = root.join("notes.md");
+        let window = Duration::from_millis(100);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut debouncer = Debouncer::default();
+        let mut allow = |ms| debouncer.allow(&file, FileEventKind::Modified, &root, window, at(ms));
+
+        // The first write is emitted at once; later ones in the window are held
+        assert!(allow(0));
+        assert!(!allow(20));
+        assert!(!allow(60));
+        assert!(debouncer.flush(at(80)).is_empty());
+
+        // When the window closes, one trailing event reports the final state
+        let trailing = debouncer.flush(at(100));
+        assert_eq!(trailing.len(), 1);
+        assert_eq!(trailing[0].kind, FileEventKind::Modified);
+        assert_eq!(trailing[0].path, file);
+        assert_eq!(trailing[0].root, Some(root.clone()));
+        assert!(debouncer.flush(at(500)).is_empty());
+
+        // The trailing event starts a new window
+        let mut allow = |ms| debouncer.allow(&file, FileEventKind::Modified, &root, window, at(ms));
+        assert!(!allow(150));
+        assert_eq!(debouncer.flush(at(200)).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_roots() {
+        let docs = TempDir::new().unwrap();
+        let code = TempDir::new().unwrap();
+        let mut watcher = DirectoryWatcher::new();
+        watcher.start().await.unwrap();
+
+        watcher
+            .add_root(DirectoryConfig::new(docs.path()))
+            .await
+            .unwrap();
+        watcher
+            .add_root(DirectoryConfig::new(code.path()).exclude("**/*.md"))
+            .await
+            .unwrap();
+        assert_eq!(watcher.stats().await.realtime_watches, 2);
+        assert!(
+            watcher
+                .add_root(DirectoryConfig::new(docs.path()))
+                .await
+                .is_err()
+        );
+
+        watcher.remove_root(docs.path()).await.unwrap();
+        let dirs = watcher.directories().await;
+        assert_eq!(dirs.len(), 1);
+        assert_eq!(dirs[0].path, code.path());
+        assert!(watcher.remove_root(docs.path()).await.is_err());
+
+        watcher.stop().await;
+    }
+
     #[tokio::test]
     async fn test_add_nonexistent_directory() {
         let mut watcher = DirectoryWatcher::new();
@@ -68,8 +68,11 @@ impl EmbeddingCache {
         Ok(cache)
     }
 
-    /// Compute a hash for cache lookup.
-    fn hash_key(text: &str, model: &str) -> String {
+    /// Compute the cache key for a text embedded with the given model.
+    ///
+    /// The model is part of the key so embeddings from different models
+    /// are never served in place of each other.
+    pub fn cache_key(text: &str, model: &str) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
@@ -81,14 +84,17 @@ impl EmbeddingCache {
 
     /// Get an embedding from the cache.
     pub async fn get(&self, text: &str, model: &str) -> Option<Embedding> {
-        let key = Self::hash_key(text, model);
+        let key = Self::cache_key(text, model);
         let cache = self.cache.read().await;
-        cache.get(&key).map(|e| e.embedding.clone())
+        cache
+            .get(&key)
+            .filter(|e| e.model == model)
+            .map(|e| e.embedding.clone())
     }
 
     /// Put an embedding in the cache.
     pub async fn put(&self, text: &str, model: &str, embedding: Embedding) -> Result<()> {
-        let key = Self::hash_key(text, model);
+        let key = Self::cache_key(text, model);
         let entry = CacheEntry {
             text_hash: key.clone(),
             embedding,
@@ -127,13 +133,13 @@ impl EmbeddingCache {
 
     /// Check if an embedding is cached.
     pub async fn contains(&self, text: &str, model: &str) -> bool {
-        let key = Self::hash_key(text, model);
+        let key = Self::cache_key(text, model);
         self.cache.read().await.contains_key(&key)
     }
 
     /// Remove an embedding from the cache.
     pub async fn remove(&self, text: &str, model: &str) {
-        let key = Self::hash_key(text, model);
+        let key = Self::cache_key(text, model);
         self.cache.write().await.remove(&key);
     }
 
@@ -230,12 +236,14 @@ where
     ) -> Result<crate::provider::EmbeddingResponse> {
         let model = request
             .model
-            .as_ref()
-            .unwrap_or(&self.provider.default_model().to_string())
-            .clone();
+            .clone()
+            .unwrap_or_else(|| self.provider.default_model().to_string());
+
+        // Key on the embedded form so different preprocessing isn't conflated.
+        let input = request.input_text().into_owned();
 
         // Check cache
-        if let Some(embedding) = self.cache.get(&request.text, &model).await {
+        if let Some(embedding) = self.cache.get(&input, &model).await {
             debug!("Cache hit for embedding");
             return Ok(crate::provider::EmbeddingResponse {
                 embedding: embedding.clone(),
@@ -248,7 +256,7 @@ where
         // Generate and cache
         let response = self.provider.embed(request.clone()).await?;
         self.cache
-            .put(&request.text, &model, response.embedding.clone())
+            .put(&input, &model, response.embedding.clone())
             .await?;
 
         Ok(response)
@@ -297,4 +305,55 @@ mod tests {
         let stats = cache.stats().await;
         assert_eq!(stats.entries, 2);
     }
+
+    #[tokio::test]
+    async fn test_cached_provider_separates_models() {
+        use crate::provider::{EmbeddingProvider, EmbeddingRequest, OpenAIProvider};
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for model in ["text-embedding-3-small", "text-embedding-3-large"] {
+            let dimension = OpenAIProvider::new().dimension_for_model(model);
+            Mock::given(method("POST"))
+                .and(path("/embeddings"))
+                .and(body_partial_json(serde_json::json!({ "model": model })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "data": [{ "embedding": vec![0.5f32; dimension], "index": 0 }],
+                    "model": model,
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let provider = OpenAIProvider::new()
+            .with_api_key("test-key")
+            .with_base_url(server.uri());
+        let cached = CachedProvider::new(provider, EmbeddingCache::new(100));
+
+        let small = cached
+            .embed(EmbeddingRequest::new("hello").with_model("text-embedding-3-small"))
+            .await
+            .unwrap();
+        let large = cached
+            .embed(EmbeddingRequest::new("hello").with_model("text-embedding-3-large"))
+            .await
+            .unwrap();
+
+        assert_eq!(small.dimension, 1536);
+        assert_eq!(large.dimension, 3072);
+        assert_ne!(
+            EmbeddingCache::cache_key("hello", "text-embedding-3-small"),
+            EmbeddingCache::cache_key("hello", "text-embedding-3-large")
+        );
+        assert_eq!(cached.cache().stats().await.entries, 2);
+
+        // Repeated requests are served from the cache for the matching model.
+        let cached_large = cached
+            .embed(EmbeddingRequest::new("hello").with_model("text-embedding-3-large"))
+            .await
+            .unwrap();
+        assert_eq!(cached_large.dimension, 3072);
+    }
 }
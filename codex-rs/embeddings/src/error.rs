@@ -28,6 +28,10 @@ pub enum EmbeddingError {
     #[error("dimension mismatch: expected {expected}, got {actual}")]
     DimensionMismatch { expected: usize, actual: usize },
 
+    /// Embedding was produced by a different model than expected.
+    #[error("model mismatch: expected {expected}, got {actual}")]
+    ModelMismatch { expected: String, actual: String },
+
     /// Cache error.
     #[error("cache error: {0}")]
     Cache(String),
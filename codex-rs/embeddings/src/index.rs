@@ -7,6 +7,7 @@ use tracing::{debug, info};
 
 use crate::Embedding;
 use crate::error::{EmbeddingError, Result};
+use crate::provider::EmbeddingResponse;
 use crate::similarity::{SimilarityResult, cosine_similarity, find_top_k, normalize};
 
 /// An entry in the similarity index.
@@ -33,6 +34,9 @@ pub struct SimilarityIndex {
     /// Expected dimension of embeddings.
     dimension: usize,
 
+    /// Model the stored embeddings were generated with, if pinned.
+    model: Option<String>,
+
     /// Whether embeddings should be normalized.
     normalize_embeddings: bool,
 }
@@ -43,10 +47,29 @@ impl SimilarityIndex {
         Self {
             entries: HashMap::new(),
             dimension,
+            model: None,
             normalize_embeddings: true,
         }
     }
 
+    /// Create a similarity index for embeddings from a specific model.
+    pub fn for_model(model: impl Into<String>, dimension: usize) -> Self {
+        Self {
+            model: Some(model.into()),
+            ..Self::new(dimension)
+        }
+    }
+
+    /// Get the dimension of embeddings stored in this index.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Get the model this index is pinned to, if any.
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
     /// Disable embedding normalization.
     pub fn without_normalization(mut self) -> Self {
         self.normalize_embeddings = false;
@@ -85,6 +108,31 @@ impl SimilarityIndex {
         Ok(())
     }
 
+    /// Add an embedding response to the index.
+    ///
+    /// Fails if the index is pinned to a different model than the one that
+    /// produced the embedding, so vectors from different models never mix.
+    pub fn add_response(
+        &mut self,
+        id: impl Into<String>,
+        response: EmbeddingResponse,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.check_model(&response.model)?;
+        self.add(id, response.embedding, metadata)
+    }
+
+    /// Check that embeddings from `model` may be stored in this index.
+    fn check_model(&self, model: &str) -> Result<()> {
+        match &self.model {
+            Some(expected) if expected != model => Err(EmbeddingError::ModelMismatch {
+                expected: expected.clone(),
+                actual: model.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
     /// Remove an embedding from the index.
     pub fn remove(&mut self, id: &str) -> Option<IndexEntry> {
         self.entries.remove(id)
@@ -215,6 +263,9 @@ impl SimilarityIndex {
                 actual: other.dimension,
             });
         }
+        if let Some(model) = &other.model {
+            self.check_model(model)?;
+        }
 
         let count = other.entries.len();
         for (id, entry) in other.entries {
@@ -260,4 +311,25 @@ mod tests {
         let result = index.add("bad", vec![1.0, 0.0], None);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_model_mismatch() {
+        let response = |model: &str| EmbeddingResponse {
+            embedding: vec![1.0, 0.0, 0.0],
+            model: model.to_string(),
+            dimension: 3,
+            tokens_used: None,
+        };
+
+        let mut index = SimilarityIndex::for_model("model-a", 3);
+        assert_eq!(index.model(), Some("model-a"));
+        index.add_response("a", response("model-a"), None).unwrap();
+
+        let result = index.add_response("b", response("model-b"), None);
+        assert!(matches!(result, Err(EmbeddingError::ModelMismatch { .. })));
+        assert!(!index.contains("b"));
+
+        let other = SimilarityIndex::for_model("model-b", 3);
+        assert!(index.merge(other).is_err());
+    }
 }
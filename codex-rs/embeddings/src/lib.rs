@@ -26,12 +26,14 @@
 pub mod cache;
 pub mod error;
 pub mod index;
+pub mod preprocess;
 pub mod provider;
 pub mod similarity;
 
 pub use cache::EmbeddingCache;
 pub use error::{EmbeddingError, Result};
 pub use index::SimilarityIndex;
+pub use preprocess::PreprocessOptions;
 pub use provider::{EmbeddingProvider, EmbeddingRequest, EmbeddingResponse, OpenAIProvider};
 pub use similarity::{SimilarityResult, cosine_similarity};
 
@@ -0,0 +1,224 @@
+//! Preprocessing of text before it is sent to an embedding provider.
+//!
+//! Chunk text often carries markdown syntax (headings, emphasis, code
+//! fences, link targets) that adds noise to embeddings. Preprocessing only
+//! affects the text that is embedded; callers keep the raw text for display.
+
+use serde::{Deserialize, Serialize};
+
+/// Options controlling how request text is cleaned before embedding.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreprocessOptions {
+    /// Remove markdown syntax, keeping the readable text.
+    pub strip_markdown: bool,
+
+    /// Collapse runs of whitespace into single spaces.
+    pub collapse_whitespace: bool,
+
+    /// Truncate the input to at most this many characters.
+    pub max_input_chars: Option<usize>,
+}
+
+impl PreprocessOptions {
+    /// Options that strip markdown and collapse whitespace.
+    pub fn clean() -> Self {
+        Self {
+            strip_markdown: true,
+            collapse_whitespace: true,
+            max_input_chars: None,
+        }
+    }
+
+    /// Set the maximum number of input characters.
+    pub fn with_max_input_chars(mut self, max: usize) -> Self {
+        self.max_input_chars = Some(max);
+        self
+    }
+
+    /// Apply these options to `text`, returning the form to embed.
+    pub fn apply(&self, text: &str) -> String {
+        let mut output = if self.strip_markdown {
+            strip_markdown(text)
+        } else {
+            text.to_string()
+        };
+
+        if self.collapse_whitespace {
+            output = output.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+
+        if let Some(max) = self.max_input_chars
+            && let Some((idx, _)) = output.char_indices().nth(max)
+        {
+            output.truncate(idx);
+        }
+
+        output
+    }
+}
+
+/// Remove common markdown syntax from `text`.
+fn strip_markdown(text: &str) -> String {
+    let mut lines = Vec::new();
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        // Fence markers and table separators carry no content.
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") || is_table_separator(trimmed) {
+            continue;
+        }
+
+        let mut content = trimmed;
+        content = content.trim_start_matches('>').trim_start();
+        content = content.trim_start_matches('#').trim_start();
+        for marker in ["- ", "* ", "+ "] {
+            if let Some(rest) = content.strip_prefix(marker) {
+                content = rest;
+                break;
+            }
+        }
+
+        lines.push(strip_inline(content));
+    }
+
+    lines.join("\n")
+}
+
+/// Check whether a line is a markdown table separator such as `|---|:--|`.
+fn is_table_separator(line: &str) -> bool {
+    line.contains('-') && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+/// Remove inline markdown: emphasis, code spans, images, and link targets.
+fn strip_inline(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // Underscores inside identifiers like `snake_case` are not emphasis.
+            '_' if output.ends_with(char::is_alphanumeric)
+                && chars.peek().is_some_and(|next| next.is_alphanumeric()) =>
+            {
+                output.push(c);
+            }
+            '*' | '_' | '`' | '|' => output.push(' '),
+            '!' if chars.peek() == Some(&'[') => {}
+            '[' => {}
+            ']' => {
+                // Drop the target of `[label](target)`, keeping the label.
+                if chars.peek() == Some(&'(') {
+                    for next in chars.by_ref() {
+                        if next == ')' {
+                            break;
+                        }
+                    }
+                }
+            }
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{EmbeddingProvider, EmbeddingRequest, EmbeddingResponse};
+    use crate::similarity::cosine_similarity;
+    use async_trait::async_trait;
+    use pretty_assertions::assert_eq;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    /// Deterministic bag-of-tokens provider for tests.
+    struct HashingProvider;
+
+    #[async_trait]
+    impl EmbeddingProvider for HashingProvider {
+        fn name(&self) -> &str {
+            "hashing"
+        }
+
+        fn default_model(&self) -> &str {
+            "hashing"
+        }
+
+        fn default_dimension(&self) -> usize {
+            256
+        }
+
+        async fn embed(&self, request: EmbeddingRequest) -> crate::Result<EmbeddingResponse> {
+            let dimension = self.default_dimension();
+            let mut embedding = vec![0.0f32; dimension];
+            for token in request.input_text().split_whitespace() {
+                let mut hasher = DefaultHasher::new();
+                token.to_lowercase().hash(&mut hasher);
+                embedding[(hasher.finish() % dimension as u64) as usize] += 1.0;
+            }
+            Ok(EmbeddingResponse {
+                embedding,
+                model: self.default_model().to_string(),
+                dimension,
+                tokens_used: None,
+            })
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_strip_markdown() {
+        let options = PreprocessOptions::clean();
+        let text = "# Title\n\n> Some **bold** and _italic_ text with `code`.\n\n\
+                    ```rust\nfn main() {}\n```\n\n- [docs](https://example.com) here\n\
+                    | a | b |\n|---|---|\n| 1 | 2 |\n![logo](logo.png)";
+
+        assert_eq!(
+            options.apply(text),
+            "Title Some bold and italic text with code . fn main() {} docs here a b 1 2 logo"
+        );
+    }
+
+    #[test]
+    fn test_max_input_chars() {
+        let options = PreprocessOptions::default().with_max_input_chars(5);
+        assert_eq!(options.apply("héllo world"), "héllo");
+        assert_eq!(options.apply("hi"), "hi");
+        assert_eq!(PreprocessOptions::default().apply("  a  b "), "  a  b ");
+    }
+
+    #[tokio::test]
+    async fn test_preprocessing_improves_similarity() {
+        let provider = HashingProvider;
+        let markdown = "## **Async** | `runtime` |\n|---|---|\n\
+                        > * _tokio_ *schedules* **tasks** on a `thread` pool *\n\
+                        ```rust\n```\n- [**docs**](https://docs.rs/tokio)";
+        let query = "async runtime tokio schedules tasks on a thread pool docs";
+
+        let query_embedding = provider
+            .embed(EmbeddingRequest::new(query))
+            .await
+            .unwrap()
+            .embedding;
+
+        let raw_request = EmbeddingRequest::new(markdown);
+        let clean_request =
+            EmbeddingRequest::new(markdown).with_preprocessing(PreprocessOptions::clean());
+        assert_eq!(clean_request.text, markdown);
+
+        let raw = provider.embed(raw_request).await.unwrap().embedding;
+        let clean = provider.embed(clean_request).await.unwrap().embedding;
+
+        let raw_similarity = cosine_similarity(&query_embedding, &raw).unwrap();
+        let clean_similarity = cosine_similarity(&query_embedding, &clean).unwrap();
+        assert!(
+            clean_similarity > raw_similarity,
+            "clean {clean_similarity} should beat raw {raw_similarity}"
+        );
+    }
+}
@@ -2,12 +2,15 @@
 //!
 //! Supports multiple embedding providers including OpenAI and local models.
 
+use std::borrow::Cow;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
 use crate::Embedding;
 use crate::error::{EmbeddingError, Result};
+use crate::preprocess::PreprocessOptions;
 
 /// Request for generating embeddings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +23,11 @@ pub struct EmbeddingRequest {
 
     /// Dimensions for the output (if supported by provider).
     pub dimensions: Option<usize>,
+
+    /// Preprocessing applied to `text` before embedding. The raw text is
+    /// left untouched so it can still be displayed.
+    #[serde(default)]
+    pub preprocess: Option<PreprocessOptions>,
 }
 
 impl EmbeddingRequest {
@@ -29,6 +37,7 @@ impl EmbeddingRequest {
             text: text.into(),
             model: None,
             dimensions: None,
+            preprocess: None,
         }
     }
 
@@ -43,6 +52,20 @@ impl EmbeddingRequest {
         self.dimensions = Some(dimensions);
         self
     }
+
+    /// Set the preprocessing applied before embedding.
+    pub fn with_preprocessing(mut self, options: PreprocessOptions) -> Self {
+        self.preprocess = Some(options);
+        self
+    }
+
+    /// The text actually sent to the provider, after preprocessing.
+    pub fn input_text(&self) -> Cow<'_, str> {
+        match &self.preprocess {
+            Some(options) => Cow::Owned(options.apply(&self.text)),
+            None => Cow::Borrowed(&self.text),
+        }
+    }
 }
 
 /// Response from embedding generation.
@@ -73,6 +96,11 @@ pub trait EmbeddingProvider: Send + Sync {
     /// Get the default embedding dimension.
     fn default_dimension(&self) -> usize;
 
+    /// Get the embedding dimension produced by the given model.
+    fn dimension_for_model(&self, _model: &str) -> usize {
+        self.default_dimension()
+    }
+
     /// Generate an embedding for the given text.
     async fn embed(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse>;
 
@@ -152,7 +180,11 @@ impl EmbeddingProvider for OpenAIProvider {
     }
 
     fn default_dimension(&self) -> usize {
-        match self.default_model.as_str() {
+        self.dimension_for_model(&self.default_model)
+    }
+
+    fn dimension_for_model(&self, model: &str) -> usize {
+        match model {
             "text-embedding-3-small" => 1536,
             "text-embedding-3-large" => 3072,
             "text-embedding-ada-002" => 1536,
@@ -166,13 +198,16 @@ impl EmbeddingProvider for OpenAIProvider {
             .as_ref()
             .ok_or(EmbeddingError::ProviderNotConfigured)?;
 
-        let model = request.model.unwrap_or_else(|| self.default_model.clone());
+        let model = request
+            .model
+            .clone()
+            .unwrap_or_else(|| self.default_model.clone());
 
         debug!("Generating embedding with model: {model}");
 
         // Build the request body
         let mut body = serde_json::json!({
-            "input": request.text,
+            "input": request.input_text(),
             "model": model
         });
 
@@ -246,7 +281,7 @@ impl EmbeddingProvider for OpenAIProvider {
             .clone()
             .unwrap_or_else(|| self.default_model.clone());
 
-        let texts: Vec<&str> = requests.iter().map(|r| r.text.as_str()).collect();
+        let texts: Vec<Cow<'_, str>> = requests.iter().map(EmbeddingRequest::input_text).collect();
 
         debug!(
             "Generating batch embeddings for {} texts with model: {model}",
@@ -371,7 +406,7 @@ impl EmbeddingProvider for LocalProvider {
             embedding,
             model: self.default_model().to_string(),
             dimension,
-            tokens_used: Some(request.text.split_whitespace().count() as u64),
+            tokens_used: Some(request.input_text().split_whitespace().count() as u64),
         })
     }
 
@@ -393,6 +428,7 @@ mod tests {
         assert_eq!(request.text, "Hello world");
         assert_eq!(request.model, Some("text-embedding-3-small".to_string()));
         assert_eq!(request.dimensions, Some(512));
+        assert_eq!(request.input_text(), "Hello world");
     }
 
     #[test]
@@ -400,4 +436,12 @@ mod tests {
         let provider = OpenAIProvider::new().with_model("text-embedding-3-large");
         assert_eq!(provider.default_dimension(), 3072);
     }
+
+    #[test]
+    fn test_openai_provider_dimension_for_model() {
+        let provider = OpenAIProvider::new();
+        assert_eq!(provider.default_dimension(), 1536);
+        assert_eq!(provider.dimension_for_model("text-embedding-3-large"), 3072);
+        assert_eq!(provider.dimension_for_model("text-embedding-ada-002"), 1536);
+    }
 }
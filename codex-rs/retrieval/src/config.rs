@@ -1,9 +1,11 @@
 //! Configuration for the unified retrieval engine.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Result, RetrievalError};
+
 /// Configuration for the unified retrieval engine.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetrievalConfig {
@@ -21,9 +23,23 @@ pub struct RetrievalConfig {
 
     /// Sync configuration.
     pub sync: SyncConfig,
+
+    /// Maximum number of chunks embedded concurrently when ingesting a file.
+    #[serde(default = "default_embed_concurrency")]
+    pub embed_concurrency: usize,
+}
+
+/// Default number of chunks embedded concurrently.
+fn default_embed_concurrency() -> usize {
+    4
 }
 
 impl RetrievalConfig {
+    /// Create a builder that validates the configuration.
+    pub fn builder() -> RetrievalConfigBuilder {
+        RetrievalConfigBuilder::new()
+    }
+
     /// Create a new configuration with default values.
     pub fn new(context_dir: impl Into<PathBuf>) -> Self {
         Self {
@@ -32,6 +48,7 @@ impl RetrievalConfig {
             embedding: EmbeddingConfig::default(),
             query: QueryConfig::default(),
             sync: SyncConfig::default(),
+            embed_concurrency: default_embed_concurrency(),
         }
     }
 
@@ -52,6 +69,67 @@ impl RetrievalConfig {
         self.query = config;
         self
     }
+
+    /// Set the maximum number of chunks embedded concurrently.
+    pub fn with_embed_concurrency(mut self, concurrency: usize) -> Self {
+        self.embed_concurrency = concurrency;
+        self
+    }
+
+    /// Check that the configuration is usable.
+    ///
+    /// The context directory must exist or be creatable, watch directories
+    /// must exist, and query weights must be non-negative with a positive sum.
+    pub fn validate(&self) -> Result<()> {
+        check_creatable_dir(&self.context_dir)?;
+        for dir in &self.watch_dirs {
+            if !dir.is_dir() {
+                return Err(config_error(format!(
+                    "watch directory {} does not exist",
+                    dir.display()
+                )));
+            }
+        }
+
+        let query = &self.query;
+        let weights = [
+            ("keyword_weight", query.keyword_weight),
+            ("semantic_weight", query.semantic_weight),
+            ("recency_weight", query.recency_weight),
+        ];
+        for (name, weight) in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(config_error(format!(
+                    "{name} must be a non-negative number, got {weight}"
+                )));
+            }
+        }
+        if weights.iter().map(|(_, weight)| weight).sum::<f32>() <= 0.0 {
+            return Err(config_error("query weights must sum to more than zero"));
+        }
+
+        for (name, value) in [
+            ("min_relevance", query.min_relevance),
+            ("overlap_dedup_threshold", query.overlap_dedup_threshold),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(config_error(format!(
+                    "{name} must be between 0 and 1, got {value}"
+                )));
+            }
+        }
+
+        if self.embed_concurrency == 0 {
+            return Err(config_error("embed_concurrency must be at least 1"));
+        }
+        if self.embedding.cache_enabled && self.embedding.cache_max_entries == 0 {
+            return Err(config_error(
+                "cache_max_entries must be at least 1 when caching is enabled",
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for RetrievalConfig {
@@ -60,6 +138,91 @@ impl Default for RetrievalConfig {
     }
 }
 
+/// Check that `dir` is a directory or can be created as one.
+fn check_creatable_dir(dir: &Path) -> Result<()> {
+    if dir.as_os_str().is_empty() {
+        return Err(config_error("context directory must not be empty"));
+    }
+
+    // The nearest existing ancestor must be a directory for `dir` to be creatable.
+    match dir.ancestors().find(|ancestor| ancestor.exists()) {
+        Some(existing) if !existing.is_dir() => Err(config_error(format!(
+            "context directory {} cannot be created: {} is not a directory",
+            dir.display(),
+            existing.display()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Create a configuration error.
+fn config_error(message: impl Into<String>) -> RetrievalError {
+    RetrievalError::Config(message.into())
+}
+
+/// Builder for a validated [`RetrievalConfig`].
+#[derive(Debug, Clone, Default)]
+pub struct RetrievalConfigBuilder {
+    config: RetrievalConfig,
+}
+
+impl RetrievalConfigBuilder {
+    /// Create a builder starting from the default configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the directory for context files.
+    pub fn context_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.context_dir = dir.into();
+        self
+    }
+
+    /// Add a directory to watch.
+    pub fn watch_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.watch_dirs.push(dir.into());
+        self
+    }
+
+    /// Set the embedding configuration.
+    pub fn embedding(mut self, config: EmbeddingConfig) -> Self {
+        self.config.embedding = config;
+        self
+    }
+
+    /// Set the query configuration.
+    pub fn query(mut self, config: QueryConfig) -> Self {
+        self.config.query = config;
+        self
+    }
+
+    /// Set the weights used to fuse keyword, semantic, and recency scores.
+    pub fn fusion(mut self, keyword: f32, semantic: f32, recency: f32) -> Self {
+        self.config.query.keyword_weight = keyword;
+        self.config.query.semantic_weight = semantic;
+        self.config.query.recency_weight = recency;
+        self
+    }
+
+    /// Set the maximum number of chunks embedded concurrently.
+    pub fn embed_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.embed_concurrency = concurrency;
+        self
+    }
+
+    /// Enable or disable realtime watching.
+    pub fn realtime_watch(mut self, enabled: bool) -> Self {
+        self.config.sync.realtime_watch = enabled;
+        self
+    }
+
+    /// Validate and build the configuration.
+    pub fn build(self) -> Result<RetrievalConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
 /// Configuration for the embedding provider.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmbeddingConfig {
@@ -119,6 +282,31 @@ pub struct QueryConfig {
 
     /// Whether to expand queries to related concepts.
     pub expand_related: bool,
+
+    /// Fraction of a chunk's text shared with a higher-scoring chunk from
+    /// the same source at which the chunk stops adding to the source's score.
+    #[serde(default = "default_overlap_dedup_threshold")]
+    pub overlap_dedup_threshold: f32,
+
+    /// Number of recent source searches whose results are cached (0 = no
+    /// caching). Any change to the similarity index invalidates the cache.
+    #[serde(default)]
+    pub cache_size: usize,
+
+    /// Milliseconds a query waits for semantic results before returning
+    /// keyword results alone (0 = wait indefinitely).
+    #[serde(default = "default_semantic_timeout_ms")]
+    pub semantic_timeout_ms: u64,
+}
+
+/// Default overlap at which a chunk counts as a duplicate.
+fn default_overlap_dedup_threshold() -> f32 {
+    0.5
+}
+
+/// Default time a query waits for semantic results.
+fn default_semantic_timeout_ms() -> u64 {
+    2000
 }
 
 impl Default for QueryConfig {
@@ -130,6 +318,9 @@ impl Default for QueryConfig {
             semantic_weight: 0.5,
             recency_weight: 0.2,
             expand_related: true,
+            overlap_dedup_threshold: default_overlap_dedup_threshold(),
+            cache_size: 0,
+            semantic_timeout_ms: default_semantic_timeout_ms(),
         }
     }
 }
@@ -170,3 +361,66 @@ pub enum ConflictResolution {
     /// Ask the user.
     AskUser,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config_message(result: Result<RetrievalConfig>) -> String {
+        match result {
+            Err(RetrievalError::Config(message)) => message,
+            other => panic!("expected a config error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        let temp_dir = TempDir::new().unwrap();
+        let context_dir = temp_dir.path().join("contexts");
+
+        let config = RetrievalConfig::builder()
+            .context_dir(&context_dir)
+            .watch_dir(temp_dir.path())
+            .fusion(0.2, 0.8, 0.0)
+            .embed_concurrency(2)
+            .build()
+            .unwrap();
+        assert_eq!(config.context_dir, context_dir);
+        assert_eq!(config.query.semantic_weight, 0.8);
+        assert_eq!(config.embed_concurrency, 2);
+
+        let message = config_message(
+            RetrievalConfig::builder()
+                .context_dir(&context_dir)
+                .fusion(0.0, 0.0, 0.0)
+                .build(),
+        );
+        assert!(message.contains("sum to more than zero"), "{message}");
+
+        let message = config_message(
+            RetrievalConfig::builder()
+                .context_dir(&context_dir)
+                .fusion(-0.5, 1.0, 0.0)
+                .build(),
+        );
+        assert!(message.contains("keyword_weight"), "{message}");
+
+        let file = temp_dir.path().join("file.txt");
+        std::fs::write(&file, "not a directory").unwrap();
+        let message = config_message(
+            RetrievalConfig::builder()
+                .context_dir(file.join("contexts"))
+                .build(),
+        );
+        assert!(message.contains("cannot be created"), "{message}");
+
+        let message = config_message(
+            RetrievalConfig::builder()
+                .context_dir(&context_dir)
+                .watch_dir(temp_dir.path().join("missing"))
+                .build(),
+        );
+        assert!(message.contains("does not exist"), "{message}");
+    }
+}
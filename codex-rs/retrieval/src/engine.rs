@@ -1,20 +1,32 @@
 //! Unified retrieval engine implementation.
 
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
 
-use tokio::sync::RwLock;
-use tracing::{debug, info};
+use tokio::sync::{RwLock, Semaphore};
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
 
+use codex_context_files::encoding::{DEFAULT_FALLBACK_ENCODING, decode, encoding_for_label};
 use codex_context_files::{
-    ConceptExtractor, ConceptIndex, ContextStore, Query, QueryResult, RetrievalEngine,
+    ConceptExtractor, ConceptIndex, ContextError, ContextStore, IndexLoad, Query, QueryResult,
+    RetrievalEngine, SemanticChunker,
 };
 use codex_directory_watcher::{DirectoryConfig, DirectoryWatcher, FileEvent};
-use codex_embeddings::{EmbeddingCache, OpenAIProvider, SimilarityIndex};
+use codex_embeddings::{
+    EmbeddingCache, EmbeddingError, EmbeddingProvider, EmbeddingRequest, OpenAIProvider,
+    SimilarityIndex, SimilarityResult,
+};
 
 use crate::config::{EmbeddingProviderType, RetrievalConfig};
 use crate::error::{Result, RetrievalError};
 
+/// File in the context directory holding the saved concept index.
+const CONCEPT_INDEX_FILE: &str = "concepts.index";
+
 /// Unified retrieval engine that combines all retrieval components.
 ///
 /// This is the main entry point for the Codex memory system. It coordinates:
@@ -35,6 +47,12 @@ pub struct UnifiedRetrieval {
     /// Embedding similarity index.
     similarity_index: Arc<RwLock<SimilarityIndex>>,
 
+    /// Provider used to embed chunks and queries.
+    embedder: Option<Arc<dyn EmbeddingProvider>>,
+
+    /// Text of each embedded chunk, kept so chunks can be re-embedded.
+    chunk_texts: Arc<RwLock<HashMap<String, String>>>,
+
     /// Directory watcher.
     watcher: Arc<RwLock<DirectoryWatcher>>,
 
@@ -44,6 +62,12 @@ pub struct UnifiedRetrieval {
     /// Context retrieval engine.
     retrieval: RetrievalEngine,
 
+    /// Bumped whenever the similarity index changes.
+    generation: AtomicU64,
+
+    /// Recent `search_sources` results, valid for a single generation.
+    query_cache: Mutex<QueryCache>,
+
     /// Whether the engine is initialized.
     initialized: bool,
 }
@@ -61,8 +85,12 @@ impl UnifiedRetrieval {
         // Initialize context store
         let context_store = ContextStore::new(&config.context_dir).await?;
 
-        // Initialize concept index
-        let concept_index = ConceptIndex::new();
+        // Warm-start the concept index from its saved copy when it is current
+        let (concept_index, load) =
+            ConceptIndex::load(&config.context_dir.join(CONCEPT_INDEX_FILE), &context_store)?;
+        if load == IndexLoad::Rebuilt {
+            debug!("Rebuilt concept index from context files");
+        }
 
         // Initialize similarity index
         let dimension = match config.embedding.provider {
@@ -90,9 +118,13 @@ impl UnifiedRetrieval {
             context_store: Arc::new(RwLock::new(context_store)),
             concept_index: Arc::new(RwLock::new(concept_index)),
             similarity_index: Arc::new(RwLock::new(similarity_index)),
+            embedder: None,
+            chunk_texts: Arc::new(RwLock::new(HashMap::new())),
             watcher: Arc::new(RwLock::new(watcher)),
             extractor: ConceptExtractor::with_defaults(),
             retrieval: RetrievalEngine::with_defaults(),
+            generation: AtomicU64::new(0),
+            query_cache: Mutex::new(QueryCache::default()),
             initialized: true,
         };
 
@@ -116,19 +148,63 @@ impl UnifiedRetrieval {
     }
 
     /// Process a natural language query.
-    pub async fn query(&self, query_text: &str) -> Result<QueryResult> {
+    ///
+    /// Context files are matched by concept and keyword while, if an
+    /// embedder is set, sources are ranked by semantic similarity at the
+    /// same time. Semantic search that fails or outlasts
+    /// `query.semantic_timeout_ms` is dropped and flagged as degraded, so a
+    /// slow embedding backend never holds up the keyword results.
+    pub async fn query(&self, query_text: &str) -> Result<UnifiedQueryResult> {
         if !self.initialized {
             return Err(RetrievalError::NotInitialized);
         }
 
         debug!("Processing query: {query_text}");
 
-        let store = self.context_store.read().await;
-        let index = self.concept_index.read().await;
+        let lexical = async {
+            let store = self.context_store.read().await;
+            let index = self.concept_index.read().await;
+            self.retrieval.retrieve(query_text, &store, &index)
+        };
+        let (contexts, (sources, semantic_degraded)) =
+            tokio::join!(lexical, self.semantic_sources(query_text));
+
+        Ok(UnifiedQueryResult {
+            contexts: contexts?,
+            sources,
+            semantic_degraded,
+        })
+    }
+
+    /// Rank sources for a query, giving up after the configured timeout.
+    ///
+    /// Returns the sources and whether semantic search failed or timed out.
+    async fn semantic_sources(&self, text: &str) -> (Vec<SourceResult>, bool) {
+        if self.embedder.is_none() {
+            return (Vec::new(), false);
+        }
 
-        let result = self.retrieval.retrieve(query_text, &store, &index)?;
+        let search = self.search_sources(text, self.config.query.max_results);
+        let timeout_ms = self.config.query.semantic_timeout_ms;
+        let result = if timeout_ms == 0 {
+            Ok(search.await)
+        } else {
+            tokio::time::timeout(Duration::from_millis(timeout_ms), search).await
+        };
 
-        Ok(result)
+        match result {
+            Ok(Ok(sources)) => (sources, false),
+            Ok(Err(e)) => {
+                warn!("Semantic search failed, returning keyword results only: {e}");
+                (Vec::new(), true)
+            }
+            Err(_) => {
+                warn!(
+                    "Semantic search timed out after {timeout_ms}ms, returning keyword results only"
+                );
+                (Vec::new(), true)
+            }
+        }
     }
 
     /// Add or update a context file.
@@ -146,11 +222,46 @@ impl UnifiedRetrieval {
         Ok(())
     }
 
+    /// Save the concept index so the next engine can warm-start from it.
+    pub async fn save_index(&self) -> Result<()> {
+        let store = self.context_store.read().await;
+        self.concept_index
+            .read()
+            .await
+            .save(&self.config.context_dir.join(CONCEPT_INDEX_FILE), &store)?;
+        Ok(())
+    }
+
     /// Get a context file by concept name.
     pub async fn get_context(&self, concept: &str) -> Option<codex_context_files::ContextFile> {
         self.context_store.read().await.get(concept).cloned()
     }
 
+    /// Get the context files generated from a context tree node.
+    ///
+    /// Files are ordered by concept.
+    pub async fn contexts_for_node(&self, node_id: &str) -> Vec<codex_context_files::ContextFile> {
+        let mut contexts: Vec<codex_context_files::ContextFile> = self
+            .context_store
+            .read()
+            .await
+            .all()
+            .filter(|cf| cf.node_id.as_deref() == Some(node_id))
+            .cloned()
+            .collect();
+        contexts.sort_by(|a, b| a.concept.cmp(&b.concept));
+        contexts
+    }
+
+    /// Get the ID of the context tree node a concept was generated from.
+    pub async fn node_for_concept(&self, concept: &str) -> Option<String> {
+        self.context_store
+            .read()
+            .await
+            .get(concept)
+            .and_then(|cf| cf.node_id.clone())
+    }
+
     /// List all concepts.
     pub async fn list_concepts(&self) -> Vec<String> {
         self.context_store
@@ -202,6 +313,350 @@ impl UnifiedRetrieval {
         Ok(())
     }
 
+    /// Embed a chunk of text and store it in the similarity index.
+    pub async fn index_chunk(&self, id: &str, text: &str) -> Result<()> {
+        let embedder = self.embedder()?;
+        let response = embedder.embed(EmbeddingRequest::new(text)).await?;
+
+        self.similarity_index
+            .write()
+            .await
+            .add_response(id, response, None)?;
+        self.chunk_texts
+            .write()
+            .await
+            .insert(id.to_string(), text.to_string());
+        self.invalidate_queries();
+
+        debug!("Indexed chunk: {id}");
+        Ok(())
+    }
+
+    /// Chunk a file and embed its chunks into the similarity index.
+    ///
+    /// Up to `embed_concurrency` chunks are embedded at once, but chunks are
+    /// added to the index in file order regardless of which finishes first.
+    /// Chunks that fail to embed are reported without aborting the file.
+    /// Chunks from a previous ingest of the same file are replaced.
+    pub async fn ingest_file(&self, path: impl AsRef<Path>) -> Result<IngestReport> {
+        let path = path.as_ref();
+        let embedder = Arc::clone(self.embedder()?);
+
+        let bytes = tokio::fs::read(path).await?;
+        let decoded =
+            decode(&bytes, encoding_for_label(DEFAULT_FALLBACK_ENCODING)).ok_or_else(|| {
+                ContextError::UnsupportedFormat(format!("{} is not a text file", path.display()))
+            })?;
+        let source = path.to_string_lossy().to_string();
+        let chunks = SemanticChunker::new().chunk_with_source(&decoded.text, &source);
+        debug!("Ingesting {} chunks from {source}", chunks.len());
+
+        // Embed concurrently, then index in chunk order.
+        let semaphore = Arc::new(Semaphore::new(self.config.embed_concurrency.max(1)));
+        let mut tasks = JoinSet::new();
+        for (position, chunk) in chunks.iter().enumerate() {
+            let embedder = Arc::clone(&embedder);
+            let semaphore = Arc::clone(&semaphore);
+            let text = chunk.content.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let response = embedder.embed(EmbeddingRequest::new(text)).await;
+                (position, response)
+            });
+        }
+
+        let mut responses: Vec<Option<codex_embeddings::Result<_>>> =
+            (0..chunks.len()).map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((position, response)) => responses[position] = Some(response),
+                Err(e) => warn!("Embedding task failed: {e}"),
+            }
+        }
+
+        let prefix = format!("{source}#");
+        let mut index = self.similarity_index.write().await;
+        let mut texts = self.chunk_texts.write().await;
+        let stale: Vec<String> = texts
+            .keys()
+            .filter(|id| id.starts_with(&prefix))
+            .cloned()
+            .collect();
+        for id in stale {
+            index.remove(&id);
+            texts.remove(&id);
+        }
+
+        let mut report = IngestReport {
+            chunks: chunks.len(),
+            indexed: 0,
+            failures: Vec::new(),
+        };
+        for (position, (chunk, response)) in chunks.into_iter().zip(responses).enumerate() {
+            let id = format!("{prefix}{position}");
+            let metadata = serde_json::json!({
+                "source": source,
+                "chunk_index": position,
+                "continuation": chunk.metadata.is_continuation,
+            });
+            let added = match response {
+                Some(Ok(response)) => index
+                    .add_response(id.as_str(), response, Some(metadata))
+                    .map_err(|e| e.to_string()),
+                Some(Err(e)) => Err(e.to_string()),
+                None => Err("embedding task did not complete".to_string()),
+            };
+            match added {
+                Ok(()) => {
+                    texts.insert(id, chunk.content);
+                    report.indexed += 1;
+                }
+                Err(e) => {
+                    warn!("Failed to embed chunk {id}: {e}");
+                    report.failures.push((id, e));
+                }
+            }
+        }
+        self.invalidate_queries();
+
+        info!(
+            "Ingested {source}: {} of {} chunks indexed",
+            report.indexed, report.chunks
+        );
+        Ok(report)
+    }
+
+    /// Find the stored chunks most similar to the given text.
+    pub async fn semantic_search(&self, text: &str, k: usize) -> Result<Vec<SimilarityResult>> {
+        let embedder = self.embedder()?;
+        let query = embedder.embed(EmbeddingRequest::new(text)).await?;
+
+        let results = self.similarity_index.read().await.search(
+            &query.embedding,
+            k,
+            self.config.query.min_relevance,
+        )?;
+        Ok(results)
+    }
+
+    /// Rank the sources of stored chunks by similarity to the given text.
+    ///
+    /// A source scores the sum of its matching chunks, but text repeated
+    /// across its chunks is only counted once: continuation chunks count
+    /// only their new text, and chunks sharing at least
+    /// `overlap_dedup_threshold` of their text with a better-matching chunk
+    /// from the same source add nothing.
+    ///
+    /// With `query.cache_size` set, repeating a search (ignoring case and
+    /// whitespace) returns cached results until the index changes.
+    pub async fn search_sources(&self, text: &str, k: usize) -> Result<Vec<SourceResult>> {
+        let capacity = self.config.query.cache_size;
+        if capacity == 0 {
+            return self.rank_sources(text, k).await;
+        }
+
+        let normalized = text
+            .to_lowercase()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let key = (normalized, k);
+        // Read before ranking, so results from an index that changes
+        // meanwhile are never cached as current
+        let generation = self.generation.load(Ordering::Acquire);
+        let cached = self.query_cache().get(&key, generation);
+        if let Some(results) = cached {
+            debug!("Serving source search {:?} from cache", key.0);
+            return Ok(results);
+        }
+
+        let results = self.rank_sources(text, k).await?;
+        self.query_cache()
+            .insert(key, results.clone(), generation, capacity);
+        Ok(results)
+    }
+
+    /// Get hit and miss counts of the source search cache.
+    pub fn query_cache_stats(&self) -> QueryCacheStats {
+        self.query_cache().stats
+    }
+
+    /// Rank sources against the text without consulting the cache.
+    async fn rank_sources(&self, text: &str, k: usize) -> Result<Vec<SourceResult>> {
+        let embedder = self.embedder()?;
+        let query = embedder.embed(EmbeddingRequest::new(text)).await?;
+
+        let index = self.similarity_index.read().await;
+        let matches = index.search(
+            &query.embedding,
+            index.len(),
+            self.config.query.min_relevance,
+        )?;
+        drop(index);
+
+        let texts = self.chunk_texts.read().await;
+        let threshold = self.config.query.overlap_dedup_threshold;
+        let mut sources: Vec<SourceResult> = Vec::new();
+        let mut seen: HashMap<String, (usize, HashSet<String>)> = HashMap::new();
+
+        // Matches arrive best first, so each chunk is compared against the
+        // better-matching chunks already counted for its source.
+        for result in matches {
+            let metadata = result.metadata.as_ref();
+            let source = metadata
+                .and_then(|m| m["source"].as_str())
+                .unwrap_or(&result.id)
+                .to_string();
+            let continuation = metadata
+                .and_then(|m| m["continuation"].as_bool())
+                .unwrap_or(false);
+
+            let (position, counted) = seen.entry(source.clone()).or_insert_with(|| {
+                sources.push(SourceResult {
+                    source,
+                    score: 0.0,
+                    chunks: Vec::new(),
+                });
+                (sources.len() - 1, HashSet::new())
+            });
+
+            let shingles = texts
+                .get(&result.id)
+                .map(|t| shingles(t))
+                .unwrap_or_default();
+            let overlap = if shingles.is_empty() {
+                0.0
+            } else {
+                shingles.intersection(counted).count() as f32 / shingles.len() as f32
+            };
+            let weight = if overlap >= threshold {
+                0.0
+            } else if continuation {
+                1.0 - overlap
+            } else {
+                1.0
+            };
+
+            let entry = &mut sources[*position];
+            entry.score += result.score * weight;
+            entry.chunks.push(result.id);
+            counted.extend(shingles);
+        }
+
+        sources.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.source.cmp(&b.source))
+        });
+        sources.truncate(k);
+        Ok(sources)
+    }
+
+    /// Re-embed every stored chunk with a new provider.
+    ///
+    /// Embeddings are written to a fresh index sized for the new provider,
+    /// which replaces the current index only once every chunk has been
+    /// processed, so the old index stays queryable until then. Each chunk
+    /// keeps its metadata, such as the source it was ingested from. Chunks
+    /// that fail to embed are reported and left out of the new index.
+    pub async fn reembed_all(
+        &mut self,
+        new_provider: Arc<dyn EmbeddingProvider>,
+    ) -> Result<ReembedReport> {
+        if !new_provider.is_available() {
+            return Err(EmbeddingError::ProviderNotConfigured.into());
+        }
+
+        let old_index = self.similarity_index.read().await;
+        let mut chunks: Vec<(String, String, Option<serde_json::Value>)> = self
+            .chunk_texts
+            .read()
+            .await
+            .iter()
+            .map(|(id, text)| {
+                let metadata = old_index.get(id).and_then(|e| e.metadata.clone());
+                (id.clone(), text.clone(), metadata)
+            })
+            .collect();
+        drop(old_index);
+        chunks.sort_by(|a, b| a.0.cmp(&b.0));
+
+        info!(
+            "Re-embedding {} chunks with {}",
+            chunks.len(),
+            new_provider.name()
+        );
+
+        let mut index = new_index(new_provider.as_ref());
+        let mut report = ReembedReport {
+            total: chunks.len(),
+            reembedded: 0,
+            failures: Vec::new(),
+            dimension: index.dimension(),
+        };
+
+        for (id, text, metadata) in chunks {
+            let added = match new_provider.embed(EmbeddingRequest::new(text)).await {
+                Ok(response) => index.add_response(id.as_str(), response, metadata),
+                Err(e) => Err(e),
+            };
+            match added {
+                Ok(()) => report.reembedded += 1,
+                Err(e) => {
+                    warn!("Failed to re-embed chunk {id}: {e}");
+                    report.failures.push((id, e.to_string()));
+                }
+            }
+        }
+
+        *self.similarity_index.write().await = index;
+        self.embedder = Some(new_provider);
+        self.invalidate_queries();
+
+        info!(
+            "Re-embedded {} of {} chunks",
+            report.reembedded, report.total
+        );
+        Ok(report)
+    }
+
+    /// Get the dimension of the embeddings in the similarity index.
+    pub async fn embedding_dimension(&self) -> usize {
+        self.similarity_index.read().await.dimension()
+    }
+
+    /// Set the provider used to embed chunks and queries.
+    ///
+    /// Replaces the similarity index with an empty index sized for the
+    /// provider. Use [`Self::reembed_all`] to switch providers while keeping
+    /// stored chunks.
+    async fn set_embedder(&mut self, provider: Arc<dyn EmbeddingProvider>) {
+        *self.similarity_index.write().await = new_index(provider.as_ref());
+        self.chunk_texts.write().await.clear();
+        self.embedder = Some(provider);
+        self.invalidate_queries();
+    }
+
+    /// Mark cached query results stale after the similarity index changes.
+    fn invalidate_queries(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Lock the query cache, recovering it if a panic poisoned the lock.
+    fn query_cache(&self) -> MutexGuard<'_, QueryCache> {
+        self.query_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the configured embedding provider.
+    fn embedder(&self) -> Result<&Arc<dyn EmbeddingProvider>> {
+        self.embedder
+            .as_ref()
+            .ok_or_else(|| EmbeddingError::ProviderNotConfigured.into())
+    }
+
     /// Add a directory to watch.
     pub async fn add_watch_dir(&self, path: impl AsRef<Path>) -> Result<()> {
         let config = DirectoryConfig::new(path.as_ref());
@@ -226,9 +681,85 @@ impl UnifiedRetrieval {
     }
 }
 
+/// Number of words in each shingle used to measure chunk overlap.
+const SHINGLE_WORDS: usize = 3;
+
+/// Split text into overlapping word sequences for overlap comparison.
+fn shingles(text: &str) -> HashSet<String> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    if words.len() < SHINGLE_WORDS {
+        return words.into_iter().collect();
+    }
+    words.windows(SHINGLE_WORDS).map(|w| w.join(" ")).collect()
+}
+
+/// Least-recently-used cache of source search results.
+#[derive(Debug, Default)]
+struct QueryCache {
+    /// Index generation the entries were computed against.
+    generation: u64,
+
+    /// Results keyed by normalized query text and `k`, oldest first.
+    entries: VecDeque<((String, usize), Vec<SourceResult>)>,
+
+    /// Hit and miss counts.
+    stats: QueryCacheStats,
+}
+
+impl QueryCache {
+    /// Look up results computed against `generation`.
+    fn get(&mut self, key: &(String, usize), generation: u64) -> Option<Vec<SourceResult>> {
+        self.advance(generation);
+        let position = self.entries.iter().position(|(k, _)| k == key);
+        let Some(entry) = position.and_then(|p| self.entries.remove(p)) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        // Move to the back as the most recently used
+        let results = entry.1.clone();
+        self.entries.push_back(entry);
+        self.stats.hits += 1;
+        Some(results)
+    }
+
+    /// Cache results computed against `generation`, evicting the least
+    /// recently used entry when full. Results from an older generation are
+    /// dropped.
+    fn insert(
+        &mut self,
+        key: (String, usize),
+        results: Vec<SourceResult>,
+        generation: u64,
+        capacity: usize,
+    ) {
+        self.advance(generation);
+        if generation != self.generation {
+            return;
+        }
+        while self.entries.len() >= capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, results));
+    }
+
+    /// Drop all entries when the index has moved to a newer generation.
+    fn advance(&mut self, generation: u64) {
+        if generation > self.generation {
+            self.entries.clear();
+            self.generation = generation;
+        }
+    }
+}
+
+/// Create an empty similarity index for embeddings from `provider`.
+fn new_index(provider: &dyn EmbeddingProvider) -> SimilarityIndex {
+    SimilarityIndex::for_model(provider.default_model(), provider.default_dimension())
+}
+
 /// Builder for unified retrieval engine.
 pub struct UnifiedRetrievalBuilder {
     config: RetrievalConfig,
+    embedder: Option<Arc<dyn EmbeddingProvider>>,
 }
 
 impl UnifiedRetrievalBuilder {
@@ -236,6 +767,7 @@ impl UnifiedRetrievalBuilder {
     pub fn new() -> Self {
         Self {
             config: RetrievalConfig::default(),
+            embedder: None,
         }
     }
 
@@ -263,9 +795,38 @@ impl UnifiedRetrievalBuilder {
         self
     }
 
+    /// Set the maximum number of chunks embedded concurrently.
+    pub fn with_embed_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.embed_concurrency = concurrency;
+        self
+    }
+
+    /// Set how many recent source searches are cached (0 = no caching).
+    pub fn with_query_cache_size(mut self, size: usize) -> Self {
+        self.config.query.cache_size = size;
+        self
+    }
+
+    /// Set how long a query waits for semantic results (zero = no limit).
+    pub fn with_semantic_timeout(mut self, timeout: Duration) -> Self {
+        self.config.query.semantic_timeout_ms =
+            u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Set the provider used to embed chunks and queries.
+    pub fn with_embedder(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.embedder = Some(provider);
+        self
+    }
+
     /// Build the engine.
     pub async fn build(self) -> Result<UnifiedRetrieval> {
-        UnifiedRetrieval::new(self.config).await
+        let mut engine = UnifiedRetrieval::new(self.config).await?;
+        if let Some(embedder) = self.embedder {
+            engine.set_embedder(embedder).await;
+        }
+        Ok(engine)
     }
 }
 
@@ -275,6 +836,71 @@ impl Default for UnifiedRetrievalBuilder {
     }
 }
 
+/// Results of `UnifiedRetrieval::query`.
+#[derive(Debug, Clone)]
+pub struct UnifiedQueryResult {
+    /// Context files matched by concept and keyword.
+    pub contexts: QueryResult,
+
+    /// Sources ranked by semantic similarity, best first.
+    pub sources: Vec<SourceResult>,
+
+    /// Whether semantic search failed or timed out, leaving `sources` empty.
+    pub semantic_degraded: bool,
+}
+
+/// Outcome of ingesting a file into the similarity index.
+#[derive(Debug, Clone)]
+pub struct IngestReport {
+    /// Number of chunks the file was split into.
+    pub chunks: usize,
+
+    /// Number of chunks embedded and indexed.
+    pub indexed: usize,
+
+    /// Chunks that failed to embed, with the error for each.
+    pub failures: Vec<(String, String)>,
+}
+
+/// A source ranked by the similarity of its chunks to a query.
+#[derive(Debug, Clone)]
+pub struct SourceResult {
+    /// Source the chunks were ingested from.
+    pub source: String,
+
+    /// Combined score of the source's chunks, with repeated text counted once.
+    pub score: f32,
+
+    /// IDs of the matching chunks, best first.
+    pub chunks: Vec<String>,
+}
+
+/// Outcome of re-embedding stored chunks with a new provider.
+#[derive(Debug, Clone)]
+pub struct ReembedReport {
+    /// Number of stored chunks.
+    pub total: usize,
+
+    /// Number of chunks re-embedded into the new index.
+    pub reembedded: usize,
+
+    /// Chunks that failed to re-embed, with the error for each.
+    pub failures: Vec<(String, String)>,
+
+    /// Dimension of the new index.
+    pub dimension: usize,
+}
+
+/// Hit and miss counts of the source search cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryCacheStats {
+    /// Searches answered from the cache.
+    pub hits: usize,
+
+    /// Searches that had to be ranked.
+    pub misses: usize,
+}
+
 /// Statistics about the retrieval engine.
 #[derive(Debug, Clone)]
 pub struct EngineStats {
@@ -297,8 +923,76 @@ pub struct EngineStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use async_trait::async_trait;
+    use codex_embeddings::EmbeddingResponse;
+    use pretty_assertions::assert_eq;
+    use std::time::{Duration, Instant};
     use tempfile::TempDir;
 
+    /// Deterministic bag-of-words provider with a fixed dimension.
+    ///
+    /// Each request waits `delay` first, and text containing "FAIL" errors.
+    struct StubProvider {
+        model: String,
+        dimension: usize,
+        delay: Duration,
+    }
+
+    fn stub_provider(dimension: usize) -> Arc<dyn EmbeddingProvider> {
+        delayed_provider(dimension, Duration::ZERO)
+    }
+
+    fn delayed_provider(dimension: usize, delay: Duration) -> Arc<dyn EmbeddingProvider> {
+        Arc::new(StubProvider {
+            model: format!("stub-{dimension}"),
+            dimension,
+            delay,
+        })
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for StubProvider {
+        fn name(&self) -> &str {
+            "stub"
+        }
+
+        fn default_model(&self) -> &str {
+            &self.model
+        }
+
+        fn default_dimension(&self) -> usize {
+            self.dimension
+        }
+
+        async fn embed(
+            &self,
+            request: EmbeddingRequest,
+        ) -> codex_embeddings::Result<EmbeddingResponse> {
+            tokio::time::sleep(self.delay).await;
+            if request.text.contains("FAIL") {
+                return Err(codex_embeddings::EmbeddingError::ApiRequest(
+                    "stub failure".to_string(),
+                ));
+            }
+
+            let mut embedding = vec![0.0f32; self.dimension];
+            for word in request.text.split_whitespace() {
+                let bucket = word.bytes().map(usize::from).sum::<usize>() % self.dimension;
+                embedding[bucket] += 1.0;
+            }
+            Ok(EmbeddingResponse {
+                embedding,
+                model: self.model.clone(),
+                dimension: self.dimension,
+                tokens_used: None,
+            })
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+    }
+
     #[tokio::test]
     async fn test_engine_creation() {
         let temp_dir = TempDir::new().unwrap();
@@ -322,4 +1016,336 @@ mod tests {
 
         assert!(engine.initialized);
     }
+
+    #[tokio::test]
+    async fn test_resolve_node_contexts() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = UnifiedRetrieval::new(RetrievalConfig::new(temp_dir.path()))
+            .await
+            .unwrap();
+        let mut linked = codex_context_files::ContextFile::new("people", "Alice and Bob");
+        linked.node_id = Some("node-1".to_string());
+        engine
+            .context_store
+            .write()
+            .await
+            .upsert(linked)
+            .await
+            .unwrap();
+        engine
+            .upsert_context("recipes", "Weeknight dinners")
+            .await
+            .unwrap();
+
+        let concepts: Vec<String> = engine
+            .contexts_for_node("node-1")
+            .await
+            .into_iter()
+            .map(|cf| cf.concept)
+            .collect();
+        assert_eq!(concepts, vec!["people".to_string()]);
+        assert_eq!(
+            engine.node_for_concept("people").await.as_deref(),
+            Some("node-1")
+        );
+        assert_eq!(engine.node_for_concept("recipes").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_query_degrades_on_slow_semantic_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = |provider: Arc<dyn EmbeddingProvider>| {
+            UnifiedRetrieval::builder()
+                .with_context_dir(temp_dir.path())
+                .with_embedding_provider(EmbeddingProviderType::None)
+                .with_realtime_watch(false)
+                .with_semantic_timeout(Duration::from_millis(50))
+                .with_embedder(provider)
+                .build()
+        };
+
+        let slow = engine(delayed_provider(16, Duration::from_secs(10)))
+            .await
+            .unwrap();
+        slow.upsert_context("rust-projects", "Rust side projects")
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+        let result = slow.query("rust projects").await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(result.semantic_degraded);
+        assert!(result.sources.is_empty());
+        let concepts: Vec<&str> = result
+            .contexts
+            .results
+            .iter()
+            .map(|r| r.concept.as_str())
+            .collect();
+        assert_eq!(concepts, vec!["rust-projects"]);
+
+        let fast = engine(stub_provider(16)).await.unwrap();
+        fast.upsert_context("rust-projects", "Rust side projects")
+            .await
+            .unwrap();
+        let result = fast.query("rust projects").await.unwrap();
+        assert!(!result.semantic_degraded);
+        assert_eq!(result.contexts.results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_concept_index_warm_start() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = UnifiedRetrieval::new(RetrievalConfig::new(temp_dir.path()))
+            .await
+            .unwrap();
+        engine
+            .upsert_context("rust-projects", "Rust side projects")
+            .await
+            .unwrap();
+        engine.save_index().await.unwrap();
+        assert!(temp_dir.path().join(CONCEPT_INDEX_FILE).exists());
+
+        let restarted = UnifiedRetrieval::new(RetrievalConfig::new(temp_dir.path()))
+            .await
+            .unwrap();
+        let index = restarted.concept_index.read().await;
+        assert!(index.contains("rust-projects"));
+        assert_eq!(index.find_by_keyword("rust").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reembed_all() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut engine = UnifiedRetrieval::builder()
+            .with_context_dir(temp_dir.path())
+            .with_embedding_provider(EmbeddingProviderType::None)
+            .with_realtime_watch(false)
+            .with_embedder(stub_provider(4))
+            .build()
+            .await
+            .unwrap();
+
+        engine.index_chunk("rust", "rust cargo").await.unwrap();
+        engine.index_chunk("python", "python pip").await.unwrap();
+        let file = temp_dir.path().join("runtime.md");
+        std::fs::write(
+            &file,
+            "The tokio runtime schedules tasks.\n\nThe tokio scheduler steals work.",
+        )
+        .unwrap();
+        engine.ingest_file(&file).await.unwrap();
+        assert_eq!(engine.embedding_dimension().await, 4);
+
+        let report = engine.reembed_all(stub_provider(8)).await.unwrap();
+        assert_eq!(
+            (report.total, report.reembedded, report.dimension),
+            (4, 4, 8)
+        );
+        assert!(report.failures.is_empty());
+        assert_eq!(engine.embedding_dimension().await, 8);
+        assert_eq!(engine.stats().await.embeddings_stored, 4);
+
+        let results = engine.semantic_search("python pip", 1).await.unwrap();
+        assert_eq!(results[0].id, "python");
+
+        // Ingested chunks still group under the file they came from
+        let source = file.to_string_lossy();
+        let sources = engine.search_sources("tokio", 10).await.unwrap();
+        let runtime = sources.iter().find(|s| s.source == source).unwrap();
+        assert_eq!(runtime.chunks.len(), 2);
+    }
+
+    /// Embed and index a chunk as if it had been ingested from `source`.
+    async fn add_source_chunk(
+        engine: &UnifiedRetrieval,
+        id: &str,
+        source: &str,
+        text: &str,
+        continuation: bool,
+    ) {
+        let embedder = engine.embedder().unwrap();
+        let response = embedder.embed(EmbeddingRequest::new(text)).await.unwrap();
+        let metadata = serde_json::json!({ "source": source, "continuation": continuation });
+        engine
+            .similarity_index
+            .write()
+            .await
+            .add_response(id, response, Some(metadata))
+            .unwrap();
+        engine
+            .chunk_texts
+            .write()
+            .await
+            .insert(id.to_string(), text.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_search_sources_dedups_overlapping_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let engine = UnifiedRetrieval::builder()
+            .with_context_dir(temp_dir.path())
+            .with_embedding_provider(EmbeddingProviderType::None)
+            .with_realtime_watch(false)
+            .with_embedder(stub_provider(64))
+            .build()
+            .await
+            .unwrap();
+
+        // The same text, once split with overlap and once without.
+        let chunks = [
+            (
+                "overlap#0",
+                "overlap",
+                "tokio runtime scheduler internals",
+                false,
+            ),
+            (
+                "overlap#1",
+                "overlap",
+                "tokio runtime scheduler internals explained",
+                true,
+            ),
+            (
+                "plain#0",
+                "plain",
+                "tokio runtime scheduler internals",
+                false,
+            ),
+            ("plain#1", "plain", "explained", false),
+        ];
+        for (id, source, text, continuation) in chunks {
+            add_source_chunk(&engine, id, source, text, continuation).await;
+        }
+
+        let query = "tokio runtime scheduler";
+        let raw_score = |source: &str, results: &[SimilarityResult]| -> f32 {
+            results
+                .iter()
+                .filter(|r| r.id.starts_with(source))
+                .map(|r| r.score)
+                .sum()
+        };
+        let chunk_results = engine.semantic_search(query, 10).await.unwrap();
+        assert!(raw_score("overlap", &chunk_results) > raw_score("plain", &chunk_results));
+
+        let sources = engine.search_sources(query, 10).await.unwrap();
+        let score = |name: &str| sources.iter().find(|s| s.source == name).unwrap().score;
+        assert!(
+            score("overlap") <= score("plain") + f32::EPSILON,
+            "{sources:?}"
+        );
+        assert_eq!(
+            sources
+                .iter()
+                .find(|s| s.source == "overlap")
+                .unwrap()
+                .chunks,
+            vec!["overlap#0".to_string(), "overlap#1".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ingest_file_embeds_concurrently_in_order() {
+        const CHUNKS: usize = 16;
+        const DELAY: Duration = Duration::from_millis(50);
+
+        let temp_dir = TempDir::new().unwrap();
+        let paragraphs: Vec<String> = (0..CHUNKS)
+            .map(|i| {
+                if i == 5 {
+                    "Paragraph FAIL cannot be embedded.".to_string()
+                } else {
+                    format!("Paragraph number {i} is here.")
+                }
+            })
+            .collect();
+        let file = temp_dir.path().join("notes.md");
+        std::fs::write(&file, paragraphs.join("\n\n")).unwrap();
+
+        let engine = UnifiedRetrieval::builder()
+            .with_context_dir(temp_dir.path())
+            .with_embedding_provider(EmbeddingProviderType::None)
+            .with_realtime_watch(false)
+            .with_embed_concurrency(8)
+            .with_embedder(delayed_provider(8, DELAY))
+            .build()
+            .await
+            .unwrap();
+
+        let start = Instant::now();
+        let report = engine.ingest_file(&file).await.unwrap();
+        let elapsed = start.elapsed();
+
+        assert_eq!((report.chunks, report.indexed), (CHUNKS, CHUNKS - 1));
+        let source = file.to_string_lossy();
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].0, format!("{source}#5"));
+        assert!(
+            elapsed < DELAY * CHUNKS as u32 / 2,
+            "took {elapsed:?}, sequential would take {:?}",
+            DELAY * CHUNKS as u32
+        );
+
+        let index = engine.similarity_index.read().await;
+        let texts = engine.chunk_texts.read().await;
+        for (position, paragraph) in paragraphs.iter().enumerate() {
+            let id = format!("{source}#{position}");
+            if position == 5 {
+                assert!(!index.contains(&id));
+                continue;
+            }
+            let entry = index.get(&id).unwrap();
+            let metadata = entry.metadata.as_ref().unwrap();
+            assert_eq!(metadata["chunk_index"], position);
+            assert_eq!(texts.get(&id), Some(paragraph));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_sources_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = temp_dir.path().join("runtime.md");
+        std::fs::write(&runtime, "tokio runtime scheduler internals").unwrap();
+        let tuning = temp_dir.path().join("tuning.md");
+        std::fs::write(&tuning, "tuning the tokio runtime scheduler").unwrap();
+
+        let engine = UnifiedRetrieval::builder()
+            .with_context_dir(temp_dir.path())
+            .with_embedding_provider(EmbeddingProviderType::None)
+            .with_realtime_watch(false)
+            .with_query_cache_size(8)
+            .with_embedder(stub_provider(64))
+            .build()
+            .await
+            .unwrap();
+        engine.ingest_file(&runtime).await.unwrap();
+
+        let first = engine
+            .search_sources("tokio runtime scheduler", 10)
+            .await
+            .unwrap();
+        let second = engine
+            .search_sources("  Tokio   runtime SCHEDULER", 10)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second[0].source, first[0].source);
+        assert_eq!(
+            engine.query_cache_stats(),
+            QueryCacheStats { hits: 1, misses: 1 }
+        );
+
+        // Ingesting a file invalidates cached results
+        engine.ingest_file(&tuning).await.unwrap();
+        let third = engine
+            .search_sources("tokio runtime scheduler", 10)
+            .await
+            .unwrap();
+        assert_eq!(third.len(), 2);
+        assert_eq!(
+            engine.query_cache_stats(),
+            QueryCacheStats { hits: 1, misses: 2 }
+        );
+    }
 }
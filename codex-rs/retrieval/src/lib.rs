@@ -52,8 +52,8 @@ pub mod config;
 pub mod engine;
 pub mod error;
 
-pub use config::RetrievalConfig;
-pub use engine::UnifiedRetrieval;
+pub use config::{RetrievalConfig, RetrievalConfigBuilder};
+pub use engine::{UnifiedQueryResult, UnifiedRetrieval};
 pub use error::{Result, RetrievalError};
 
 // Re-export from dependencies for convenience